@@ -1,31 +1,129 @@
-    use anyhow::Result;
-use futures::stream::{self, StreamExt};
-use std::collections::HashMap;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
 
 use crate::api::{ApiClient, OpenRouterRequest, RequestMessage};
 use crate::config::Config;
-use crate::database::{DatabaseManager, generate_word_cards, generate_grammar_cards};
+use crate::database::{generate_word_cards, generate_grammar_cards, generate_kanji_cards, generate_conjugation_cards, generate_sentence_cards, render_grammar_details, render_word_details};
+use crate::conjugation::ConjugationForm;
+use crate::pos::{PartOfSpeech, normalize_field, normalize_labels};
+use crate::prompts::{Language, PromptKind, PromptLibrary};
+use crate::grammar::canonical_grammar;
+use crate::html::{SanitizedHtml, sanitize_analysis, sanitize_grammar_details, sanitize_word_details};
 use crate::models::*;
+use crate::storage::{self, Storage};
+use crate::scheduler::{Resource, Scheduler};
+use crate::editor::{edit_in_editor, prompt_line};
+use crate::export::{EXPORT_FORMAT_VERSION, ExportFormat, ExportMetadata, KnowledgeBase};
+use crate::known_words::{KnownWordSource, read_known_words};
+use crate::levels::{LevelLists, passes_filter};
+use crate::study_plan::{PlanItem, plan_order};
+use crate::fsrs::{Fsrs, anki_interval, format_interval};
+use crate::review::{ReviewItem, TIME_FORMAT, grade_options, now_utc, play_audio, read_line};
 use crate::tts::{AzureTts, TtsConfig};
+use crate::kanji::{KanjiDictionary, collect_kanji};
+use crate::sentences::{contains_grammar, contains_word, examples_in_html, split_sentences};
+use crate::text::strip_html;
+
+// 每次请求翻译的例句数
+const SENTENCE_BATCH_SIZE: usize = 20;
 
 pub struct AnkiCreator {
     api_client: ApiClient,
-    db_manager: DatabaseManager,
+    db_manager: Arc<dyn Storage>,
+    scheduler: Arc<Scheduler>,
+    user_id: i64,
+    prompts: PromptLibrary,
     pub config: Config,
 }
 
 impl AnkiCreator {
     pub async fn new(config: Config) -> Result<Self> {
-        let api_client = ApiClient::new(config.clone())?;
-        let db_manager = DatabaseManager::new(config.clone()).await?;
+        let db_manager: Arc<dyn Storage> = Arc::from(storage::connect(&config).await?);
+        let scheduler = Arc::new(Scheduler::new(&config));
+        scheduler.listen_for_ctrl_c();
+        let api_client = ApiClient::new(config.clone(), db_manager.clone(), scheduler.clone())?;
+        let user_id = db_manager.ensure_user(&config.user.name).await?;
+        let prompts = PromptLibrary::load(&config.prompts.dir)?;
+        
+        let purged = api_client.purge_expired_cache().await?;
+        if purged > 0 {
+            println!("🧹 清理了 {} 条过期的请求缓存", purged);
+        }
         
         Ok(AnkiCreator {
             api_client,
             db_manager,
+            scheduler,
+            user_id,
+            prompts,
             config,
         })
     }
 
+    // 汇总本次运行按用途统计的模型请求用量
+    pub async fn print_usage_summary(&self) -> Result<()> {
+        let summaries = self.db_manager.get_api_usage(Some(self.api_client.run_id())).await?;
+        let cache_hits = self.api_client.cache_hits();
+        if summaries.is_empty() && cache_hits == 0 {
+            return Ok(());
+        }
+        
+        println!("\n💰 本次运行的模型用量：");
+        let format_cost = |cost: Option<f64>| cost.map_or("费用未知".to_string(), |c| format!("${:.4}", c));
+        for summary in &summaries {
+            let label = ApiOperation::parse(&summary.operation).map_or(summary.operation.as_str(), |op| op.label());
+            println!("  {}: {} 次请求，输入 {} / 输出 {} tokens，{}",
+                label, summary.requests, summary.prompt_tokens, summary.completion_tokens, format_cost(summary.cost));
+        }
+        if summaries.len() > 1 {
+            let cost = summaries.iter().filter_map(|s| s.cost).reduce(|a, b| a + b);
+            println!("  合计: {} 次请求，输入 {} / 输出 {} tokens，{}",
+                summaries.iter().map(|s| s.requests).sum::<i64>(),
+                summaries.iter().map(|s| s.prompt_tokens).sum::<i64>(),
+                summaries.iter().map(|s| s.completion_tokens).sum::<i64>(),
+                format_cost(cost));
+        }
+        if cache_hits > 0 {
+            println!("  缓存命中: {} 次（不产生费用）", cache_hits);
+        }
+        Ok(())
+    }
+
+    // 开始新的菜单操作前清除上一次操作的 Ctrl-C 取消状态
+    pub fn reset_cancellation(&self) {
+        self.scheduler.reset_cancellation();
+    }
+
+    // 是否按 Ctrl-C 请求了退出
+    pub fn exit_requested(&self) -> bool {
+        self.scheduler.exit_requested()
+    }
+
+    // 等待 Ctrl-C 退出请求
+    pub async fn wait_for_exit(&self) {
+        self.scheduler.wait_for_exit().await
+    }
+
+    // 当前学习者名称
+    pub fn user_name(&self) -> &str {
+        &self.config.user.name
+    }
+
+    // 设置当前学习者对某个条目的学习状态
+    pub async fn set_entry_status(&self, kind: EntryKind, id: i64, status: EntryStatus) -> Result<()> {
+        let word = match kind {
+            EntryKind::Word => self.db_manager.get_word_by_id(id).await?.map(|w| w.word),
+            EntryKind::Grammar => self.db_manager.get_grammar_by_id(id).await?.map(|g| g.word),
+        };
+        let word = word.ok_or_else(|| anyhow::anyhow!("未找到ID为 {} 的{}", id, kind.label()))?;
+        
+        self.db_manager.set_entry_status(self.user_id, kind, &word, status).await?;
+        println!("✅ {} 对 {} 的状态已设置为: {}", self.user_name(), word, status.label());
+        Ok(())
+    }
+
     // 更新所有单词的词性
     pub async fn update_all_word_parts_of_speech(&self) -> Result<()> {
         println!("🔄 开始更新所有单词的词性...");
@@ -38,123 +136,88 @@ impl AnkiCreator {
             return Ok(());
         }
         
-        println!("📊 找到 {} 个单词需要更新词性", words.len());
+        // 跳过词性已锁定（手动编辑过）的单词
+        let (locked_words, words): (Vec<JapaneseWord>, Vec<JapaneseWord>) = words
+            .into_iter()
+            .partition(|word| word.pos_locked);
         
-        // 使用并发流处理所有单词
-        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(self.config.processing.concurrent_requests));
+        println!("📊 找到 {} 个单词需要更新词性", words.len());
         
+        // 并发处理所有单词
         let total_words = words.len();
-        let update_results: Result<Vec<()>, anyhow::Error> = stream::iter(words.into_iter().enumerate())
-            .map(|(i, word)| {
-                let semaphore = semaphore.clone();
-                let api_client = &self.api_client;
-                let db_manager = &self.db_manager;
-                async move {
-                    let _permit = semaphore.acquire().await.unwrap();
+        self.scheduler.run(Resource::Llm, words.into_iter().enumerate(), |(i, word)| async move {
+            println!("  🔍 更新单词 {}/{}: {} ({})", 
+                i + 1, total_words, word.word, word.kana);
+            
+            // 重新分析单词以获取最新的词性
+            let prompt = self.prompts.part_of_speech_prompt(&word.word, &word.kana, &word.pitch);
+
+            let request = OpenRouterRequest {
+                model: self.config.api.models.word_analysis_model.clone(),
+                messages: vec![RequestMessage {
+                    role: "user".to_string(),
+                    content: prompt,
+                }],
+                max_tokens: self.config.api.max_tokens_for(ApiOperation::PartOfSpeech),
+                temperature: 0.1,
+            };
+
+            match self.api_client.make_fresh_request(ApiOperation::PartOfSpeech, &word.word, &request).await {
+                Ok(api_response) => {
+                    let content = &api_response.choices[0].message.content;
                     
-                    println!("  🔍 更新单词 {}/{}: {} ({})", 
-                        i + 1, total_words, word.word, word.kana);
+                    // 提取JSON部分
+                    let json_start = content.find('{').unwrap_or(0);
+                    let json_end = content.rfind('}').map(|i| i + 1).unwrap_or(content.len());
+                    let json_content = &content[json_start..json_end];
                     
-                    // 重新分析单词以获取最新的词性
-                    let prompt = format!(r#"
-请重新分析这个日语单词的词性，只需要返回准确的词性信息：
-
-单词：{}
-假名：{}
-音调：{}
-
-请用以下JSON格式返回结果（只需要词性信息）：
-{{
-  "part_of_speech": ["词性1", "词性2"]
-}}
-
-重要词性标注规则：
-1. 动词必须明确标注为"自动词"或"他动词"，不要只写"动词"
-2. 如果一个词既是自动词又是他动词，就标注["自动词", "他动词"]
-3. 形容词分为"一类形容词"和"二类形容词"
-4. 一律使用简体中文词性标注：名词、自动词、他动词、一类形容词、二类形容词、副词、连词、助词、感叹词等
-5. 不要出现"動詞｜自動詞"这种重复标注
-6. 只返回JSON格式，不要添加其他内容
-"#, word.word, word.kana, word.pitch);
-
-                    let request = OpenRouterRequest {
-                        model: self.config.api.models.word_analysis_model.clone(),
-                        messages: vec![RequestMessage {
-                            role: "user".to_string(),
-                            content: prompt,
-                        }],
-                        max_tokens: 1000,
-                        temperature: 0.1,
-                    };
-
-                    match api_client.make_request_with_retry(&request).await {
-                        Ok(api_response) => {
-                            let content = &api_response.choices[0].message.content;
-                            
-                            // 提取JSON部分
-                            let json_start = content.find('{').unwrap_or(0);
-                            let json_end = content.rfind('}').map(|i| i + 1).unwrap_or(content.len());
-                            let json_content = &content[json_start..json_end];
-                            
-                            // 解析词性结果
-                            match serde_json::from_str::<serde_json::Value>(json_content) {
-                                Ok(parsed) => {
-                                    if let Some(pos_array) = parsed.get("part_of_speech").and_then(|v| v.as_array()) {
-                                        let new_parts_of_speech: Vec<String> = pos_array
-                                            .iter()
-                                            .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                                            .collect();
+                    // 解析词性结果
+                    match serde_json::from_str::<serde_json::Value>(json_content) {
+                        Ok(parsed) => {
+                            if let Some(pos_array) = parsed.get("part_of_speech").and_then(|v| v.as_array()) {
+                                let new_parts_of_speech: Vec<String> = pos_array
+                                    .iter()
+                                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                                    .collect();
+                                
+                                if !new_parts_of_speech.is_empty() {
+                                    self.api_client.cache_response(&request, &api_response).await;
+                                    let new_pos_str = normalize_labels(&new_parts_of_speech).to_field();
+                                    
+                                    // 检查词性是否有变化
+                                    if word.part_of_speech != new_pos_str {
+                                        println!("    🔄 词性更新: {} -> {}", 
+                                            word.part_of_speech, new_pos_str);
                                         
-                                        if !new_parts_of_speech.is_empty() {
-                                            let new_pos_str = new_parts_of_speech.join("｜");
-                                            
-                                            // 检查词性是否有变化
-                                            if word.part_of_speech != new_pos_str {
-                                                println!("    🔄 词性更新: {} -> {}", 
-                                                    word.part_of_speech, new_pos_str);
-                                                
-                                                // 更新数据库中的词性
-                                                if let Err(e) = db_manager.update_word_part_of_speech(word.id, &new_pos_str).await {
-                                                    println!("    ❌ 更新失败: {}", e);
-                                                } else {
-                                                    println!("    ✅ 更新成功");
-                                                }
-                                            } else {
-                                                println!("    ✅ 词性无变化，跳过更新");
-                                            }
+                                        // 更新数据库中的词性
+                                        let _write = self.scheduler.acquire(Resource::DbWrite).await;
+                                        if let Err(e) = self.db_manager.update_word_part_of_speech(word.id, &new_pos_str).await {
+                                            println!("    ❌ 更新失败: {}", e);
                                         } else {
-                                            println!("    ⚠️  未能解析到有效词性");
+                                            println!("    ✅ 更新成功");
                                         }
                                     } else {
-                                        println!("    ⚠️  响应格式不正确");
+                                        println!("    ✅ 词性无变化，跳过更新");
                                     }
-                                },
-                                Err(e) => {
-                                    println!("    ❌ JSON解析失败: {}", e);
+                                } else {
+                                    println!("    ⚠️  未能解析到有效词性");
                                 }
+                            } else {
+                                println!("    ⚠️  响应格式不正确");
                             }
                         },
                         Err(e) => {
-                            println!("    ❌ API请求失败: {}", e);
+                            println!("    ❌ JSON解析失败: {}", e);
                         }
                     }
-                    
-                    // 添加延迟以避免过于频繁的请求
-                    tokio::time::sleep(tokio::time::Duration::from_millis(
-                        self.config.processing.request_delay_ms
-                    )).await;
-                    
-                    Ok(())
+                },
+                Err(e) => {
+                    println!("    ❌ API请求失败: {}", e);
                 }
-            })
-            .buffer_unordered(self.config.processing.concurrent_requests)
-            .collect::<Vec<Result<(), anyhow::Error>>>()
-            .await
-            .into_iter()
-            .collect();
-        
-        update_results?;
+            }
+        }).await;
         
+        report_locked_words(&locked_words, LockField::PartOfSpeech);
         println!("🎉 所有单词词性更新完成！");
         Ok(())
     }
@@ -171,72 +234,76 @@ impl AnkiCreator {
             return Ok(());
         }
         
-        println!("📊 找到 {} 个单词需要更新解析", words.len());
+        // 跳过解析已锁定（手动编辑过）的单词，锁定只作用于主语言的解析
+        let primary = self.explains_in_primary_language();
+        let (locked_words, words): (Vec<JapaneseWord>, Vec<JapaneseWord>) = words
+            .into_iter()
+            .partition(|word| primary && word.analysis_locked);
+        
+        // 其他语言下与该语言已有的解析比较
+        let words = self.with_current_analysis(EntryKind::Word, words, |w| (w.id, &mut w.analysis, &mut w.prompt_version)).await?;
+        
+        println!("📊 找到 {} 个单词需要更新解析（{}）", words.len(), self.config.language.explanation.label());
         
-        // 使用并发流处理所有单词
-        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(self.config.processing.concurrent_requests));
+        self.reanalyze_words(words).await?;
         
+        report_locked_words(&locked_words, LockField::Analysis);
+        println!("🎉 所有单词解析更新完成！");
+        Ok(())
+    }
+
+    // 并发重新分析单词，解析或提示词版本有变化时保存
+    async fn reanalyze_words(&self, words: Vec<JapaneseWord>) -> Result<()> {
+        let current_version = self.prompt_version(PromptKind::WordAnalysis);
+        
+        // 并发处理所有单词
         let total_words = words.len();
-        let update_results: Result<Vec<()>, anyhow::Error> = stream::iter(words.into_iter().enumerate())
-            .map(|(i, word)| {
-                let semaphore = semaphore.clone();
-                let analyzer = self;
-                async move {
-                    let _permit = semaphore.acquire().await.unwrap();
-                    
-                    println!("  🔍 更新单词解析 {}/{}: {} ({})", 
-                        i + 1, total_words, word.word, word.kana);
-                    
-                    // 复用现有的分析逻辑
-                    let parts_of_speech: Vec<&str> = word.part_of_speech.split('｜').collect();
-                    let parts_of_speech_vec: Vec<String> = parts_of_speech.iter().map(|s| s.to_string()).collect();
-                    
-                    let basic_word = BasicWordInfo {
-                        word: word.word.clone(),
-                        kana: word.kana.clone(),
-                        pitch: word.pitch.clone(),
-                        part_of_speech: parts_of_speech_vec.clone(),
-                    };
-                    
-                    match analyzer.analyze_word_with_multiple_pos(&basic_word, &parts_of_speech_vec).await {
-                        Ok(new_analysis) => {
-                            // 检查解析是否有变化
-                            if word.analysis != new_analysis {
-                                println!("    🔄 解析更新: 长度 {} -> {}", 
-                                    word.analysis.len(), new_analysis.len());
-                                
-                                // 更新数据库中的解析
-                                if let Err(e) = analyzer.db_manager.update_word_analysis(word.id, &new_analysis).await {
-                                    println!("    ❌ 更新失败: {}", e);
-                                } else {
-                                    println!("    ✅ 更新成功");
-                                }
-                            } else {
-                                println!("    ✅ 解析无变化，跳过更新");
-                            }
-                        },
-                        Err(e) => {
-                            println!("    ❌ 分析失败: {}", e);
+        self.scheduler.run(Resource::Llm, words.into_iter().enumerate(), |(i, word)| async move {
+            println!("  🔍 更新单词解析 {}/{}: {} ({})", 
+                i + 1, total_words, word.word, word.kana);
+            
+            // 复用现有的分析逻辑
+            let parts_of_speech: Vec<&str> = word.part_of_speech.split('｜').collect();
+            let parts_of_speech_vec: Vec<String> = parts_of_speech.iter().map(|s| s.to_string()).collect();
+            
+            let basic_word = BasicWordInfo {
+                word: word.word.clone(),
+                kana: word.kana.clone(),
+                pitch: word.pitch.clone(),
+                part_of_speech: parts_of_speech_vec.clone(),
+            };
+            
+            match self.analyze_word_with_multiple_pos(&basic_word, &parts_of_speech_vec, true).await {
+                Ok((sanitized, details)) => {
+                    let new_analysis = &sanitized.html;
+                    let _write = self.scheduler.acquire(Resource::DbWrite).await;
+                    // 检查解析或提示词版本是否有变化
+                    if word.analysis != *new_analysis || word.prompt_version != current_version {
+                        println!("    🔄 解析更新: 长度 {} -> {}", 
+                            word.analysis.len(), new_analysis.len());
+                        
+                        // 更新数据库中的解析
+                        let stored = match self.store_analysis(EntryKind::Word, word.id, new_analysis).await {
+                            Ok(_) => self.store_word_details(word.id, details.as_ref()).await,
+                            Err(e) => Err(e),
+                        };
+                        if let Err(e) = stored {
+                            println!("    ❌ 更新失败: {}", e);
+                        } else {
+                            println!("    ✅ 更新成功");
                         }
+                    } else {
+                        println!("    ✅ 解析无变化，跳过更新");
                     }
-                    
-                    // 添加延迟以避免过于频繁的请求
-                    tokio::time::sleep(tokio::time::Duration::from_millis(
-                        analyzer.config.processing.request_delay_ms
-                    )).await;
-                    
-                    Ok(())
+                    if let Err(e) = self.record_validation(EntryKind::Word, word.id, &sanitized).await {
+                        println!("    ❌ 记录校验结果失败: {}", e);
+                    }
+                },
+                Err(e) => {
+                    println!("    ❌ 分析失败: {}", e);
                 }
-            })
-            .buffer_unordered(self.config.processing.concurrent_requests)
-            .collect::<Vec<Result<(), anyhow::Error>>>()
-            .await
-            .into_iter()
-            .collect();
-        
-        update_results?;
-        
-        println!("🎉 所有单词解析更新完成！");
+            }
+        }).await;
         Ok(())
     }
 
@@ -255,6 +322,14 @@ impl AnkiCreator {
         
         println!("📝 找到单词: {} ({}) - {}", word.word, word.kana, word.part_of_speech);
         
+        if self.explains_in_primary_language() && word.analysis_locked {
+            println!("🔒 该单词的解析已手动编辑并锁定，跳过更新（如需重新分析请先解除锁定）");
+            return Ok(());
+        }
+        let word = self.with_current_analysis(EntryKind::Word, vec![word], |w| (w.id, &mut w.analysis, &mut w.prompt_version))
+            .await?
+            .remove(0);
+        
         // 复用现有的分析逻辑
         let parts_of_speech: Vec<&str> = word.part_of_speech.split('｜').collect();
         let parts_of_speech_vec: Vec<String> = parts_of_speech.iter().map(|s| s.to_string()).collect();
@@ -266,19 +341,22 @@ impl AnkiCreator {
             part_of_speech: parts_of_speech_vec.clone(),
         };
         
-        match self.analyze_word_with_multiple_pos(&basic_word, &parts_of_speech_vec).await {
-            Ok(new_analysis) => {
-                // 检查解析是否有变化
-                if word.analysis != new_analysis {
+        match self.analyze_word_with_multiple_pos(&basic_word, &parts_of_speech_vec, true).await {
+            Ok((sanitized, details)) => {
+                let new_analysis = &sanitized.html;
+                // 检查解析或提示词版本是否有变化
+                if word.analysis != *new_analysis || word.prompt_version != self.prompt_version(PromptKind::WordAnalysis) {
                     println!("🔄 解析更新: 长度 {} -> {}", 
                         word.analysis.len(), new_analysis.len());
                     
                     // 更新数据库中的解析
-                    self.db_manager.update_word_analysis(id, &new_analysis).await?;
+                    self.store_analysis(EntryKind::Word, id, new_analysis).await?;
+                    self.store_word_details(id, details.as_ref()).await?;
                     println!("✅ 单词解析更新成功");
                 } else {
                     println!("✅ 解析无变化，跳过更新");
                 }
+                self.record_validation(EntryKind::Word, id, &sanitized).await?;
             },
             Err(e) => {
                 println!("❌ 分析失败: {}", e);
@@ -289,116 +367,946 @@ impl AnkiCreator {
         Ok(())
     }
 
-    // 第一步：提取单词和语法的基本信息
-    pub async fn extract_words_and_grammar(&self, text: &str) -> Result<ExtractionResult> {
-        let prompt = format!(r#"
-请分析以下日语文本，提取出所有单词和语法点的基本信息：
-
-1. 单词部分：
-   - 将所有单词转换为辞书形（原形）
-   - 提供假名读音
-   - 提供音调（用0-9数字表示）
-   - 确定词性（请精确标注）
-
-2. 语法部分：
-   - 识别语法结构和表达方式
-   - 提供假名读音
-
-重要词性标注规则：
-- 动词必须明确标注为"自动词"或"他动词"，不要只写"动词"
-- 如果一个词既是自动词又是他动词，就标注["自动词", "他动词"]
-- 形容词分为"一类形容词"和"二类形容词"
-- 一律使用简体中文词性：名词、自动词、他动词、一类形容词、二类形容词、副词、连词、助词、感叹词等
-- 不要出现重复标注如"動詞｜自動詞"
-
-请用以下JSON格式返回结果（只需要基本信息，不需要详细解释）：
-{{
-  "words": [
-    {{
-      "word": "単语辞书形",
-      "kana": "かな",
-      "pitch": "0",
-      "part_of_speech": ["名词", "他动词"]
-    }}
-  ],
-  "grammar": [
-    {{
-      "grammar": "语法表达",
-      "kana": "かな"
-    }}
-  ]
-}}
-
-要分析的文本：
-{}
-"#, text);
-
-        let request = OpenRouterRequest {
-            model: self.config.api.models.extraction_model.clone(),
-            messages: vec![RequestMessage {
-                role: "user".to_string(),
-                content: prompt,
-            }],
-            max_tokens: 100000,
-            temperature: 0.1,
-        };
+    // 重新分析用旧版本提示词生成或未通过校验的单词和语法解析（当前解析语言，跳过锁定的条目）
+    pub async fn reanalyze_outdated(&self) -> Result<()> {
+        let language = self.config.language.explanation;
+        let word_version = self.prompt_version(PromptKind::WordAnalysis);
+        let grammar_version = self.prompt_version(PromptKind::GrammarAnalysis);
+        println!("🔄 查找提示词版本过旧或未通过校验的{}解析（单词模板 v{}，语法模板 v{}）...",
+            language.label(), word_version, grammar_version);
+        let flagged_words = self.db_manager.get_analysis_flags(EntryKind::Word, language.code()).await?;
+        let flagged_grammar = self.db_manager.get_analysis_flags(EntryKind::Grammar, language.code()).await?;
+        
+        let primary = self.explains_in_primary_language();
+        let words: Vec<JapaneseWord> = self.db_manager.get_all_words().await?
+            .into_iter()
+            .filter(|w| !(primary && w.analysis_locked))
+            .collect();
+        let words: Vec<JapaneseWord> = self.with_current_analysis(EntryKind::Word, words, |w| (w.id, &mut w.analysis, &mut w.prompt_version))
+            .await?
+            .into_iter()
+            .filter(|w| flagged_words.contains_key(&w.id) || (!w.analysis.is_empty() && w.prompt_version != word_version))
+            .collect();
+        
+        let grammar: Vec<JapaneseGrammar> = self.db_manager.get_all_grammar().await?
+            .into_iter()
+            .filter(|g| !(primary && g.analysis_locked))
+            .collect();
+        let grammar: Vec<JapaneseGrammar> = self.with_current_analysis(EntryKind::Grammar, grammar, |g| (g.id, &mut g.analysis, &mut g.prompt_version))
+            .await?
+            .into_iter()
+            .filter(|g| flagged_grammar.contains_key(&g.id) || (!g.analysis.is_empty() && g.prompt_version != grammar_version))
+            .collect();
+        
+        if words.is_empty() && grammar.is_empty() {
+            println!("✅ 所有解析都使用当前版本的提示词生成并通过了校验");
+            return Ok(());
+        }
+        
+        println!("📊 {} 个单词、{} 个语法的解析需要重新生成", words.len(), grammar.len());
+        self.reanalyze_words(words).await?;
+        
+        let total_grammar = grammar.len();
+        self.scheduler.run(Resource::Llm, grammar.into_iter().enumerate(), |(i, item)| async move {
+            println!("  🔍 更新语法解析 {}/{}: {}", i + 1, total_grammar, item.word);
+            let basic_grammar = BasicGrammarInfo {
+                grammar: item.word.clone(),
+                kana: item.kana.clone(),
+            };
+            match self.analyze_grammar(&basic_grammar, true).await {
+                Ok((sanitized, details)) => {
+                    let _write = self.scheduler.acquire(Resource::DbWrite).await;
+                    let stored = match self.store_analysis(EntryKind::Grammar, item.id, &sanitized.html).await {
+                        Ok(_) => self.store_grammar_details(item.id, details.as_ref()).await,
+                        Err(e) => Err(e),
+                    };
+                    match stored {
+                        Ok(_) => println!("    ✅ 更新成功"),
+                        Err(e) => println!("    ❌ 更新失败: {}", e),
+                    }
+                    if let Err(e) = self.record_validation(EntryKind::Grammar, item.id, &sanitized).await {
+                        println!("    ❌ 记录校验结果失败: {}", e);
+                    }
+                },
+                Err(e) => println!("    ❌ 分析失败: {}", e),
+            }
+        }).await;
+        
+        println!("🎉 重新分析完成！");
+        Ok(())
+    }
+
+    // 清理并校验已保存的所有语言的解析：修正格式问题，未通过校验的标记为待重新分析（跳过锁定的主语言解析）
+    pub async fn validate_existing_analyses(&self) -> Result<()> {
+        println!("🔍 校验已保存的解析...");
+        let mut fixed = 0;
+        let mut flagged = 0;
+        
+        for kind in [EntryKind::Word, EntryKind::Grammar] {
+            // 主语言解析：（条目ID，解析，提示词版本）
+            let entries: Vec<(i64, String, i64)> = match kind {
+                EntryKind::Word => self.db_manager.get_all_words().await?
+                    .into_iter()
+                    .filter(|w| !w.analysis_locked)
+                    .map(|w| (w.id, w.analysis, w.prompt_version))
+                    .collect(),
+                EntryKind::Grammar => self.db_manager.get_all_grammar().await?
+                    .into_iter()
+                    .filter(|g| !g.analysis_locked)
+                    .map(|g| (g.id, g.analysis, g.prompt_version))
+                    .collect(),
+            };
+            
+            for language in Language::ALL {
+                // 格式通过校验但未通过审查的解析仍保留待重新分析的标记
+                let reviews = self.db_manager.get_analysis_reviews(kind, language.code()).await?;
+                let entries = if language == Language::PRIMARY {
+                    entries.clone()
+                } else {
+                    let analyses = self.db_manager.get_entry_analyses(kind, language.code()).await?;
+                    let versions = self.db_manager.get_entry_analysis_versions(kind, language.code()).await?;
+                    analyses.into_iter()
+                        .map(|(id, analysis)| (id, analysis, versions.get(&id).copied().unwrap_or(0)))
+                        .collect()
+                };
+                
+                for (id, analysis, version) in entries {
+                    // 没有解析的条目不校验（例如只有其他语言解析的条目）
+                    if analysis.trim().is_empty() {
+                        continue;
+                    }
+                    let sanitized = sanitize_analysis(&analysis);
+                    if sanitized.html != analysis {
+                        match (language == Language::PRIMARY, kind) {
+                            (true, EntryKind::Word) => self.db_manager.update_word_analysis(id, &sanitized.html, version).await?,
+                            (true, EntryKind::Grammar) => self.db_manager.update_grammar_analysis(id, &sanitized.html, version).await?,
+                            (false, _) => self.db_manager.save_entry_analysis(kind, id, language.code(), &sanitized.html, version).await?,
+                        }
+                        fixed += 1;
+                    }
+                    
+                    let reason = sanitized.failure_reason().or_else(|| {
+                        reviews.get(&id)?.failure_reason(self.config.processing.review_min_score)
+                    });
+                    if let Some(reason) = &reason {
+                        println!("  ⚠️  {} ID {}（{}）: {}", kind.label(), id, language.label(), reason);
+                        flagged += 1;
+                    }
+                    self.db_manager.set_analysis_flag(kind, id, language.code(), reason.as_deref()).await?;
+                }
+            }
+        }
+        
+        println!("✅ 校验完成：修正了 {} 条解析的格式，{} 条未通过校验已标记待重新分析", fixed, flagged);
+        if flagged > 0 {
+            println!("💡 可以使用“重新分析旧版提示词生成或未通过校验的解析”重新生成被标记的解析");
+        }
+        Ok(())
+    }
+
+    // 用审查模型检查当前解析语言下还没有审查结果的解析（跳过锁定的和已标记待重新分析的条目）
+    pub async fn review_existing_analyses(&self) -> Result<()> {
+        let Some(model) = &self.config.api.models.review_model else {
+            println!("⚠️  未配置审查模型，请在 [api.models] 中设置 review_model");
+            return Ok(());
+        };
+        let language = self.config.language.explanation;
+        let primary = self.explains_in_primary_language();
+        
+        // （类型，ID，条目信息，解析）
+        let mut pending: Vec<(EntryKind, i64, String, String)> = Vec::new();
+        for kind in [EntryKind::Word, EntryKind::Grammar] {
+            let reviewed = self.db_manager.get_analysis_reviews(kind, language.code()).await?;
+            let flagged = self.db_manager.get_analysis_flags(kind, language.code()).await?;
+            let skip = |id: i64| reviewed.contains_key(&id) || flagged.contains_key(&id);
+            match kind {
+                EntryKind::Word => {
+                    let words: Vec<JapaneseWord> = self.db_manager.get_all_words().await?
+                        .into_iter()
+                        .filter(|w| !(primary && w.analysis_locked))
+                        .filter(|w| !skip(w.id))
+                        .collect();
+                    for word in self.with_current_analysis(kind, words, |w| (w.id, &mut w.analysis, &mut w.prompt_version)).await? {
+                        let entry = format!("单词：{}\n假名：{}\n音调：{}\n词性：{}",
+                            word.word, word.kana, word.pitch, normalize_field(&word.part_of_speech).labels_in(Language::PRIMARY).join("、"));
+                        pending.push((kind, word.id, entry, word.analysis));
+                    }
+                }
+                EntryKind::Grammar => {
+                    let grammar: Vec<JapaneseGrammar> = self.db_manager.get_all_grammar().await?
+                        .into_iter()
+                        .filter(|g| !(primary && g.analysis_locked))
+                        .filter(|g| !skip(g.id))
+                        .collect();
+                    for item in self.with_current_analysis(kind, grammar, |g| (g.id, &mut g.analysis, &mut g.prompt_version)).await? {
+                        let entry = format!("语法：{}\n假名：{}", item.word, item.kana);
+                        pending.push((kind, item.id, entry, item.analysis));
+                    }
+                }
+            }
+        }
+        pending.retain(|(_, _, _, analysis)| !analysis.trim().is_empty());
+        
+        if pending.is_empty() {
+            println!("✅ 所有{}解析都已审查过", language.label());
+            return Ok(());
+        }
+        println!("🔍 用 {} 审查 {} 条{}解析...", model, pending.len(), language.label());
+        
+        let total = pending.len();
+        let results: Vec<(EntryKind, i64, String, Result<AnalysisReview>)> = self.scheduler
+            .run(Resource::Llm, pending.into_iter().enumerate(), |(i, (kind, id, entry, analysis))| async move {
+                println!("  🔍 审查 {}/{}: {} ID {}", i + 1, total, kind.label(), id);
+                let review = self.review_analysis(model, &entry, &analysis).await;
+                (kind, id, analysis, review)
+            })
+            .await;
+        
+        let mut failed = 0;
+        for (kind, id, html, review) in results {
+            match review {
+                Ok(review) => {
+                    if review.failure_reason(self.config.processing.review_min_score).is_some() {
+                        failed += 1;
+                    }
+                    self.record_validation(kind, id, &SanitizedHtml { html, issues: Vec::new(), review: Some(review) }).await?;
+                }
+                Err(e) => println!("  ❌ {} ID {} 审查失败: {}", kind.label(), id, e),
+            }
+        }
+        
+        println!("✅ 审查完成：{} 条未通过审查已标记待重新分析", failed);
+        if failed > 0 {
+            println!("💡 可以使用“重新分析旧版提示词生成或未通过校验的解析”重新生成被标记的解析");
+        }
+        Ok(())
+    }
+
+    // 手动编辑条目字段（解析在 $EDITOR 中编辑 HTML），编辑后自动锁定该字段
+    pub async fn edit_entry_field(&self, kind: EntryKind, id: i64, field: LockField) -> Result<()> {
+        if !field.applies_to(kind) {
+            println!("❌ {}没有{}字段", kind.label(), field.label());
+            return Ok(());
+        }
+        
+        let current = match kind {
+            EntryKind::Word => match self.db_manager.get_word_by_id(id).await? {
+                Some(word) => {
+                    println!("📝 找到单词: {} ({}) - {}", word.word, word.kana, word.part_of_speech);
+                    word.field_value(field).to_string()
+                },
+                None => {
+                    println!("❌ 未找到ID为 {} 的单词", id);
+                    return Ok(());
+                }
+            },
+            EntryKind::Grammar => match self.db_manager.get_grammar_by_id(id).await? {
+                Some(grammar) => {
+                    println!("📝 找到语法: {} ({})", grammar.word, grammar.kana);
+                    let aliases = self.db_manager.get_grammar_aliases(grammar.id).await?;
+                    if !aliases.is_empty() {
+                        println!("   其他写法: {}", aliases.join("、"));
+                    }
+                    match field {
+                        LockField::Analysis => grammar.analysis,
+                        _ => grammar.kana,
+                    }
+                },
+                None => {
+                    println!("❌ 未找到ID为 {} 的语法", id);
+                    return Ok(());
+                }
+            },
+        };
+        
+        let edited = match field {
+            LockField::Analysis => {
+                println!("✏️  正在打开编辑器...");
+                edit_in_editor(&current, "html")?.trim().to_string()
+            },
+            LockField::PartOfSpeech => {
+                let normalized = normalize_field(&prompt_line(field.label(), &current)?);
+                if !normalized.unrecognized.is_empty() {
+                    println!("⚠️  无法识别的词性将原样保存: {}", normalized.unrecognized.join("、"));
+                }
+                normalized.to_field()
+            },
+            _ => prompt_line(field.label(), &current)?,
+        };
+        
+        if edited.is_empty() {
+            println!("⚠️  内容为空，取消编辑");
+            return Ok(());
+        }
+        
+        if edited == current {
+            println!("✅ 内容无变化，跳过更新");
+            return Ok(());
+        }
+        
+        self.db_manager.update_entry_field(kind, id, field, &edited).await?;
+        println!("✅ {}已更新并锁定，批量更新将不再覆盖该字段", field.label());
+        
+        Ok(())
+    }
+
+    // 设置或解除条目字段的锁定
+    pub async fn set_entry_lock(&self, kind: EntryKind, id: i64, field: LockField, locked: bool) -> Result<()> {
+        self.db_manager.set_entry_lock(kind, id, field, locked).await?;
+        
+        if locked {
+            println!("🔒 已锁定{} ID {} 的{}", kind.label(), id, field.label());
+        } else {
+            println!("🔓 已解除{} ID {} 的{}锁定", kind.label(), id, field.label());
+        }
+        
+        Ok(())
+    }
+
+    // 从单词表 / CSV / Anki 牌组导入已掌握的单词，解析时会跳过这些单词
+    pub async fn import_known_words(&self, source: &KnownWordSource) -> Result<()> {
+        println!("📥 从 {} 读取已掌握的单词...", source.path());
+        let words = read_known_words(source).await?;
+        println!("📊 读取到 {} 个单词", words.len());
+        
+        let statuses = self.db_manager.get_entry_statuses(self.user_id, EntryKind::Word).await?;
+        let mut marked_count = 0;
+        let mut skipped_count = 0;
+        
+        for word in &words {
+            // 已经是排除状态的单词保持不变（例如黑名单）
+            if statuses.get(word).is_some_and(|s| s.is_excluded()) {
+                skipped_count += 1;
+                continue;
+            }
+            self.db_manager.set_entry_status(self.user_id, EntryKind::Word, word, EntryStatus::Known).await?;
+            marked_count += 1;
+        }
+        
+        println!("✅ 已为 {} 标记 {} 个已掌握单词，跳过 {} 个已掌握或忽略的单词",
+            self.user_name(), marked_count, skipped_count);
+        Ok(())
+    }
+
+    // 根据本地词频表和 JLPT 等级表标注所有单词和语法，返回更新的条目数
+    pub async fn annotate_levels(&self) -> Result<usize> {
+        let lists = LevelLists::load(&self.config.levels)?;
+        if lists.is_empty() {
+            return Ok(0);
+        }
+        
+        let mut updated_count = 0;
+        for word in self.db_manager.get_all_words().await? {
+            let (rank, level) = lists.word_levels(&word.word, &word.kana);
+            if rank != word.frequency_rank || level != word.jlpt_level {
+                self.db_manager.update_entry_levels(EntryKind::Word, word.id, rank, level.as_deref()).await?;
+                updated_count += 1;
+            }
+        }
+        for grammar in self.db_manager.get_all_grammar().await? {
+            let (rank, level) = lists.grammar_levels(&grammar.word);
+            if rank != grammar.frequency_rank || level != grammar.jlpt_level {
+                self.db_manager.update_entry_levels(EntryKind::Grammar, grammar.id, rank, level.as_deref()).await?;
+                updated_count += 1;
+            }
+        }
+        
+        Ok(updated_count)
+    }
+
+    // 词性统计：每个标准词性的单词数，以及无法识别的标签和使用它们的单词
+    pub async fn report_parts_of_speech(&self) -> Result<()> {
+        let words = self.db_manager.get_all_words().await?;
+        let mut counts: HashMap<PartOfSpeech, usize> = HashMap::new();
+        let mut unrecognized: Vec<(String, Vec<String>)> = Vec::new();
+        
+        for word in &words {
+            let normalized = normalize_field(&word.part_of_speech);
+            for pos in normalized.parts {
+                *counts.entry(pos).or_default() += 1;
+            }
+            for label in normalized.unrecognized {
+                match unrecognized.iter_mut().find(|(l, _)| *l == label) {
+                    Some((_, examples)) => examples.push(word.word.clone()),
+                    None => unrecognized.push((label, vec![word.word.clone()])),
+                }
+            }
+        }
+        
+        println!("📊 共 {} 个单词", words.len());
+        for pos in PartOfSpeech::ALL {
+            if let Some(count) = counts.get(&pos) {
+                println!("  {} ({}): {}", pos.label(), pos.key(), count);
+            }
+        }
+        
+        if unrecognized.is_empty() {
+            println!("✅ 所有词性标签都已识别");
+        } else {
+            println!("⚠️  无法识别的词性标签（可通过菜单 8 手动修改词性）：");
+            for (label, examples) in &unrecognized {
+                let shown: Vec<&str> = examples.iter().take(5).map(String::as_str).collect();
+                let more = if examples.len() > 5 { " …" } else { "" };
+                println!("  「{}」 {} 个单词: {}{}", label, examples.len(), shown.join("、"), more);
+            }
+        }
+        
+        Ok(())
+    }
+
+    // 全文搜索词库
+    pub async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        self.db_manager.search(query).await
+    }
+
+    // 导出整个知识库（单词、语法、出现记录和元数据）到 JSON / NDJSON
+    pub async fn export_knowledge_base(&self, path: &str) -> Result<()> {
+        let format = ExportFormat::from_path(path);
+        println!("📤 导出知识库到 {} ({:?})...", path, format);
+        
+        let words = self.db_manager.export_words().await?;
+        let grammar = self.db_manager.export_grammar().await?;
+        let occurrences = self.db_manager.export_occurrences().await?;
+        let statuses = self.db_manager.export_statuses().await?;
+        let analyses = self.db_manager.export_analyses().await?;
+        let word_details = self.db_manager.export_word_details().await?;
+        let grammar_details = self.db_manager.export_grammar_details().await?;
+        let kanji = self.db_manager.export_kanji().await?;
+        let sentences = self.db_manager.export_sentences().await?;
+        let review_cards = self.db_manager.export_review_cards().await?;
+        let review_log = self.db_manager.export_review_history().await?;
+        
+        let knowledge_base = KnowledgeBase {
+            metadata: ExportMetadata {
+                format_version: EXPORT_FORMAT_VERSION,
+                app_version: env!("CARGO_PKG_VERSION").to_string(),
+                exported_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                source_db: self.config.database.db_file.clone(),
+                word_count: words.len(),
+                grammar_count: grammar.len(),
+                occurrence_count: occurrences.len(),
+            },
+            words,
+            grammar,
+            occurrences,
+            statuses,
+            analyses,
+            word_details,
+            grammar_details,
+            kanji,
+            sentences,
+            review_cards,
+            review_log,
+        };
+        
+        knowledge_base.write_to_file(path, format)?;
+        
+        println!("✅ 导出完成：{} 个单词，{} 个语法，{} 条出现记录，{} 条学习状态，{} 条其他语言解析，{} 条结构化单词解析，{} 条结构化语法解析，{} 个汉字，{} 个例句，{} 张复习卡片，{} 条复习记录",
+            knowledge_base.metadata.word_count,
+            knowledge_base.metadata.grammar_count,
+            knowledge_base.metadata.occurrence_count,
+            knowledge_base.statuses.len(),
+            knowledge_base.analyses.len(),
+            knowledge_base.word_details.len(),
+            knowledge_base.grammar_details.len(),
+            knowledge_base.kanji.len(),
+            knowledge_base.sentences.len(),
+            knowledge_base.review_cards.len(),
+            knowledge_base.review_log.len()
+        );
+        Ok(())
+    }
+
+    // 从 JSON / NDJSON 导入并合并到当前数据库
+    pub async fn import_knowledge_base(&self, path: &str, policy: ConflictPolicy) -> Result<()> {
+        let format = ExportFormat::from_path(path);
+        println!("📥 从 {} 导入知识库 ({:?})...", path, format);
+        
+        let knowledge_base = KnowledgeBase::read_from_file(path, format)?;
+        println!("📊 导入文件来自 {}，导出时间 {}：{} 个单词，{} 个语法，{} 条出现记录",
+            knowledge_base.metadata.source_db,
+            knowledge_base.metadata.exported_at,
+            knowledge_base.words.len(),
+            knowledge_base.grammar.len(),
+            knowledge_base.occurrences.len()
+        );
+        
+        let mut word_stats = ImportStats::default();
+        for word in &knowledge_base.words {
+            word_stats.record(self.db_manager.import_word(word, policy).await?);
+        }
+        
+        let mut grammar_stats = ImportStats::default();
+        for grammar in &knowledge_base.grammar {
+            grammar_stats.record(self.db_manager.import_grammar(grammar, policy).await?);
+        }
+        
+        let mut occurrence_stats = ImportStats::default();
+        for occurrence in &knowledge_base.occurrences {
+            occurrence_stats.record(self.db_manager.import_occurrence(occurrence).await?);
+        }
+        
+        let mut status_stats = ImportStats::default();
+        for status in &knowledge_base.statuses {
+            status_stats.record(self.db_manager.import_status(status, policy).await?);
+        }
+        
+        // 汉字和例句关联的单词、语法需要先导入
+        let mut kanji_stats = ImportStats::default();
+        for kanji in &knowledge_base.kanji {
+            kanji_stats.record(self.db_manager.import_kanji(kanji, policy).await?);
+        }
+        let mut sentence_stats = ImportStats::default();
+        for sentence in &knowledge_base.sentences {
+            sentence_stats.record(self.db_manager.import_sentence(sentence, policy).await?);
+        }
+        
+        // 复习记录挂在卡片下，先导入卡片
+        let mut card_stats = ImportStats::default();
+        for card in &knowledge_base.review_cards {
+            card_stats.record(self.db_manager.import_review_card(card, policy).await?);
+        }
+        let mut review_stats = ImportStats::default();
+        for review in &knowledge_base.review_log {
+            review_stats.record(self.db_manager.import_review_history(review).await?);
+        }
+        
+        // 其他语言的解析依赖单词和语法，最后导入
+        let mut analysis_stats = ImportStats::default();
+        for analysis in &knowledge_base.analyses {
+            analysis_stats.record(self.db_manager.import_analysis(analysis, policy).await?);
+        }
+        let mut details_stats = ImportStats::default();
+        for details in &knowledge_base.word_details {
+            details_stats.record(self.db_manager.import_word_details(details, policy).await?);
+        }
+        let mut grammar_details_stats = ImportStats::default();
+        for details in &knowledge_base.grammar_details {
+            grammar_details_stats.record(self.db_manager.import_grammar_details(details, policy).await?);
+        }
+        
+        println!("✅ 导入完成：");
+        println!("   单词：新增 {}，更新 {}，跳过 {}", word_stats.inserted, word_stats.updated, word_stats.skipped);
+        println!("   语法：新增 {}，更新 {}，跳过 {}", grammar_stats.inserted, grammar_stats.updated, grammar_stats.skipped);
+        println!("   出现记录：新增 {}，跳过 {}", occurrence_stats.inserted, occurrence_stats.skipped);
+        println!("   学习状态：新增 {}，更新 {}，跳过 {}", status_stats.inserted, status_stats.updated, status_stats.skipped);
+        println!("   汉字：新增 {}，更新 {}，跳过 {}", kanji_stats.inserted, kanji_stats.updated, kanji_stats.skipped);
+        println!("   例句：新增 {}，更新 {}，跳过 {}", sentence_stats.inserted, sentence_stats.updated, sentence_stats.skipped);
+        println!("   复习卡片：新增 {}，更新 {}，跳过 {}", card_stats.inserted, card_stats.updated, card_stats.skipped);
+        println!("   复习记录：新增 {}，跳过 {}", review_stats.inserted, review_stats.skipped);
+        println!("   其他语言解析：新增 {}，更新 {}，跳过 {}", analysis_stats.inserted, analysis_stats.updated, analysis_stats.skipped);
+        println!("   结构化单词解析：新增 {}，更新 {}，跳过 {}", details_stats.inserted, details_stats.updated, details_stats.skipped);
+        println!("   结构化语法解析：新增 {}，更新 {}，跳过 {}", grammar_details_stats.inserted, grammar_details_stats.updated, grammar_details_stats.skipped);
+        Ok(())
+    }
+
+    // 第一步：提取单词和语法的基本信息
+    pub async fn extract_words_and_grammar(&self, text: &str) -> Result<ExtractionResult> {
+        let prompt = self.prompts.extraction_prompt(text);
+
+        let request = OpenRouterRequest {
+            model: self.config.api.models.extraction_model.clone(),
+            messages: vec![RequestMessage {
+                role: "user".to_string(),
+                content: prompt,
+            }],
+            max_tokens: self.config.api.max_tokens_for(ApiOperation::Extraction),
+            temperature: 0.1,
+        };
+
+        let api_response = self.api_client.make_request_with_retry(ApiOperation::Extraction, "输入文本", &request).await?;
+        let content = &api_response.choices[0].message.content;
+        
+        // 提取JSON部分
+        let json_start = content.find('{').unwrap_or(0);
+        let json_end = content.rfind('}').map(|i| i + 1).unwrap_or(content.len());
+        let json_content = &content[json_start..json_end];
+        
+        // 解析提取结果
+        let extraction: ExtractionResult = serde_json::from_str(json_content)
+            .map_err(|e| anyhow::anyhow!("解析提取结果失败: {}\n响应内容: {}", e, json_content))?;
+        self.api_client.cache_response(&request, &api_response).await;
+        
+        Ok(extraction)
+    }
+
+    // 第二步：详细分析单个单词（支持多词性），返回渲染后的解析和结构化解析；refresh 为 true 时不使用缓存的回复
+    pub async fn analyze_word_with_multiple_pos(&self, word: &BasicWordInfo, parts_of_speech: &[String], refresh: bool) -> Result<(SanitizedHtml, Option<WordDetails>)> {
+        let pos_labels = normalize_labels(parts_of_speech).labels_in(self.config.language.pos_labels);
+        let prompt = self.prompts.word_analysis_prompt(&self.config.language, &word.word, &word.kana, &word.pitch, &pos_labels);
+
+        let request = OpenRouterRequest {
+            model: self.config.api.models.word_analysis_model.clone(),
+            messages: vec![RequestMessage {
+                role: "user".to_string(),
+                content: prompt,
+            }],
+            max_tokens: self.config.api.max_tokens_for(ApiOperation::WordAnalysis),
+            temperature: 0.1,
+        };
+
+        let api_response = if refresh {
+            self.api_client.make_fresh_request(ApiOperation::WordAnalysis, &word.word, &request).await?
+        } else {
+            self.api_client.make_request_with_retry(ApiOperation::WordAnalysis, &word.word, &request).await?
+        };
+        let content = &api_response.choices[0].message.content;
+        
+        let entry = format!("单词：{}\n假名：{}\n音调：{}\n词性：{}",
+            word.word, word.kana, word.pitch, normalize_labels(parts_of_speech).labels_in(Language::PRIMARY).join("、"));
+        
+        // 自定义模板仍返回 HTML 时按原样清理保存
+        let Some(json_content) = json_object(content) else {
+            self.api_client.cache_response(&request, &api_response).await;
+            return Ok((self.with_review(&entry, sanitize_analysis(content)).await, None));
+        };
+        let mut details: WordDetails = serde_json::from_str(json_content)
+            .map_err(|e| anyhow::anyhow!("解析单词解析结果失败: {}\n响应内容: {}", e, json_content))?;
+        self.api_client.cache_response(&request, &api_response).await;
+        
+        // 清理每个字段中的 markdown 和不允许的标签，再渲染为 HTML 保存到 analysis（供搜索和手动编辑使用）
+        let issues = sanitize_word_details(&mut details);
+        let html = render_word_details(&word.word, &details, self.config.language.explanation);
+        Ok((self.with_review(&entry, SanitizedHtml { html, issues, review: None }).await, Some(details)))
+    }
+
+    // 第二步：详细分析单个语法，返回渲染后的解析和结构化解析；refresh 为 true 时不使用缓存的回复
+    pub async fn analyze_grammar(&self, grammar: &BasicGrammarInfo, refresh: bool) -> Result<(SanitizedHtml, Option<GrammarDetails>)> {
+        let prompt = self.prompts.grammar_analysis_prompt(&self.config.language, &grammar.grammar, &grammar.kana);
+
+        let request = OpenRouterRequest {
+            model: self.config.api.models.grammar_analysis_model.clone(),
+            messages: vec![RequestMessage {
+                role: "user".to_string(),
+                content: prompt,
+            }],
+            max_tokens: self.config.api.max_tokens_for(ApiOperation::GrammarAnalysis),
+            temperature: 0.1,
+        };
+
+        let api_response = if refresh {
+            self.api_client.make_fresh_request(ApiOperation::GrammarAnalysis, &grammar.grammar, &request).await?
+        } else {
+            self.api_client.make_request_with_retry(ApiOperation::GrammarAnalysis, &grammar.grammar, &request).await?
+        };
+        let content = &api_response.choices[0].message.content;
+        
+        let entry = format!("语法：{}\n假名：{}", grammar.grammar, grammar.kana);
+        
+        // 自定义模板仍返回 HTML 时按原样清理保存
+        let Some(json_content) = json_object(content) else {
+            self.api_client.cache_response(&request, &api_response).await;
+            return Ok((self.with_review(&entry, sanitize_analysis(content)).await, None));
+        };
+        let mut details: GrammarDetails = serde_json::from_str(json_content)
+            .map_err(|e| anyhow::anyhow!("解析语法解析结果失败: {}\n响应内容: {}", e, json_content))?;
+        self.api_client.cache_response(&request, &api_response).await;
+        
+        let issues = sanitize_grammar_details(&mut details);
+        // 相关语法统一为规范写法，便于关联到已收录的条目
+        for related in &mut details.related {
+            related.grammar = canonical_grammar(&related.grammar);
+        }
+        let html = render_grammar_details(&grammar.grammar, &details, self.config.language.explanation);
+        Ok((self.with_review(&entry, SanitizedHtml { html, issues, review: None }).await, Some(details)))
+    }
+
+    // 配置了审查模型时交给审查模型检查解析；未通过格式校验的解析已经会被重新分析，不再审查
+    // 审查请求失败时只给出提示，不影响解析的保存
+    async fn with_review(&self, entry: &str, mut analysis: SanitizedHtml) -> SanitizedHtml {
+        let Some(model) = &self.config.api.models.review_model else {
+            return analysis;
+        };
+        if analysis.failure_reason().is_some() {
+            return analysis;
+        }
+        match self.review_analysis(model, entry, &analysis.html).await {
+            Ok(review) => analysis.review = Some(review),
+            Err(e) => println!("    ⚠️  审查解析失败: {}", e),
+        }
+        analysis
+    }
+
+    // 让审查模型检查解析中的读音、音调、词性、释义和例句，返回分数和发现的问题
+    async fn review_analysis(&self, model: &str, entry: &str, html: &str) -> Result<AnalysisReview> {
+        let prompt = self.prompts.analysis_review_prompt(entry, &strip_html(html));
+
+        let request = OpenRouterRequest {
+            model: model.to_string(),
+            messages: vec![RequestMessage {
+                role: "user".to_string(),
+                content: prompt,
+            }],
+            max_tokens: self.config.api.max_tokens_for(ApiOperation::AnalysisReview),
+            temperature: 0.0,
+        };
+
+        let api_response = self.api_client.make_request_with_retry(ApiOperation::AnalysisReview, entry.lines().next().unwrap_or(entry), &request).await?;
+        let content = &api_response.choices[0].message.content;
+        let json_content = json_object(content)
+            .ok_or_else(|| anyhow::anyhow!("审查结果不是JSON\n响应内容: {}", content))?;
+        let review: AnalysisReview = serde_json::from_str(json_content)
+            .map_err(|e| anyhow::anyhow!("解析审查结果失败: {}\n响应内容: {}", e, json_content))?;
+        self.api_client.cache_response(&request, &api_response).await;
+        
+        Ok(review)
+    }
+
+    // 当前配置的解析语言是否为主语言（即 words / grammar 表自身的 analysis 列）
+    fn explains_in_primary_language(&self) -> bool {
+        self.config.language.explanation == Language::PRIMARY
+    }
+
+    // 当前解析语言下已有的解析；主语言返回 None，直接使用条目自身的解析
+    async fn localized_analyses(&self, kind: EntryKind) -> Result<Option<HashMap<i64, String>>> {
+        if self.explains_in_primary_language() {
+            return Ok(None);
+        }
+        let analyses = self.db_manager
+            .get_entry_analyses(kind, self.config.language.explanation.code())
+            .await?;
+        Ok(Some(analyses))
+    }
+
+    // 当前解析语言下某种提示词模板的版本
+    fn prompt_version(&self, kind: PromptKind) -> i64 {
+        self.prompts.version(kind, self.config.language.explanation)
+    }
+
+    // 按当前解析语言保存解析，并记录所用的提示词版本
+    async fn store_analysis(&self, kind: EntryKind, id: i64, analysis: &str) -> Result<()> {
+        let prompt_kind = match kind {
+            EntryKind::Word => PromptKind::WordAnalysis,
+            EntryKind::Grammar => PromptKind::GrammarAnalysis,
+        };
+        let version = self.prompt_version(prompt_kind);
+        if !self.explains_in_primary_language() {
+            return self.db_manager
+                .save_entry_analysis(kind, id, self.config.language.explanation.code(), analysis, version)
+                .await;
+        }
+        match kind {
+            EntryKind::Word => self.db_manager.update_word_analysis(id, analysis, version).await,
+            EntryKind::Grammar => self.db_manager.update_grammar_analysis(id, analysis, version).await,
+        }
+    }
+
+    // 按当前解析语言保存结构化单词解析，None（模板返回 HTML）时删除旧的结构化解析
+    async fn store_word_details(&self, id: i64, details: Option<&WordDetails>) -> Result<()> {
+        self.db_manager
+            .save_word_details(id, self.config.language.explanation.code(), details)
+            .await
+    }
+
+    // 按当前解析语言保存结构化语法解析，None 时删除旧的结构化解析
+    async fn store_grammar_details(&self, id: i64, details: Option<&GrammarDetails>) -> Result<()> {
+        self.db_manager
+            .save_grammar_details(id, self.config.language.explanation.code(), details)
+            .await
+    }
+
+    // 记录解析的校验和审查结果：未通过的标记为待重新分析，通过时清除之前的标记
+    async fn record_validation(&self, kind: EntryKind, id: i64, analysis: &SanitizedHtml) -> Result<()> {
+        let language = self.config.language.explanation.code();
+        if let (Some(review), Some(model)) = (&analysis.review, &self.config.api.models.review_model) {
+            self.db_manager.save_analysis_review(kind, id, language, review, model).await?;
+        }
+        
+        let reason = analysis.failure_reason();
+        if let Some(reason) = &reason {
+            println!("    ⚠️  {}解析未通过校验，已标记待重新分析: {}", kind.label(), reason);
+        }
+        let reason = reason.or_else(|| {
+            let reason = analysis.review.as_ref()?.failure_reason(self.config.processing.review_min_score)?;
+            println!("    ⚠️  {}解析未通过审查，已标记待重新分析: {}", kind.label(), reason);
+            Some(reason)
+        });
+        self.db_manager
+            .set_analysis_flag(kind, id, language, reason.as_deref())
+            .await
+    }
+
+    // 其他解析语言下，把条目的解析和提示词版本换成该语言已有的（没有时为空和 0），用于比较是否需要更新
+    async fn with_current_analysis<T>(&self, kind: EntryKind, mut entries: Vec<T>, fields_of: impl Fn(&mut T) -> (i64, &mut String, &mut i64)) -> Result<Vec<T>> {
+        let Some(analyses) = self.localized_analyses(kind).await? else {
+            return Ok(entries);
+        };
+        let versions = self.db_manager
+            .get_entry_analysis_versions(kind, self.config.language.explanation.code())
+            .await?;
+        for entry in &mut entries {
+            let (id, analysis, version) = fields_of(entry);
+            *analysis = analyses.get(&id).cloned().unwrap_or_default();
+            *version = versions.get(&id).copied().unwrap_or(0);
+        }
+        Ok(entries)
+    }
+
+    // 把条目的解析替换为当前解析语言的版本，没有该语言解析的条目保留主语言解析
+    async fn localize<T>(&self, kind: EntryKind, mut entries: Vec<T>, analysis_of: impl Fn(&mut T) -> (i64, &mut String)) -> Result<Vec<T>> {
+        let Some(analyses) = self.localized_analyses(kind).await? else {
+            return Ok(entries);
+        };
+        let mut missing = 0;
+        for entry in &mut entries {
+            let (id, analysis) = analysis_of(entry);
+            match analyses.get(&id) {
+                Some(localized) if !localized.is_empty() => *analysis = localized.clone(),
+                _ => missing += 1,
+            }
+        }
+        if missing > 0 {
+            println!("ℹ️  {} 个{}没有{}解析，使用{}解析",
+                missing, kind.label(), self.config.language.explanation.label(), Language::PRIMARY.label());
+        }
+        Ok(entries)
+    }
+
+    // 当前学习者要学习的单词：排除已掌握/忽略和不满足等级筛选的条目，按学习计划排序
+    async fn study_words(&self) -> Result<Vec<JapaneseWord>> {
+        let statuses = self.db_manager.get_entry_statuses(self.user_id, EntryKind::Word).await?;
+        let words: Vec<JapaneseWord> = self.db_manager.get_all_words().await?
+            .into_iter()
+            .filter(|w| !statuses.get(&w.word).is_some_and(|s| s.is_excluded()))
+            .filter(|w| passes_filter(&self.config.levels, w.frequency_rank, w.jlpt_level.as_deref()))
+            .collect();
+        let words = self.localize(EntryKind::Word, words, |w| (w.id, &mut w.analysis)).await?;
+        
+        let occurrences = self.db_manager.get_occurrence_counts(EntryKind::Word).await?;
+        let items: Vec<PlanItem> = words.iter().map(|w| PlanItem {
+            id: w.id,
+            word: &w.word,
+            frequency_rank: w.frequency_rank,
+            jlpt_level: w.jlpt_level.as_deref(),
+            occurrences: occurrences.get(&w.id).copied().unwrap_or(0),
+        }).collect();
+        let order = plan_order(&items, &self.config.study_plan);
+        Ok(order_by_ids(words, &order, |w| w.id))
+    }
+
+    // 当前学习者要学习的语法，规则同单词
+    async fn study_grammar(&self) -> Result<Vec<JapaneseGrammar>> {
+        let statuses = self.db_manager.get_entry_statuses(self.user_id, EntryKind::Grammar).await?;
+        let grammar: Vec<JapaneseGrammar> = self.db_manager.get_all_grammar().await?
+            .into_iter()
+            .filter(|g| !statuses.get(&g.word).is_some_and(|s| s.is_excluded()))
+            .filter(|g| passes_filter(&self.config.levels, g.frequency_rank, g.jlpt_level.as_deref()))
+            .collect();
+        let grammar = self.localize(EntryKind::Grammar, grammar, |g| (g.id, &mut g.analysis)).await?;
+        
+        let occurrences = self.db_manager.get_occurrence_counts(EntryKind::Grammar).await?;
+        let items: Vec<PlanItem> = grammar.iter().map(|g| PlanItem {
+            id: g.id,
+            word: &g.word,
+            frequency_rank: g.frequency_rank,
+            jlpt_level: g.jlpt_level.as_deref(),
+            occurrences: occurrences.get(&g.id).copied().unwrap_or(0),
+        }).collect();
+        let order = plan_order(&items, &self.config.study_plan);
+        Ok(order_by_ids(grammar, &order, |g| g.id))
+    }
+
+    // 生成单词 Anki 卡片（按学习计划排序，Anki 按导入顺序引入新卡片）
+    pub async fn generate_word_cards(&self) -> Result<()> {
+        let words = self.study_words().await?;
+        let language = self.config.language.explanation;
+        let mut details = self.db_manager.get_word_details(language.code()).await?;
+        // 手动编辑并锁定的解析以保存的 HTML 为准
+        if self.explains_in_primary_language() {
+            for word in words.iter().filter(|w| w.analysis_locked) {
+                details.remove(&word.id);
+            }
+        }
+        generate_word_cards(&words, &details, &self.config.output.words_file, self.config.language.pos_labels, language)?;
+        
+        let conjugation_config = &self.config.conjugation;
+        if conjugation_config.drill_cards {
+            let mut forms = Vec::new();
+            let mut unknown = Vec::new();
+            for key in &conjugation_config.drill_forms {
+                match ConjugationForm::from_key(key) {
+                    Some(form) => forms.push(form),
+                    None => unknown.push(key.as_str()),
+                }
+            }
+            // 未知的活用形直接报错，避免全部写错时空列表被当作"练习所有活用形"
+            if !unknown.is_empty() {
+                let known: Vec<&str> = ConjugationForm::ALL.iter().map(|f| f.key()).collect();
+                return Err(anyhow::anyhow!(
+                    "未知的活用形: {}（可用: {}）",
+                    unknown.join(", "),
+                    known.join(", ")
+                ));
+            }
+            generate_conjugation_cards(&words, &forms, &self.config.output.conjugation_file)?;
+        }
+        Ok(())
+    }
+
+    // 生成语法 Anki 卡片  
+    pub async fn generate_grammar_cards(&self) -> Result<()> {
+        let grammar = self.study_grammar().await?;
+        let language = self.config.language.explanation;
+        let mut details = self.db_manager.get_grammar_details(language.code()).await?;
+        // 手动编辑并锁定的解析以保存的 HTML 为准
+        if self.explains_in_primary_language() {
+            for item in grammar.iter().filter(|g| g.analysis_locked) {
+                details.remove(&item.id);
+            }
+        }
+        generate_grammar_cards(&grammar, &details, &self.config.output.grammar_file, language)?;
+        Ok(())
+    }
 
-        let api_response = self.api_client.make_request_with_retry(&request).await?;
-        let content = &api_response.choices[0].message.content;
+    // 更新汉字库（从单词中收集汉字并关联），可选用 AI 生成记忆口诀，然后生成汉字卡片
+    pub async fn update_kanji(&self, generate_mnemonics: bool) -> Result<()> {
+        let dictionary = KanjiDictionary::load(
+            self.config.kanji.kanjidic_file.as_deref(),
+            self.config.kanji.kradfile.as_deref(),
+        )?;
+        if dictionary.is_empty() {
+            println!("⚠️  未配置 [kanji] 数据文件，汉字卡片只包含汉字本身和例词");
+        } else {
+            println!("📚 已加载 {} 个汉字的数据", dictionary.len());
+        }
         
-        // 提取JSON部分
-        let json_start = content.find('{').unwrap_or(0);
-        let json_end = content.rfind('}').map(|i| i + 1).unwrap_or(content.len());
-        let json_content = &content[json_start..json_end];
+        let words = self.db_manager.get_all_words().await?;
+        let collected = collect_kanji(&words);
+        println!("🔍 单词中共出现 {} 个汉字", collected.len());
         
-        // 解析提取结果
-        let extraction: ExtractionResult = serde_json::from_str(json_content)
-            .map_err(|e| anyhow::anyhow!("解析提取结果失败: {}\n响应内容: {}", e, json_content))?;
+        for (character, word_ids) in &collected {
+            let kanji_id = self.db_manager.save_kanji(&dictionary.lookup(*character)).await?;
+            self.db_manager.link_kanji_words(kanji_id, word_ids).await?;
+        }
         
-        Ok(extraction)
+        if generate_mnemonics {
+            let pending: Vec<Kanji> = self.db_manager.get_all_kanji().await?
+                .into_iter()
+                .filter(|k| k.mnemonic.is_empty())
+                .collect();
+            println!("🤖 为 {} 个汉字生成记忆口诀...", pending.len());
+            
+            let total = pending.len();
+            let saved: Vec<Result<()>> = self.scheduler.run(Resource::Llm, pending.iter().enumerate(), |(index, kanji)| async move {
+                match self.generate_kanji_mnemonic(kanji).await {
+                    Ok(mnemonic) => {
+                        let _write = self.scheduler.acquire(Resource::DbWrite).await;
+                        self.db_manager.update_kanji_mnemonic(kanji.id, &mnemonic).await?;
+                        println!("✅ [{}/{}] {}", index + 1, total, kanji.character);
+                    }
+                    Err(e) => println!("❌ [{}/{}] {} 生成失败: {}", index + 1, total, kanji.character, e),
+                }
+                Ok(())
+            }).await;
+            saved.into_iter().collect::<Result<Vec<()>>>()?;
+        }
+        
+        self.generate_kanji_cards().await
     }
 
-    // 第二步：详细分析单个单词（支持多词性）
-    pub async fn analyze_word_with_multiple_pos(&self, word: &BasicWordInfo, parts_of_speech: &[String]) -> Result<String> {
-        let pos_list = parts_of_speech.join("、");
-        let prompt = format!(r#"
-请分析这个日语单词的用法，以纯HTML格式回复，参考以下示例格式：
-
-示例（单词：帯，假名：おび，音调：obi，词性：名词）：
-<div>「帯」（おび、obi）是一个日语名词，意思是<b>"腰带"、"带子"或"地带"</b>。它是一个非常通用的词，根据不同的语境有不同的含义，但核心都与"带状物"或"区域"有关。</div>
-<hr>
-<div>1. 服饰上的"腰带" 👘<br>
-这是最常见、最核心的用法。特指系在和服、浴衣等传统日本服饰上的宽腰带。<br>
-例： 帯を締める (obi o shimeru) - 系腰带。<br>
-例： 着物と帯 (kimono to obi) - 和服和腰带。<br><br>
-2. "地带"、"区域" 🗺️<br>
-带有比喻色彩，指某个具有特定特征的带状区域。<br>
-例： 台風の帯 (taifū no obi) - 台风带。<br>
-例： 火山帯 (kazan tai) - 火山带。</div>
-<hr>
-<div>「帯」这个汉字本身就带有<b>"束缚"、"捆绑"或"带状"</b>的含义。在日语中，它完美地保留了这些核心概念，从具体的服饰腰带到抽象的地理区域，都用这个词来表达。不同语境下，重点会从具体的物理对象转向抽象的概念性区域。</div>
-<hr>
-<div>总的来说，「帯」的核心概念是<b>"带状物"或"带状区域"</b>，它可以指实际的物品，也可以指抽象的概念。不同语境下，重点会从具体的物理对象转向抽象的概念性区域。</div>
-<hr>
-<div><b>词汇对比：</b><br><br>
-<b>「帯」vs「ベルト」：</b> 「ベルト」是外来词，多指现代服饰的皮带，而「帯」更偏向传统文化，如和服腰带。<br><br>
-<b>「帯」vs「紐」：</b> 「紐」通常指细绳、细带，「帯」则指较宽的带状物，且更正式。<br><br>
-<b>「帯」vs「バンド」：</b> 「バンド」多用于技术或医疗领域（如频段、绷带），「帯」更多用于地理和服饰领域。</div>
-
-现在请按照上述格式分析：
-
-单词：{}
-假名：{}
-音调：{}
-词性：{}
-
-重要事项：
-1. 直接回复HTML内容，不要使用markdown代码块格式
-2. 不要添加```html```标记
-3. 粗体使用<b></b>标签，绝不要使用**符号
-4. 如果有多个词性，请全面分析所有词性的用法
-5. 不要重复模板化的标题
-"#, word.word, word.kana, word.pitch, pos_list);
+    // 让 AI 根据汉字的部件和意思编写简短的记忆口诀
+    async fn generate_kanji_mnemonic(&self, kanji: &Kanji) -> Result<String> {
+        let prompt = self.prompts.kanji_mnemonic_prompt(&self.config.language, kanji);
 
         let request = OpenRouterRequest {
             model: self.config.api.models.word_analysis_model.clone(),
@@ -406,62 +1314,465 @@ impl AnkiCreator {
                 role: "user".to_string(),
                 content: prompt,
             }],
-            max_tokens: 100000,
-            temperature: 0.1,
+            max_tokens: self.config.api.max_tokens_for(ApiOperation::KanjiMnemonic),
+            temperature: 0.3,
         };
 
-        let api_response = self.api_client.make_request_with_retry(&request).await?;
-        let analysis = &api_response.choices[0].message.content.trim();
+        let api_response = self.api_client.make_request_with_retry(ApiOperation::KanjiMnemonic, &kanji.character.to_string(), &request).await?;
+        let mnemonic = api_response.choices[0].message.content.trim();
+        if mnemonic.is_empty() {
+            return Err(anyhow::anyhow!("记忆口诀为空"));
+        }
+        self.api_client.cache_response(&request, &api_response).await;
         
-        Ok(analysis.to_string())
+        Ok(mnemonic.to_string())
     }
 
-    // 第二步：详细分析单个语法
-    pub async fn analyze_grammar(&self, grammar: &BasicGrammarInfo) -> Result<String> {
-        let prompt = format!(r#"
-请详细分析这个日语语法点：
+    // 生成汉字 Anki 卡片：只包含当前学习者要学习的单词中的汉字，按这些单词的学习顺序排列
+    pub async fn generate_kanji_cards(&self) -> Result<()> {
+        let study_words = self.study_words().await?;
+        let order: HashMap<char, usize> = collect_kanji(&study_words)
+            .into_iter()
+            .enumerate()
+            .map(|(index, (character, _))| (character, index))
+            .collect();
+        
+        let mut kanji: Vec<Kanji> = self.db_manager.get_all_kanji().await?
+            .into_iter()
+            .filter(|k| k.character.chars().next().is_some_and(|c| order.contains_key(&c)))
+            .collect();
+        kanji.sort_by_key(|k| k.character.chars().next().and_then(|c| order.get(&c).copied()));
+        
+        // 例词同样按学习顺序排列
+        let words_by_id: HashMap<i64, &JapaneseWord> = study_words.iter().map(|w| (w.id, w)).collect();
+        let position: HashMap<i64, usize> = study_words.iter().enumerate().map(|(i, w)| (w.id, i)).collect();
+        let mut examples: HashMap<i64, Vec<&JapaneseWord>> = HashMap::new();
+        for (kanji_id, word_id) in self.db_manager.get_kanji_word_links().await? {
+            if let Some(word) = words_by_id.get(&word_id) {
+                examples.entry(kanji_id).or_default().push(word);
+            }
+        }
+        for list in examples.values_mut() {
+            list.sort_by_key(|w| position.get(&w.id).copied());
+        }
+        
+        generate_kanji_cards(&kanji, &examples, &self.config.output.kanji_file)?;
+        Ok(())
+    }
 
-语法：{}
-假名：{}
+    // 更新例句库（解析中的例句和输入文本中的句子），可选用 AI 补全读音和翻译，然后生成例句卡片
+    pub async fn update_sentences(&self, translate: bool) -> Result<()> {
+        let document = &self.config.input.text_file;
+        let text = match std::fs::read_to_string(document) {
+            Ok(text) => Some(text),
+            Err(e) => {
+                println!("⚠️  无法读取输入文件 '{}'，只收集解析中的例句: {}", document, e);
+                None
+            }
+        };
+        self.collect_sentences(text.as_deref(), &[], &[]).await?;
+        
+        if translate {
+            let no_translation = self.config.language.translation == Language::Japanese;
+            let pending: Vec<Sentence> = self.db_manager.get_all_sentences().await?
+                .into_iter()
+                .filter(|s| s.reading.is_empty() || (s.translation.is_empty() && !no_translation))
+                .collect();
+            println!("🤖 为 {} 个例句补全读音和翻译...", pending.len());
+            
+            let total_batches = pending.len().div_ceil(SENTENCE_BATCH_SIZE);
+            let saved: Vec<Result<()>> = self.scheduler.run(Resource::Llm, pending.chunks(SENTENCE_BATCH_SIZE).enumerate(), |(index, batch)| async move {
+                match self.translate_sentences(batch).await {
+                    Ok(translated) => {
+                        let _write = self.scheduler.acquire(Resource::DbWrite).await;
+                        for (sentence, result) in batch.iter().zip(translated) {
+                            let reading = if sentence.reading.is_empty() { result.reading.trim() } else { &sentence.reading };
+                            let translation = if sentence.translation.is_empty() { result.translation.trim() } else { &sentence.translation };
+                            self.db_manager.update_sentence_translation(sentence.id, reading, translation).await?;
+                        }
+                        println!("✅ [{}/{}] 完成 {} 个例句", index + 1, total_batches, batch.len());
+                    }
+                    Err(e) => println!("❌ [{}/{}] 翻译失败: {}", index + 1, total_batches, e),
+                }
+                Ok(())
+            }).await;
+            saved.into_iter().collect::<Result<Vec<()>>>()?;
+        }
+        
+        self.generate_sentence_cards().await
+    }
 
-请提供：
-1. 详细的中文解释
-2. 语法功能和意义
-3. 使用场合和语境
-4. 接续方法（前后可以接什么）
-5. 用法例句和注意点
-6. 相似语法的区别
+    // 把解析中的例句和输入文本中的句子保存到例句库，并关联句子中出现的单词和语法
+    // 已有的例句和条目在之前的运行中已经关联过，只需关联新增的例句和本次解析的条目（new_words/new_grammar）
+    async fn collect_sentences(&self, text: Option<&str>, new_words: &[i64], new_grammar: &[i64]) -> Result<()> {
+        let existing: HashSet<i64> = self.db_manager.get_all_sentences().await?.iter().map(|s| s.id).collect();
+        let language = self.config.language.explanation.code();
+        let words = self.localize(EntryKind::Word, self.db_manager.get_all_words().await?, |w| (w.id, &mut w.analysis)).await?;
+        let grammar = self.localize(EntryKind::Grammar, self.db_manager.get_all_grammar().await?, |g| (g.id, &mut g.analysis)).await?;
+        let word_details = self.db_manager.get_word_details(language).await?;
+        let grammar_details = self.db_manager.get_grammar_details(language).await?;
+        let mut link_count = 0;
+        
+        // 有结构化解析的条目直接使用其中的例句，否则从 HTML 解析中取出
+        for word in &words {
+            let examples = match word_details.get(&word.id) {
+                Some(details) => details.senses.iter().flat_map(|sense| sense.examples.clone()).collect(),
+                None => examples_in_html(&word.analysis),
+            };
+            for example in &examples {
+                let id = self.db_manager.save_sentence(example, SentenceSource::Example, None).await?;
+                if self.db_manager.link_sentence(id, EntryKind::Word, word.id).await? {
+                    link_count += 1;
+                }
+            }
+        }
+        for item in &grammar {
+            let examples = match grammar_details.get(&item.id) {
+                Some(details) => details.examples.clone(),
+                None => examples_in_html(&item.analysis),
+            };
+            for example in &examples {
+                let id = self.db_manager.save_sentence(example, SentenceSource::Example, None).await?;
+                if self.db_manager.link_sentence(id, EntryKind::Grammar, item.id).await? {
+                    link_count += 1;
+                }
+            }
+        }
+        
+        if let Some(text) = text {
+            let document = &self.config.input.text_file;
+            for japanese in split_sentences(text) {
+                let sentence = ExampleSentence { japanese, ..Default::default() };
+                self.db_manager.save_sentence(&sentence, SentenceSource::Input, Some(document)).await?;
+            }
+        }
+        
+        // 按内容关联例句中出现的单词和语法：新例句和所有条目比较，已有例句只和本次解析的条目比较
+        let sentences = self.db_manager.get_all_sentences().await?;
+        let new_words: HashSet<i64> = new_words.iter().copied().collect();
+        let new_grammar: HashSet<i64> = new_grammar.iter().copied().collect();
+        let all_words: Vec<&JapaneseWord> = words.iter().collect();
+        let all_grammar: Vec<&JapaneseGrammar> = grammar.iter().collect();
+        let fresh_words: Vec<&JapaneseWord> = words.iter().filter(|w| new_words.contains(&w.id)).collect();
+        let fresh_grammar: Vec<&JapaneseGrammar> = grammar.iter().filter(|g| new_grammar.contains(&g.id)).collect();
+        let mut new_count = 0;
+        for sentence in &sentences {
+            let (candidate_words, candidate_grammar) = if existing.contains(&sentence.id) {
+                (&fresh_words, &fresh_grammar)
+            } else {
+                new_count += 1;
+                (&all_words, &all_grammar)
+            };
+            for word in candidate_words.iter().filter(|w| contains_word(&sentence.japanese, w)) {
+                if self.db_manager.link_sentence(sentence.id, EntryKind::Word, word.id).await? {
+                    link_count += 1;
+                }
+            }
+            for item in candidate_grammar.iter().filter(|g| contains_grammar(&sentence.japanese, &g.word)) {
+                if self.db_manager.link_sentence(sentence.id, EntryKind::Grammar, item.id).await? {
+                    link_count += 1;
+                }
+            }
+        }
+        println!("📝 例句库共 {} 个例句（新增 {} 个），新关联了 {} 处单词和语法", sentences.len(), new_count, link_count);
+        Ok(())
+    }
 
-请只返回详细的中文分析内容，不需要JSON格式。
-"#, grammar.grammar, grammar.kana);
+    // 让 AI 为一批例句标注读音并翻译，返回结果与输入一一对应（按原句匹配，匹配不到时按顺序）
+    async fn translate_sentences(&self, batch: &[Sentence]) -> Result<Vec<ExampleSentence>> {
+        let lines: Vec<String> = batch.iter().map(|s| s.japanese.clone()).collect();
+        let prompt = self.prompts.sentence_translation_prompt(&self.config.language, &lines);
 
         let request = OpenRouterRequest {
-            model: self.config.api.models.grammar_analysis_model.clone(),
+            model: self.config.api.models.word_analysis_model.clone(),
             messages: vec![RequestMessage {
                 role: "user".to_string(),
                 content: prompt,
             }],
-            max_tokens: 100000,
+            max_tokens: self.config.api.max_tokens_for(ApiOperation::SentenceTranslation),
             temperature: 0.1,
         };
 
-        let api_response = self.api_client.make_request_with_retry(&request).await?;
-        let analysis = &api_response.choices[0].message.content.trim();
+        let api_response = self.api_client.make_request_with_retry(ApiOperation::SentenceTranslation, &format!("{} 个例句", batch.len()), &request).await?;
+        let content = &api_response.choices[0].message.content;
+        let json_content = json_object(content)
+            .ok_or_else(|| anyhow::anyhow!("例句翻译结果不是JSON\n响应内容: {}", content))?;
+        let result: SentenceTranslationResult = serde_json::from_str(json_content)
+            .map_err(|e| anyhow::anyhow!("解析例句翻译结果失败: {}\n响应内容: {}", e, json_content))?;
+        self.api_client.cache_response(&request, &api_response).await;
         
-        Ok(analysis.to_string())
+        Ok(batch.iter().enumerate().map(|(index, sentence)| {
+            result.sentences.iter()
+                .find(|r| r.japanese.trim() == sentence.japanese)
+                .or_else(|| result.sentences.get(index))
+                .cloned()
+                .unwrap_or_default()
+        }).collect())
     }
 
-    // 生成单词 Anki 卡片
-    pub async fn generate_word_cards(&self) -> Result<()> {
-        let words = self.db_manager.get_all_words().await?;
-        generate_word_cards(&words, &self.config.output.words_file)?;
+    // 生成例句 Anki 卡片：只包含出现了当前学习者要学习的单词或语法的例句，按其中最早学习的条目排序
+    pub async fn generate_sentence_cards(&self) -> Result<()> {
+        let study_words = self.study_words().await?;
+        let study_grammar = self.study_grammar().await?;
+        let word_position: HashMap<i64, usize> = study_words.iter().enumerate().map(|(i, w)| (w.id, i)).collect();
+        let grammar_position: HashMap<i64, usize> = study_grammar.iter().enumerate().map(|(i, g)| (g.id, i)).collect();
+        let words_by_id: HashMap<i64, &JapaneseWord> = study_words.iter().map(|w| (w.id, w)).collect();
+        let grammar_by_id: HashMap<i64, &JapaneseGrammar> = study_grammar.iter().map(|g| (g.id, g)).collect();
+        
+        let mut words: HashMap<i64, Vec<&JapaneseWord>> = HashMap::new();
+        let mut grammar: HashMap<i64, Vec<&JapaneseGrammar>> = HashMap::new();
+        let mut order: HashMap<i64, usize> = HashMap::new();
+        for (sentence_id, kind, entry_id) in self.db_manager.get_sentence_links().await? {
+            let position = match EntryKind::parse(&kind) {
+                Some(EntryKind::Word) => words_by_id.get(&entry_id).map(|word| {
+                    words.entry(sentence_id).or_default().push(word);
+                    word_position[&entry_id]
+                }),
+                Some(EntryKind::Grammar) => grammar_by_id.get(&entry_id).map(|item| {
+                    grammar.entry(sentence_id).or_default().push(item);
+                    grammar_position[&entry_id]
+                }),
+                None => None,
+            };
+            if let Some(position) = position {
+                let earliest = order.entry(sentence_id).or_insert(position);
+                *earliest = (*earliest).min(position);
+            }
+        }
+        for list in words.values_mut() {
+            list.sort_by_key(|w| word_position.get(&w.id).copied());
+        }
+        for list in grammar.values_mut() {
+            list.sort_by_key(|g| grammar_position.get(&g.id).copied());
+        }
+        
+        let mut sentences: Vec<Sentence> = self.db_manager.get_all_sentences().await?
+            .into_iter()
+            .filter(|s| order.contains_key(&s.id))
+            .collect();
+        sentences.sort_by_key(|s| order.get(&s.id).copied());
+        
+        generate_sentence_cards(&sentences, &words, &grammar, &self.config.output.sentences_file)?;
         Ok(())
     }
 
-    // 生成语法 Anki 卡片  
-    pub async fn generate_grammar_cards(&self) -> Result<()> {
-        let grammar = self.db_manager.get_all_grammar().await?;
-        generate_grammar_cards(&grammar, &self.config.output.grammar_file)?;
+    // 列出包含某个单词或语法的所有例句，query 为条目ID或单词/语法本身
+    pub async fn show_sentences_for_entry(&self, kind: EntryKind, query: &str) -> Result<()> {
+        let entry = match kind {
+            EntryKind::Word => {
+                let words = self.db_manager.get_all_words().await?;
+                match query.parse::<i64>() {
+                    Ok(id) => words.into_iter().find(|w| w.id == id),
+                    Err(_) => words.into_iter().find(|w| w.word == query || w.kana == query),
+                }
+                .map(|w| (w.id, w.word))
+            }
+            EntryKind::Grammar => match query.parse::<i64>() {
+                Ok(id) => self.db_manager.get_grammar_by_id(id).await?,
+                Err(_) => self.db_manager.get_grammar_by_word(query).await?,
+            }
+            .map(|g| (g.id, g.word)),
+        };
+        let Some((id, text)) = entry else {
+            println!("❌ 找不到{}: {}", kind.label(), query);
+            return Ok(());
+        };
+        
+        let sentences = self.db_manager.get_sentences_for_entry(kind, id).await?;
+        if sentences.is_empty() {
+            println!("ℹ️  例句库中没有包含「{}」的例句（可先通过菜单 23 更新例句库）", text);
+            return Ok(());
+        }
+        println!("📝 包含「{}」的例句（{} 个）：", text, sentences.len());
+        for sentence in &sentences {
+            let source = SentenceSource::parse(&sentence.source).map(|s| s.label()).unwrap_or("未知");
+            println!("\n  [{}] {}（{}）", sentence.id, sentence.japanese, source);
+            if !sentence.reading.is_empty() {
+                println!("      {}", sentence.reading);
+            }
+            if !sentence.translation.is_empty() {
+                println!("      {}", sentence.translation);
+            }
+        }
+        Ok(())
+    }
+
+    // 在终端中复习：先复习到期的卡片，再学习新卡片（每天上限见 [review] 配置）
+    pub async fn run_review_session(&self) -> Result<()> {
+        let review_config = &self.config.review;
+        let fsrs = Fsrs::new(&review_config.fsrs_weights, review_config.desired_retention, review_config.maximum_interval);
+        let now = now_utc();
+        let now_str = now.format(TIME_FORMAT).to_string();
+        
+        let words = self.study_words().await?;
+        let grammar = self.study_grammar().await?;
+        let mut cards: HashMap<(String, i64), ReviewCard> = self.db_manager.get_review_cards(self.user_id).await?
+            .into_iter()
+            .map(|card| ((card.entry_kind.clone(), card.entry_id), card))
+            .collect();
+        
+        // 到期的卡片按到期时间排序，新卡片按学习计划排序
+        let mut due_items = Vec::new();
+        let mut new_items = Vec::new();
+        for word in &words {
+            match cards.remove(&(EntryKind::Word.as_str().to_string(), word.id)) {
+                Some(card) if card.due <= now_str => due_items.push(ReviewItem::from_word(word, Some(card))),
+                Some(_) => {},
+                None => new_items.push(ReviewItem::from_word(word, None)),
+            }
+        }
+        for item in &grammar {
+            match cards.remove(&(EntryKind::Grammar.as_str().to_string(), item.id)) {
+                Some(card) if card.due <= now_str => due_items.push(ReviewItem::from_grammar(item, Some(card))),
+                Some(_) => {},
+                None => new_items.push(ReviewItem::from_grammar(item, None)),
+            }
+        }
+        due_items.sort_by(|a, b| a.card.as_ref().map(|c| &c.due).cmp(&b.card.as_ref().map(|c| &c.due)));
+        
+        // 今天（本地时间）已经学过的新卡片计入每日上限
+        let today_start = chrono::Local::now()
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .and_then(|t| t.and_local_timezone(chrono::Local).single())
+            .map(|t| t.naive_utc().format(TIME_FORMAT).to_string())
+            .unwrap_or_else(|| now_str.clone());
+        let introduced = self.db_manager.count_cards_introduced(self.user_id, &today_start).await? as usize;
+        new_items.truncate(review_config.new_cards_per_day.saturating_sub(introduced));
+        
+        println!("📚 {} 的复习：{} 张到期卡片，{} 张新卡片", self.user_name(), due_items.len(), new_items.len());
+        let mut queue: VecDeque<ReviewItem> = due_items.into_iter().chain(new_items).collect();
+        if queue.is_empty() {
+            println!("🎉 今天没有需要复习的卡片");
+            return Ok(());
+        }
+        
+        let mut reviewed_count = 0;
+        let mut again_count = 0;
+        
+        while let Some(mut item) = queue.pop_front() {
+            // 按 Ctrl-C 请求退出时结束复习，已评分的卡片都已保存
+            if self.exit_requested() {
+                break;
+            }
+            let state = item.card.as_ref().map(|c| c.state()).unwrap_or(CardState::New);
+            println!("\n────────────────────────────────────────");
+            println!("[剩余 {}] {} · {}", queue.len() + 1, item.kind.label(), match state {
+                CardState::New => "新卡片",
+                CardState::Learning | CardState::Relearning => "学习中",
+                CardState::Review => "复习",
+            });
+            println!("\n    {}\n", item.front);
+            
+            // 单词卡片播放发音
+            if item.kind == EntryKind::Word && let Some(player) = &review_config.audio_player {
+                let audio_path = std::path::Path::new(&self.config.output.audio_dir)
+                    .join(format!("japanese_word_{}.wav", item.entry_id));
+                if audio_path.exists() {
+                    play_audio(player, &audio_path);
+                }
+            }
+            
+            let started = std::time::Instant::now();
+            match read_line("按 Enter 显示答案（q 退出）: ")? {
+                Some(input) if input != "q" => {},
+                _ => break,
+            }
+            println!("\n{}\n", item.answer.trim());
+            
+            let now = now_utc();
+            let grade = loop {
+                let prompt = format!("{}\n评分（q 退出）: ", grade_options(&fsrs, &item, now));
+                match read_line(&prompt)? {
+                    Some(input) if input == "q" => break None,
+                    None => break None,
+                    Some(input) => match input.parse::<u8>().ok().and_then(Grade::from_number) {
+                        Some(grade) => break Some(grade),
+                        None => println!("❌ 请输入 1-4"),
+                    },
+                }
+            };
+            let Some(grade) = grade else {
+                break;
+            };
+            let duration_ms = (started.elapsed().as_millis() as i64).min(60_000);
+            
+            let (state, memory, elapsed_days) = item.memory(now);
+            let scheduled = fsrs.schedule(state, memory, elapsed_days, grade);
+            let previous = item.card.clone();
+            let due = now + chrono::Duration::seconds((scheduled.interval_days * 86400.0).round() as i64);
+            let last_interval_days = previous.as_ref()
+                .and_then(|c| {
+                    let due = NaiveDateTime::parse_from_str(&c.due, TIME_FORMAT).ok()?;
+                    let last = NaiveDateTime::parse_from_str(c.last_review.as_deref()?, TIME_FORMAT).ok()?;
+                    Some((due - last).num_seconds() as f64 / 86400.0)
+                })
+                .unwrap_or(0.0);
+            
+            let mut card = previous.unwrap_or(ReviewCard {
+                id: 0,
+                user_id: self.user_id,
+                entry_kind: item.kind.as_str().to_string(),
+                entry_id: item.entry_id,
+                state: CardState::New.as_str().to_string(),
+                due: now.format(TIME_FORMAT).to_string(),
+                stability: 0.0,
+                difficulty: 0.0,
+                reps: 0,
+                lapses: 0,
+                last_review: None,
+            });
+            if state == CardState::Review && grade == Grade::Again {
+                card.lapses += 1;
+            }
+            card.reps += 1;
+            card.state = scheduled.state.as_str().to_string();
+            card.stability = scheduled.memory.stability;
+            card.difficulty = scheduled.memory.difficulty;
+            card.due = due.format(TIME_FORMAT).to_string();
+            card.last_review = Some(now.format(TIME_FORMAT).to_string());
+            
+            let log = ReviewLogEntry {
+                reviewed_at: now.format(TIME_FORMAT).to_string(),
+                reviewed_at_ms: chrono::Utc::now().timestamp_millis(),
+                ease: grade as i64,
+                ivl: anki_interval(scheduled.interval_days),
+                last_ivl: anki_interval(last_interval_days),
+                time_ms: duration_ms,
+                review_type: state.revlog_type(),
+                elapsed_days,
+            };
+            card.id = self.db_manager.save_review(&card, &log).await?;
+            
+            reviewed_count += 1;
+            if grade == Grade::Again {
+                again_count += 1;
+            }
+            println!("✅ 下次复习: {}", format_interval(scheduled.interval_days));
+            
+            // 学习阶段的卡片在本次复习结束前再出现一次
+            if scheduled.state != CardState::Review {
+                item.card = Some(card);
+                queue.push_back(item);
+            }
+        }
+        
+        println!("\n📊 本次复习 {} 张卡片，其中重来 {} 次", reviewed_count, again_count);
+        Ok(())
+    }
+
+    // 导出复习记录为 CSV，列与 Anki revlog 表一致
+    pub async fn export_review_log(&self, path: &str) -> Result<()> {
+        let records = self.db_manager.export_review_log(self.user_id).await?;
+        
+        let mut writer = csv::Writer::from_path(path)
+            .map_err(|e| anyhow::anyhow!("无法创建导出文件 {}: {}", path, e))?;
+        for record in &records {
+            writer.serialize(record)?;
+        }
+        writer.flush()?;
+        
+        println!("✅ 已导出 {} 的 {} 条复习记录到 {}", self.user_name(), records.len(), path);
         Ok(())
     }
 
@@ -484,7 +1795,7 @@ impl AnkiCreator {
         
         for word in extraction.words.iter() {
             let key = (word.word.clone(), word.kana.clone(), word.pitch.clone());
-            let group = word_groups.entry(key).or_insert_with(Vec::new);
+            let group = word_groups.entry(key).or_default();
             
             // 合并词性，避免重复
             for pos in &word.part_of_speech {
@@ -494,32 +1805,84 @@ impl AnkiCreator {
             }
         }
         
+        // 词性统一为标准写法
+        for group in word_groups.values_mut() {
+            *group = normalize_labels(group).labels();
+        }
+        
+        // 跳过当前学习者已掌握、忽略或拉黑的单词
+        let statuses = self.db_manager.get_entry_statuses(self.user_id, EntryKind::Word).await?;
+        let group_count = word_groups.len();
+        word_groups.retain(|(word, _, _), _| !statuses.get(word).is_some_and(|s| s.is_excluded()));
+        if word_groups.len() < group_count {
+            println!("  🙈 跳过 {} 个{}已掌握或忽略的单词", group_count - word_groups.len(), self.user_name());
+        }
+        
         // 检查哪些单词已存在，哪些需要分析
         let mut words_to_analyze = Vec::new();
         let mut words_to_update: Vec<(i64, String, String)> = Vec::new();
         let mut skipped_count = 0;
+        let mut locked_skipped_count = 0;
+        let localized = self.localized_analyses(EntryKind::Word).await?;
         
         for ((word, kana, pitch), parts_of_speech) in word_groups.iter() {
-            let exists = self.db_manager.check_word_exists(word, kana).await?;
+            let mut exists = self.db_manager.check_word_exists(word, kana).await?;
+            let mut existing_lookup = None;
+            
+            // 假名被手动修改并锁定的单词，按单词本身匹配
+            if !exists && let Some(locked_word) = self.db_manager.get_kana_locked_word(word).await? {
+                exists = true;
+                existing_lookup = Some(locked_word);
+            }
             
             if exists {
+                if existing_lookup.is_none() {
+                    existing_lookup = self.db_manager.get_existing_word_by_word_kana(word, kana).await?;
+                }
+                
                 // 获取已存在的单词信息
-                if let Some(existing_word) = self.db_manager.get_existing_word_by_word_kana(word, kana).await? {
-                    let new_pos_str = parts_of_speech.join("｜");
+                if let Some(existing_word) = existing_lookup {
+                    // 锁定的音调和词性保留手动编辑的值
+                    let new_pitch = if existing_word.pitch_locked { existing_word.pitch.clone() } else { pitch.clone() };
+                    let new_pos_str = if existing_word.pos_locked {
+                        existing_word.part_of_speech.clone()
+                    } else {
+                        parts_of_speech.join("｜")
+                    };
+                    
+                    if (existing_word.pitch_locked && existing_word.pitch != *pitch)
+                        || (existing_word.pos_locked && existing_word.part_of_speech != parts_of_speech.join("｜"))
+                    {
+                        locked_skipped_count += 1;
+                        println!("  🔒 保留已锁定字段: {} ({})", word, existing_word.kana);
+                    }
                     
                     // 检查是否需要更新 pitch 或词性
-                    if existing_word.pitch != *pitch || existing_word.part_of_speech != new_pos_str {
+                    if existing_word.pitch != new_pitch || existing_word.part_of_speech != new_pos_str {
                         println!("  🔄 更新已存在单词: {} ({}) - pitch: {}->{}, pos: {}->{}", 
                             word, kana, 
-                            existing_word.pitch, pitch,
+                            existing_word.pitch, new_pitch,
                             existing_word.part_of_speech, new_pos_str
                         );
                         
-                        words_to_update.push((existing_word.id, pitch.clone(), new_pos_str));
+                        words_to_update.push((existing_word.id, new_pitch, new_pos_str));
                     } else {
                         skipped_count += 1;
                         println!("  ✅ 跳过已存在的单词（无变化）: {} ({})", word, kana);
                     }
+                    
+                    // 其他解析语言下，已存在但缺少该语言解析的单词也需要分析
+                    if let Some(analyses) = &localized
+                        && analyses.get(&existing_word.id).is_none_or(|a| a.is_empty())
+                    {
+                        let basic_word = BasicWordInfo {
+                            word: existing_word.word.clone(),
+                            kana: existing_word.kana.clone(),
+                            pitch: existing_word.pitch.clone(),
+                            part_of_speech: parts_of_speech.clone(),
+                        };
+                        words_to_analyze.push((basic_word, parts_of_speech.clone(), Some(existing_word.id)));
+                    }
                 } else {
                     // 理论上不应该到这里，但为了安全起见
                     skipped_count += 1;
@@ -532,15 +1895,18 @@ impl AnkiCreator {
                     pitch: pitch.clone(),
                     part_of_speech: parts_of_speech.clone(),
                 };
-                words_to_analyze.push((basic_word, parts_of_speech.clone()));
+                words_to_analyze.push((basic_word, parts_of_speech.clone(), None));
             }
         }
 
-        println!("  跳过 {} 个已存在的单词，需要更新 {} 个单词，需要分析 {} 个新单词", 
+        println!("  跳过 {} 个已存在的单词，需要更新 {} 个单词，需要分析 {} 个单词", 
             skipped_count, 
             words_to_update.len(),
             words_to_analyze.len()
         );
+        if locked_skipped_count > 0 {
+            println!("  🔒 {} 个单词含有锁定字段，已保留手动编辑的内容", locked_skipped_count);
+        }
 
         // 先更新已存在的单词
         if !words_to_update.is_empty() {
@@ -554,51 +1920,101 @@ impl AnkiCreator {
             }
         }
         
-        // 使用并发流处理所有单词
-        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(self.config.processing.concurrent_requests));
-        
-        let word_analyses_results: Result<Vec<Vec<WordAnalysis>>, anyhow::Error> = stream::iter(words_to_analyze.into_iter().enumerate())
-            .map(|(i, (word, parts_of_speech))| {
-                let semaphore = semaphore.clone();
-                let analyzer = self;
-                async move {
-                    let _permit = semaphore.acquire().await.unwrap();
-                    
-                    let pos_display = parts_of_speech.join("、");
-                    println!("  分析单词 {}: {} ({})", i + 1, word.word, pos_display);
-                    let analysis = analyzer.analyze_word_with_multiple_pos(&word, &parts_of_speech).await?;
-                    
-                    // 为每个单词创建一个WordAnalysis记录，所有词性用｜分隔
-                    let merged_parts_of_speech = parts_of_speech.join("｜");
-                    let word_analysis = WordAnalysis {
-                        word: word.word.clone(),
-                        kana: word.kana.clone(),
-                        pitch: word.pitch.clone(),
-                        part_of_speech: merged_parts_of_speech,
-                        analysis: analysis,
-                    };
-                    
-                    Ok(vec![word_analysis])
-                }
+        // 并发分析所有单词；中途停止、预算用完或个别单词失败时只保存已完成的单词
+        let word_analyses_results: Vec<Result<(WordAnalysis, Option<i64>, SanitizedHtml)>> = self.scheduler
+            .run(Resource::Llm, words_to_analyze.into_iter().enumerate(), |(i, (word, parts_of_speech, existing_id))| async move {
+                let pos_display = parts_of_speech.join("、");
+                println!("  分析单词 {}: {} ({})", i + 1, word.word, pos_display);
+                let (sanitized, details) = self.analyze_word_with_multiple_pos(&word, &parts_of_speech, false).await
+                    .map_err(|e| anyhow::anyhow!("{} ({}): {}", word.word, word.kana, e))?;
+                
+                // 为每个单词创建一个WordAnalysis记录，所有词性用｜分隔
+                let merged_parts_of_speech = parts_of_speech.join("｜");
+                let word_analysis = WordAnalysis {
+                    word: word.word.clone(),
+                    kana: word.kana.clone(),
+                    pitch: word.pitch.clone(),
+                    part_of_speech: merged_parts_of_speech,
+                    analysis: sanitized.html.clone(),
+                    details,
+                    prompt_version: self.prompt_version(PromptKind::WordAnalysis),
+                };
+                
+                Ok((word_analysis, existing_id, sanitized))
             })
-            .buffer_unordered(10) // 允许最多10个并发任务
-            .collect::<Vec<Result<Vec<WordAnalysis>, anyhow::Error>>>()
-            .await
-            .into_iter()
-            .collect();
+            .await;
         
-        // 展平结果
-        let new_word_analyses: Vec<WordAnalysis> = word_analyses_results?.into_iter().flatten().collect();
+        // 区分新单词和补充解析的已存在单词
+        let (new_word_analyses, existing_word_analyses): (Vec<_>, Vec<_>) = keep_completed(word_analyses_results, "单词")
+            .into_iter()
+            .partition(|(_, existing_id, _)| existing_id.is_none());
+        let (mut new_word_analyses, new_word_results): (Vec<WordAnalysis>, Vec<SanitizedHtml>) = new_word_analyses
+            .into_iter()
+            .map(|(analysis, _, sanitized)| (analysis, sanitized))
+            .unzip();
 
         println!("💾 保存分析结果到数据库...");
 
-        // 保存新分析的单词到数据库
+        // 保存新分析的单词到数据库；其他解析语言下主语言解析留空，解析单独保存
         if !new_word_analyses.is_empty() {
+            let localized_texts: Vec<String> = if localized.is_some() {
+                new_word_analyses.iter_mut().map(|w| std::mem::take(&mut w.analysis)).collect()
+            } else {
+                Vec::new()
+            };
             self.db_manager.save_words(&new_word_analyses).await?;
+            for (index, (word, sanitized)) in new_word_analyses.iter().zip(&new_word_results).enumerate() {
+                // 只有需要单独保存解析、结构化解析、审查结果或记录校验失败时才查询ID
+                if localized_texts.is_empty() && word.details.is_none() && sanitized.failure_reason().is_none() && sanitized.review.is_none() {
+                    continue;
+                }
+                if let Some(entry) = self.db_manager.get_existing_word_by_word_kana(&word.word, &word.kana).await? {
+                    if let Some(analysis) = localized_texts.get(index) {
+                        self.store_analysis(EntryKind::Word, entry.id, analysis).await?;
+                    }
+                    if word.details.is_some() {
+                        self.store_word_details(entry.id, word.details.as_ref()).await?;
+                    }
+                    self.record_validation(EntryKind::Word, entry.id, sanitized).await?;
+                }
+            }
             println!("  ✅ 保存了 {} 个新单词到数据库", new_word_analyses.len());
         } else {
             println!("  ℹ️  没有新单词需要保存");
         }
+        
+        for (word_analysis, existing_id, sanitized) in &existing_word_analyses {
+            if let Some(id) = existing_id {
+                self.store_analysis(EntryKind::Word, *id, &word_analysis.analysis).await?;
+                self.store_word_details(*id, word_analysis.details.as_ref()).await?;
+                self.record_validation(EntryKind::Word, *id, sanitized).await?;
+            }
+        }
+        if !existing_word_analyses.is_empty() {
+            println!("  ✅ 补充了 {} 个已存在单词的{}解析", existing_word_analyses.len(), self.config.language.explanation.label());
+        }
+
+        // 记录单词出现的输入文档
+        let document = &self.config.input.text_file;
+        let mut analyzed_ids = Vec::new();
+        for (word, kana, _) in word_groups.keys() {
+            let entry = match self.db_manager.get_existing_word_by_word_kana(word, kana).await? {
+                Some(entry) => Some(entry),
+                None => self.db_manager.get_kana_locked_word(word).await?,
+            };
+            if let Some(entry) = entry {
+                self.db_manager.record_occurrence(EntryKind::Word, entry.id, document).await?;
+                analyzed_ids.push(entry.id);
+            }
+        }
+
+        let annotated_count = self.annotate_levels().await?;
+        if annotated_count > 0 {
+            println!("🏷️  更新了 {} 个条目的词频和 JLPT 等级", annotated_count);
+        }
+
+        // 输入文本中的句子和新解析中的例句加入例句库
+        self.collect_sentences(Some(text), &analyzed_ids, &[]).await?;
 
         println!("📄 生成单词 Anki 卡片文件...");
 
@@ -620,40 +2036,91 @@ impl AnkiCreator {
         
         println!("📝 找到 {} 个语法点", extraction.grammar.len());
         
-        println!("🔄 第二步：并发详细分析每个语法点...");
-        
-        // 使用并发处理语法分析
-        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(self.config.processing.concurrent_requests));
+        // 同一语法的不同写法和活用形只分析一次（保存时原写法记为别名）
+        let mut seen = std::collections::HashSet::new();
+        let extracted_count = extraction.grammar.len();
+        let extracted: Vec<BasicGrammarInfo> = extraction.grammar
+            .into_iter()
+            .filter(|g| seen.insert(canonical_grammar(&g.grammar)))
+            .collect();
+        if extracted.len() < extracted_count {
+            println!("  🔗 合并了 {} 个同一语法的不同写法", extracted_count - extracted.len());
+        }
         
-        let grammar_analyses: Result<Vec<GrammarAnalysis>, anyhow::Error> = stream::iter(extraction.grammar.into_iter().enumerate())
-            .map(|(i, grammar)| {
-                let semaphore = semaphore.clone();
-                let analyzer = self;
-                async move {
-                    let _permit = semaphore.acquire().await.unwrap();
-                    
-                    println!("  分析语法 {}: {}", i + 1, grammar.grammar);
-                    let analysis = analyzer.analyze_grammar(&grammar).await?;
-                    
-                    Ok(GrammarAnalysis {
-                        grammar: grammar.grammar.clone(),
-                        kana: grammar.kana.clone(),
-                        analysis,
-                    })
-                }
-            })
-            .buffer_unordered(10)
-            .collect::<Vec<Result<GrammarAnalysis, anyhow::Error>>>()
-            .await
+        // 跳过当前学习者已掌握、忽略或拉黑的语法
+        let statuses = self.db_manager.get_entry_statuses(self.user_id, EntryKind::Grammar).await?;
+        let grammar_count = extracted.len();
+        let grammar_list: Vec<BasicGrammarInfo> = extracted
             .into_iter()
+            .filter(|g| !statuses.get(&canonical_grammar(&g.grammar)).is_some_and(|s| s.is_excluded()))
             .collect();
+        if grammar_list.len() < grammar_count {
+            println!("  🙈 跳过 {} 个{}已掌握或忽略的语法", grammar_count - grammar_list.len(), self.user_name());
+        }
+        
+        println!("🔄 第二步：并发详细分析每个语法点...");
+        
+        // 并发分析所有语法点；中途停止、预算用完或个别语法失败时只保存已完成的语法
+        let grammar_analyses: Vec<Result<(GrammarAnalysis, SanitizedHtml)>> = self.scheduler
+            .run(Resource::Llm, grammar_list.into_iter().enumerate(), |(i, grammar)| async move {
+                println!("  分析语法 {}: {}", i + 1, grammar.grammar);
+                let canonical = BasicGrammarInfo {
+                    grammar: canonical_grammar(&grammar.grammar),
+                    kana: grammar.kana.clone(),
+                };
+                let (sanitized, details) = self.analyze_grammar(&canonical, false).await
+                    .map_err(|e| anyhow::anyhow!("{}: {}", grammar.grammar, e))?;
+                
+                let grammar_analysis = GrammarAnalysis {
+                    grammar: grammar.grammar.clone(),
+                    kana: grammar.kana.clone(),
+                    analysis: sanitized.html.clone(),
+                    prompt_version: self.prompt_version(PromptKind::GrammarAnalysis),
+                    details,
+                };
+                Ok((grammar_analysis, sanitized))
+            })
+            .await;
         
-        let grammar_analyses = grammar_analyses?;
+        let (mut grammar_analyses, grammar_results): (Vec<GrammarAnalysis>, Vec<SanitizedHtml>) = keep_completed(grammar_analyses, "语法")
+            .into_iter()
+            .unzip();
 
         println!("💾 保存分析结果到数据库...");
         
+        // 其他解析语言下主语言解析留空（保留已有的），解析单独保存
+        let localized_texts: Vec<String> = if self.explains_in_primary_language() {
+            Vec::new()
+        } else {
+            grammar_analyses.iter_mut().map(|g| std::mem::take(&mut g.analysis)).collect()
+        };
         self.db_manager.save_grammar(&grammar_analyses).await?;
         
+        // 记录语法出现的输入文档
+        let mut analyzed_ids = Vec::new();
+        for (index, item) in grammar_analyses.iter().enumerate() {
+            if let Some(entry) = self.db_manager.get_grammar_by_word(&item.grammar).await? {
+                self.db_manager.record_occurrence(EntryKind::Grammar, entry.id, &self.config.input.text_file).await?;
+                analyzed_ids.push(entry.id);
+                if let Some(analysis) = localized_texts.get(index) {
+                    self.store_analysis(EntryKind::Grammar, entry.id, analysis).await?;
+                }
+                self.store_grammar_details(entry.id, item.details.as_ref()).await?;
+                // 锁定的主语言解析保留手动编辑的内容，不记录校验结果
+                if !(self.explains_in_primary_language() && entry.analysis_locked) {
+                    self.record_validation(EntryKind::Grammar, entry.id, &grammar_results[index]).await?;
+                }
+            }
+        }
+        
+        let annotated_count = self.annotate_levels().await?;
+        if annotated_count > 0 {
+            println!("🏷️  更新了 {} 个条目的词频和 JLPT 等级", annotated_count);
+        }
+        
+        // 输入文本中的句子和新解析中的例句加入例句库
+        self.collect_sentences(Some(text), &[], &analyzed_ids).await?;
+        
         println!("📄 生成语法 Anki 卡片文件...");
 
         // 生成语法 Anki 卡片
@@ -670,15 +2137,20 @@ impl AnkiCreator {
         std::fs::create_dir_all(&self.config.output.audio_dir)
             .map_err(|e| anyhow::anyhow!("无法创建音频目录 {}: {}", self.config.output.audio_dir, e))?;
         
-        // 获取所有单词
+        // 单词使用假名（发音）而不是汉字，例句直接朗读原句
         let words = self.db_manager.get_all_words().await?;
+        let sentences = self.db_manager.get_all_sentences().await?;
         
-        if words.is_empty() {
-            println!("⚠️  数据库中没有找到任何单词");
+        if words.is_empty() && sentences.is_empty() {
+            println!("⚠️  数据库中没有找到任何单词和例句");
             return Ok(());
         }
         
-        println!("📊 找到 {} 个单词，检查缺失的音频文件...", words.len());
+        println!("📊 找到 {} 个单词、{} 个例句，检查缺失的音频文件...", words.len(), sentences.len());
+        let items: Vec<(String, String, String)> = words.into_iter()
+            .map(|w| (format!("japanese_word_{}.wav", w.id), w.kana, w.word))
+            .chain(sentences.into_iter().map(|s| (format!("japanese_sentence_{}.wav", s.id), s.japanese.clone(), s.japanese)))
+            .collect();
         
         // 创建 TTS 客户端
         let tts_config = TtsConfig::from_config(&self.config.tts);
@@ -687,45 +2159,35 @@ impl AnkiCreator {
         let mut missing_count = 0;
         let mut generated_count = 0;
         
-        // 使用并发流处理所有单词
-        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(self.config.processing.concurrent_requests));
-        
-        let total_words = words.len();
-        let results: Vec<Result<(), anyhow::Error>> = stream::iter(words.into_iter().enumerate())
-            .map(|(i, word)| {
-                let semaphore = semaphore.clone();
-                let tts = &tts;
-                let audio_dir = &self.config.output.audio_dir;
-                async move {
-                    let _permit = semaphore.acquire().await.unwrap();
-                    
-                    let audio_filename = format!("japanese_word_{}.wav", word.id);
-                    let audio_path = std::path::Path::new(audio_dir).join(&audio_filename);
-                    
-                    // 检查音频文件是否存在
-                    if audio_path.exists() {
-                        println!("  ✅ 音频文件已存在: {} ({})", audio_filename, word.word);
-                        return Ok(());
-                    }
-                    
-                    println!("  🎵 生成音频文件 {}/{}: {} ({})", 
-                        i + 1, total_words, audio_filename, word.kana);
-                    
-                    // 生成音频文件，使用假名（发音）而不是汉字
-                    match tts.synthesize_text_to_file(&word.kana, &audio_path.to_string_lossy()).await {
-                        Ok(_) => {
-                            println!("  ✅ 音频文件生成成功: {}", audio_filename);
-                            Ok(())
-                        },
-                        Err(e) => {
-                            println!("  ❌ 音频文件生成失败: {} - {}", audio_filename, e);
-                            Err(e)
-                        }
+        // 并发生成所有音频
+        let total_items = items.len();
+        let tts = &tts;
+        let audio_dir = &self.config.output.audio_dir;
+        let results: Vec<Result<(), anyhow::Error>> = self.scheduler
+            .run(Resource::Tts, items.into_iter().enumerate(), |(i, (audio_filename, speech, display))| async move {
+                let audio_path = std::path::Path::new(audio_dir).join(&audio_filename);
+                
+                // 检查音频文件是否存在
+                if audio_path.exists() {
+                    println!("  ✅ 音频文件已存在: {} ({})", audio_filename, display);
+                    return Ok(());
+                }
+                
+                println!("  🎵 生成音频文件 {}/{}: {} ({})", 
+                    i + 1, total_items, audio_filename, speech);
+                
+                self.scheduler.throttle(Resource::Tts).await;
+                match tts.synthesize_text_to_file(&speech, &audio_path.to_string_lossy()).await {
+                    Ok(_) => {
+                        println!("  ✅ 音频文件生成成功: {}", audio_filename);
+                        Ok(())
+                    },
+                    Err(e) => {
+                        println!("  ❌ 音频文件生成失败: {} - {}", audio_filename, e);
+                        Err(e)
                     }
                 }
             })
-            .buffer_unordered(self.config.processing.concurrent_requests)
-            .collect::<Vec<_>>()
             .await;
         
         // 统计结果
@@ -745,6 +2207,49 @@ impl AnkiCreator {
         
         Ok(())
     }
+}
+
+// 取出模型回复中的 JSON 对象（允许外面包着 markdown 代码块），不是 JSON 时返回 None
+fn json_object(content: &str) -> Option<&str> {
+    let content = content.trim();
+    let content = content.strip_prefix("```json").or_else(|| content.strip_prefix("```")).unwrap_or(content);
+    let content = content.trim().strip_suffix("```").unwrap_or(content).trim();
+    (content.starts_with('{') && content.ends_with('}')).then_some(content)
+}
+
+// 并发任务的结果中失败的只提示并跳过，返回已完成的结果
+fn keep_completed<T>(results: Vec<Result<T>>, kind: &str) -> Vec<T> {
+    let mut completed = Vec::new();
+    let mut failed = 0;
+    for result in results {
+        match result {
+            Ok(value) => completed.push(value),
+            Err(e) => {
+                failed += 1;
+                println!("  ❌ 分析失败: {}", e);
+            }
+        }
+    }
+    if failed > 0 {
+        println!("⚠️  {} 个{}分析失败，已跳过；已完成的 {} 个照常保存", failed, kind, completed.len());
+    }
+    completed
+}
 
+// 输出因字段锁定而跳过的单词
+fn report_locked_words(locked_words: &[JapaneseWord], field: LockField) {
+    if locked_words.is_empty() {
+        return;
+    }
+    
+    println!("🔒 跳过 {} 个{}已锁定的单词：", locked_words.len(), field.label());
+    for word in locked_words {
+        println!("   - ID {}: {} ({})", word.id, word.word, word.kana);
+    }
+}
 
+// 按给定的ID顺序重新排列条目
+fn order_by_ids<T>(entries: Vec<T>, ids: &[i64], id_of: impl Fn(&T) -> i64) -> Vec<T> {
+    let mut by_id: HashMap<i64, T> = entries.into_iter().map(|e| (id_of(&e), e)).collect();
+    ids.iter().filter_map(|id| by_id.remove(id)).collect()
 }
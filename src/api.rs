@@ -1,20 +1,61 @@
 use anyhow::Result;
-use reqwest::{Client, ClientBuilder};
+use futures::stream::{self, BoxStream, StreamExt};
+use reqwest::{Client, ClientBuilder, Response};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::sync::Notify;
 use tokio::time::sleep;
 
 use crate::config::Config;
+use crate::models::{ApiCall, ApiOperation, CachedResponse};
+use crate::rate_limit::{Failure, backoff_delay, quota_exhausted, retry_after};
+use crate::scheduler::{Resource, Scheduler};
+use crate::review::{TIME_FORMAT, now_utc};
+use crate::storage::Storage;
 
 // OpenRouter API 响应结构
 #[derive(Debug, Deserialize)]
 pub struct OpenRouterResponse {
     pub choices: Vec<Choice>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+    // 是否来自缓存
+    #[serde(skip)]
+    pub cached: bool,
+}
+
+impl OpenRouterResponse {
+    // 由缓存的回复内容还原响应（缓存命中不产生用量）
+    fn from_content(content: String) -> Self {
+        OpenRouterResponse {
+            choices: vec![Choice { message: Message { content }, finish_reason: None }],
+            usage: None,
+            cached: true,
+        }
+    }
+}
+
+// 请求用量，cost 为服务商返回的费用（美元）
+#[derive(Debug, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub prompt_tokens: i64,
+    #[serde(default)]
+    pub completion_tokens: i64,
+    #[serde(default)]
+    pub cost: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Choice {
     pub message: Message,
+    // 生成结束的原因，length 表示达到 max_tokens 被截断
+    #[serde(default)]
+    pub finish_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -36,73 +77,570 @@ pub struct RequestMessage {
     pub content: String,
 }
 
+// 实际发送的请求体：要求 OpenRouter 在响应中返回费用（其他服务商不认识该字段，不发送），
+// stream 为 true 时以 SSE 流式返回，并要求在最后返回用量
+#[derive(Serialize)]
+struct RequestBody<'a> {
+    #[serde(flatten)]
+    request: &'a OpenRouterRequest,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<UsageOption>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+impl<'a> RequestBody<'a> {
+    fn new(request: &'a OpenRouterRequest, openrouter: bool, stream: bool) -> Self {
+        RequestBody {
+            request,
+            usage: openrouter.then_some(UsageOption { include: true }),
+            stream,
+            stream_options: stream.then_some(StreamOptions { include_usage: true }),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct UsageOption {
+    include: bool,
+}
+
+#[derive(Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+/// 流式响应中的一个事件
+#[derive(Debug)]
+pub enum StreamEvent {
+    /// 新生成的一段内容
+    Delta(String),
+    /// 生成结束的原因（stop、length 等）
+    Finish(String),
+    /// 请求用量，在内容结束后返回
+    Usage(Usage),
+}
+
+// OpenAI 兼容的流式响应块
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: Option<Delta>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Delta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+// 把收到的字节拆成 SSE 事件的 data 行（按完整的行解码，避免截断多字节字符）
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+        let mut data = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            // 以冒号开头的是注释（OpenRouter 处理中的心跳），其他字段不需要
+            if let Some(payload) = line.trim_end().strip_prefix("data:") {
+                data.push(payload.trim_start().to_string());
+            }
+        }
+        data
+    }
+}
+
+// 解析一个 data 行，[DONE] 表示结束
+fn parse_stream_data(data: &str) -> Result<Option<Vec<StreamEvent>>> {
+    if data == "[DONE]" {
+        return Ok(None);
+    }
+    let chunk: StreamChunk = serde_json::from_str(data)
+        .map_err(|e| anyhow::anyhow!("流式响应解析失败: {}\n响应内容: {}", e, data))?;
+    if let Some(error) = chunk.error {
+        return Err(anyhow::anyhow!("生成过程中出错: {}", error));
+    }
+    let mut events = Vec::new();
+    for choice in chunk.choices {
+        if let Some(content) = choice.delta.and_then(|delta| delta.content).filter(|c| !c.is_empty()) {
+            events.push(StreamEvent::Delta(content));
+        }
+        events.extend(choice.finish_reason.map(StreamEvent::Finish));
+    }
+    events.extend(chunk.usage.map(StreamEvent::Usage));
+    Ok(Some(events))
+}
+
+// 流式读取的状态：响应、未解析完的字节和已解析但还没交出的事件
+struct StreamState {
+    response: Response,
+    parser: SseParser,
+    pending: VecDeque<StreamEvent>,
+    done: bool,
+}
+
+// 本次运行的累计用量，用于预算检查和结束时的汇总
+#[derive(Debug, Default)]
+struct RunUsage {
+    cost: f64,
+    tokens: i64,
+    cache_hits: u64,
+    // 返回了 token 数 / 费用的请求数，用于估算下一个请求的用量
+    token_calls: u64,
+    cost_calls: u64,
+    // 已发出、尚未完成的请求预留的用量
+    in_flight: usize,
+    reserved_cost: f64,
+    reserved_tokens: i64,
+}
+
+impl RunUsage {
+    // 平均每个请求的 token 数，还没有请求完成时按提示词长度加 max_tokens 估算（上限）
+    fn estimate_tokens(&self, request: &OpenRouterRequest) -> i64 {
+        if self.token_calls > 0 {
+            return self.tokens / self.token_calls as i64;
+        }
+        request.prompt_chars() + request.max_tokens as i64
+    }
+
+    // 平均每个请求的费用；还没有请求完成时无法估算，服务商不返回费用时按 0 计
+    fn estimate_cost(&self) -> Option<f64> {
+        if self.cost_calls > 0 {
+            Some(self.cost / self.cost_calls as f64)
+        } else {
+            (self.token_calls > 0).then_some(0.0)
+        }
+    }
+
+    // 平均每个 token 的费用
+    fn cost_per_token(&self) -> Option<f64> {
+        (self.cost_calls > 0 && self.tokens > 0).then(|| self.cost / self.tokens as f64)
+    }
+}
+
+// 一个请求预留的预算，请求结束（记录实际用量后）释放
+struct BudgetReservation<'a> {
+    client: &'a ApiClient,
+    cost: f64,
+    tokens: i64,
+}
+
+impl Drop for BudgetReservation<'_> {
+    fn drop(&mut self) {
+        {
+            let mut usage = self.client.usage.lock().unwrap();
+            usage.in_flight -= 1;
+            usage.reserved_cost -= self.cost;
+            usage.reserved_tokens -= self.tokens;
+        }
+        self.client.budget_released.notify_waiters();
+    }
+}
+
+impl OpenRouterRequest {
+    // 缓存键：服务商、模型、temperature、max_tokens 和全部消息的 SHA-256
+    fn cache_key(&self, provider: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{}\n{}\n{}\n{}\n", provider, self.model, self.temperature, self.max_tokens));
+        for message in &self.messages {
+            hasher.update(format!("{}\n{}\n", message.role, message.content));
+        }
+        hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    // 全部消息的字数，用于估算提示词的 token 数
+    fn prompt_chars(&self) -> i64 {
+        self.messages.iter().map(|m| m.content.chars().count() as i64).sum()
+    }
+}
+
 pub struct ApiClient {
     client: Client,
     config: Config,
+    storage: Arc<dyn Storage>,
+    run_id: String,
+    usage: Mutex<RunUsage>,
+    // 有请求结束、释放预留的预算时通知等待中的请求
+    budget_released: Notify,
+    scheduler: Arc<Scheduler>,
 }
 
 impl ApiClient {
-    pub fn new(config: Config) -> Result<Self> {
+    pub fn new(config: Config, storage: Arc<dyn Storage>, scheduler: Arc<Scheduler>) -> Result<Self> {
         let client = ClientBuilder::new()
             .timeout(Duration::from_secs(config.processing.request_timeout_seconds))
             .build()?;
+        let run_id = format!("{}-{}", now_utc().format("%Y%m%d%H%M%S"), std::process::id());
         
-        Ok(ApiClient { client, config })
+        Ok(ApiClient {
+            client,
+            config,
+            storage,
+            run_id,
+            usage: Mutex::new(RunUsage::default()),
+            budget_released: Notify::new(),
+            scheduler,
+        })
+    }
+
+    /// 本次运行的 ID，api_calls 表按它区分每次运行
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// 本次运行中命中缓存的请求数
+    pub fn cache_hits(&self) -> u64 {
+        self.usage.lock().unwrap().cache_hits
     }
 
-    // 带重试机制的API请求函数
-    pub async fn make_request_with_retry(&self, request: &OpenRouterRequest) -> Result<OpenRouterResponse> {
+    /// 发送请求，相同的请求优先使用缓存的回复；task 为显示进度时的任务名
+    pub async fn make_request_with_retry(&self, operation: ApiOperation, task: &str, request: &OpenRouterRequest) -> Result<OpenRouterResponse> {
+        let cache = &self.config.cache;
+        if cache.enabled && !cache.bypass {
+            let since = (cache.ttl_days > 0)
+                .then(|| (now_utc() - chrono::Duration::days(cache.ttl_days)).format(TIME_FORMAT).to_string());
+            match self.storage.get_cached_response(&request.cache_key(&self.config.api.provider()), since.as_deref()).await {
+                Ok(Some(content)) => {
+                    self.usage.lock().unwrap().cache_hits += 1;
+                    return Ok(OpenRouterResponse::from_content(content));
+                },
+                Ok(None) => {},
+                Err(e) => println!("    ⚠️  读取请求缓存失败: {}", e),
+            }
+        }
+        self.make_fresh_request(operation, task, request).await
+    }
+
+    /// 跳过缓存直接发送请求（用于重新生成解析）
+    pub async fn make_fresh_request(&self, operation: ApiOperation, task: &str, request: &OpenRouterRequest) -> Result<OpenRouterResponse> {
+        // 预留的预算在记录实际用量后释放
+        let _reservation = self.reserve_budget(request).await?;
+        let response = if self.config.streaming.enabled {
+            self.collect_stream(operation, task, request).await?
+        } else {
+            self.send_with_retry(request, false, |response| async move {
+                response.json::<OpenRouterResponse>().await
+                    .map_err(|e| anyhow::anyhow!("JSON解析失败: {}", e))
+            }).await?
+        };
+        self.record_usage(operation, &request.model, response.usage.as_ref()).await;
+        // 达到 max_tokens 被截断的回复不完整，按该条目失败处理，由调用方跳过
+        if response.choices.first().and_then(|c| c.finish_reason.as_deref()) == Some("length") {
+            return Err(anyhow::anyhow!(
+                "{} 的回复达到 max_tokens（{}）被截断，已跳过，可在 [api.max_tokens] 中调大 {}",
+                task, request.max_tokens, operation.as_str()
+            ));
+        }
+        Ok(response)
+    }
+
+    /// 把回复写入缓存；由调用方在回复解析成功后调用，避免缓存无法使用的回复（写入失败只提示）
+    pub async fn cache_response(&self, request: &OpenRouterRequest, response: &OpenRouterResponse) {
+        if !self.config.cache.enabled || response.cached {
+            return;
+        }
+        let Some(choice) = response.choices.first() else {
+            return;
+        };
+        let entry = CachedResponse {
+            key: request.cache_key(&self.config.api.provider()),
+            provider: self.config.api.provider(),
+            model: request.model.clone(),
+            temperature: request.temperature as f64,
+            max_tokens: request.max_tokens as i64,
+            response: choice.message.content.clone(),
+        };
+        if let Err(e) = self.storage.save_cached_response(&entry).await {
+            println!("    ⚠️  写入请求缓存失败: {}", e);
+        }
+    }
+
+    /// 以 SSE 流式发送请求（不读写缓存、不记录用量），返回生成内容的增量和最后的用量；
+    /// 建立连接前的失败按重试策略重试，开始接收后出错时流中返回错误
+    pub async fn stream_request(&self, request: &OpenRouterRequest) -> Result<BoxStream<'static, Result<StreamEvent>>> {
+        let response = self.send_with_retry(request, true, |response| async move { Ok(response) }).await?;
+        let state = StreamState {
+            response,
+            parser: SseParser::default(),
+            pending: VecDeque::new(),
+            done: false,
+        };
+        
+        Ok(stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(event) = state.pending.pop_front() {
+                    return Some((Ok(event), state));
+                }
+                if state.done {
+                    return None;
+                }
+                match state.response.chunk().await {
+                    Ok(Some(bytes)) => {
+                        for data in state.parser.push(&bytes) {
+                            match parse_stream_data(&data) {
+                                Ok(Some(events)) => state.pending.extend(events),
+                                Ok(None) => state.done = true,
+                                Err(e) => {
+                                    state.done = true;
+                                    state.pending.clear();
+                                    return Some((Err(e), state));
+                                }
+                            }
+                        }
+                    },
+                    Ok(None) => state.done = true,
+                    Err(e) => {
+                        state.done = true;
+                        state.pending.clear();
+                        return Some((Err(anyhow::anyhow!("读取流式响应失败: {}", e)), state));
+                    }
+                }
+            }
+        }).boxed())
+    }
+
+    // 流式接收完整的回复：每生成一定字数显示一次进度，超过该用途的字数上限时中断生成
+    async fn collect_stream(&self, operation: ApiOperation, task: &str, request: &OpenRouterRequest) -> Result<OpenRouterResponse> {
+        let streaming = &self.config.streaming;
+        let max_chars = streaming.max_chars_for(operation);
+        let mut events = self.stream_request(request).await?;
+        let mut content = String::new();
+        let mut chars = 0;
+        let mut usage = None;
+        let mut finish_reason = None;
+        
+        while let Some(event) = events.next().await {
+            match event? {
+                StreamEvent::Delta(text) => {
+                    let reported = chars;
+                    chars += text.chars().count();
+                    content.push_str(&text);
+                    if max_chars > 0 && chars > max_chars {
+                        // 断开连接停止生成，服务商不再返回用量，按已生成的字数估算
+                        drop(events);
+                        let partial = self.estimate_partial_usage(request, chars);
+                        self.record_usage(operation, &request.model, Some(&partial)).await;
+                        return Err(anyhow::anyhow!("{} 的生成内容超过 {} 字，已中断并跳过", task, max_chars));
+                    }
+                    let interval = streaming.progress_interval;
+                    if interval > 0 && chars / interval > reported / interval {
+                        println!("    ✍️  {} {}: 已生成 {} 字", operation.label(), task, chars);
+                    }
+                },
+                StreamEvent::Finish(reason) => finish_reason = Some(reason),
+                StreamEvent::Usage(reported) => usage = Some(reported),
+            }
+        }
+        
+        Ok(OpenRouterResponse {
+            choices: vec![Choice { message: Message { content }, finish_reason }],
+            usage,
+            cached: false,
+        })
+    }
+
+    /// 删除超过保留天数的缓存，返回删除的条数
+    pub async fn purge_expired_cache(&self) -> Result<u64> {
+        let cache = &self.config.cache;
+        if !cache.enabled || cache.ttl_days <= 0 {
+            return Ok(0);
+        }
+        let before = (now_utc() - chrono::Duration::days(cache.ttl_days)).format(TIME_FORMAT).to_string();
+        self.storage.purge_cached_responses(&before).await
+    }
+
+    // 为一个请求预留预算：已花费加上进行中请求的预估用量会超过上限时，等进行中的请求结束再判断；
+    // 已花费的用量达到上限时拒绝请求，并停止调度新的任务（已完成的结果照常保存）
+    async fn reserve_budget(&self, request: &OpenRouterRequest) -> Result<BudgetReservation<'_>> {
+        loop {
+            let released = self.budget_released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            match self.try_reserve_budget(request) {
+                Ok(Some(reservation)) => return Ok(reservation),
+                Ok(None) => released.await,
+                Err(e) => {
+                    if !self.scheduler.cancel() {
+                        println!("⏹️  {}，不再开始新的请求", e);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    // 预算足够时预留并返回，需要等待进行中的请求结束时返回 None
+    fn try_reserve_budget(&self, request: &OpenRouterRequest) -> Result<Option<BudgetReservation<'_>>> {
+        let budget = &self.config.budget;
+        let mut usage = self.usage.lock().unwrap();
+        if budget.max_cost > 0.0 && usage.cost >= budget.max_cost {
+            return Err(anyhow::anyhow!("已达到本次运行的费用上限: ${:.4} / ${:.4}", usage.cost, budget.max_cost));
+        }
+        if budget.max_tokens > 0 && usage.tokens >= budget.max_tokens {
+            return Err(anyhow::anyhow!("已达到本次运行的 token 上限: {} / {}", usage.tokens, budget.max_tokens));
+        }
+        
+        let tokens = usage.estimate_tokens(request);
+        let cost = usage.estimate_cost();
+        // 没有进行中的请求时总是放行，避免预估偏大导致一个请求也发不出
+        if usage.in_flight > 0 {
+            let over_cost = budget.max_cost > 0.0
+                && cost.is_none_or(|cost| usage.cost + usage.reserved_cost + cost > budget.max_cost);
+            let over_tokens = budget.max_tokens > 0
+                && usage.tokens + usage.reserved_tokens + tokens > budget.max_tokens;
+            if over_cost || over_tokens {
+                return Ok(None);
+            }
+        }
+        
+        let cost = cost.unwrap_or(0.0);
+        usage.in_flight += 1;
+        usage.reserved_cost += cost;
+        usage.reserved_tokens += tokens;
+        Ok(Some(BudgetReservation { client: self, cost, tokens }))
+    }
+
+    // 中断的流式请求的估算用量：提示词和已生成内容按每字一个 token 计，费用按本次运行的平均单价
+    fn estimate_partial_usage(&self, request: &OpenRouterRequest, generated_chars: usize) -> Usage {
+        let prompt_tokens = request.prompt_chars();
+        let completion_tokens = generated_chars as i64;
+        let cost = self.usage.lock().unwrap().cost_per_token()
+            .map(|per_token| per_token * (prompt_tokens + completion_tokens) as f64);
+        Usage { prompt_tokens, completion_tokens, cost }
+    }
+
+    // 累计本次运行的用量并写入 api_calls 表（写入失败只提示）
+    async fn record_usage(&self, operation: ApiOperation, model: &str, usage: Option<&Usage>) {
+        let call = ApiCall {
+            run_id: self.run_id.clone(),
+            operation: operation.as_str().to_string(),
+            model: model.to_string(),
+            prompt_tokens: usage.map_or(0, |u| u.prompt_tokens),
+            completion_tokens: usage.map_or(0, |u| u.completion_tokens),
+            cost: usage.and_then(|u| u.cost),
+        };
+        {
+            let mut run_usage = self.usage.lock().unwrap();
+            run_usage.cost += call.cost.unwrap_or(0.0);
+            run_usage.tokens += call.prompt_tokens + call.completion_tokens;
+            if usage.is_some() {
+                run_usage.token_calls += 1;
+            }
+            if call.cost.is_some() {
+                run_usage.cost_calls += 1;
+            }
+        }
+        if let Err(e) = self.storage.save_api_call(&call).await {
+            println!("    ⚠️  记录请求用量失败: {}", e);
+        }
+    }
+
+    // 带重试机制的API请求函数：致命错误直接返回，临时错误指数退避，限流时按响应头等待并暂停所有请求；
+    // 成功的响应交给 read 读取，读取失败同样重试
+    async fn send_with_retry<T, F, Fut>(&self, request: &OpenRouterRequest, stream: bool, read: F) -> Result<T>
+    where
+        F: Fn(Response) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let processing = &self.config.processing;
         let mut last_error = None;
+        // 上一次失败时响应头要求的等待时间
+        let mut wait_hint: Option<Duration> = None;
         
-        for attempt in 0..=self.config.processing.max_retries {
+        for attempt in 0..=processing.max_retries {
             if attempt > 0 {
-                let delay = Duration::from_millis(self.config.processing.request_delay_ms * (attempt as u64 + 1));
-                println!("    ⏳ 重试 {}/{} 次，等待 {:?}...", attempt, self.config.processing.max_retries, delay);
+                let delay = wait_hint.take()
+                    .unwrap_or_else(|| backoff_delay(processing.request_delay_ms, processing.max_retry_delay_ms, attempt));
+                println!("    ⏳ 重试 {}/{} 次，等待 {:?}...", attempt, processing.max_retries, delay);
                 sleep(delay).await;
             }
             
+            self.scheduler.throttle(Resource::Llm).await;
             match self.client
-                .post("https://openrouter.ai/api/v1/chat/completions")
+                .post(self.config.api.chat_completions_url())
                 .header("Authorization", format!("Bearer {}", self.config.api.openrouter_key))
                 .header("Content-Type", "application/json")
-                .json(request)
+                .json(&RequestBody::new(request, self.config.api.is_openrouter(), stream))
                 .send()
                 .await
             {
                 Ok(response) => {
-                    if response.status().is_success() {
-                        match response.json::<OpenRouterResponse>().await {
-                            Ok(api_response) => {
+                    let status = response.status();
+                    let headers = response.headers().clone();
+                    if status.is_success() {
+                        // 本周期的额度已用完时，其他请求等到额度重置再发
+                        if let Some(wait) = quota_exhausted(&headers) {
+                            self.pause_all(wait);
+                        }
+                        match read(response).await {
+                            Ok(result) => {
                                 if attempt > 0 {
                                     println!("    ✅ 重试成功！");
                                 }
-                                return Ok(api_response);
+                                return Ok(result);
                             },
                             Err(e) => {
-                                let error_msg = format!("JSON解析失败: {}", e);
-                                println!("    ❌ 尝试 {}: {}", attempt + 1, error_msg);
-                                last_error = Some(anyhow::anyhow!(error_msg));
+                                println!("    ❌ 尝试 {}: {}", attempt + 1, e);
+                                last_error = Some(e);
                             }
                         }
-                    } else {
-                        let status = response.status();
-                        match response.text().await {
-                            Ok(error_text) => {
-                                let error_msg = format!("API请求失败 (状态码: {}): {}", status, error_text);
-                                println!("    ❌ 尝试 {}: {}", attempt + 1, error_msg);
-                                last_error = Some(anyhow::anyhow!(error_msg));
-                            },
-                            Err(e) => {
-                                let error_msg = format!("读取错误响应失败: {}", e);
-                                println!("    ❌ 尝试 {}: {}", attempt + 1, error_msg);
-                                last_error = Some(anyhow::anyhow!(error_msg));
+                        continue;
+                    }
+                    
+                    let error_text = response.text().await
+                        .unwrap_or_else(|e| format!("读取错误响应失败: {}", e));
+                    let error_msg = format!("API请求失败 (状态码: {}): {}", status, error_text);
+                    println!("    ❌ 尝试 {}: {}", attempt + 1, error_msg);
+                    match Failure::from_status(status) {
+                        Failure::Fatal => {
+                            println!("    🚫 请求无法通过重试解决，不再重试");
+                            return Err(anyhow::anyhow!(error_msg));
+                        },
+                        Failure::RateLimited => {
+                            // 响应头要求的等待超过上限时只等待上限时间，由后续重试继续试探
+                            let max_delay = Duration::from_millis(processing.max_retry_delay_ms);
+                            let requested = retry_after(&headers)
+                                .unwrap_or_else(|| backoff_delay(processing.request_delay_ms, processing.max_retry_delay_ms, attempt + 1));
+                            if requested > max_delay {
+                                println!("    ⏳ 服务商要求等待 {:?}，超过最长重试等待时间，先等待 {:?}", requested, max_delay);
                             }
-                        }
+                            let delay = requested.min(max_delay);
+                            self.pause_all(delay);
+                            wait_hint = Some(delay);
+                        },
+                        Failure::Retryable => {
+                            wait_hint = retry_after(&headers)
+                                .filter(|delay| *delay <= Duration::from_millis(processing.max_retry_delay_ms));
+                        },
                     }
+                    last_error = Some(anyhow::anyhow!(error_msg));
                 },
                 Err(e) => {
                     let error_msg = format!("网络请求失败: {}", e);
                     println!("    ❌ 尝试 {}: {}", attempt + 1, error_msg);
+                    if e.is_builder() {
+                        return Err(anyhow::anyhow!(error_msg));
+                    }
                     last_error = Some(anyhow::anyhow!(error_msg));
                 }
             }
@@ -110,4 +648,99 @@ impl ApiClient {
         
         Err(last_error.unwrap_or_else(|| anyhow::anyhow!("所有重试都失败了")))
     }
+
+    // 暂停所有并发请求
+    fn pause_all(&self, wait: Duration) {
+        println!("    ⏸️  触发限流，所有请求暂停 {:?}", wait);
+        self.scheduler.pause(Resource::Llm, wait);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_parser_joins_split_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"data: {\"choices\"").is_empty());
+        assert!(parser.push(b":[]}").is_empty());
+        assert_eq!(parser.push(b"\n\ndata: x\r\n"), vec!["{\"choices\":[]}", "x"]);
+    }
+
+    #[test]
+    fn sse_parser_keeps_multibyte_characters_split_across_chunks() {
+        let bytes = "data: 日本語\n".as_bytes();
+        let mut parser = SseParser::default();
+        // 在"日"的 UTF-8 编码中间切开
+        assert!(parser.push(&bytes[..7]).is_empty());
+        assert_eq!(parser.push(&bytes[7..]), vec!["日本語"]);
+    }
+
+    #[test]
+    fn sse_parser_skips_comments_and_other_fields() {
+        let mut parser = SseParser::default();
+        let data = parser.push(b": OPENROUTER PROCESSING\n\nevent: message\nid: 1\ndata: [DONE]\n\n");
+        assert_eq!(data, vec!["[DONE]"]);
+        assert!(parse_stream_data(&data[0]).unwrap().is_none());
+    }
+
+    #[test]
+    fn stream_data_yields_delta_finish_and_usage() {
+        let events = parse_stream_data(
+            r#"{"choices":[{"delta":{"content":"はい"},"finish_reason":"length"}],"usage":{"prompt_tokens":3,"completion_tokens":5,"cost":0.01}}"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(matches!(&events[0], StreamEvent::Delta(text) if text == "はい"));
+        assert!(matches!(&events[1], StreamEvent::Finish(reason) if reason == "length"));
+        assert!(matches!(&events[2], StreamEvent::Usage(Usage { prompt_tokens: 3, completion_tokens: 5, cost: Some(_) })));
+    }
+
+    #[test]
+    fn stream_data_reports_errors() {
+        assert!(parse_stream_data(r#"{"error":{"message":"overloaded"}}"#).is_err());
+        assert!(parse_stream_data("not json").is_err());
+    }
+
+    fn api_config(base_url: Option<&str>) -> crate::config::ApiConfig {
+        let base_url = base_url.map(|url| format!("base_url = \"{}\"\n", url)).unwrap_or_default();
+        toml::from_str(&format!(
+            "openrouter_key = \"key\"\n{}[models]\nextraction_model = \"m\"\nword_analysis_model = \"m\"\ngrammar_analysis_model = \"m\"\n",
+            base_url
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn base_url_selects_endpoint_and_cache_provider() {
+        let default = api_config(None);
+        assert_eq!(default.chat_completions_url(), "https://openrouter.ai/api/v1/chat/completions");
+        assert_eq!(default.provider(), "openrouter");
+
+        let local = api_config(Some("http://localhost:11434/v1/"));
+        assert_eq!(local.chat_completions_url(), "http://localhost:11434/v1/chat/completions");
+        assert_eq!(local.provider(), "http://localhost:11434/v1");
+
+        let request = OpenRouterRequest {
+            model: "m".to_string(),
+            messages: vec![RequestMessage { role: "user".to_string(), content: "こんにちは".to_string() }],
+            max_tokens: 100,
+            temperature: 0.1,
+        };
+        assert_ne!(request.cache_key(&default.provider()), request.cache_key(&local.provider()));
+    }
+
+    #[test]
+    fn request_body_asks_only_openrouter_for_cost() {
+        let request = OpenRouterRequest { model: "m".to_string(), messages: Vec::new(), max_tokens: 100, temperature: 0.1 };
+        let body = |openrouter, stream| serde_json::to_value(RequestBody::new(&request, openrouter, stream)).unwrap();
+
+        assert_eq!(body(true, false)["usage"]["include"], true);
+        assert!(body(true, false).get("stream").is_none());
+        assert!(body(false, false).get("usage").is_none());
+        let streamed = body(false, true);
+        assert_eq!(streamed["stream"], true);
+        assert_eq!(streamed["stream_options"]["include_usage"], true);
+    }
 }
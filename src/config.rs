@@ -1,6 +1,11 @@
 use anyhow::Result;
 use serde::Deserialize;
 
+use std::collections::HashMap;
+
+use crate::models::ApiOperation;
+use crate::prompts::Language;
+
 // 配置文件结构
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
@@ -10,12 +15,68 @@ pub struct Config {
     pub output: OutputConfig,
     pub input: InputConfig,
     pub tts: TtsConfig,
+    #[serde(default)]
+    pub user: UserConfig,
+    #[serde(default)]
+    pub levels: LevelsConfig,
+    #[serde(default)]
+    pub study_plan: StudyPlanConfig,
+    #[serde(default)]
+    pub review: ReviewConfig,
+    #[serde(default)]
+    pub kanji: KanjiConfig,
+    #[serde(default)]
+    pub conjugation: ConjugationConfig,
+    #[serde(default)]
+    pub language: LanguageConfig,
+    #[serde(default)]
+    pub prompts: PromptConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub budget: BudgetConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+    #[serde(default)]
+    pub streaming: StreamingConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ApiConfig {
     pub openrouter_key: String,
+    // OpenAI 兼容接口的地址（/chat/completions 之前的部分），默认为 OpenRouter
+    #[serde(default = "default_base_url")]
+    pub base_url: String,
     pub models: ModelConfig,
+    // 各用途（extraction、word_analysis 等）请求的 max_tokens，未列出的用途使用默认值
+    #[serde(default)]
+    pub max_tokens: HashMap<String, u32>,
+}
+
+impl ApiConfig {
+    // 聊天补全接口的完整地址
+    pub fn chat_completions_url(&self) -> String {
+        format!("{}/chat/completions", self.base_url.trim_end_matches('/'))
+    }
+
+    // 是否为 OpenRouter，只有 OpenRouter 支持在响应中返回费用
+    pub fn is_openrouter(&self) -> bool {
+        self.base_url.trim_end_matches('/') == OPENROUTER_BASE_URL
+    }
+
+    // 缓存键中记录的服务商：OpenRouter 沿用 "openrouter"（兼容已有的缓存），其他服务商为接口地址
+    pub fn provider(&self) -> String {
+        if self.is_openrouter() {
+            "openrouter".to_string()
+        } else {
+            self.base_url.trim_end_matches('/').to_string()
+        }
+    }
+
+    // 某个用途请求的 max_tokens
+    pub fn max_tokens_for(&self, operation: ApiOperation) -> u32 {
+        self.max_tokens.get(operation.as_str()).copied().unwrap_or_else(|| operation.default_max_tokens())
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -23,6 +84,9 @@ pub struct ModelConfig {
     pub extraction_model: String,
     pub word_analysis_model: String,
     pub grammar_analysis_model: String,
+    // 审查解析的模型，设置后每个新解析都会交给该模型检查，未设置时不审查
+    #[serde(default)]
+    pub review_model: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -31,11 +95,42 @@ pub struct ProcessingConfig {
     pub request_delay_ms: u64,
     pub max_retries: u32,
     pub request_timeout_seconds: u64,
+    // 每分钟最多发出的请求数，所有并发请求共享，0 表示不限制
+    #[serde(default)]
+    pub requests_per_minute: u32,
+    // 重试前最长等待时间（毫秒），限流要求等待更久时放弃该请求
+    #[serde(default = "default_max_retry_delay_ms")]
+    pub max_retry_delay_ms: u64,
+    // 审查分数低于该值的解析标记为待重新分析
+    #[serde(default = "default_review_min_score")]
+    pub review_min_score: i64,
+}
+
+const OPENROUTER_BASE_URL: &str = "https://openrouter.ai/api/v1";
+
+fn default_base_url() -> String {
+    OPENROUTER_BASE_URL.to_string()
+}
+
+fn default_review_min_score() -> i64 {
+    70
+}
+
+fn default_max_retry_delay_ms() -> u64 {
+    60_000
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct DatabaseConfig {
+    #[serde(default = "default_db_file")]
     pub db_file: String,
+    // 数据库地址，postgres:// 使用 PostgreSQL 后端（需启用 postgres feature），未设置时使用 db_file
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+fn default_db_file() -> String {
+    "anki_cards.db".to_string()
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -43,6 +138,24 @@ pub struct OutputConfig {
     pub words_file: String,
     pub grammar_file: String,
     pub audio_dir: String,
+    #[serde(default = "default_kanji_file")]
+    pub kanji_file: String,
+    #[serde(default = "default_conjugation_file")]
+    pub conjugation_file: String,
+    #[serde(default = "default_sentences_file")]
+    pub sentences_file: String,
+}
+
+fn default_kanji_file() -> String {
+    "japanese_kanji.csv".to_string()
+}
+
+fn default_conjugation_file() -> String {
+    "japanese_conjugation.csv".to_string()
+}
+
+fn default_sentences_file() -> String {
+    "japanese_sentences.csv".to_string()
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -57,6 +170,214 @@ pub struct TtsConfig {
     pub azure_voice_name: String,
 }
 
+// 当前学习者，多人共享词库时每人使用自己的名称
+#[derive(Debug, Deserialize, Clone)]
+pub struct UserConfig {
+    #[serde(default = "default_user_name")]
+    pub name: String,
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        UserConfig { name: default_user_name() }
+    }
+}
+
+fn default_user_name() -> String {
+    "default".to_string()
+}
+
+// 本地词频表和 JLPT 等级表，以及生成卡片时的筛选条件
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct LevelsConfig {
+    pub word_frequency_file: Option<String>,
+    pub word_jlpt_file: Option<String>,
+    pub grammar_frequency_file: Option<String>,
+    pub grammar_jlpt_file: Option<String>,
+    // 只为词频排名在此范围内的条目生成卡片，设置后未收录排名的条目视为超出范围
+    pub min_frequency_rank: Option<i64>,
+    pub max_frequency_rank: Option<i64>,
+    // 只为这些 JLPT 等级生成卡片，例如 ["N5", "N4"]，为空时不过滤
+    pub jlpt_levels: Vec<String>,
+}
+
+// 新卡片排序权重，全部为 0 时按 ID 排序
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct StudyPlanConfig {
+    pub frequency_weight: f64,
+    pub occurrence_weight: f64,
+    pub jlpt_weight: f64,
+    pub kanji_overlap_weight: f64,
+}
+
+impl Default for StudyPlanConfig {
+    fn default() -> Self {
+        StudyPlanConfig {
+            frequency_weight: 1.0,
+            occurrence_weight: 1.0,
+            jlpt_weight: 1.0,
+            kanji_overlap_weight: 0.5,
+        }
+    }
+}
+
+// 内置复习（FSRS）设置
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct ReviewConfig {
+    pub desired_retention: f64,
+    pub maximum_interval: f64,
+    pub new_cards_per_day: usize,
+    // 自定义 FSRS 参数（17 个），为空时使用默认参数
+    pub fsrs_weights: Vec<f64>,
+    // 播放音频的外部命令，例如 "mpv --really-quiet"，音频文件路径作为最后一个参数
+    pub audio_player: Option<String>,
+}
+
+impl Default for ReviewConfig {
+    fn default() -> Self {
+        ReviewConfig {
+            desired_retention: 0.9,
+            maximum_interval: 36500.0,
+            new_cards_per_day: 20,
+            fsrs_weights: Vec::new(),
+            audio_player: None,
+        }
+    }
+}
+
+// 汉字数据文件（UTF-8 编码），未配置时汉字卡片只包含字本身和例词
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct KanjiConfig {
+    // KANJIDIC 格式：读音、意思和笔画数
+    pub kanjidic_file: Option<String>,
+    // KRADFILE 格式：汉字部件
+    pub kradfile: Option<String>,
+}
+
+// 活用练习卡片：每个动词/形容词的每个活用形一张卡片
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ConjugationConfig {
+    // 生成单词卡片时同时生成活用练习卡片
+    pub drill_cards: bool,
+    // 练习的活用形，例如 ["te", "nai", "potential"]，为空时练习所有活用形
+    pub drill_forms: Vec<String>,
+}
+
+// 解析语言：zh（简体中文）、en（英语）、ja（日语，单语解析）
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct LanguageConfig {
+    // 单词、语法解析和汉字记忆口诀使用的语言
+    pub explanation: Language,
+    // 卡片上词性名称使用的语言
+    pub pos_labels: Language,
+    // 例句翻译使用的语言，与 explanation 同为 ja 时不翻译
+    pub translation: Language,
+}
+
+// 提示词模板目录，目录中的模板文件覆盖同名的内置模板
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct PromptConfig {
+    pub dir: String,
+}
+
+impl Default for PromptConfig {
+    fn default() -> Self {
+        PromptConfig {
+            dir: "prompts".to_string(),
+        }
+    }
+}
+
+// 模型回复缓存：相同的请求（模型、提示词、temperature、max_tokens）直接使用缓存的回复
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    // 缓存保留天数，0 表示不过期
+    pub ttl_days: i64,
+    // 命令行 --no-cache：本次运行不读取缓存，新的回复仍写入缓存
+    #[serde(skip)]
+    pub bypass: bool,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            enabled: true,
+            ttl_days: 30,
+            bypass: false,
+        }
+    }
+}
+
+// 每次运行的请求预算，达到上限后不再发出新的请求；0 表示不限制
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct BudgetConfig {
+    // 费用上限（美元），只统计服务商返回了费用的请求
+    pub max_cost: f64,
+    // token 上限（提示词和回复合计）
+    pub max_tokens: i64,
+}
+
+// 各资源的并发和每分钟请求数上限，未设置的项使用默认值
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    // 模型请求，默认使用 [processing] 的 concurrent_requests 和 requests_per_minute
+    pub llm: ResourceLimits,
+    // 语音合成，默认并发 concurrent_requests、不限制每分钟请求数
+    pub tts: ResourceLimits,
+    // 数据库写入，默认同时只有一个写入
+    pub db_write: ResourceLimits,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ResourceLimits {
+    pub concurrency: Option<usize>,
+    // 0 表示不限制
+    pub requests_per_minute: Option<u32>,
+}
+
+// 流式接收模型回复：显示生成进度，超过字数上限时中断
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct StreamingConfig {
+    pub enabled: bool,
+    // 每生成多少字显示一次进度，0 表示不显示
+    pub progress_interval: usize,
+    // 各用途（extraction、word_analysis 等）生成内容的字数上限，未列出的用途不限制
+    pub max_chars: HashMap<String, usize>,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        StreamingConfig {
+            enabled: true,
+            progress_interval: 2000,
+            max_chars: HashMap::from([
+                (ApiOperation::WordAnalysis.as_str().to_string(), 20000),
+                (ApiOperation::GrammarAnalysis.as_str().to_string(), 20000),
+            ]),
+        }
+    }
+}
+
+impl StreamingConfig {
+    // 某个用途的字数上限，0 表示不限制
+    pub fn max_chars_for(&self, operation: ApiOperation) -> usize {
+        self.max_chars.get(operation.as_str()).copied().unwrap_or(0)
+    }
+}
+
 impl Config {
     pub fn load() -> Result<Config> {
         let config_content = std::fs::read_to_string("config.toml")
@@ -0,0 +1,395 @@
+use crate::pos::{self, PartOfSpeech};
+use crate::text::is_kanji;
+
+/// 活用类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordClass {
+    // 五段动词（行く的て形/た形特殊）
+    Godan { iku: bool, aru: bool },
+    // 一段动词
+    Ichidan,
+    // サ变动词（する、勉強する、名词 + する）
+    Suru,
+    // カ变动词（来る）
+    Kuru,
+    IAdjective,
+    NaAdjective,
+}
+
+impl WordClass {
+    pub fn label(&self) -> &'static str {
+        match self {
+            WordClass::Godan { .. } => "五段动词",
+            WordClass::Ichidan => "一段动词",
+            WordClass::Suru => "サ变动词",
+            WordClass::Kuru => "カ变动词",
+            WordClass::IAdjective => "一类形容词",
+            WordClass::NaAdjective => "二类形容词",
+        }
+    }
+
+    /// 该类型生成的活用形
+    pub fn forms(&self) -> &'static [ConjugationForm] {
+        use ConjugationForm::*;
+        match self {
+            WordClass::IAdjective | WordClass::NaAdjective => &[Masu, Te, Ta, Nai, PastNegative, Conditional],
+            _ => &[Masu, Te, Ta, Nai, Potential, Passive, Causative, Volitional, Conditional],
+        }
+    }
+}
+
+/// 活用形
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConjugationForm {
+    Masu,
+    Te,
+    Ta,
+    Nai,
+    PastNegative,
+    Potential,
+    Passive,
+    Causative,
+    Volitional,
+    Conditional,
+}
+
+impl ConjugationForm {
+    pub const ALL: [ConjugationForm; 10] = [
+        ConjugationForm::Masu,
+        ConjugationForm::Te,
+        ConjugationForm::Ta,
+        ConjugationForm::Nai,
+        ConjugationForm::PastNegative,
+        ConjugationForm::Potential,
+        ConjugationForm::Passive,
+        ConjugationForm::Causative,
+        ConjugationForm::Volitional,
+        ConjugationForm::Conditional,
+    ];
+
+    /// 配置和卡片ID中使用的名称
+    pub fn key(&self) -> &'static str {
+        match self {
+            ConjugationForm::Masu => "masu",
+            ConjugationForm::Te => "te",
+            ConjugationForm::Ta => "ta",
+            ConjugationForm::Nai => "nai",
+            ConjugationForm::PastNegative => "past_negative",
+            ConjugationForm::Potential => "potential",
+            ConjugationForm::Passive => "passive",
+            ConjugationForm::Causative => "causative",
+            ConjugationForm::Volitional => "volitional",
+            ConjugationForm::Conditional => "conditional",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|form| form.key() == key.trim())
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConjugationForm::Masu => "ます形",
+            ConjugationForm::Te => "て形",
+            ConjugationForm::Ta => "た形",
+            ConjugationForm::Nai => "ない形",
+            ConjugationForm::PastNegative => "过去否定",
+            ConjugationForm::Potential => "可能形",
+            ConjugationForm::Passive => "被动形",
+            ConjugationForm::Causative => "使役形",
+            ConjugationForm::Volitional => "意志形",
+            ConjugationForm::Conditional => "条件形",
+        }
+    }
+}
+
+/// 一个活用形的单词写法和假名
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conjugation {
+    pub form: ConjugationForm,
+    pub word: String,
+    pub kana: String,
+}
+
+// 以る结尾、前一个假名为い段/え段，但属于五段动词的词（按写法匹配，変える、閉める等同音的一段动词不受影响）
+const GODAN_RU_WORDS: &[&str] = &[
+    "帰る", "入る", "走る", "知る", "切る", "要る", "限る", "減る", "滑る", "握る", "練る", "参る", "混じる",
+    "喋る", "蹴る", "茂る", "焦る", "覆る", "遮る", "罵る", "捻る", "翻る", "照る", "湿る", "嘲る", "陥る",
+    "散る", "耽る", "蘇る", "甦る", "挿る", "競る",
+];
+// 只用假名书写时的五段动词，仅在单词本身就是假名写法时匹配
+const GODAN_RU_KANA: &[&str] = &["かえる", "はいる", "はしる", "しゃべる", "ける", "あせる", "ひねる", "しめる"];
+
+/// 根据假名和词性判断活用类型，不是动词或形容词时返回 None
+pub fn classify(word: &str, kana: &str, part_of_speech: &str) -> Option<WordClass> {
+    let parts = pos::normalize_field(part_of_speech).parts;
+    let has = |targets: &[PartOfSpeech]| parts.iter().any(|p| targets.contains(p));
+
+    if has(&[PartOfSpeech::IAdjective]) && kana.ends_with('い') {
+        return Some(WordClass::IAdjective);
+    }
+    if has(&[PartOfSpeech::NaAdjective]) {
+        return Some(WordClass::NaAdjective);
+    }
+    if !has(&[PartOfSpeech::Verb, PartOfSpeech::IntransitiveVerb, PartOfSpeech::TransitiveVerb]) {
+        return None;
+    }
+
+    // 按写法判断，擦る（こする）、刷る（する）等读音以 する 结尾的五段动词不受影响
+    if word.ends_with("する") || word == "為る" {
+        return Some(WordClass::Suru);
+    }
+    // 繰る（くる）等同音的五段动词不受影响
+    if kana == "くる" && (word == kana || word.ends_with("来る")) || kana.ends_with("くる") && word.ends_with("来る") {
+        return Some(WordClass::Kuru);
+    }
+    // 以汉字或片假名结尾的动词视为 名词 + する
+    if word.chars().last().is_some_and(|c| is_kanji(c) || matches!(c, '\u{30A0}'..='\u{30FF}')) {
+        return Some(WordClass::Suru);
+    }
+
+    let mut chars = kana.chars().rev();
+    let last = chars.next()?;
+    if last == 'る' {
+        let before = chars.next().unwrap_or(' ');
+        let is_exception =
+            GODAN_RU_WORDS.iter().any(|w| word.ends_with(w)) || word == kana && GODAN_RU_KANA.contains(&kana);
+        if is_i_or_e_row(before) && !is_exception {
+            return Some(WordClass::Ichidan);
+        }
+    }
+    godan_row(last)?;
+
+    let iku = kana.ends_with("いく") && (word.ends_with("行く") || word == kana) || kana == "ゆく";
+    let aru = kana == "ある";
+    Some(WordClass::Godan { iku, aru })
+}
+
+/// 生成活用表；单词写法不以对应假名结尾时，单词列与假名列相同
+pub fn conjugate(word: &str, kana: &str, class: WordClass) -> Vec<Conjugation> {
+    class
+        .forms()
+        .iter()
+        .filter_map(|&form| {
+            let kana_form = conjugate_text(kana, class, form, true)?;
+            let word_form = conjugate_text(word, class, form, word == kana).unwrap_or_else(|| kana_form.clone());
+            Some(Conjugation { form, word: word_form, kana: kana_form })
+        })
+        .collect()
+}
+
+fn conjugate_text(text: &str, class: WordClass, form: ConjugationForm, is_kana: bool) -> Option<String> {
+    use ConjugationForm::*;
+    match class {
+        WordClass::Godan { iku, aru } => {
+            let last = text.chars().last()?;
+            let stem = &text[..text.len() - last.len_utf8()];
+            let [a, i, e, o] = godan_row(last)?;
+            let a = if last == 'う' { 'わ' } else { a };
+            Some(match form {
+                Masu => format!("{}{}ます", stem, i),
+                Te => format!("{}{}", stem, godan_te(last, iku, 'て')),
+                Ta => format!("{}{}", stem, godan_te(last, iku, 'た')),
+                Nai if aru => "ない".to_string(),
+                Nai => format!("{}{}ない", stem, a),
+                Potential => format!("{}{}る", stem, e),
+                Passive => format!("{}{}れる", stem, a),
+                Causative => format!("{}{}せる", stem, a),
+                Volitional => format!("{}{}う", stem, o),
+                Conditional => format!("{}{}ば", stem, e),
+                PastNegative => return None,
+            })
+        }
+        WordClass::Ichidan => {
+            let stem = text.strip_suffix('る')?;
+            Some(match form {
+                Masu => format!("{}ます", stem),
+                Te => format!("{}て", stem),
+                Ta => format!("{}た", stem),
+                Nai => format!("{}ない", stem),
+                Potential | Passive => format!("{}られる", stem),
+                Causative => format!("{}させる", stem),
+                Volitional => format!("{}よう", stem),
+                Conditional => format!("{}れば", stem),
+                PastNegative => return None,
+            })
+        }
+        WordClass::Suru => {
+            let stem = text.strip_suffix("する").or_else(|| text.strip_suffix("為る")).unwrap_or(text);
+            Some(match form {
+                Masu => format!("{}します", stem),
+                Te => format!("{}して", stem),
+                Ta => format!("{}した", stem),
+                Nai => format!("{}しない", stem),
+                Potential => format!("{}できる", stem),
+                Passive => format!("{}される", stem),
+                Causative => format!("{}させる", stem),
+                Volitional => format!("{}しよう", stem),
+                Conditional => format!("{}すれば", stem),
+                PastNegative => return None,
+            })
+        }
+        WordClass::Kuru => {
+            let (stem, kanji) = match text.strip_suffix("来る") {
+                Some(stem) if !is_kana => (stem, true),
+                _ => (text.strip_suffix("くる")?, false),
+            };
+            // 汉字写法统一用"来"，假名写法随活用变为 き/こ/く
+            let (kana_head, tail) = match form {
+                Masu => ("き", "ます"),
+                Te => ("き", "て"),
+                Ta => ("き", "た"),
+                Nai => ("こ", "ない"),
+                Potential | Passive => ("こ", "られる"),
+                Causative => ("こ", "させる"),
+                Volitional => ("こ", "よう"),
+                Conditional => ("く", "れば"),
+                PastNegative => return None,
+            };
+            Some(format!("{}{}{}", stem, if kanji { "来" } else { kana_head }, tail))
+        }
+        WordClass::IAdjective => {
+            if form == Masu {
+                return Some(format!("{}です", text));
+            }
+            // いい 的其他活用使用 よ
+            let stem = match text.strip_suffix("いい") {
+                Some(head) if head.is_empty() || head.ends_with('が') || head.ends_with("っこ") => format!("{}よ", head),
+                _ => text.strip_suffix('い')?.to_string(),
+            };
+            Some(match form {
+                Te => format!("{}くて", stem),
+                Ta => format!("{}かった", stem),
+                Nai => format!("{}くない", stem),
+                PastNegative => format!("{}くなかった", stem),
+                Conditional => format!("{}ければ", stem),
+                _ => return None,
+            })
+        }
+        WordClass::NaAdjective => {
+            let stem = text.strip_suffix('な').unwrap_or(text);
+            Some(match form {
+                Masu => format!("{}です", stem),
+                Te => format!("{}で", stem),
+                Ta => format!("{}だった", stem),
+                Nai => format!("{}じゃない", stem),
+                PastNegative => format!("{}じゃなかった", stem),
+                Conditional => format!("{}なら", stem),
+                _ => return None,
+            })
+        }
+    }
+}
+
+// 五段动词词尾所在行的 あ/い/え/お 段假名
+fn godan_row(last: char) -> Option<[char; 4]> {
+    Some(match last {
+        'う' => ['あ', 'い', 'え', 'お'],
+        'く' => ['か', 'き', 'け', 'こ'],
+        'ぐ' => ['が', 'ぎ', 'げ', 'ご'],
+        'す' => ['さ', 'し', 'せ', 'そ'],
+        'つ' => ['た', 'ち', 'て', 'と'],
+        'ぬ' => ['な', 'に', 'ね', 'の'],
+        'ぶ' => ['ば', 'び', 'べ', 'ぼ'],
+        'む' => ['ま', 'み', 'め', 'も'],
+        'る' => ['ら', 'り', 'れ', 'ろ'],
+        _ => return None,
+    })
+}
+
+// 五段动词的て形/た形词尾（音便）
+fn godan_te(last: char, iku: bool, te: char) -> String {
+    let voiced = if te == 'て' { 'で' } else { 'だ' };
+    match last {
+        'う' | 'つ' | 'る' => format!("っ{}", te),
+        'く' if iku => format!("っ{}", te),
+        'む' | 'ぶ' | 'ぬ' => format!("ん{}", voiced),
+        'く' => format!("い{}", te),
+        'ぐ' => format!("い{}", voiced),
+        _ => format!("し{}", te),
+    }
+}
+
+fn is_i_or_e_row(c: char) -> bool {
+    "いきぎしじちぢにひびぴみりえけげせぜてでねへべぺめれ".contains(c)
+}
+
+/// 单词卡片的活用表（HTML），不是动词或形容词时返回空字符串
+pub fn conjugation_table(word: &str, kana: &str, part_of_speech: &str) -> String {
+    let Some(class) = classify(word, kana, part_of_speech) else {
+        return String::new();
+    };
+
+    let rows: String = conjugate(word, kana, class)
+        .iter()
+        .map(|c| {
+            let reading = if c.word == c.kana { String::new() } else { format!("（{}）", c.kana) };
+            format!("<tr><td>{}</td><td>{}{}</td></tr>", c.form.label(), c.word, reading)
+        })
+        .collect();
+    format!("<div><b>活用（{}）</b></div><table>{}</table>", class.label(), rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form(word: &str, kana: &str, class: WordClass, form: ConjugationForm) -> String {
+        conjugate(word, kana, class).into_iter().find(|c| c.form == form).unwrap().word
+    }
+
+    #[test]
+    fn godan_exception_matches_written_form() {
+        assert_eq!(classify("帰る", "かえる", "自动词"), Some(WordClass::Godan { iku: false, aru: false }));
+        assert_eq!(classify("変える", "かえる", "他动词"), Some(WordClass::Ichidan));
+        assert_eq!(classify("替える", "かえる", "他动词"), Some(WordClass::Ichidan));
+        assert_eq!(classify("かえる", "かえる", "自动词"), Some(WordClass::Godan { iku: false, aru: false }));
+    }
+
+    #[test]
+    fn shimeru_homophones_are_ichidan() {
+        for word in ["閉める", "締める", "占める"] {
+            let class = classify(word, "しめる", "他动词");
+            assert_eq!(class, Some(WordClass::Ichidan), "{}", word);
+            assert_eq!(form(word, "しめる", WordClass::Ichidan, ConjugationForm::Te), format!("{}て", word.trim_end_matches('る')));
+        }
+        assert_eq!(classify("湿る", "しめる", "自动词"), Some(WordClass::Godan { iku: false, aru: false }));
+        assert_eq!(classify("褪せる", "あせる", "自动词"), Some(WordClass::Ichidan));
+        assert_eq!(classify("焦る", "あせる", "自动词"), Some(WordClass::Godan { iku: false, aru: false }));
+    }
+
+    #[test]
+    fn na_adjective_is_not_i_adjective() {
+        assert_eq!(classify("綺麗", "きれい", "ナ形容詞"), Some(WordClass::NaAdjective));
+        assert_eq!(classify("綺麗", "きれい", "二类形容词"), Some(WordClass::NaAdjective));
+        assert_eq!(form("綺麗", "きれい", WordClass::NaAdjective, ConjugationForm::Nai), "綺麗じゃない");
+        assert_eq!(classify("高い", "たかい", "形容詞"), Some(WordClass::IAdjective));
+    }
+
+    #[test]
+    fn suru_and_kuru_match_written_form() {
+        let godan = Some(WordClass::Godan { iku: false, aru: false });
+        assert_eq!(classify("擦る", "こする", "他动词"), godan);
+        assert_eq!(form("擦る", "こする", WordClass::Godan { iku: false, aru: false }, ConjugationForm::Masu), "擦ります");
+        assert_eq!(classify("刷る", "する", "他动词"), godan);
+        assert_eq!(classify("繰る", "くる", "五段动词"), godan);
+        assert_eq!(form("繰る", "くる", WordClass::Godan { iku: false, aru: false }, ConjugationForm::Te), "繰って");
+
+        assert_eq!(classify("為る", "する", "他动词"), Some(WordClass::Suru));
+        assert_eq!(form("為る", "する", WordClass::Suru, ConjugationForm::Masu), "します");
+        assert_eq!(classify("勉強する", "べんきょうする", "サ变动词"), Some(WordClass::Suru));
+        assert_eq!(classify("勉強", "べんきょう", "サ变动词"), Some(WordClass::Suru));
+        assert_eq!(classify("する", "する", "他动词"), Some(WordClass::Suru));
+
+        assert_eq!(classify("来る", "くる", "自动词"), Some(WordClass::Kuru));
+        assert_eq!(classify("くる", "くる", "自动词"), Some(WordClass::Kuru));
+        assert_eq!(classify("持って来る", "もってくる", "自动词"), Some(WordClass::Kuru));
+        assert_eq!(classify("送る", "おくる", "他动词"), godan);
+    }
+
+    #[test]
+    fn auxiliary_verb_is_not_conjugated() {
+        assert_eq!(classify("たい", "たい", "助动词"), None);
+        assert_eq!(classify("られる", "られる", "助動詞"), None);
+    }
+}
@@ -1,20 +1,96 @@
 use anyhow::Result;
-use sqlx::SqlitePool;
+use async_trait::async_trait;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
 
 use crate::config::Config;
+use crate::conjugation::{ConjugationForm, classify, conjugate, conjugation_table};
+use crate::levels::entry_tags;
 use crate::models::*;
+use crate::grammar::{ConnectionBase, GrammarRegister, canonical_grammar};
+use crate::pos::{normalize_field, normalize_label};
+use crate::prompts::Language;
+use crate::storage::Storage;
+use crate::text::{escape_html, kana_to_romaji, strip_html};
 
+// 查询单词和语法时使用的列
+const WORD_COLUMNS: &str = "id, word, kana, pitch, part_of_speech, analysis, updated_at, analysis_locked, pos_locked, pitch_locked, kana_locked, tags, frequency_rank, jlpt_level, prompt_version";
+const GRAMMAR_COLUMNS: &str = "id, word, kana, analysis, analysis_locked, kana_locked, tags, updated_at, frequency_rank, jlpt_level, prompt_version";
+
+// 导出复习记录，列名与 Anki revlog 一致（SQLite 和 PostgreSQL 通用）
+pub(crate) const REVLOG_QUERY: &str = r#"
+    SELECT l.reviewed_at_ms AS id, l.card_id AS cid, CAST(-1 AS BIGINT) AS usn, l.ease, l.ivl, l.last_ivl,
+        CAST(0 AS BIGINT) AS factor,
+        l.time_ms AS time, l.review_type, c.entry_kind, COALESCE(w.word, g.word, '') AS word,
+        l.stability, l.difficulty
+    FROM review_log l
+    JOIN review_cards c ON l.card_id = c.id
+    LEFT JOIN words w ON c.entry_kind = 'word' AND w.id = c.entry_id
+    LEFT JOIN grammar g ON c.entry_kind = 'grammar' AND g.id = c.entry_id
+    WHERE l.user_id = $1
+    ORDER BY l.reviewed_at_ms
+"#;
+
+// 导出所有学习者的复习卡片和复习记录，条目用自然键表示（SQLite 和 PostgreSQL 通用）
+pub(crate) const REVIEW_CARDS_QUERY: &str = r#"
+    SELECT u.name AS user_name, c.entry_kind, COALESCE(w.word, g.word) AS word, COALESCE(w.kana, g.kana) AS kana,
+        w.pitch, c.state, c.due, c.stability, c.difficulty, c.reps, c.lapses, c.last_review, c.created_at
+    FROM review_cards c
+    JOIN users u ON c.user_id = u.id
+    LEFT JOIN words w ON c.entry_kind = 'word' AND w.id = c.entry_id
+    LEFT JOIN grammar g ON c.entry_kind = 'grammar' AND g.id = c.entry_id
+    WHERE COALESCE(w.id, g.id) IS NOT NULL
+    ORDER BY u.name, c.id
+"#;
+
+pub(crate) const REVIEW_HISTORY_QUERY: &str = r#"
+    SELECT u.name AS user_name, c.entry_kind, COALESCE(w.word, g.word) AS word, COALESCE(w.kana, g.kana) AS kana,
+        w.pitch, l.ease, l.ivl, l.last_ivl, l.time_ms, l.review_type, l.stability, l.difficulty, l.elapsed_days,
+        l.reviewed_at, l.reviewed_at_ms
+    FROM review_log l
+    JOIN review_cards c ON l.card_id = c.id
+    JOIN users u ON l.user_id = u.id
+    LEFT JOIN words w ON c.entry_kind = 'word' AND w.id = c.entry_id
+    LEFT JOIN grammar g ON c.entry_kind = 'grammar' AND g.id = c.entry_id
+    WHERE COALESCE(w.id, g.id) IS NOT NULL
+    ORDER BY u.name, l.reviewed_at_ms, l.id
+"#;
+
+// 导出例句和条目的关联，条目用自然键表示（SQLite 和 PostgreSQL 通用）
+pub(crate) const SENTENCE_ENTRIES_QUERY: &str = r#"
+    SELECT e.sentence_id, e.entry_kind, COALESCE(w.word, g.word) AS word, COALESCE(w.kana, g.kana) AS kana, w.pitch
+    FROM sentence_entries e
+    LEFT JOIN words w ON e.entry_kind = 'word' AND w.id = e.entry_id
+    LEFT JOIN grammar g ON e.entry_kind = 'grammar' AND g.id = e.entry_id
+    WHERE COALESCE(w.id, g.id) IS NOT NULL
+    ORDER BY e.sentence_id, e.entry_kind, e.entry_id
+"#;
+
+// 导出其他语言的解析（SQLite 和 PostgreSQL 通用）
+pub(crate) const ANALYSES_QUERY: &str = r#"
+    SELECT a.entry_kind, COALESCE(w.word, g.word) AS word, COALESCE(w.kana, g.kana) AS kana,
+        a.language, a.analysis, a.updated_at, a.prompt_version
+    FROM entry_analyses a
+    LEFT JOIN words w ON a.entry_kind = 'word' AND w.id = a.entry_id
+    LEFT JOIN grammar g ON a.entry_kind = 'grammar' AND g.id = a.entry_id
+    WHERE COALESCE(w.id, g.id) IS NOT NULL
+    ORDER BY a.entry_kind, a.entry_id, a.language
+"#;
+
+// SQLite 存储后端（默认）
 pub struct DatabaseManager {
     pool: SqlitePool,
-    config: Config,
 }
 
 impl DatabaseManager {
     pub async fn new(config: Config) -> Result<Self> {
-        // 创建数据库文件路径
-        let db_path = std::env::current_dir()?.join(&config.database.db_file);
+        // 创建数据库文件路径（database.url 为 sqlite: 地址时优先使用）
+        let db_file = config.database.url.as_deref()
+            .and_then(|url| url.strip_prefix("sqlite:"))
+            .unwrap_or(&config.database.db_file);
+        let db_path = std::env::current_dir()?.join(db_file);
         let db_url = format!("sqlite:{}", db_path.display());
         
         println!("💾 连接数据库: {}", db_path.display());
@@ -27,7 +103,7 @@ impl DatabaseManager {
         
         let pool = SqlitePool::connect(&db_url).await?;
         
-        let manager = DatabaseManager { pool, config };
+        let manager = DatabaseManager { pool };
         manager.initialize_tables().await?;
         
         Ok(manager)
@@ -142,266 +218,3075 @@ impl DatabaseManager {
                 word TEXT NOT NULL UNIQUE,
                 kana TEXT NOT NULL,
                 analysis TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
             )
             "#
         ).execute(&self.pool).await?;
 
-        Ok(())
-    }
+        // 检查字段锁定列是否存在（手动编辑过的字段不会被批量更新覆盖）
+        let lock_columns = [
+            ("words", "analysis_locked"),
+            ("words", "pos_locked"),
+            ("words", "pitch_locked"),
+            ("words", "kana_locked"),
+            ("grammar", "analysis_locked"),
+            ("grammar", "kana_locked"),
+        ];
+        for (table, column) in lock_columns {
+            let exists = sqlx::query(&format!("SELECT {} FROM {} LIMIT 1", column, table))
+                .execute(&self.pool)
+                .await
+                .is_ok();
 
-    // 检查单词是否已存在（根据单词、假名，不依据音调和词性）
-    pub async fn check_word_exists(&self, word: &str, kana: &str) -> Result<bool> {
-        let count: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM words WHERE word = ? AND kana = ?"
-        )
-        .bind(word)
-        .bind(kana)
-        .fetch_one(&self.pool)
-        .await?;
-        
-        Ok(count.0 > 0)
-    }
+            if !exists {
+                println!("   添加 {}.{} 列...", table, column);
+                sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} INTEGER NOT NULL DEFAULT 0", table, column))
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
 
-    // 获取已存在的单词信息（只基于 word 和 kana）
-    pub async fn get_existing_word_by_word_kana(&self, word: &str, kana: &str) -> Result<Option<JapaneseWord>> {
-        let result = sqlx::query_as::<_, JapaneseWord>(
-            "SELECT id, word, kana, pitch, part_of_speech, analysis, updated_at FROM words WHERE word = ? AND kana = ? LIMIT 1"
-        )
-        .bind(word)
-        .bind(kana)
-        .fetch_optional(&self.pool)
-        .await?;
-        
-        Ok(result)
-    }
+        // 检查语法表的 updated_at 列是否存在
+        let grammar_updated_at_exists = sqlx::query("SELECT updated_at FROM grammar LIMIT 1")
+            .execute(&self.pool)
+            .await
+            .is_ok();
 
-    // 获取已存在的单词信息（支持多词性合并）
-    pub async fn get_existing_word(&self, word: &str, kana: &str, pitch: &str) -> Result<Option<MergedWord>> {
-        let result = sqlx::query_as::<_, JapaneseWord>(
-            "SELECT id, word, kana, pitch, part_of_speech, analysis, updated_at FROM words WHERE word = ? AND kana = ? AND pitch = ? LIMIT 1"
-        )
-        .bind(word)
-        .bind(kana)
-        .bind(pitch)
-        .fetch_optional(&self.pool)
-        .await?;
-        
-        if let Some(existing) = result {
-            // 获取该单词的所有词性
-            let all_pos: Vec<(String,)> = sqlx::query_as(
-                "SELECT DISTINCT part_of_speech FROM words WHERE word = ? AND kana = ? AND pitch = ?"
-            )
-            .bind(word)
-            .bind(kana)
-            .bind(pitch)
-            .fetch_all(&self.pool)
-            .await?;
-            
-            let parts_of_speech: Vec<String> = all_pos.into_iter().map(|row| row.0).collect();
-            
-            Ok(Some(MergedWord {
-                id: existing.id,
-                word: existing.word,
-                kana: existing.kana,
-                pitch: existing.pitch,
-                parts_of_speech,
-                analysis: existing.analysis,
-            }))
-        } else {
-            Ok(None)
+        if !grammar_updated_at_exists {
+            println!("   添加 grammar.updated_at 列...");
+            sqlx::query("ALTER TABLE grammar ADD COLUMN updated_at DATETIME")
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("UPDATE grammar SET updated_at = COALESCE(created_at, datetime('now')) WHERE updated_at IS NULL")
+                .execute(&self.pool)
+                .await?;
         }
-    }
 
-    // 获取所有单词
-    pub async fn get_all_words(&self) -> Result<Vec<JapaneseWord>> {
-        let words = sqlx::query_as::<_, JapaneseWord>(
-            "SELECT id, word, kana, pitch, part_of_speech, analysis, updated_at FROM words ORDER BY id"
-        ).fetch_all(&self.pool).await?;
-        
-        Ok(words)
-    }
+        // 检查标签列是否存在
+        for table in ["words", "grammar"] {
+            let tags_exists = sqlx::query(&format!("SELECT tags FROM {} LIMIT 1", table))
+                .execute(&self.pool)
+                .await
+                .is_ok();
 
-    // 保存单词到数据库（新的词性覆盖旧的，不再合并）
-    pub async fn save_words(&self, words: &[WordAnalysis]) -> Result<()> {
-        for word in words {
-            // 检查是否已存在同样的单词（不考虑词性）
-            let existing = sqlx::query_as::<_, JapaneseWord>(
-                "SELECT id, word, kana, pitch, part_of_speech, analysis, updated_at FROM words WHERE word = ? AND kana = ? AND pitch = ? LIMIT 1"
-            )
-            .bind(&word.word)
-            .bind(&word.kana)
-            .bind(&word.pitch)
-            .fetch_optional(&self.pool)
-            .await?;
-            
-            if let Some(existing_word) = existing {
-                // 如果已存在，检查词性是否不同
-                if existing_word.part_of_speech != word.part_of_speech {
-                    println!("  🔄 更新单词词性: {} ({}) - {} -> {}", 
-                        word.word, word.kana, 
-                        existing_word.part_of_speech, 
-                        word.part_of_speech
-                    );
-                    
-                    // 更新记录，以新的词性和分析为准，并更新时间
-                    sqlx::query(
-                        "UPDATE words SET part_of_speech = ?, analysis = ?, updated_at = datetime('now') WHERE id = ?"
-                    )
-                    .bind(&word.part_of_speech)
-                    .bind(&word.analysis)
-                    .bind(existing_word.id)
+            if !tags_exists {
+                println!("   添加 {}.tags 列...", table);
+                sqlx::query(&format!("ALTER TABLE {} ADD COLUMN tags TEXT NOT NULL DEFAULT ''", table))
                     .execute(&self.pool)
                     .await?;
-                } else {
-                    println!("  ✅ 单词词性未变化，跳过更新: {} ({})", word.word, word.kana);
-                }
-            } else {
-                // 如果不存在，直接插入
-                println!("  ➕ 新增单词: {} ({}) - {}", word.word, word.kana, word.part_of_speech);
-                sqlx::query(
-                    "INSERT INTO words (word, kana, pitch, part_of_speech, analysis, updated_at) VALUES (?, ?, ?, ?, ?, datetime('now'))"
-                )
-                .bind(&word.word)
-                .bind(&word.kana)
-                .bind(&word.pitch)
-                .bind(&word.part_of_speech)
-                .bind(&word.analysis)
-                .execute(&self.pool)
-                .await?;
             }
         }
-        Ok(())
-    }
 
-    // 更新单词词性
-    pub async fn update_word_part_of_speech(&self, id: i64, new_pos: &str) -> Result<()> {
-        sqlx::query(
-            "UPDATE words SET part_of_speech = ?, updated_at = datetime('now') WHERE id = ?"
-        )
-        .bind(new_pos)
-        .bind(id)
-        .execute(&self.pool)
-        .await?;
-        
-        Ok(())
-    }
+        // 检查词频和 JLPT 等级列是否存在
+        for table in ["words", "grammar"] {
+            let levels_exist = sqlx::query(&format!("SELECT frequency_rank, jlpt_level FROM {} LIMIT 1", table))
+                .execute(&self.pool)
+                .await
+                .is_ok();
 
-    // 更新单词的 pitch 和词性（处理唯一约束冲突）
-    pub async fn update_word_pitch_and_pos(&self, id: i64, new_pitch: &str, new_pos: &str) -> Result<()> {
-        // 首先获取当前记录的信息
-        let current_word = self.get_word_by_id(id).await?;
-        if current_word.is_none() {
-            return Err(anyhow::anyhow!("单词 ID {} 不存在", id));
+            if !levels_exist {
+                println!("   添加 {}.frequency_rank / jlpt_level 列...", table);
+                sqlx::query(&format!("ALTER TABLE {} ADD COLUMN frequency_rank INTEGER", table))
+                    .execute(&self.pool)
+                    .await?;
+                sqlx::query(&format!("ALTER TABLE {} ADD COLUMN jlpt_level TEXT", table))
+                    .execute(&self.pool)
+                    .await?;
+            }
         }
-        let current = current_word.unwrap();
-        
-        // 检查是否存在相同 (word, kana, pitch) 的其他记录
-        let existing_conflict = sqlx::query_as::<_, JapaneseWord>(
-            "SELECT id, word, kana, pitch, part_of_speech, analysis, updated_at FROM words WHERE word = ? AND kana = ? AND pitch = ? AND id != ? LIMIT 1"
-        )
-        .bind(&current.word)
-        .bind(&current.kana)
-        .bind(new_pitch)
-        .bind(id)
-        .fetch_optional(&self.pool)
-        .await?;
-        
-        if let Some(conflict_record) = existing_conflict {
-            // 如果存在冲突记录，删除冲突记录，然后更新当前记录
-            println!("  🔄 发现冲突记录，删除旧记录 ID {}，更新当前记录 ID {}", 
-                conflict_record.id, id
-            );
-            
-            // 删除冲突记录
-            sqlx::query("DELETE FROM words WHERE id = ?")
-                .bind(conflict_record.id)
-                .execute(&self.pool)
-                .await?;
-            
-            // 更新当前记录
-            sqlx::query(
-                "UPDATE words SET pitch = ?, part_of_speech = ?, updated_at = datetime('now') WHERE id = ?"
-            )
-            .bind(new_pitch)
-            .bind(new_pos)
-            .bind(id)
-            .execute(&self.pool)
-            .await?;
-            
-            println!("  ✅ 冲突处理完成: {} ({}) - pitch: {}->{}, pos: {}->{}", 
-                current.word, current.kana, 
-                current.pitch, new_pitch,
-                current.part_of_speech, new_pos
-            );
-        } else {
-            // 没有冲突，直接更新
-            sqlx::query(
-                "UPDATE words SET pitch = ?, part_of_speech = ?, updated_at = datetime('now') WHERE id = ?"
+
+        // 记录单词和语法出现在哪些输入文档中
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS occurrences (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                entry_kind TEXT NOT NULL,
+                entry_id INTEGER NOT NULL,
+                document TEXT NOT NULL,
+                created_at DATETIME DEFAULT (datetime('now'))
             )
-            .bind(new_pitch)
-            .bind(new_pos)
-            .bind(id)
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_occurrences_entry ON occurrences (entry_kind, entry_id)")
             .execute(&self.pool)
             .await?;
-        }
-        
-        Ok(())
-    }
 
-    // 更新单词解析
-    pub async fn update_word_analysis(&self, id: i64, new_analysis: &str) -> Result<()> {
+        // 学习者和每个学习者的学习状态（按辞书形记录，未收录的单词也可以标记）
         sqlx::query(
-            "UPDATE words SET analysis = ?, updated_at = datetime('now') WHERE id = ?"
-        )
-        .bind(new_analysis)
-        .bind(id)
-        .execute(&self.pool)
-        .await?;
-        
-        Ok(())
-    }
+            r#"
+            CREATE TABLE IF NOT EXISTS users (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                created_at DATETIME DEFAULT (datetime('now'))
+            )
+            "#
+        ).execute(&self.pool).await?;
 
-    // 根据ID获取单词信息
-    pub async fn get_word_by_id(&self, id: i64) -> Result<Option<JapaneseWord>> {
-        let word = sqlx::query_as::<_, JapaneseWord>(
-            "SELECT id, word, kana, pitch, part_of_speech, analysis, updated_at FROM words WHERE id = ?"
-        )
-        .bind(id)
-        .fetch_optional(&self.pool)
-        .await?;
-        
-        Ok(word)
-    }
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS entry_status (
+                user_id INTEGER NOT NULL,
+                entry_kind TEXT NOT NULL,
+                word TEXT NOT NULL,
+                status TEXT NOT NULL,
+                updated_at DATETIME DEFAULT (datetime('now')),
+                PRIMARY KEY (user_id, entry_kind, word)
+            )
+            "#
+        ).execute(&self.pool).await?;
 
-    // 保存语法到数据库
-    pub async fn save_grammar(&self, grammar: &[GrammarAnalysis]) -> Result<()> {
-        for item in grammar {
-            sqlx::query(
-                "INSERT OR REPLACE INTO grammar (word, kana, analysis) VALUES (?, ?, ?)"
+        // 非主语言（中文以外）的解析，与 analysis 列并存
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS entry_analyses (
+                entry_kind TEXT NOT NULL,
+                entry_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                analysis TEXT NOT NULL,
+                updated_at DATETIME DEFAULT (datetime('now')),
+                PRIMARY KEY (entry_kind, entry_id, language)
             )
-            .bind(&item.grammar)
-            .bind(&item.kana)
-            .bind(&item.analysis)
-            .execute(&self.pool)
-            .await?;
-        }
-        Ok(())
-    }
+            "#
+        ).execute(&self.pool).await?;
 
-    // 获取所有语法
-    pub async fn get_all_grammar(&self) -> Result<Vec<JapaneseGrammar>> {
-        let grammar = sqlx::query_as::<_, JapaneseGrammar>(
-            "SELECT id, word, kana, analysis FROM grammar ORDER BY id"
-        ).fetch_all(&self.pool).await?;
-        
-        Ok(grammar)
-    }
-}
+        // 未通过校验、等待重新分析的解析
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS analysis_flags (
+                entry_kind TEXT NOT NULL,
+                entry_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                created_at DATETIME DEFAULT (datetime('now')),
+                PRIMARY KEY (entry_kind, entry_id, language)
+            )
+            "#
+        ).execute(&self.pool).await?;
 
-// 辅助函数：将音调数字转换为上标符号
-pub fn pitch_to_superscript(pitch: &str) -> String {
-    match pitch {
+        // 审查模型对解析的审查结果，问题列表以 JSON 保存
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS analysis_reviews (
+                entry_kind TEXT NOT NULL,
+                entry_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                score INTEGER NOT NULL,
+                issues TEXT NOT NULL DEFAULT '[]',
+                model TEXT NOT NULL,
+                created_at DATETIME DEFAULT (datetime('now')),
+                PRIMARY KEY (entry_kind, entry_id, language)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        // 结构化单词解析：概要、义项、例句和近义词比较，按语言分别保存
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS word_details (
+                word_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                core_meaning TEXT NOT NULL,
+                etymology TEXT NOT NULL DEFAULT '',
+                summary TEXT NOT NULL DEFAULT '',
+                updated_at DATETIME DEFAULT (datetime('now')),
+                PRIMARY KEY (word_id, language)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS word_senses (
+                word_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                meaning TEXT NOT NULL,
+                explanation TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (word_id, language, position)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS word_examples (
+                word_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                sense_position INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                japanese TEXT NOT NULL,
+                reading TEXT NOT NULL DEFAULT '',
+                translation TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (word_id, language, sense_position, position)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS word_comparisons (
+                word_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                other_word TEXT NOT NULL,
+                difference TEXT NOT NULL,
+                PRIMARY KEY (word_id, language, position)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        // 结构化语法解析：意思、语体、接续规则、例句和相关语法，按语言分别保存
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS grammar_details (
+                grammar_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                meaning TEXT NOT NULL,
+                register TEXT NOT NULL DEFAULT 'neutral',
+                usage TEXT NOT NULL DEFAULT '',
+                updated_at DATETIME DEFAULT (datetime('now')),
+                PRIMARY KEY (grammar_id, language)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS grammar_connections (
+                grammar_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                base TEXT NOT NULL,
+                form TEXT NOT NULL DEFAULT '',
+                note TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (grammar_id, language, position)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS grammar_examples (
+                grammar_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                japanese TEXT NOT NULL,
+                reading TEXT NOT NULL DEFAULT '',
+                translation TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (grammar_id, language, position)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS grammar_related (
+                grammar_id INTEGER NOT NULL,
+                language TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                related_grammar TEXT NOT NULL,
+                difference TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (grammar_id, language, position)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        // 记录生成解析所用的提示词模板版本，0 表示记录版本之前生成的解析
+        for table in ["words", "grammar", "entry_analyses"] {
+            let version_exists = sqlx::query(&format!("SELECT prompt_version FROM {} LIMIT 1", table))
+                .execute(&self.pool)
+                .await
+                .is_ok();
+
+            if !version_exists {
+                println!("   添加 {}.prompt_version 列...", table);
+                sqlx::query(&format!("ALTER TABLE {} ADD COLUMN prompt_version INTEGER NOT NULL DEFAULT 0", table))
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        // 汉字及其与单词的关联
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS kanji (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                character TEXT NOT NULL UNIQUE,
+                on_readings TEXT NOT NULL DEFAULT '',
+                kun_readings TEXT NOT NULL DEFAULT '',
+                meanings TEXT NOT NULL DEFAULT '',
+                stroke_count INTEGER,
+                components TEXT NOT NULL DEFAULT '',
+                mnemonic TEXT NOT NULL DEFAULT '',
+                created_at DATETIME DEFAULT (datetime('now')),
+                updated_at DATETIME DEFAULT (datetime('now'))
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS kanji_words (
+                kanji_id INTEGER NOT NULL,
+                word_id INTEGER NOT NULL,
+                PRIMARY KEY (kanji_id, word_id)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        // 例句库及例句中出现的单词和语法
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS sentences (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                japanese TEXT NOT NULL UNIQUE,
+                reading TEXT NOT NULL DEFAULT '',
+                translation TEXT NOT NULL DEFAULT '',
+                source TEXT NOT NULL,
+                document TEXT,
+                created_at DATETIME DEFAULT (datetime('now')),
+                updated_at DATETIME DEFAULT (datetime('now'))
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS sentence_entries (
+                sentence_id INTEGER NOT NULL,
+                entry_kind TEXT NOT NULL,
+                entry_id INTEGER NOT NULL,
+                PRIMARY KEY (sentence_id, entry_kind, entry_id)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_sentence_entries_entry ON sentence_entries(entry_kind, entry_id)")
+            .execute(&self.pool).await?;
+
+        // 模型回复缓存
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS response_cache (
+                key TEXT PRIMARY KEY,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                temperature REAL NOT NULL,
+                max_tokens INTEGER NOT NULL,
+                response TEXT NOT NULL,
+                created_at DATETIME DEFAULT (datetime('now'))
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        // 模型请求用量
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS api_calls (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                run_id TEXT NOT NULL,
+                operation TEXT NOT NULL,
+                model TEXT NOT NULL,
+                prompt_tokens INTEGER NOT NULL,
+                completion_tokens INTEGER NOT NULL,
+                cost REAL,
+                created_at DATETIME DEFAULT (datetime('now'))
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_api_calls_run ON api_calls(run_id)")
+            .execute(&self.pool).await?;
+
+        // 内置复习：每个学习者每个条目一张卡片（FSRS 记忆状态）和复习记录
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS review_cards (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                entry_kind TEXT NOT NULL,
+                entry_id INTEGER NOT NULL,
+                state TEXT NOT NULL,
+                due TEXT NOT NULL,
+                stability REAL NOT NULL,
+                difficulty REAL NOT NULL,
+                reps INTEGER NOT NULL DEFAULT 0,
+                lapses INTEGER NOT NULL DEFAULT 0,
+                last_review TEXT,
+                created_at DATETIME DEFAULT (datetime('now')),
+                UNIQUE(user_id, entry_kind, entry_id)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS review_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                card_id INTEGER NOT NULL,
+                user_id INTEGER NOT NULL,
+                ease INTEGER NOT NULL,
+                ivl INTEGER NOT NULL,
+                last_ivl INTEGER NOT NULL,
+                time_ms INTEGER NOT NULL,
+                review_type INTEGER NOT NULL,
+                stability REAL NOT NULL,
+                difficulty REAL NOT NULL,
+                elapsed_days REAL NOT NULL,
+                reviewed_at TEXT NOT NULL,
+                reviewed_at_ms INTEGER NOT NULL
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_review_log_user ON review_log(user_id, reviewed_at_ms)")
+            .execute(&self.pool)
+            .await?;
+
+        // 全文搜索索引（trigram 分词，支持日文子串匹配）
+        sqlx::query(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+                kind UNINDEXED,
+                entry_id UNINDEXED,
+                word,
+                kana,
+                romaji,
+                part_of_speech,
+                analysis_text,
+                tags,
+                updated_at UNINDEXED,
+                tokenize = 'trigram'
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        // 单词和标准词性的关联（由 part_of_speech 字段规范化得到）
+        let pos_table_exists = sqlx::query("SELECT word_id FROM word_pos LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await
+            .is_ok();
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS word_pos (
+                word_id INTEGER NOT NULL,
+                pos TEXT NOT NULL,
+                PRIMARY KEY (word_id, pos)
+            )
+            "#
+        ).execute(&self.pool).await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_word_pos_pos ON word_pos(pos)")
+            .execute(&self.pool)
+            .await?;
+
+        if !pos_table_exists {
+            println!("🔧 规范化单词词性...");
+            let changed = self.normalize_parts_of_speech().await?;
+            println!("   ✅ 已规范化 {} 个单词的词性", changed);
+            let filled = self.fill_word_pos().await?;
+            println!("   ✅ 已填充 {} 个单词的词性关联", filled);
+            sqlx::query("DELETE FROM search_index").execute(&self.pool).await?;
+        }
+
+        // 语法的其他写法（变体和活用形），指向规范写法的语法条目
+        let aliases_table_exists = sqlx::query("SELECT alias FROM grammar_aliases LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await
+            .is_ok();
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS grammar_aliases (
+                alias TEXT PRIMARY KEY,
+                grammar_id INTEGER NOT NULL,
+                created_at DATETIME DEFAULT (datetime('now'))
+            )
+            "#
+        ).execute(&self.pool).await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_grammar_aliases_grammar ON grammar_aliases(grammar_id)")
+            .execute(&self.pool)
+            .await?;
+
+        if !aliases_table_exists {
+            println!("🔧 合并语法的不同写法...");
+            let merged = self.merge_grammar_variants().await?;
+            println!("   ✅ 已合并 {} 个重复的语法条目", merged);
+        }
+
+        let (indexed,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM search_index")
+            .fetch_one(&self.pool)
+            .await?;
+        let (entries,): (i64,) = sqlx::query_as("SELECT (SELECT COUNT(*) FROM words) + (SELECT COUNT(*) FROM grammar)")
+            .fetch_one(&self.pool)
+            .await?;
+
+        if indexed != entries {
+            println!("🔧 重建全文搜索索引...");
+            self.rebuild_search_index().await?;
+            println!("   ✅ 已索引 {} 个条目", entries);
+        }
+
+        Ok(())
+    }
+
+    // 将已有单词的词性字段改写为标准写法，返回修改的单词数
+    async fn normalize_parts_of_speech(&self) -> Result<usize> {
+        let mut changed = 0;
+        for word in self.get_all_words().await? {
+            let normalized = normalize_field(&word.part_of_speech).to_field();
+            if normalized != word.part_of_speech {
+                sqlx::query("UPDATE words SET part_of_speech = ? WHERE id = ?")
+                    .bind(&normalized)
+                    .bind(word.id)
+                    .execute(&self.pool)
+                    .await?;
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    // 按所有单词的词性字段重新填充词性关联，返回单词数
+    async fn fill_word_pos(&self) -> Result<usize> {
+        let words = self.get_all_words().await?;
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM word_pos").execute(&mut *tx).await?;
+        for word in &words {
+            Self::write_word_pos(&mut tx, word.id, &word.part_of_speech).await?;
+        }
+        tx.commit().await?;
+        Ok(words.len())
+    }
+
+    // 按词性字段重写单词的词性关联，和单词本身的修改放在同一个事务中
+    async fn write_word_pos(conn: &mut SqliteConnection, word_id: i64, part_of_speech: &str) -> Result<()> {
+        sqlx::query("DELETE FROM word_pos WHERE word_id = ?")
+            .bind(word_id)
+            .execute(&mut *conn)
+            .await?;
+        for pos in normalize_field(part_of_speech).parts {
+            sqlx::query("INSERT OR IGNORE INTO word_pos (word_id, pos) VALUES (?, ?)")
+                .bind(word_id)
+                .bind(pos.key())
+                .execute(&mut *conn)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // 把规范写法相同的语法合并为一个条目，其余写法记为别名，返回删除的重复条目数
+    async fn merge_grammar_variants(&self) -> Result<usize> {
+        let mut groups: HashMap<String, Vec<JapaneseGrammar>> = HashMap::new();
+        for grammar in self.get_all_grammar().await? {
+            groups.entry(canonical_grammar(&grammar.word)).or_default().push(grammar);
+        }
+
+        let mut merged = 0;
+        for (canonical, mut entries) in groups {
+            if entries.len() == 1 && entries[0].word == canonical {
+                continue;
+            }
+            // 保留已是规范写法的条目，其次是解析被锁定的，再其次是最早收录的
+            entries.sort_by_key(|g| (g.word != canonical, !g.analysis_locked, g.id));
+            let keeper = entries.remove(0);
+            for duplicate in &entries {
+                self.merge_grammar_into(duplicate, keeper.id, &canonical).await?;
+                merged += 1;
+            }
+            if keeper.word != canonical {
+                self.rename_grammar(&keeper, &canonical).await?;
+            }
+            self.reindex_entry(EntryKind::Grammar, keeper.id).await?;
+        }
+        Ok(merged)
+    }
+
+    // 把重复语法的出现记录、学习状态、复习卡片、例句关联、其他语言解析和结构化解析移到保留的条目，然后删除重复条目
+    // 两边都有的数据（同一语言的解析、同一学习者的卡片）以保留的条目为准
+    async fn merge_grammar_into(&self, duplicate: &JapaneseGrammar, keeper_id: i64, canonical: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE occurrences SET entry_id = ? WHERE entry_kind = 'grammar' AND entry_id = ?")
+            .bind(keeper_id)
+            .bind(duplicate.id)
+            .execute(&mut *tx)
+            .await?;
+
+        for (table, key) in [
+            ("entry_analyses", "language"),
+            ("analysis_flags", "language"),
+            ("analysis_reviews", "language"),
+            ("review_cards", "user_id"),
+            ("sentence_entries", "sentence_id"),
+        ] {
+            sqlx::query(&format!(
+                "UPDATE {table} SET entry_id = ? WHERE entry_kind = 'grammar' AND entry_id = ? AND {key} NOT IN (SELECT {key} FROM {table} WHERE entry_kind = 'grammar' AND entry_id = ?)"
+            ))
+            .bind(keeper_id)
+            .bind(duplicate.id)
+            .bind(keeper_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query("DELETE FROM review_log WHERE card_id IN (SELECT id FROM review_cards WHERE entry_kind = 'grammar' AND entry_id = ?)")
+            .bind(duplicate.id)
+            .execute(&mut *tx)
+            .await?;
+        for table in ["entry_analyses", "analysis_flags", "analysis_reviews", "review_cards", "sentence_entries"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE entry_kind = 'grammar' AND entry_id = ?", table))
+                .bind(duplicate.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("DELETE FROM search_index WHERE kind = 'grammar' AND entry_id = ?")
+            .bind(duplicate.id)
+            .execute(&mut *tx)
+            .await?;
+
+        // 结构化解析按语言整体移动，grammar_details 放在最后，移动子表时仍按保留条目原有的语言判断
+        for table in ["grammar_connections", "grammar_examples", "grammar_related", "grammar_details"] {
+            sqlx::query(&format!(
+                "UPDATE {} SET grammar_id = ? WHERE grammar_id = ? AND language NOT IN (SELECT language FROM grammar_details WHERE grammar_id = ?)",
+                table
+            ))
+            .bind(keeper_id)
+            .bind(duplicate.id)
+            .bind(keeper_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        for table in ["grammar_connections", "grammar_examples", "grammar_related", "grammar_details"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE grammar_id = ?", table))
+                .bind(duplicate.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        // 学习状态按语法写法记录
+        sqlx::query(
+            "UPDATE entry_status SET word = ? WHERE entry_kind = 'grammar' AND word = ? AND user_id NOT IN (SELECT user_id FROM entry_status WHERE entry_kind = 'grammar' AND word = ?)"
+        )
+        .bind(canonical)
+        .bind(&duplicate.word)
+        .bind(canonical)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM entry_status WHERE entry_kind = 'grammar' AND word = ?")
+            .bind(&duplicate.word)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE grammar_aliases SET grammar_id = ? WHERE grammar_id = ?")
+            .bind(keeper_id)
+            .bind(duplicate.id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM grammar WHERE id = ?")
+            .bind(duplicate.id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT OR IGNORE INTO grammar_aliases (alias, grammar_id) VALUES (?, ?)")
+            .bind(&duplicate.word)
+            .bind(keeper_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // 把重复单词的出现记录、复习卡片、其他语言解析、结构化解析、汉字和例句关联移到保留的条目，删除重复条目后更新保留条目的音调和词性
+    // 两边都有的数据（同一语言的解析、同一学习者的卡片、同一例句）以保留的条目为准，全部在一个事务中完成
+    async fn merge_word_into(&self, duplicate_id: i64, keeper_id: i64, pitch: &str, part_of_speech: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE occurrences SET entry_id = ? WHERE entry_kind = 'word' AND entry_id = ?")
+            .bind(keeper_id)
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let entry_tables = [
+            ("entry_analyses", "language"),
+            ("analysis_flags", "language"),
+            ("analysis_reviews", "language"),
+            ("review_cards", "user_id"),
+            ("sentence_entries", "sentence_id"),
+        ];
+        for (table, key) in entry_tables {
+            sqlx::query(&format!(
+                "UPDATE {table} SET entry_id = ? WHERE entry_kind = 'word' AND entry_id = ? AND {key} NOT IN (SELECT {key} FROM {table} WHERE entry_kind = 'word' AND entry_id = ?)"
+            ))
+            .bind(keeper_id)
+            .bind(duplicate_id)
+            .bind(keeper_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query("DELETE FROM review_log WHERE card_id IN (SELECT id FROM review_cards WHERE entry_kind = 'word' AND entry_id = ?)")
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+        for (table, _) in entry_tables {
+            sqlx::query(&format!("DELETE FROM {} WHERE entry_kind = 'word' AND entry_id = ?", table))
+                .bind(duplicate_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("DELETE FROM search_index WHERE kind = 'word' AND entry_id = ?")
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // 结构化解析按语言整体移动，word_details 放在最后，移动子表时仍按保留条目原有的语言判断
+        for table in ["word_senses", "word_examples", "word_comparisons", "word_details"] {
+            sqlx::query(&format!(
+                "UPDATE {} SET word_id = ? WHERE word_id = ? AND language NOT IN (SELECT language FROM word_details WHERE word_id = ?)",
+                table
+            ))
+            .bind(keeper_id)
+            .bind(duplicate_id)
+            .bind(keeper_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query("UPDATE kanji_words SET word_id = ? WHERE word_id = ? AND kanji_id NOT IN (SELECT kanji_id FROM kanji_words WHERE word_id = ?)")
+            .bind(keeper_id)
+            .bind(duplicate_id)
+            .bind(keeper_id)
+            .execute(&mut *tx)
+            .await?;
+        for table in ["word_senses", "word_examples", "word_comparisons", "word_details", "kanji_words", "word_pos"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE word_id = ?", table))
+                .bind(duplicate_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query("DELETE FROM words WHERE id = ?")
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE words SET pitch = ?, part_of_speech = ?, updated_at = datetime('now') WHERE id = ?")
+            .bind(pitch)
+            .bind(part_of_speech)
+            .bind(keeper_id)
+            .execute(&mut *tx)
+            .await?;
+        Self::write_word_pos(&mut tx, keeper_id, part_of_speech).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // 把语法改为规范写法，原写法记为别名
+    async fn rename_grammar(&self, grammar: &JapaneseGrammar, canonical: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE grammar SET word = ? WHERE id = ?")
+            .bind(canonical)
+            .bind(grammar.id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "UPDATE entry_status SET word = ? WHERE entry_kind = 'grammar' AND word = ? AND user_id NOT IN (SELECT user_id FROM entry_status WHERE entry_kind = 'grammar' AND word = ?)"
+        )
+        .bind(canonical)
+        .bind(&grammar.word)
+        .bind(canonical)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM entry_status WHERE entry_kind = 'grammar' AND word = ?")
+            .bind(&grammar.word)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT OR IGNORE INTO grammar_aliases (alias, grammar_id) VALUES (?, ?)")
+            .bind(&grammar.word)
+            .bind(grammar.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // 记录语法的其他写法，与条目写法相同或已记录时跳过
+    async fn add_grammar_alias(&self, grammar: &JapaneseGrammar, alias: &str) -> Result<()> {
+        let alias = alias.trim();
+        if alias.is_empty() || alias == grammar.word {
+            return Ok(());
+        }
+        sqlx::query("INSERT OR IGNORE INTO grammar_aliases (alias, grammar_id) VALUES (?, ?)")
+            .bind(alias)
+            .bind(grammar.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // 重建全文搜索索引
+    pub async fn rebuild_search_index(&self) -> Result<()> {
+        sqlx::query("DELETE FROM search_index").execute(&self.pool).await?;
+
+        for word in self.get_all_words().await? {
+            self.insert_word_index(&word).await?;
+        }
+        for grammar in self.get_all_grammar().await? {
+            self.insert_grammar_index(&grammar).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn insert_word_index(&self, word: &JapaneseWord) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO search_index (kind, entry_id, word, kana, romaji, part_of_speech, analysis_text, tags, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(EntryKind::Word.as_str())
+        .bind(word.id)
+        .bind(&word.word)
+        .bind(&word.kana)
+        .bind(kana_to_romaji(&word.kana))
+        .bind(&word.part_of_speech)
+        .bind(strip_html(&word.analysis))
+        .bind(&word.tags)
+        .bind(&word.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_grammar_index(&self, grammar: &JapaneseGrammar) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO search_index (kind, entry_id, word, kana, romaji, part_of_speech, analysis_text, tags, updated_at) VALUES (?, ?, ?, ?, ?, '', ?, ?, ?)"
+        )
+        .bind(EntryKind::Grammar.as_str())
+        .bind(grammar.id)
+        .bind(&grammar.word)
+        .bind(&grammar.kana)
+        .bind(kana_to_romaji(&grammar.kana))
+        .bind(strip_html(&grammar.analysis))
+        .bind(&grammar.tags)
+        .bind(&grammar.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // 替换语法某种语言的结构化解析（None 时只删除），updated_at 为空时使用当前时间
+    async fn write_grammar_details(&self, id: i64, language: &str, details: Option<&GrammarDetails>, updated_at: Option<&str>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for table in ["grammar_details", "grammar_connections", "grammar_examples", "grammar_related"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE grammar_id = ? AND language = ?", table))
+                .bind(id)
+                .bind(language)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let Some(details) = details else {
+            tx.commit().await?;
+            return Ok(());
+        };
+
+        sqlx::query(
+            "INSERT INTO grammar_details (grammar_id, language, meaning, register, usage, updated_at) VALUES (?, ?, ?, ?, ?, COALESCE(?, datetime('now')))"
+        )
+        .bind(id)
+        .bind(language)
+        .bind(&details.meaning)
+        .bind(details.register.key())
+        .bind(&details.usage)
+        .bind(updated_at)
+        .execute(&mut *tx)
+        .await?;
+
+        for (position, rule) in details.connections.iter().enumerate() {
+            sqlx::query("INSERT INTO grammar_connections (grammar_id, language, position, base, form, note) VALUES (?, ?, ?, ?, ?, ?)")
+                .bind(id)
+                .bind(language)
+                .bind(position as i64)
+                .bind(rule.base.key())
+                .bind(&rule.form)
+                .bind(&rule.note)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for (position, example) in details.examples.iter().enumerate() {
+            sqlx::query("INSERT INTO grammar_examples (grammar_id, language, position, japanese, reading, translation) VALUES (?, ?, ?, ?, ?, ?)")
+                .bind(id)
+                .bind(language)
+                .bind(position as i64)
+                .bind(&example.japanese)
+                .bind(&example.reading)
+                .bind(&example.translation)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for (position, related) in details.related.iter().enumerate() {
+            sqlx::query("INSERT INTO grammar_related (grammar_id, language, position, related_grammar, difference) VALUES (?, ?, ?, ?, ?)")
+                .bind(id)
+                .bind(language)
+                .bind(position as i64)
+                .bind(&related.grammar)
+                .bind(&related.difference)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // 替换单词某种语言的结构化解析（None 时只删除），updated_at 为空时使用当前时间
+    async fn write_word_details(&self, id: i64, language: &str, details: Option<&WordDetails>, updated_at: Option<&str>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for table in ["word_details", "word_senses", "word_examples", "word_comparisons"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE word_id = ? AND language = ?", table))
+                .bind(id)
+                .bind(language)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let Some(details) = details else {
+            tx.commit().await?;
+            return Ok(());
+        };
+
+        sqlx::query(
+            "INSERT INTO word_details (word_id, language, core_meaning, etymology, summary, updated_at) VALUES (?, ?, ?, ?, ?, COALESCE(?, datetime('now')))"
+        )
+        .bind(id)
+        .bind(language)
+        .bind(&details.core_meaning)
+        .bind(&details.etymology)
+        .bind(&details.summary)
+        .bind(updated_at)
+        .execute(&mut *tx)
+        .await?;
+
+        for (sense_position, sense) in details.senses.iter().enumerate() {
+            sqlx::query("INSERT INTO word_senses (word_id, language, position, meaning, explanation) VALUES (?, ?, ?, ?, ?)")
+                .bind(id)
+                .bind(language)
+                .bind(sense_position as i64)
+                .bind(&sense.meaning)
+                .bind(&sense.explanation)
+                .execute(&mut *tx)
+                .await?;
+
+            for (position, example) in sense.examples.iter().enumerate() {
+                sqlx::query(
+                    "INSERT INTO word_examples (word_id, language, sense_position, position, japanese, reading, translation) VALUES (?, ?, ?, ?, ?, ?, ?)"
+                )
+                .bind(id)
+                .bind(language)
+                .bind(sense_position as i64)
+                .bind(position as i64)
+                .bind(&example.japanese)
+                .bind(&example.reading)
+                .bind(&example.translation)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        for (position, comparison) in details.comparisons.iter().enumerate() {
+            sqlx::query("INSERT INTO word_comparisons (word_id, language, position, other_word, difference) VALUES (?, ?, ?, ?, ?)")
+                .bind(id)
+                .bind(language)
+                .bind(position as i64)
+                .bind(&comparison.word)
+                .bind(&comparison.difference)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // 条目修改后刷新其搜索索引
+    async fn reindex_entry(&self, kind: EntryKind, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM search_index WHERE kind = ? AND entry_id = ?")
+            .bind(kind.as_str())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        match kind {
+            EntryKind::Word => {
+                if let Some(word) = self.get_word_by_id(id).await? {
+                    self.insert_word_index(&word).await?;
+                }
+            },
+            EntryKind::Grammar => {
+                if let Some(grammar) = self.get_grammar_by_id(id).await? {
+                    self.insert_grammar_index(&grammar).await?;
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    // 按导出文件中的自然键查找条目ID：单词按 (word, kana, pitch)，语法按语法表达
+    async fn find_entry_id(&self, entry_kind: &str, word: &str, kana: &str, pitch: Option<&str>) -> Result<Option<i64>> {
+        match EntryKind::parse(entry_kind) {
+            Some(EntryKind::Word) => {
+                let row: Option<(i64,)> = sqlx::query_as("SELECT id FROM words WHERE word = ? AND kana = ? AND pitch = ?")
+                    .bind(word)
+                    .bind(kana)
+                    .bind(pitch.unwrap_or("0"))
+                    .fetch_optional(&self.pool)
+                    .await?;
+                Ok(row.map(|(id,)| id))
+            },
+            Some(EntryKind::Grammar) => Ok(self.get_grammar_by_word(word).await?.map(|g| g.id)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for DatabaseManager {
+    // 更新条目的词频排名和 JLPT 等级
+    async fn update_entry_levels(&self, kind: EntryKind, id: i64, frequency_rank: Option<i64>, jlpt_level: Option<&str>) -> Result<()> {
+        sqlx::query(&format!("UPDATE {} SET frequency_rank = ?, jlpt_level = ? WHERE id = ?", kind.table()))
+            .bind(frequency_rank)
+            .bind(jlpt_level)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // 获取学习者ID，不存在时创建
+    async fn ensure_user(&self, name: &str) -> Result<i64> {
+        sqlx::query("INSERT OR IGNORE INTO users (name) VALUES (?)")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+
+        let (id,): (i64,) = sqlx::query_as("SELECT id FROM users WHERE name = ?")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(id)
+    }
+
+    // 设置学习者对某个辞书形的学习状态
+    async fn set_entry_status(&self, user_id: i64, kind: EntryKind, word: &str, status: EntryStatus) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO entry_status (user_id, entry_kind, word, status, updated_at)
+            VALUES (?, ?, ?, ?, datetime('now'))
+            ON CONFLICT (user_id, entry_kind, word) DO UPDATE SET status = excluded.status, updated_at = excluded.updated_at
+            "#
+        )
+        .bind(user_id)
+        .bind(kind.as_str())
+        .bind(word)
+        .bind(status.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // 获取学习者的所有学习状态
+    async fn get_entry_statuses(&self, user_id: i64, kind: EntryKind) -> Result<HashMap<String, EntryStatus>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT word, status FROM entry_status WHERE user_id = ? AND entry_kind = ?"
+        )
+        .bind(user_id)
+        .bind(kind.as_str())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(word, status)| Some((word, EntryStatus::parse(&status)?)))
+            .collect())
+    }
+
+    // 获取某种语言的所有解析
+    async fn get_entry_analyses(&self, kind: EntryKind, language: &str) -> Result<HashMap<i64, String>> {
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT entry_id, analysis FROM entry_analyses WHERE entry_kind = ? AND language = ?"
+        )
+        .bind(kind.as_str())
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    // 获取某种语言的所有解析所用的提示词版本
+    async fn get_entry_analysis_versions(&self, kind: EntryKind, language: &str) -> Result<HashMap<i64, i64>> {
+        let rows: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT entry_id, prompt_version FROM entry_analyses WHERE entry_kind = ? AND language = ?"
+        )
+        .bind(kind.as_str())
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    // 保存条目某种语言的解析
+    async fn save_entry_analysis(&self, kind: EntryKind, id: i64, language: &str, analysis: &str, prompt_version: i64) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO entry_analyses (entry_kind, entry_id, language, analysis, prompt_version, updated_at)
+            VALUES (?, ?, ?, ?, ?, datetime('now'))
+            ON CONFLICT (entry_kind, entry_id, language) DO UPDATE SET analysis = excluded.analysis,
+                prompt_version = excluded.prompt_version, updated_at = excluded.updated_at
+            "#
+        )
+        .bind(kind.as_str())
+        .bind(id)
+        .bind(language)
+        .bind(analysis)
+        .bind(prompt_version)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // 记录或清除解析未通过校验的原因
+    async fn set_analysis_flag(&self, kind: EntryKind, id: i64, language: &str, reason: Option<&str>) -> Result<()> {
+        let query = match reason {
+            Some(_) => r#"
+                INSERT INTO analysis_flags (entry_kind, entry_id, language, reason, created_at)
+                VALUES (?, ?, ?, ?, datetime('now'))
+                ON CONFLICT (entry_kind, entry_id, language) DO UPDATE SET reason = excluded.reason,
+                    created_at = excluded.created_at
+                "#,
+            None => "DELETE FROM analysis_flags WHERE entry_kind = ? AND entry_id = ? AND language = ?",
+        };
+        let mut query = sqlx::query(query).bind(kind.as_str()).bind(id).bind(language);
+        if let Some(reason) = reason {
+            query = query.bind(reason);
+        }
+        query.execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    // 获取某种语言下未通过校验的解析
+    async fn get_analysis_flags(&self, kind: EntryKind, language: &str) -> Result<HashMap<i64, String>> {
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT entry_id, reason FROM analysis_flags WHERE entry_kind = ? AND language = ?"
+        )
+        .bind(kind.as_str())
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    // 保存审查模型的审查结果
+    async fn save_analysis_review(&self, kind: EntryKind, id: i64, language: &str, review: &AnalysisReview, model: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO analysis_reviews (entry_kind, entry_id, language, score, issues, model, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, datetime('now'))
+            ON CONFLICT (entry_kind, entry_id, language) DO UPDATE SET score = excluded.score,
+                issues = excluded.issues, model = excluded.model, created_at = excluded.created_at
+            "#
+        )
+        .bind(kind.as_str())
+        .bind(id)
+        .bind(language)
+        .bind(review.score)
+        .bind(serde_json::to_string(&review.issues)?)
+        .bind(model)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // 获取某种语言下所有解析的审查结果
+    async fn get_analysis_reviews(&self, kind: EntryKind, language: &str) -> Result<HashMap<i64, AnalysisReview>> {
+        let rows: Vec<(i64, i64, String)> = sqlx::query_as(
+            "SELECT entry_id, score, issues FROM analysis_reviews WHERE entry_kind = ? AND language = ?"
+        )
+        .bind(kind.as_str())
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(id, score, issues)| {
+                let issues = serde_json::from_str(&issues)
+                    .map_err(|e| anyhow::anyhow!("审查结果格式错误: {}", e))?;
+                Ok((id, AnalysisReview { score, issues }))
+            })
+            .collect()
+    }
+
+    // 保存或删除单词某种语言的结构化解析
+    async fn save_word_details(&self, id: i64, language: &str, details: Option<&WordDetails>) -> Result<()> {
+        self.write_word_details(id, language, details, None).await
+    }
+
+    // 获取某种语言的所有结构化单词解析，义项、例句和比较按保存顺序组装
+    async fn get_word_details(&self, language: &str) -> Result<HashMap<i64, WordDetails>> {
+        let rows: Vec<(i64, String, String, String)> = sqlx::query_as(
+            "SELECT word_id, core_meaning, etymology, summary FROM word_details WHERE language = ?"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut details: HashMap<i64, WordDetails> = rows
+            .into_iter()
+            .map(|(id, core_meaning, etymology, summary)| (id, WordDetails {
+                core_meaning,
+                etymology,
+                summary,
+                ..Default::default()
+            }))
+            .collect();
+
+        let senses: Vec<(i64, String, String)> = sqlx::query_as(
+            "SELECT word_id, meaning, explanation FROM word_senses WHERE language = ? ORDER BY word_id, position"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, meaning, explanation) in senses {
+            if let Some(entry) = details.get_mut(&id) {
+                entry.senses.push(WordSense { meaning, explanation, examples: Vec::new() });
+            }
+        }
+
+        let examples: Vec<(i64, i64, String, String, String)> = sqlx::query_as(
+            "SELECT word_id, sense_position, japanese, reading, translation FROM word_examples WHERE language = ? ORDER BY word_id, sense_position, position"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, sense_position, japanese, reading, translation) in examples {
+            if let Some(sense) = details.get_mut(&id).and_then(|d| d.senses.get_mut(sense_position as usize)) {
+                sense.examples.push(ExampleSentence { japanese, reading, translation });
+            }
+        }
+
+        let comparisons: Vec<(i64, String, String)> = sqlx::query_as(
+            "SELECT word_id, other_word, difference FROM word_comparisons WHERE language = ? ORDER BY word_id, position"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, word, difference) in comparisons {
+            if let Some(entry) = details.get_mut(&id) {
+                entry.comparisons.push(WordComparison { word, difference });
+            }
+        }
+
+        Ok(details)
+    }
+
+    // 导出所有结构化单词解析
+    async fn export_word_details(&self) -> Result<Vec<ExportedWordDetails>> {
+        let rows: Vec<(i64, String, String, String, Option<String>)> = sqlx::query_as(
+            "SELECT d.word_id, w.word, w.kana, d.language, d.updated_at FROM word_details d JOIN words w ON w.id = d.word_id ORDER BY d.word_id, d.language"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut by_language: HashMap<String, HashMap<i64, WordDetails>> = HashMap::new();
+        let mut exported = Vec::new();
+        for (id, word, kana, language, updated_at) in rows {
+            if !by_language.contains_key(&language) {
+                by_language.insert(language.clone(), self.get_word_details(&language).await?);
+            }
+            if let Some(details) = by_language.get_mut(&language).and_then(|d| d.remove(&id)) {
+                exported.push(ExportedWordDetails { word, kana, language, details, updated_at });
+            }
+        }
+
+        Ok(exported)
+    }
+
+    // 导入结构化单词解析
+    async fn import_word_details(&self, details: &ExportedWordDetails, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let word_id: Option<(i64,)> = sqlx::query_as("SELECT id FROM words WHERE word = ? AND kana = ? LIMIT 1")
+            .bind(&details.word)
+            .bind(&details.kana)
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some((word_id,)) = word_id else {
+            return Ok(ImportOutcome::Skipped);
+        };
+
+        let existing: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT updated_at FROM word_details WHERE word_id = ? AND language = ?"
+        )
+        .bind(word_id)
+        .bind(&details.language)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let outcome = match &existing {
+            Some((updated_at,)) => {
+                if !should_replace(policy, updated_at.as_deref(), details.updated_at.as_deref()) {
+                    return Ok(ImportOutcome::Skipped);
+                }
+                ImportOutcome::Updated
+            },
+            None => ImportOutcome::Inserted,
+        };
+
+        self.write_word_details(word_id, &details.language, Some(&details.details), details.updated_at.as_deref()).await?;
+        Ok(outcome)
+    }
+
+    // 保存或删除语法某种语言的结构化解析
+    async fn save_grammar_details(&self, id: i64, language: &str, details: Option<&GrammarDetails>) -> Result<()> {
+        self.write_grammar_details(id, language, details, None).await
+    }
+
+    // 获取某种语言的所有结构化语法解析，相关语法按语法表达关联到语法库中的条目
+    async fn get_grammar_details(&self, language: &str) -> Result<HashMap<i64, GrammarDetails>> {
+        let rows: Vec<(i64, String, String, String)> = sqlx::query_as(
+            "SELECT grammar_id, meaning, register, usage FROM grammar_details WHERE language = ?"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut details: HashMap<i64, GrammarDetails> = rows
+            .into_iter()
+            .map(|(id, meaning, register, usage)| (id, GrammarDetails {
+                meaning,
+                register: GrammarRegister::from_key(&register).unwrap_or_default(),
+                usage,
+                ..Default::default()
+            }))
+            .collect();
+
+        let connections: Vec<(i64, String, String, String)> = sqlx::query_as(
+            "SELECT grammar_id, base, form, note FROM grammar_connections WHERE language = ? ORDER BY grammar_id, position"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, base, form, note) in connections {
+            if let Some(entry) = details.get_mut(&id) {
+                let base = ConnectionBase::from_key(&base).unwrap_or_default();
+                entry.connections.push(ConnectionRule { base, form, note });
+            }
+        }
+
+        let examples: Vec<(i64, String, String, String)> = sqlx::query_as(
+            "SELECT grammar_id, japanese, reading, translation FROM grammar_examples WHERE language = ? ORDER BY grammar_id, position"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, japanese, reading, translation) in examples {
+            if let Some(entry) = details.get_mut(&id) {
+                entry.examples.push(ExampleSentence { japanese, reading, translation });
+            }
+        }
+
+        let related: Vec<(i64, String, String, Option<i64>)> = sqlx::query_as(
+            "SELECT r.grammar_id, r.related_grammar, r.difference, COALESCE(g.id, a.grammar_id) FROM grammar_related r LEFT JOIN grammar g ON g.word = r.related_grammar LEFT JOIN grammar_aliases a ON a.alias = r.related_grammar WHERE r.language = ? ORDER BY r.grammar_id, r.position"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, grammar, difference, grammar_id) in related {
+            if let Some(entry) = details.get_mut(&id) {
+                entry.related.push(RelatedGrammar { grammar, difference, grammar_id });
+            }
+        }
+
+        Ok(details)
+    }
+
+    // 导出所有结构化语法解析
+    async fn export_grammar_details(&self) -> Result<Vec<ExportedGrammarDetails>> {
+        let rows: Vec<(i64, String, String, Option<String>)> = sqlx::query_as(
+            "SELECT d.grammar_id, g.word, d.language, d.updated_at FROM grammar_details d JOIN grammar g ON g.id = d.grammar_id ORDER BY d.grammar_id, d.language"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut by_language: HashMap<String, HashMap<i64, GrammarDetails>> = HashMap::new();
+        let mut exported = Vec::new();
+        for (id, grammar, language, updated_at) in rows {
+            if !by_language.contains_key(&language) {
+                by_language.insert(language.clone(), self.get_grammar_details(&language).await?);
+            }
+            if let Some(details) = by_language.get_mut(&language).and_then(|d| d.remove(&id)) {
+                exported.push(ExportedGrammarDetails { grammar, language, details, updated_at });
+            }
+        }
+
+        Ok(exported)
+    }
+
+    // 导入结构化语法解析
+    async fn import_grammar_details(&self, details: &ExportedGrammarDetails, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let Some(grammar_id) = self.get_grammar_by_word(&details.grammar).await?.map(|g| g.id) else {
+            return Ok(ImportOutcome::Skipped);
+        };
+
+        let existing: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT updated_at FROM grammar_details WHERE grammar_id = ? AND language = ?"
+        )
+        .bind(grammar_id)
+        .bind(&details.language)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let outcome = match &existing {
+            Some((updated_at,)) => {
+                if !should_replace(policy, updated_at.as_deref(), details.updated_at.as_deref()) {
+                    return Ok(ImportOutcome::Skipped);
+                }
+                ImportOutcome::Updated
+            },
+            None => ImportOutcome::Inserted,
+        };
+
+        self.write_grammar_details(grammar_id, &details.language, Some(&details.details), details.updated_at.as_deref()).await?;
+        Ok(outcome)
+    }
+
+    // 导出所有其他语言的解析
+    async fn export_analyses(&self) -> Result<Vec<ExportedAnalysis>> {
+        let analyses = sqlx::query_as::<_, ExportedAnalysis>(ANALYSES_QUERY)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(analyses)
+    }
+
+    // 导入其他语言的解析
+    async fn import_analysis(&self, analysis: &ExportedAnalysis, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let entry_id: Option<(i64,)> = if analysis.entry_kind == EntryKind::Word.as_str() {
+            sqlx::query_as("SELECT id FROM words WHERE word = ? AND kana = ? LIMIT 1")
+                .bind(&analysis.word)
+                .bind(&analysis.kana)
+                .fetch_optional(&self.pool)
+                .await?
+        } else {
+            self.get_grammar_by_word(&analysis.word).await?.map(|g| (g.id,))
+        };
+        let Some((entry_id,)) = entry_id else {
+            return Ok(ImportOutcome::Skipped);
+        };
+
+        let existing: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT updated_at FROM entry_analyses WHERE entry_kind = ? AND entry_id = ? AND language = ?"
+        )
+        .bind(&analysis.entry_kind)
+        .bind(entry_id)
+        .bind(&analysis.language)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let outcome = match &existing {
+            Some((updated_at,)) => {
+                if !should_replace(policy, updated_at.as_deref(), analysis.updated_at.as_deref()) {
+                    return Ok(ImportOutcome::Skipped);
+                }
+                ImportOutcome::Updated
+            },
+            None => ImportOutcome::Inserted,
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO entry_analyses (entry_kind, entry_id, language, analysis, updated_at, prompt_version)
+            VALUES (?, ?, ?, ?, COALESCE(?, datetime('now')), ?)
+            ON CONFLICT (entry_kind, entry_id, language) DO UPDATE SET analysis = excluded.analysis,
+                updated_at = excluded.updated_at, prompt_version = excluded.prompt_version
+            "#
+        )
+        .bind(&analysis.entry_kind)
+        .bind(entry_id)
+        .bind(&analysis.language)
+        .bind(&analysis.analysis)
+        .bind(&analysis.updated_at)
+        .bind(analysis.prompt_version)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(outcome)
+    }
+
+    // 保存汉字信息（按字合并，保留已有的记忆口诀）
+    async fn save_kanji(&self, info: &KanjiInfo) -> Result<i64> {
+        sqlx::query(
+            r#"
+            INSERT INTO kanji (character, on_readings, kun_readings, meanings, stroke_count, components)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT (character) DO UPDATE SET
+                on_readings = excluded.on_readings, kun_readings = excluded.kun_readings,
+                meanings = excluded.meanings, stroke_count = excluded.stroke_count,
+                components = excluded.components, updated_at = datetime('now')
+            "#
+        )
+        .bind(&info.character)
+        .bind(info.on_readings.join("、"))
+        .bind(info.kun_readings.join("、"))
+        .bind(info.meanings.join("; "))
+        .bind(info.stroke_count)
+        .bind(info.components.join(" "))
+        .execute(&self.pool)
+        .await?;
+
+        let (id,): (i64,) = sqlx::query_as("SELECT id FROM kanji WHERE character = ?")
+            .bind(&info.character)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(id)
+    }
+
+    // 替换汉字关联的单词
+    async fn link_kanji_words(&self, kanji_id: i64, word_ids: &[i64]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM kanji_words WHERE kanji_id = ?")
+            .bind(kanji_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for word_id in word_ids {
+            sqlx::query("INSERT INTO kanji_words (kanji_id, word_id) VALUES (?, ?)")
+                .bind(kanji_id)
+                .bind(word_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // 获取所有汉字
+    async fn get_all_kanji(&self) -> Result<Vec<Kanji>> {
+        let kanji = sqlx::query_as::<_, Kanji>(
+            "SELECT id, character, on_readings, kun_readings, meanings, stroke_count, components, mnemonic, updated_at FROM kanji ORDER BY id"
+        ).fetch_all(&self.pool).await?;
+
+        Ok(kanji)
+    }
+
+    // 获取所有汉字和单词的关联
+    async fn get_kanji_word_links(&self) -> Result<Vec<(i64, i64)>> {
+        let links = sqlx::query_as("SELECT kanji_id, word_id FROM kanji_words ORDER BY kanji_id, word_id")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(links)
+    }
+
+    // 更新汉字记忆口诀
+    async fn update_kanji_mnemonic(&self, id: i64, mnemonic: &str) -> Result<()> {
+        sqlx::query("UPDATE kanji SET mnemonic = ?, updated_at = datetime('now') WHERE id = ?")
+            .bind(mnemonic)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // 导出所有汉字及其关联的单词
+    async fn export_kanji(&self) -> Result<Vec<ExportedKanji>> {
+        let links: Vec<(i64, String, String, String)> = sqlx::query_as(
+            "SELECT kw.kanji_id, w.word, w.kana, w.pitch FROM kanji_words kw JOIN words w ON w.id = kw.word_id ORDER BY kw.kanji_id, w.id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut words: HashMap<i64, Vec<ExportedEntryRef>> = HashMap::new();
+        for (kanji_id, word, kana, pitch) in links {
+            words.entry(kanji_id).or_default().push(ExportedEntryRef {
+                entry_kind: EntryKind::Word.as_str().to_string(),
+                word,
+                kana,
+                pitch: Some(pitch),
+            });
+        }
+
+        Ok(self.get_all_kanji().await?.into_iter().map(|k| ExportedKanji {
+            words: words.remove(&k.id).unwrap_or_default(),
+            character: k.character,
+            on_readings: k.on_readings,
+            kun_readings: k.kun_readings,
+            meanings: k.meanings,
+            stroke_count: k.stroke_count,
+            components: k.components,
+            mnemonic: k.mnemonic,
+            updated_at: k.updated_at,
+        }).collect())
+    }
+
+    // 导入汉字；汉字和单词的关联总是合并，不受冲突策略影响
+    async fn import_kanji(&self, kanji: &ExportedKanji, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let existing: Option<(i64, Option<String>)> = sqlx::query_as("SELECT id, updated_at FROM kanji WHERE character = ?")
+            .bind(&kanji.character)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let (kanji_id, outcome) = match existing {
+            Some((id, updated_at)) if !should_replace(policy, updated_at.as_deref(), kanji.updated_at.as_deref()) => {
+                (id, ImportOutcome::Skipped)
+            },
+            existing => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO kanji (character, on_readings, kun_readings, meanings, stroke_count, components, mnemonic, updated_at)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, COALESCE(?, datetime('now')))
+                    ON CONFLICT (character) DO UPDATE SET
+                        on_readings = excluded.on_readings, kun_readings = excluded.kun_readings,
+                        meanings = excluded.meanings, stroke_count = excluded.stroke_count,
+                        components = excluded.components, mnemonic = excluded.mnemonic, updated_at = excluded.updated_at
+                    "#
+                )
+                .bind(&kanji.character)
+                .bind(&kanji.on_readings)
+                .bind(&kanji.kun_readings)
+                .bind(&kanji.meanings)
+                .bind(kanji.stroke_count)
+                .bind(&kanji.components)
+                .bind(&kanji.mnemonic)
+                .bind(&kanji.updated_at)
+                .execute(&self.pool)
+                .await?;
+
+                let (id,): (i64,) = sqlx::query_as("SELECT id FROM kanji WHERE character = ?")
+                    .bind(&kanji.character)
+                    .fetch_one(&self.pool)
+                    .await?;
+                (id, if existing.is_some() { ImportOutcome::Updated } else { ImportOutcome::Inserted })
+            },
+        };
+
+        for word in &kanji.words {
+            if let Some(word_id) = self.find_entry_id(&word.entry_kind, &word.word, &word.kana, word.pitch.as_deref()).await? {
+                sqlx::query("INSERT OR IGNORE INTO kanji_words (kanji_id, word_id) VALUES (?, ?)")
+                    .bind(kanji_id)
+                    .bind(word_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    // 保存例句，已有的例句只补全空的读音和翻译
+    async fn save_sentence(&self, sentence: &ExampleSentence, source: SentenceSource, document: Option<&str>) -> Result<i64> {
+        sqlx::query(
+            r#"
+            INSERT INTO sentences (japanese, reading, translation, source, document)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (japanese) DO UPDATE SET
+                reading = CASE WHEN sentences.reading = '' THEN excluded.reading ELSE sentences.reading END,
+                translation = CASE WHEN sentences.translation = '' THEN excluded.translation ELSE sentences.translation END,
+                updated_at = datetime('now')
+            "#
+        )
+        .bind(sentence.japanese.trim())
+        .bind(sentence.reading.trim())
+        .bind(sentence.translation.trim())
+        .bind(source.as_str())
+        .bind(document)
+        .execute(&self.pool)
+        .await?;
+
+        let (id,): (i64,) = sqlx::query_as("SELECT id FROM sentences WHERE japanese = ?")
+            .bind(sentence.japanese.trim())
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(id)
+    }
+
+    // 记录例句中出现的单词或语法，返回是否新增了关联
+    async fn link_sentence(&self, sentence_id: i64, kind: EntryKind, entry_id: i64) -> Result<bool> {
+        let result = sqlx::query("INSERT OR IGNORE INTO sentence_entries (sentence_id, entry_kind, entry_id) VALUES (?, ?, ?)")
+            .bind(sentence_id)
+            .bind(kind.as_str())
+            .bind(entry_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // 更新例句的读音和翻译
+    async fn update_sentence_translation(&self, id: i64, reading: &str, translation: &str) -> Result<()> {
+        sqlx::query("UPDATE sentences SET reading = ?, translation = ?, updated_at = datetime('now') WHERE id = ?")
+            .bind(reading)
+            .bind(translation)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // 获取所有例句
+    async fn get_all_sentences(&self) -> Result<Vec<Sentence>> {
+        let sentences = sqlx::query_as::<_, Sentence>(
+            "SELECT id, japanese, reading, translation, source, document, updated_at FROM sentences ORDER BY id"
+        ).fetch_all(&self.pool).await?;
+
+        Ok(sentences)
+    }
+
+    // 获取所有例句和条目的关联
+    async fn get_sentence_links(&self) -> Result<Vec<(i64, String, i64)>> {
+        let links = sqlx::query_as("SELECT sentence_id, entry_kind, entry_id FROM sentence_entries ORDER BY sentence_id, entry_kind, entry_id")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(links)
+    }
+
+    // 获取包含某个单词或语法的所有例句
+    async fn get_sentences_for_entry(&self, kind: EntryKind, id: i64) -> Result<Vec<Sentence>> {
+        let sentences = sqlx::query_as::<_, Sentence>(
+            r#"
+            SELECT s.id, s.japanese, s.reading, s.translation, s.source, s.document, s.updated_at
+            FROM sentences s
+            JOIN sentence_entries e ON e.sentence_id = s.id
+            WHERE e.entry_kind = ? AND e.entry_id = ?
+            ORDER BY s.id
+            "#
+        )
+        .bind(kind.as_str())
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sentences)
+    }
+
+    // 导出所有例句及其关联的单词和语法
+    async fn export_sentences(&self) -> Result<Vec<ExportedSentence>> {
+        let links: Vec<(i64, String, String, String, Option<String>)> = sqlx::query_as(SENTENCE_ENTRIES_QUERY)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut entries: HashMap<i64, Vec<ExportedEntryRef>> = HashMap::new();
+        for (sentence_id, entry_kind, word, kana, pitch) in links {
+            entries.entry(sentence_id).or_default().push(ExportedEntryRef { entry_kind, word, kana, pitch });
+        }
+
+        Ok(self.get_all_sentences().await?.into_iter().map(|s| ExportedSentence {
+            entries: entries.remove(&s.id).unwrap_or_default(),
+            japanese: s.japanese,
+            reading: s.reading,
+            translation: s.translation,
+            source: s.source,
+            document: s.document,
+            updated_at: s.updated_at,
+        }).collect())
+    }
+
+    // 导入例句；例句和条目的关联总是合并，不受冲突策略影响
+    async fn import_sentence(&self, sentence: &ExportedSentence, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let existing: Option<(i64, Option<String>)> = sqlx::query_as("SELECT id, updated_at FROM sentences WHERE japanese = ?")
+            .bind(&sentence.japanese)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let (sentence_id, outcome) = match existing {
+            Some((id, updated_at)) if !should_replace(policy, updated_at.as_deref(), sentence.updated_at.as_deref()) => {
+                (id, ImportOutcome::Skipped)
+            },
+            existing => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO sentences (japanese, reading, translation, source, document, updated_at)
+                    VALUES (?, ?, ?, ?, ?, COALESCE(?, datetime('now')))
+                    ON CONFLICT (japanese) DO UPDATE SET
+                        reading = excluded.reading, translation = excluded.translation, source = excluded.source,
+                        document = excluded.document, updated_at = excluded.updated_at
+                    "#
+                )
+                .bind(&sentence.japanese)
+                .bind(&sentence.reading)
+                .bind(&sentence.translation)
+                .bind(&sentence.source)
+                .bind(&sentence.document)
+                .bind(&sentence.updated_at)
+                .execute(&self.pool)
+                .await?;
+
+                let (id,): (i64,) = sqlx::query_as("SELECT id FROM sentences WHERE japanese = ?")
+                    .bind(&sentence.japanese)
+                    .fetch_one(&self.pool)
+                    .await?;
+                (id, if existing.is_some() { ImportOutcome::Updated } else { ImportOutcome::Inserted })
+            },
+        };
+
+        for entry in &sentence.entries {
+            if let Some(entry_id) = self.find_entry_id(&entry.entry_kind, &entry.word, &entry.kana, entry.pitch.as_deref()).await? {
+                sqlx::query("INSERT OR IGNORE INTO sentence_entries (sentence_id, entry_kind, entry_id) VALUES (?, ?, ?)")
+                    .bind(sentence_id)
+                    .bind(&entry.entry_kind)
+                    .bind(entry_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    // 获取学习者的所有复习卡片
+    async fn get_review_cards(&self, user_id: i64) -> Result<Vec<ReviewCard>> {
+        let cards = sqlx::query_as::<_, ReviewCard>(
+            "SELECT id, user_id, entry_kind, entry_id, state, due, stability, difficulty, reps, lapses, last_review FROM review_cards WHERE user_id = ? ORDER BY due"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(cards)
+    }
+
+    // 统计某个时间之后第一次复习的卡片数
+    async fn count_cards_introduced(&self, user_id: i64, since: &str) -> Result<i64> {
+        let (count,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM review_cards WHERE user_id = ? AND created_at >= ?"
+        )
+        .bind(user_id)
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    // 保存卡片的新状态并写入复习记录
+    async fn save_review(&self, card: &ReviewCard, log: &ReviewLogEntry) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO review_cards (user_id, entry_kind, entry_id, state, due, stability, difficulty, reps, lapses, last_review)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (user_id, entry_kind, entry_id) DO UPDATE SET
+                state = excluded.state, due = excluded.due, stability = excluded.stability,
+                difficulty = excluded.difficulty, reps = excluded.reps, lapses = excluded.lapses,
+                last_review = excluded.last_review
+            "#
+        )
+        .bind(card.user_id)
+        .bind(&card.entry_kind)
+        .bind(card.entry_id)
+        .bind(&card.state)
+        .bind(&card.due)
+        .bind(card.stability)
+        .bind(card.difficulty)
+        .bind(card.reps)
+        .bind(card.lapses)
+        .bind(&card.last_review)
+        .execute(&mut *tx)
+        .await?;
+
+        let (card_id,): (i64,) = sqlx::query_as(
+            "SELECT id FROM review_cards WHERE user_id = ? AND entry_kind = ? AND entry_id = ?"
+        )
+        .bind(card.user_id)
+        .bind(&card.entry_kind)
+        .bind(card.entry_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO review_log (card_id, user_id, ease, ivl, last_ivl, time_ms, review_type,
+                stability, difficulty, elapsed_days, reviewed_at, reviewed_at_ms)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(card_id)
+        .bind(card.user_id)
+        .bind(log.ease)
+        .bind(log.ivl)
+        .bind(log.last_ivl)
+        .bind(log.time_ms)
+        .bind(log.review_type)
+        .bind(card.stability)
+        .bind(card.difficulty)
+        .bind(log.elapsed_days)
+        .bind(&log.reviewed_at)
+        .bind(log.reviewed_at_ms)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(card_id)
+    }
+
+    // 导出学习者的复习记录
+    async fn export_review_log(&self, user_id: i64) -> Result<Vec<RevlogRecord>> {
+        let records = sqlx::query_as::<_, RevlogRecord>(REVLOG_QUERY)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(records)
+    }
+
+    // 读取缓存的模型回复
+    async fn get_cached_response(&self, key: &str, since: Option<&str>) -> Result<Option<String>> {
+        let row: Option<(String,)> = sqlx::query_as(
+            "SELECT response FROM response_cache WHERE key = ? AND created_at >= ?"
+        )
+        .bind(key)
+        .bind(since.unwrap_or(""))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|(response,)| response))
+    }
+
+    // 写入模型回复缓存
+    async fn save_cached_response(&self, entry: &CachedResponse) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO response_cache (key, provider, model, temperature, max_tokens, response, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, datetime('now'))
+            ON CONFLICT (key) DO UPDATE SET response = excluded.response, created_at = excluded.created_at
+            "#
+        )
+        .bind(&entry.key)
+        .bind(&entry.provider)
+        .bind(&entry.model)
+        .bind(entry.temperature)
+        .bind(entry.max_tokens)
+        .bind(&entry.response)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // 删除过期的缓存
+    async fn purge_cached_responses(&self, before: &str) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM response_cache WHERE created_at < ?")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // 记录一次模型请求的用量
+    async fn save_api_call(&self, call: &ApiCall) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO api_calls (run_id, operation, model, prompt_tokens, completion_tokens, cost)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&call.run_id)
+        .bind(&call.operation)
+        .bind(&call.model)
+        .bind(call.prompt_tokens)
+        .bind(call.completion_tokens)
+        .bind(call.cost)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // 按用途汇总请求用量
+    async fn get_api_usage(&self, run_id: Option<&str>) -> Result<Vec<ApiUsageSummary>> {
+        let summaries = sqlx::query_as::<_, ApiUsageSummary>(
+            r#"
+            SELECT operation, COUNT(*) AS requests,
+                   COALESCE(SUM(prompt_tokens), 0) AS prompt_tokens,
+                   COALESCE(SUM(completion_tokens), 0) AS completion_tokens,
+                   SUM(cost) AS cost
+            FROM api_calls
+            WHERE ? IS NULL OR run_id = ?
+            GROUP BY operation
+            ORDER BY operation
+            "#
+        )
+        .bind(run_id)
+        .bind(run_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(summaries)
+    }
+
+    // 记录条目在某个输入文档中出现
+    async fn record_occurrence(&self, kind: EntryKind, id: i64, document: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO occurrences (entry_kind, entry_id, document) VALUES (?, ?, ?)"
+        )
+        .bind(kind.as_str())
+        .bind(id)
+        .bind(document)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // 统计每个条目出现过的次数
+    async fn get_occurrence_counts(&self, kind: EntryKind) -> Result<HashMap<i64, i64>> {
+        let rows: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT entry_id, COUNT(*) FROM occurrences WHERE entry_kind = ? GROUP BY entry_id"
+        )
+        .bind(kind.as_str())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    // 全文搜索单词和语法
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        let text = query.text.trim();
+        let mut sql = String::from(
+            "SELECT kind, CAST(entry_id AS INTEGER), word, kana, romaji, part_of_speech, analysis_text, tags, updated_at FROM search_index WHERE 1 = 1"
+        );
+        let mut binds: Vec<String> = Vec::new();
+
+        // trigram 分词至少需要 3 个字符，较短的关键词使用 LIKE 匹配
+        let use_match = text.chars().count() >= 3;
+        if use_match {
+            sql.push_str(" AND search_index MATCH ?");
+            binds.push(format!("\"{}\"", text.replace('"', "\"\"")));
+        } else if !text.is_empty() {
+            sql.push_str(
+                " AND (word LIKE ? ESCAPE '\\' OR kana LIKE ? ESCAPE '\\' OR romaji LIKE ? ESCAPE '\\' OR part_of_speech LIKE ? ESCAPE '\\' OR analysis_text LIKE ? ESCAPE '\\')"
+            );
+            let pattern = format!("%{}%", escape_like(text));
+            for _ in 0..5 {
+                binds.push(pattern.clone());
+            }
+        }
+
+        if let Some(kind) = query.kind {
+            sql.push_str(" AND kind = ?");
+            binds.push(kind.as_str().to_string());
+        }
+        if let Some(pos) = &query.part_of_speech {
+            // 能识别的词性按关联表精确过滤，否则按文本匹配
+            match normalize_label(pos).first() {
+                Some(pos) => {
+                    sql.push_str(" AND kind = 'word' AND CAST(entry_id AS INTEGER) IN (SELECT word_id FROM word_pos WHERE pos = ?)");
+                    binds.push(pos.key().to_string());
+                }
+                None => {
+                    sql.push_str(" AND part_of_speech LIKE ? ESCAPE '\\'");
+                    binds.push(format!("%{}%", escape_like(pos)));
+                }
+            }
+        }
+        if let Some(from) = &query.date_from {
+            sql.push_str(" AND date(updated_at) >= date(?)");
+            binds.push(from.clone());
+        }
+        if let Some(to) = &query.date_to {
+            sql.push_str(" AND date(updated_at) <= date(?)");
+            binds.push(to.clone());
+        }
+        if let Some(tag) = &query.tag {
+            sql.push_str(" AND (' ' || tags || ' ') LIKE ? ESCAPE '\\'");
+            binds.push(format!("% {} %", escape_like(tag)));
+        }
+        if let Some(document) = &query.document {
+            sql.push_str(" AND EXISTS (SELECT 1 FROM occurrences o WHERE o.entry_kind = search_index.kind AND o.entry_id = search_index.entry_id AND o.document LIKE ? ESCAPE '\\')");
+            binds.push(format!("%{}%", escape_like(document)));
+        }
+
+        if use_match {
+            sql.push_str(" ORDER BY rank");
+        } else {
+            sql.push_str(" ORDER BY kind DESC, CAST(entry_id AS INTEGER)");
+        }
+        sql.push_str(&format!(" LIMIT {}", if query.limit == 0 { 50 } else { query.limit }));
+
+        let mut db_query = sqlx::query_as::<_, (String, i64, String, String, String, String, String, String, Option<String>)>(&sql);
+        for value in &binds {
+            db_query = db_query.bind(value);
+        }
+        let rows = db_query.fetch_all(&self.pool).await?;
+
+        let hits = rows
+            .into_iter()
+            .filter_map(|(kind, id, word, kana, romaji, part_of_speech, analysis_text, tags, updated_at)| {
+                Some(SearchHit {
+                    kind: EntryKind::parse(&kind)?,
+                    id,
+                    word,
+                    kana,
+                    romaji,
+                    part_of_speech,
+                    snippet: make_snippet(&analysis_text, text),
+                    tags,
+                    updated_at,
+                })
+            })
+            .collect();
+
+        Ok(hits)
+    }
+
+    // 检查单词是否已存在（根据单词、假名，不依据音调和词性）
+    async fn check_word_exists(&self, word: &str, kana: &str) -> Result<bool> {
+        let count: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM words WHERE word = ? AND kana = ?"
+        )
+        .bind(word)
+        .bind(kana)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(count.0 > 0)
+    }
+
+    // 获取已存在的单词信息（只基于 word 和 kana）
+    async fn get_existing_word_by_word_kana(&self, word: &str, kana: &str) -> Result<Option<JapaneseWord>> {
+        let result = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words WHERE word = ? AND kana = ? LIMIT 1", WORD_COLUMNS)
+        )
+        .bind(word)
+        .bind(kana)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(result)
+    }
+
+    // 获取已存在的单词信息（支持多词性合并）
+    async fn get_existing_word(&self, word: &str, kana: &str, pitch: &str) -> Result<Option<MergedWord>> {
+        let result = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words WHERE word = ? AND kana = ? AND pitch = ? LIMIT 1", WORD_COLUMNS)
+        )
+        .bind(word)
+        .bind(kana)
+        .bind(pitch)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        if let Some(existing) = result {
+            // 获取该单词的所有词性
+            let all_pos: Vec<(String,)> = sqlx::query_as(
+                "SELECT DISTINCT part_of_speech FROM words WHERE word = ? AND kana = ? AND pitch = ?"
+            )
+            .bind(word)
+            .bind(kana)
+            .bind(pitch)
+            .fetch_all(&self.pool)
+            .await?;
+            
+            let parts_of_speech: Vec<String> = all_pos.into_iter().map(|row| row.0).collect();
+            
+            Ok(Some(MergedWord {
+                id: existing.id,
+                word: existing.word,
+                kana: existing.kana,
+                pitch: existing.pitch,
+                parts_of_speech,
+                analysis: existing.analysis,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // 获取所有单词
+    async fn get_all_words(&self) -> Result<Vec<JapaneseWord>> {
+        let words = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words ORDER BY id", WORD_COLUMNS)
+        ).fetch_all(&self.pool).await?;
+        
+        Ok(words)
+    }
+
+    // 保存单词到数据库（新的词性覆盖旧的，不再合并）
+    async fn save_words(&self, words: &[WordAnalysis]) -> Result<()> {
+        for word in words {
+            // 检查是否已存在同样的单词（不考虑词性）
+            let existing = sqlx::query_as::<_, JapaneseWord>(
+                &format!("SELECT {} FROM words WHERE word = ? AND kana = ? AND pitch = ? LIMIT 1", WORD_COLUMNS)
+            )
+            .bind(&word.word)
+            .bind(&word.kana)
+            .bind(&word.pitch)
+            .fetch_optional(&self.pool)
+            .await?;
+            
+            if let Some(existing_word) = existing {
+                // 如果已存在，检查词性是否不同
+                if existing_word.part_of_speech != word.part_of_speech {
+                    if existing_word.pos_locked {
+                        println!("  🔒 单词词性已锁定，跳过更新: {} ({}) - {}",
+                            word.word, word.kana, existing_word.part_of_speech);
+                        continue;
+                    }
+
+                    println!("  🔄 更新单词词性: {} ({}) - {} -> {}", 
+                        word.word, word.kana, 
+                        existing_word.part_of_speech, 
+                        word.part_of_speech
+                    );
+                    
+                    // 更新记录，以新的词性和分析为准（解析锁定时保留原解析），并更新时间
+                    let (analysis, prompt_version) = if existing_word.analysis_locked {
+                        println!("  🔒 单词解析已锁定，保留原解析: {} ({})", word.word, word.kana);
+                        (&existing_word.analysis, existing_word.prompt_version)
+                    } else if word.analysis.is_empty() {
+                        // 只保存了其他语言解析的单词，保留原有的中文解析
+                        (&existing_word.analysis, existing_word.prompt_version)
+                    } else {
+                        (&word.analysis, word.prompt_version)
+                    };
+                    let mut tx = self.pool.begin().await?;
+                    sqlx::query(
+                        "UPDATE words SET part_of_speech = ?, analysis = ?, prompt_version = ?, updated_at = datetime('now') WHERE id = ?"
+                    )
+                    .bind(&word.part_of_speech)
+                    .bind(analysis)
+                    .bind(prompt_version)
+                    .bind(existing_word.id)
+                    .execute(&mut *tx)
+                    .await?;
+                    Self::write_word_pos(&mut tx, existing_word.id, &word.part_of_speech).await?;
+                    tx.commit().await?;
+                    
+                    self.reindex_entry(EntryKind::Word, existing_word.id).await?;
+                } else {
+                    println!("  ✅ 单词词性未变化，跳过更新: {} ({})", word.word, word.kana);
+                }
+            } else {
+                // 如果不存在，直接插入
+                println!("  ➕ 新增单词: {} ({}) - {}", word.word, word.kana, word.part_of_speech);
+                let mut tx = self.pool.begin().await?;
+                let result = sqlx::query(
+                    "INSERT INTO words (word, kana, pitch, part_of_speech, analysis, prompt_version, updated_at) VALUES (?, ?, ?, ?, ?, ?, datetime('now'))"
+                )
+                .bind(&word.word)
+                .bind(&word.kana)
+                .bind(&word.pitch)
+                .bind(&word.part_of_speech)
+                .bind(&word.analysis)
+                .bind(word.prompt_version)
+                .execute(&mut *tx)
+                .await?;
+                let id = result.last_insert_rowid();
+                Self::write_word_pos(&mut tx, id, &word.part_of_speech).await?;
+                tx.commit().await?;
+                
+                self.reindex_entry(EntryKind::Word, id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    // 更新单词词性
+    async fn update_word_part_of_speech(&self, id: i64, new_pos: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "UPDATE words SET part_of_speech = ?, updated_at = datetime('now') WHERE id = ?"
+        )
+        .bind(new_pos)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        Self::write_word_pos(&mut tx, id, new_pos).await?;
+        tx.commit().await?;
+        
+        self.reindex_entry(EntryKind::Word, id).await?;
+        
+        Ok(())
+    }
+
+    // 更新单词的 pitch 和词性（处理唯一约束冲突）
+    async fn update_word_pitch_and_pos(&self, id: i64, new_pitch: &str, new_pos: &str) -> Result<()> {
+        // 首先获取当前记录的信息
+        let current_word = self.get_word_by_id(id).await?;
+        if current_word.is_none() {
+            return Err(anyhow::anyhow!("单词 ID {} 不存在", id));
+        }
+        let current = current_word.unwrap();
+        
+        // 检查是否存在相同 (word, kana, pitch) 的其他记录
+        let existing_conflict = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words WHERE word = ? AND kana = ? AND pitch = ? AND id != ? LIMIT 1", WORD_COLUMNS)
+        )
+        .bind(&current.word)
+        .bind(&current.kana)
+        .bind(new_pitch)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        if let Some(conflict_record) = existing_conflict {
+            // 冲突记录包含手动编辑的字段时不删除，保留手动编辑的内容
+            if LockField::ALL.iter().any(|field| conflict_record.is_locked(*field)) {
+                println!("  🔒 冲突记录 ID {} 含有锁定字段，跳过更新 ID {}", conflict_record.id, id);
+                return Ok(());
+            }
+
+            // 如果存在冲突记录，删除冲突记录，然后更新当前记录
+            println!("  🔄 发现冲突记录，删除旧记录 ID {}，更新当前记录 ID {}", 
+                conflict_record.id, id
+            );
+            
+            // 冲突记录的关联数据归并到当前记录，删除冲突记录并更新当前记录
+            self.merge_word_into(conflict_record.id, id, new_pitch, new_pos).await?;
+            
+            println!("  ✅ 冲突处理完成: {} ({}) - pitch: {}->{}, pos: {}->{}", 
+                current.word, current.kana, 
+                current.pitch, new_pitch,
+                current.part_of_speech, new_pos
+            );
+        } else {
+            // 没有冲突，直接更新
+            let mut tx = self.pool.begin().await?;
+            sqlx::query(
+                "UPDATE words SET pitch = ?, part_of_speech = ?, updated_at = datetime('now') WHERE id = ?"
+            )
+            .bind(new_pitch)
+            .bind(new_pos)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+            Self::write_word_pos(&mut tx, id, new_pos).await?;
+            tx.commit().await?;
+        }
+        
+        self.reindex_entry(EntryKind::Word, id).await?;
+        
+        Ok(())
+    }
+
+    // 更新单词解析
+    async fn update_word_analysis(&self, id: i64, new_analysis: &str, prompt_version: i64) -> Result<()> {
+        sqlx::query(
+            "UPDATE words SET analysis = ?, prompt_version = ?, updated_at = datetime('now') WHERE id = ?"
+        )
+        .bind(new_analysis)
+        .bind(prompt_version)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        
+        self.reindex_entry(EntryKind::Word, id).await?;
+        
+        Ok(())
+    }
+
+    // 根据ID获取单词信息
+    async fn get_word_by_id(&self, id: i64) -> Result<Option<JapaneseWord>> {
+        let word = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words WHERE id = ?", WORD_COLUMNS)
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(word)
+    }
+
+    // 保存语法到数据库（锁定的字段保留原值）
+    async fn save_grammar(&self, grammar: &[GrammarAnalysis]) -> Result<()> {
+        for item in grammar {
+            // 按规范写法和已记录的别名查找，变体和活用形合并到同一条目
+            let existing = self.get_grammar_by_word(&item.grammar).await?;
+
+            let id = match existing {
+                Some(existing) => {
+                    if existing.analysis_locked || existing.kana_locked {
+                        println!("  🔒 语法存在锁定字段，保留手动编辑内容: {}", item.grammar);
+                    }
+                    let kana = if existing.kana_locked { &existing.kana } else { &item.kana };
+                    // 解析锁定或只保存了其他语言解析时保留原有的中文解析
+                    let (analysis, prompt_version) = if existing.analysis_locked || item.analysis.is_empty() {
+                        (&existing.analysis, existing.prompt_version)
+                    } else {
+                        (&item.analysis, item.prompt_version)
+                    };
+
+                    sqlx::query(
+                        "UPDATE grammar SET kana = ?, analysis = ?, prompt_version = ?, updated_at = datetime('now') WHERE id = ?"
+                    )
+                    .bind(kana)
+                    .bind(analysis)
+                    .bind(prompt_version)
+                    .bind(existing.id)
+                    .execute(&self.pool)
+                    .await?;
+                    
+                    existing.id
+                },
+                None => {
+                    let result = sqlx::query(
+                        "INSERT INTO grammar (word, kana, analysis, prompt_version, updated_at) VALUES (?, ?, ?, ?, datetime('now'))"
+                    )
+                    .bind(canonical_grammar(&item.grammar))
+                    .bind(&item.kana)
+                    .bind(&item.analysis)
+                    .bind(item.prompt_version)
+                    .execute(&self.pool)
+                    .await?;
+                    
+                    result.last_insert_rowid()
+                }
+            };
+
+            self.reindex_entry(EntryKind::Grammar, id).await?;
+            if let Some(entry) = self.get_grammar_by_id(id).await? {
+                self.add_grammar_alias(&entry, &item.grammar).await?;
+            }
+        }
+        Ok(())
+    }
+
+    // 更新语法解析
+    async fn update_grammar_analysis(&self, id: i64, new_analysis: &str, prompt_version: i64) -> Result<()> {
+        sqlx::query(
+            "UPDATE grammar SET analysis = ?, prompt_version = ?, updated_at = datetime('now') WHERE id = ?"
+        )
+        .bind(new_analysis)
+        .bind(prompt_version)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        
+        self.reindex_entry(EntryKind::Grammar, id).await?;
+        
+        Ok(())
+    }
+
+    // 根据ID获取语法信息
+    async fn get_grammar_by_id(&self, id: i64) -> Result<Option<JapaneseGrammar>> {
+        let grammar = sqlx::query_as::<_, JapaneseGrammar>(
+            &format!("SELECT {} FROM grammar WHERE id = ?", GRAMMAR_COLUMNS)
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(grammar)
+    }
+
+    // 根据语法表达获取语法信息（按规范写法或已记录的别名匹配）
+    async fn get_grammar_by_word(&self, word: &str) -> Result<Option<JapaneseGrammar>> {
+        let canonical = canonical_grammar(word);
+        let grammar = sqlx::query_as::<_, JapaneseGrammar>(
+            &format!(
+                "SELECT {} FROM grammar WHERE word = ? OR word = ? OR id IN (SELECT grammar_id FROM grammar_aliases WHERE alias = ?) ORDER BY word = ? DESC, word = ? DESC LIMIT 1",
+                GRAMMAR_COLUMNS
+            )
+        )
+        .bind(word.trim())
+        .bind(&canonical)
+        .bind(word.trim())
+        .bind(word.trim())
+        .bind(&canonical)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(grammar)
+    }
+
+    async fn get_grammar_aliases(&self, id: i64) -> Result<Vec<String>> {
+        let aliases: Vec<(String,)> = sqlx::query_as("SELECT alias FROM grammar_aliases WHERE grammar_id = ? ORDER BY alias")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(aliases.into_iter().map(|(alias,)| alias).collect())
+    }
+
+    // 查找假名被锁定的同名单词（手动修改过假名后，重新提取时仍视为同一单词）
+    async fn get_kana_locked_word(&self, word: &str) -> Result<Option<JapaneseWord>> {
+        let result = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words WHERE word = ? AND kana_locked = 1 LIMIT 1", WORD_COLUMNS)
+        )
+        .bind(word)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    // 手动编辑条目字段，编辑后自动锁定该字段
+    async fn update_entry_field(&self, kind: EntryKind, id: i64, field: LockField, value: &str) -> Result<()> {
+        if !field.applies_to(kind) {
+            return Err(anyhow::anyhow!("{}没有{}字段", kind.label(), field.label()));
+        }
+
+        let sql = match kind {
+            EntryKind::Word => format!(
+                "UPDATE words SET {} = ?, {} = 1, updated_at = datetime('now') WHERE id = ?",
+                field.value_column(), field.lock_column()
+            ),
+            EntryKind::Grammar => format!(
+                "UPDATE grammar SET {} = ?, {} = 1, updated_at = datetime('now') WHERE id = ?",
+                field.value_column(), field.lock_column()
+            ),
+        };
+
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(&sql)
+            .bind(value)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("{} ID {} 不存在", kind.label(), id));
+        }
+        if kind == EntryKind::Word && field == LockField::PartOfSpeech {
+            Self::write_word_pos(&mut tx, id, value).await?;
+        }
+        tx.commit().await?;
+
+        self.reindex_entry(kind, id).await?;
+
+        Ok(())
+    }
+
+    // 设置或解除字段锁定
+    async fn set_entry_lock(&self, kind: EntryKind, id: i64, field: LockField, locked: bool) -> Result<()> {
+        if !field.applies_to(kind) {
+            return Err(anyhow::anyhow!("{}没有{}字段", kind.label(), field.label()));
+        }
+
+        let table = match kind {
+            EntryKind::Word => "words",
+            EntryKind::Grammar => "grammar",
+        };
+
+        let result = sqlx::query(&format!("UPDATE {} SET {} = ? WHERE id = ?", table, field.lock_column()))
+            .bind(locked)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("{} ID {} 不存在", kind.label(), id));
+        }
+
+        Ok(())
+    }
+
+    // 获取所有语法
+    async fn get_all_grammar(&self) -> Result<Vec<JapaneseGrammar>> {
+        let grammar = sqlx::query_as::<_, JapaneseGrammar>(
+            &format!("SELECT {} FROM grammar ORDER BY id", GRAMMAR_COLUMNS)
+        ).fetch_all(&self.pool).await?;
+        
+        Ok(grammar)
+    }
+
+    // 导出所有单词（包含全部列）
+    async fn export_words(&self) -> Result<Vec<ExportedWord>> {
+        let words = sqlx::query_as::<_, ExportedWord>(
+            "SELECT word, kana, pitch, part_of_speech, analysis, created_at, updated_at, analysis_locked, pos_locked, pitch_locked, kana_locked, tags, frequency_rank, jlpt_level, prompt_version FROM words ORDER BY id"
+        ).fetch_all(&self.pool).await?;
+
+        Ok(words)
+    }
+
+    // 导出所有语法（包含全部列）
+    async fn export_grammar(&self) -> Result<Vec<ExportedGrammar>> {
+        let grammar = sqlx::query_as::<_, ExportedGrammar>(
+            "SELECT word, kana, analysis, created_at, updated_at, analysis_locked, kana_locked, tags, frequency_rank, jlpt_level, prompt_version FROM grammar ORDER BY id"
+        ).fetch_all(&self.pool).await?;
+
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT g.word, a.alias FROM grammar_aliases a JOIN grammar g ON g.id = a.grammar_id ORDER BY a.alias"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut aliases: HashMap<String, Vec<String>> = HashMap::new();
+        for (word, alias) in rows {
+            aliases.entry(word).or_default().push(alias);
+        }
+
+        Ok(grammar.into_iter().map(|g| ExportedGrammar { aliases: aliases.remove(&g.word).unwrap_or_default(), ..g }).collect())
+    }
+
+    // 导出所有出现记录
+    async fn export_occurrences(&self) -> Result<Vec<ExportedOccurrence>> {
+        let occurrences = sqlx::query_as::<_, ExportedOccurrence>(
+            r#"
+            SELECT o.entry_kind, w.word, w.kana, w.pitch, o.document, o.created_at
+            FROM occurrences o JOIN words w ON o.entry_kind = 'word' AND o.entry_id = w.id
+            UNION ALL
+            SELECT o.entry_kind, g.word, g.kana, NULL, o.document, o.created_at
+            FROM occurrences o JOIN grammar g ON o.entry_kind = 'grammar' AND o.entry_id = g.id
+            "#
+        ).fetch_all(&self.pool).await?;
+
+        Ok(occurrences)
+    }
+
+    // 导入单词，按 (word, kana, pitch) 合并
+    async fn import_word(&self, word: &ExportedWord, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let existing = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words WHERE word = ? AND kana = ? AND pitch = ? LIMIT 1", WORD_COLUMNS)
+        )
+        .bind(&word.word)
+        .bind(&word.kana)
+        .bind(&word.pitch)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match existing {
+            Some(existing) => {
+                if !should_replace(policy, existing.updated_at.as_deref(), word.updated_at.as_deref()) {
+                    return Ok(ImportOutcome::Skipped);
+                }
+
+                let mut tx = self.pool.begin().await?;
+                sqlx::query(
+                    r#"
+                    UPDATE words SET part_of_speech = ?, analysis = ?, updated_at = COALESCE(?, datetime('now')),
+                        analysis_locked = ?, pos_locked = ?, pitch_locked = ?, kana_locked = ?, tags = ?,
+                        frequency_rank = ?, jlpt_level = ?, prompt_version = ?
+                    WHERE id = ?
+                    "#
+                )
+                .bind(&word.part_of_speech)
+                .bind(&word.analysis)
+                .bind(&word.updated_at)
+                .bind(word.analysis_locked)
+                .bind(word.pos_locked)
+                .bind(word.pitch_locked)
+                .bind(word.kana_locked)
+                .bind(&word.tags)
+                .bind(word.frequency_rank)
+                .bind(&word.jlpt_level)
+                .bind(word.prompt_version)
+                .bind(existing.id)
+                .execute(&mut *tx)
+                .await?;
+                Self::write_word_pos(&mut tx, existing.id, &word.part_of_speech).await?;
+                tx.commit().await?;
+
+                self.reindex_entry(EntryKind::Word, existing.id).await?;
+                Ok(ImportOutcome::Updated)
+            },
+            None => {
+                let mut tx = self.pool.begin().await?;
+                let result = sqlx::query(
+                    r#"
+                    INSERT INTO words (word, kana, pitch, part_of_speech, analysis, created_at, updated_at,
+                        analysis_locked, pos_locked, pitch_locked, kana_locked, tags, frequency_rank, jlpt_level, prompt_version)
+                    VALUES (?, ?, ?, ?, ?, COALESCE(?, datetime('now')), COALESCE(?, datetime('now')), ?, ?, ?, ?, ?, ?, ?, ?)
+                    "#
+                )
+                .bind(&word.word)
+                .bind(&word.kana)
+                .bind(&word.pitch)
+                .bind(&word.part_of_speech)
+                .bind(&word.analysis)
+                .bind(&word.created_at)
+                .bind(&word.updated_at)
+                .bind(word.analysis_locked)
+                .bind(word.pos_locked)
+                .bind(word.pitch_locked)
+                .bind(word.kana_locked)
+                .bind(&word.tags)
+                .bind(word.frequency_rank)
+                .bind(&word.jlpt_level)
+                .bind(word.prompt_version)
+                .execute(&mut *tx)
+                .await?;
+                let id = result.last_insert_rowid();
+                Self::write_word_pos(&mut tx, id, &word.part_of_speech).await?;
+                tx.commit().await?;
+
+                self.reindex_entry(EntryKind::Word, id).await?;
+                Ok(ImportOutcome::Inserted)
+            },
+        }
+    }
+
+    // 导入语法，按语法表达合并
+    async fn import_grammar(&self, grammar: &ExportedGrammar, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let outcome = match self.get_grammar_by_word(&grammar.word).await? {
+            Some(existing) if !should_replace(policy, existing.updated_at.as_deref(), grammar.updated_at.as_deref()) => {
+                ImportOutcome::Skipped
+            },
+            Some(existing) => {
+                sqlx::query(
+                    r#"
+                    UPDATE grammar SET kana = ?, analysis = ?, updated_at = COALESCE(?, datetime('now')),
+                        analysis_locked = ?, kana_locked = ?, tags = ?, frequency_rank = ?, jlpt_level = ?, prompt_version = ?
+                    WHERE id = ?
+                    "#
+                )
+                .bind(&grammar.kana)
+                .bind(&grammar.analysis)
+                .bind(&grammar.updated_at)
+                .bind(grammar.analysis_locked)
+                .bind(grammar.kana_locked)
+                .bind(&grammar.tags)
+                .bind(grammar.frequency_rank)
+                .bind(&grammar.jlpt_level)
+                .bind(grammar.prompt_version)
+                .bind(existing.id)
+                .execute(&self.pool)
+                .await?;
+
+                self.reindex_entry(EntryKind::Grammar, existing.id).await?;
+                ImportOutcome::Updated
+            },
+            None => {
+                let result = sqlx::query(
+                    r#"
+                    INSERT INTO grammar (word, kana, analysis, created_at, updated_at, analysis_locked, kana_locked, tags,
+                        frequency_rank, jlpt_level, prompt_version)
+                    VALUES (?, ?, ?, COALESCE(?, datetime('now')), COALESCE(?, datetime('now')), ?, ?, ?, ?, ?, ?)
+                    "#
+                )
+                .bind(canonical_grammar(&grammar.word))
+                .bind(&grammar.kana)
+                .bind(&grammar.analysis)
+                .bind(&grammar.created_at)
+                .bind(&grammar.updated_at)
+                .bind(grammar.analysis_locked)
+                .bind(grammar.kana_locked)
+                .bind(&grammar.tags)
+                .bind(grammar.frequency_rank)
+                .bind(&grammar.jlpt_level)
+                .bind(grammar.prompt_version)
+                .execute(&self.pool)
+                .await?;
+
+                let id = result.last_insert_rowid();
+                self.reindex_entry(EntryKind::Grammar, id).await?;
+                ImportOutcome::Inserted
+            },
+        };
+
+        // 导入的写法和其他写法都指向合并后的条目，不受冲突策略影响
+        if let Some(entry) = self.get_grammar_by_word(&grammar.word).await? {
+            for alias in std::iter::once(&grammar.word).chain(&grammar.aliases) {
+                self.add_grammar_alias(&entry, alias).await?;
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    // 导入出现记录，找不到对应条目或已存在相同记录时跳过
+    async fn import_occurrence(&self, occurrence: &ExportedOccurrence) -> Result<ImportOutcome> {
+        let entry_id = self.find_entry_id(&occurrence.entry_kind, &occurrence.word, &occurrence.kana, occurrence.pitch.as_deref()).await?;
+        let Some(entry_id) = entry_id else {
+            return Ok(ImportOutcome::Skipped);
+        };
+
+        let (duplicates,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM occurrences WHERE entry_kind = ? AND entry_id = ? AND document = ? AND created_at IS ?"
+        )
+        .bind(&occurrence.entry_kind)
+        .bind(entry_id)
+        .bind(&occurrence.document)
+        .bind(&occurrence.created_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if duplicates > 0 {
+            return Ok(ImportOutcome::Skipped);
+        }
+
+        sqlx::query(
+            "INSERT INTO occurrences (entry_kind, entry_id, document, created_at) VALUES (?, ?, ?, COALESCE(?, datetime('now')))"
+        )
+        .bind(&occurrence.entry_kind)
+        .bind(entry_id)
+        .bind(&occurrence.document)
+        .bind(&occurrence.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(ImportOutcome::Inserted)
+    }
+
+    // 导出所有学习状态
+    async fn export_statuses(&self) -> Result<Vec<ExportedStatus>> {
+        let statuses = sqlx::query_as::<_, ExportedStatus>(
+            r#"
+            SELECT u.name AS user_name, s.entry_kind, s.word, s.status, s.updated_at
+            FROM entry_status s JOIN users u ON s.user_id = u.id
+            ORDER BY u.name, s.entry_kind, s.word
+            "#
+        ).fetch_all(&self.pool).await?;
+
+        Ok(statuses)
+    }
+
+    // 导入学习状态
+    async fn import_status(&self, status: &ExportedStatus, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let user_id = self.ensure_user(&status.user_name).await?;
+
+        let existing: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT updated_at FROM entry_status WHERE user_id = ? AND entry_kind = ? AND word = ?"
+        )
+        .bind(user_id)
+        .bind(&status.entry_kind)
+        .bind(&status.word)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let outcome = match &existing {
+            Some((updated_at,)) => {
+                if !should_replace(policy, updated_at.as_deref(), status.updated_at.as_deref()) {
+                    return Ok(ImportOutcome::Skipped);
+                }
+                ImportOutcome::Updated
+            },
+            None => ImportOutcome::Inserted,
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO entry_status (user_id, entry_kind, word, status, updated_at)
+            VALUES (?, ?, ?, ?, COALESCE(?, datetime('now')))
+            ON CONFLICT (user_id, entry_kind, word) DO UPDATE SET status = excluded.status, updated_at = excluded.updated_at
+            "#
+        )
+        .bind(user_id)
+        .bind(&status.entry_kind)
+        .bind(&status.word)
+        .bind(&status.status)
+        .bind(&status.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(outcome)
+    }
+
+    // 导出所有学习者的复习卡片
+    async fn export_review_cards(&self) -> Result<Vec<ExportedReviewCard>> {
+        let cards = sqlx::query_as::<_, ExportedReviewCard>(REVIEW_CARDS_QUERY)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(cards)
+    }
+
+    // 导入复习卡片，找不到对应条目时跳过
+    async fn import_review_card(&self, card: &ExportedReviewCard, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let Some(entry_id) = self.find_entry_id(&card.entry_kind, &card.word, &card.kana, card.pitch.as_deref()).await? else {
+            return Ok(ImportOutcome::Skipped);
+        };
+        let user_id = self.ensure_user(&card.user_name).await?;
+
+        let existing: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT last_review FROM review_cards WHERE user_id = ? AND entry_kind = ? AND entry_id = ?"
+        )
+        .bind(user_id)
+        .bind(&card.entry_kind)
+        .bind(entry_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let outcome = match &existing {
+            Some((last_review,)) => {
+                if !should_replace(policy, last_review.as_deref(), card.last_review.as_deref()) {
+                    return Ok(ImportOutcome::Skipped);
+                }
+                ImportOutcome::Updated
+            },
+            None => ImportOutcome::Inserted,
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO review_cards (user_id, entry_kind, entry_id, state, due, stability, difficulty, reps, lapses, last_review, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, COALESCE(?, datetime('now')))
+            ON CONFLICT (user_id, entry_kind, entry_id) DO UPDATE SET
+                state = excluded.state, due = excluded.due, stability = excluded.stability,
+                difficulty = excluded.difficulty, reps = excluded.reps, lapses = excluded.lapses,
+                last_review = excluded.last_review
+            "#
+        )
+        .bind(user_id)
+        .bind(&card.entry_kind)
+        .bind(entry_id)
+        .bind(&card.state)
+        .bind(&card.due)
+        .bind(card.stability)
+        .bind(card.difficulty)
+        .bind(card.reps)
+        .bind(card.lapses)
+        .bind(&card.last_review)
+        .bind(&card.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(outcome)
+    }
+
+    // 导出所有学习者的复习记录
+    async fn export_review_history(&self) -> Result<Vec<ExportedReviewLog>> {
+        let history = sqlx::query_as::<_, ExportedReviewLog>(REVIEW_HISTORY_QUERY)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(history)
+    }
+
+    // 导入复习记录，挂到同一学习者同一条目的卡片下
+    async fn import_review_history(&self, review: &ExportedReviewLog) -> Result<ImportOutcome> {
+        let Some(entry_id) = self.find_entry_id(&review.entry_kind, &review.word, &review.kana, review.pitch.as_deref()).await? else {
+            return Ok(ImportOutcome::Skipped);
+        };
+        let card: Option<(i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT c.id, c.user_id FROM review_cards c JOIN users u ON c.user_id = u.id
+            WHERE u.name = ? AND c.entry_kind = ? AND c.entry_id = ?
+            "#
+        )
+        .bind(&review.user_name)
+        .bind(&review.entry_kind)
+        .bind(entry_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some((card_id, user_id)) = card else {
+            return Ok(ImportOutcome::Skipped);
+        };
+
+        let (duplicates,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM review_log WHERE card_id = ? AND reviewed_at_ms = ?"
+        )
+        .bind(card_id)
+        .bind(review.reviewed_at_ms)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if duplicates > 0 {
+            return Ok(ImportOutcome::Skipped);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO review_log (card_id, user_id, ease, ivl, last_ivl, time_ms, review_type,
+                stability, difficulty, elapsed_days, reviewed_at, reviewed_at_ms)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(card_id)
+        .bind(user_id)
+        .bind(review.ease)
+        .bind(review.ivl)
+        .bind(review.last_ivl)
+        .bind(review.time_ms)
+        .bind(review.review_type)
+        .bind(review.stability)
+        .bind(review.difficulty)
+        .bind(review.elapsed_days)
+        .bind(&review.reviewed_at)
+        .bind(review.reviewed_at_ms)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(ImportOutcome::Inserted)
+    }
+}
+
+// 辅助函数：根据冲突策略判断是否用导入的条目替换现有条目
+pub(crate) fn should_replace(policy: ConflictPolicy, existing: Option<&str>, incoming: Option<&str>) -> bool {
+    match policy {
+        ConflictPolicy::KeepExisting => false,
+        ConflictPolicy::Overwrite => true,
+        // 时间格式为 YYYY-MM-DD HH:MM:SS，可以直接按字符串比较
+        ConflictPolicy::KeepNewer => match (existing, incoming) {
+            (Some(existing), Some(incoming)) => incoming > existing,
+            (None, Some(_)) => true,
+            _ => false,
+        },
+    }
+}
+
+// 辅助函数：转义 LIKE 模式中的通配符（配合 ESCAPE '\'），让用户输入的 % 和 _ 按字面匹配
+pub(crate) fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// 辅助函数：截取关键词附近的解析文本作为搜索摘要
+pub(crate) fn make_snippet(text: &str, keyword: &str) -> String {
+    const CONTEXT: usize = 20;
+    let chars: Vec<char> = text.chars().filter(|c| *c != '\n').collect();
+    let flat: String = chars.iter().collect();
+
+    let start = if keyword.is_empty() {
+        0
+    } else {
+        match flat.find(keyword) {
+            Some(byte_index) => flat[..byte_index].chars().count().saturating_sub(CONTEXT),
+            None => 0,
+        }
+    };
+    let end = (start + CONTEXT * 2 + keyword.chars().count()).min(chars.len());
+
+    let mut snippet: String = chars[start..end].iter().collect();
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+// 辅助函数：合并固定标签和条目的附加标签
+fn card_tags(base: &str, extra: &str) -> String {
+    let extra = extra.trim();
+    if extra.is_empty() {
+        base.to_string()
+    } else {
+        format!("{} {}", base, extra).replace('"', "\"\"")
+    }
+}
+
+// 学习顺序字段：补零以便在 Anki 中按文本排序
+fn sort_field(index: usize) -> String {
+    format!("{:05}", index + 1)
+}
+
+// 辅助函数：将音调数字转换为上标符号
+pub fn pitch_to_superscript(pitch: &str) -> String {
+    match pitch {
         "0" => "⓪".to_string(),
         "1" => "①".to_string(),
         "2" => "②".to_string(),
@@ -427,19 +3312,79 @@ pub fn pitch_to_superscript(pitch: &str) -> String {
     }
 }
 
-// 生成单词 Anki 卡片（支持词性合并和HTML格式）
-pub fn generate_word_cards(words: &[JapaneseWord], output_file: &str) -> Result<()> {
+// 结构化解析中各部分的标题，按解析语言显示
+fn details_labels(language: Language) -> (&'static str, &'static str) {
+    match language {
+        Language::Chinese => ("例：", "词汇对比："),
+        Language::English => ("Ex:", "Similar words:"),
+        Language::Japanese => ("例：", "類義語との比較："),
+    }
+}
+
+// 一条例句：日语 (读音) - 翻译，读音和翻译为空时省略
+fn render_example(example: &ExampleSentence) -> String {
+    let mut line = example.japanese.clone();
+    if !example.reading.is_empty() {
+        line.push_str(&format!(" ({})", example.reading));
+    }
+    if !example.translation.is_empty() {
+        line.push_str(&format!(" - {}", example.translation));
+    }
+    line
+}
+
+/// 把结构化单词解析渲染为卡片背面的 HTML：概要、义项和例句、词源、总结、近义词比较，空的部分省略
+pub fn render_word_details(word: &str, details: &WordDetails, language: Language) -> String {
+    let (example_label, comparison_label) = details_labels(language);
+    let mut sections = vec![format!("<div>{}</div>", details.core_meaning)];
+
+    if !details.senses.is_empty() {
+        let senses: Vec<String> = details.senses.iter().enumerate().map(|(index, sense)| {
+            let mut lines = vec![format!("{}. {}", index + 1, sense.meaning)];
+            if !sense.explanation.is_empty() {
+                lines.push(sense.explanation.clone());
+            }
+            for example in &sense.examples {
+                lines.push(format!("{} {}", example_label, render_example(example)));
+            }
+            lines.join("<br>")
+        }).collect();
+        sections.push(format!("<div>{}</div>", senses.join("<br><br>")));
+    }
+    for text in [&details.etymology, &details.summary] {
+        if !text.is_empty() {
+            sections.push(format!("<div>{}</div>", text));
+        }
+    }
+    if !details.comparisons.is_empty() {
+        let comparisons: Vec<String> = details.comparisons.iter().map(|c| {
+            let pair = match language {
+                Language::Japanese => format!("「{}」と「{}」", word, c.word),
+                _ => format!("「{}」vs「{}」", word, c.word),
+            };
+            let colon = if language == Language::English { ":" } else { "：" };
+            format!("<b>{}{}</b> {}", pair, colon, c.difference)
+        }).collect();
+        sections.push(format!("<div><b>{}</b><br><br>{}</div>", comparison_label, comparisons.join("<br><br>")));
+    }
+
+    sections.join("\n<hr>\n")
+}
+
+// 生成单词 Anki 卡片（支持词性合并和HTML格式），按传入顺序编号作为学习顺序
+// 有结构化解析的单词按当前布局渲染背面，并单独输出核心意思和例句字段
+pub fn generate_word_cards(words: &[JapaneseWord], details: &HashMap<i64, WordDetails>, output_file: &str, pos_language: Language, explanation_language: Language) -> Result<()> {
     let mut file = File::create(output_file)?;
     
-    for word in words {
-        // 解析词性字段（用｜分隔）
-        let parts_of_speech: Vec<&str> = word.part_of_speech.split('｜').collect();
+    for (index, word) in words.iter().enumerate() {
+        // 解析词性字段（用｜分隔），按配置的语言显示词性名称
+        let parts_of_speech = normalize_field(&word.part_of_speech).labels_in(pos_language);
         
         // 直接使用数据库中的pitch字段
         let pitch = &word.pitch;
         
         // 生成正面内容（HTML格式）
-        let word_with_pitch = format!("{}{}", word.word, pitch_to_superscript(&pitch));
+        let word_with_pitch = format!("{}{}", word.word, pitch_to_superscript(pitch));
         
         // 添加语音文件引用
         let audio_tag = format!("[sound:japanese_word_{}.wav]", word.id);
@@ -463,11 +3408,29 @@ pub fn generate_word_cards(words: &[JapaneseWord], output_file: &str) -> Result<
             )
         };
         
-        // CSV 格式：id:正面:背面:标签
-        writeln!(file, "{}:\"{}\":\"{}\":\"单词\"", 
+        // 没有结构化解析的单词（旧数据或手动编辑过的解析）直接使用保存的 HTML
+        let (back, meaning, examples) = match details.get(&word.id) {
+            Some(details) => (
+                render_word_details(&word.word, details, explanation_language),
+                details.core_meaning.clone(),
+                details.senses.iter()
+                    .flat_map(|sense| sense.examples.iter().map(render_example))
+                    .collect::<Vec<_>>()
+                    .join("<br>"),
+            ),
+            None => (word.analysis.clone(), String::new(), String::new()),
+        };
+        
+        // CSV 格式：id:正面:背面:标签:学习顺序:活用表:核心意思:例句
+        writeln!(file, "{}:\"{}\":\"{}\":\"{}\":{}:\"{}\":\"{}\":\"{}\"", 
             word.id,
             front.replace("\"", "\"\""),
-            word.analysis.replace("\"", "\"\"")
+            back.replace("\"", "\"\""),
+            card_tags("单词", &entry_tags(word.frequency_rank, word.jlpt_level.as_deref(), &word.tags)),
+            sort_field(index),
+            conjugation_table(&word.word, &word.kana, &word.part_of_speech).replace("\"", "\"\""),
+            meaning.replace("\"", "\"\""),
+            examples.replace("\"", "\"\"")
         )?;
     }
     
@@ -475,11 +3438,76 @@ pub fn generate_word_cards(words: &[JapaneseWord], output_file: &str) -> Result<
     Ok(())
 }
 
-// 生成语法 Anki 卡片  
-pub fn generate_grammar_cards(grammar: &[JapaneseGrammar], output_file: &str) -> Result<()> {
+// 结构化语法解析中各部分的标题：语体、接续、相关语法
+fn grammar_labels(language: Language) -> (&'static str, &'static str, &'static str) {
+    match language {
+        Language::Chinese => ("语体：", "接续：", "相关语法："),
+        Language::English => ("Register:", "Connection:", "Related grammar:"),
+        Language::Japanese => ("文体：", "接続：", "関連する文法："),
+    }
+}
+
+// 接续规则：词类（活用形）+ 语法，有补充说明时附在后面
+fn render_connection(grammar: &str, rule: &ConnectionRule, language: Language) -> String {
+    let pattern = grammar.trim_start_matches(['〜', '～']);
+    let mut line = match (rule.form.is_empty(), language) {
+        (true, _) => format!("{} + {}", rule.base.label_in(language), pattern),
+        (false, Language::English) => format!("{} ({}) + {}", rule.base.label_in(language), rule.form, pattern),
+        (false, _) => format!("{}（{}）+ {}", rule.base.label_in(language), rule.form, pattern),
+    };
+    if !rule.note.is_empty() {
+        line.push_str(&format!(" - {}", rule.note));
+    }
+    line
+}
+
+// 相关语法，已收录的语法附上卡片ID方便在 Anki 中查找
+fn render_related(grammar: &str, related: &RelatedGrammar, language: Language) -> String {
+    let pair = match language {
+        Language::Japanese => format!("「{}」と「{}」", grammar, related.grammar),
+        _ => format!("「{}」vs「{}」", grammar, related.grammar),
+    };
+    let colon = if language == Language::English { ":" } else { "：" };
+    let link = related.grammar_id.map(|id| format!(" (#{})", id)).unwrap_or_default();
+    format!("<b>{}{}</b>{} {}", pair, colon, link, related.difference)
+}
+
+/// 把结构化语法解析渲染为卡片背面的 HTML：意思和语体、接续、用法、例句、相关语法，空的部分省略
+pub fn render_grammar_details(grammar: &str, details: &GrammarDetails, language: Language) -> String {
+    let (example_label, _) = details_labels(language);
+    let (register_label, connection_label, related_label) = grammar_labels(language);
+    let mut sections = vec![format!(
+        "<div>{}<br><b>{}</b> {}</div>",
+        details.meaning, register_label, details.register.label_in(language)
+    )];
+
+    if !details.connections.is_empty() {
+        let rules: Vec<String> = details.connections.iter().map(|r| render_connection(grammar, r, language)).collect();
+        sections.push(format!("<div><b>{}</b><br>{}</div>", connection_label, rules.join("<br>")));
+    }
+    if !details.usage.is_empty() {
+        sections.push(format!("<div>{}</div>", details.usage));
+    }
+    if !details.examples.is_empty() {
+        let examples: Vec<String> = details.examples.iter()
+            .map(|e| format!("{} {}", example_label, render_example(e)))
+            .collect();
+        sections.push(format!("<div>{}</div>", examples.join("<br>")));
+    }
+    if !details.related.is_empty() {
+        let related: Vec<String> = details.related.iter().map(|r| render_related(grammar, r, language)).collect();
+        sections.push(format!("<div><b>{}</b><br><br>{}</div>", related_label, related.join("<br><br>")));
+    }
+
+    sections.join("\n<hr>\n")
+}
+
+// 生成语法 Anki 卡片，按传入顺序编号作为学习顺序
+// 有结构化解析的语法按当前布局渲染背面，并单独输出意思、语体、接续、例句和相关语法字段
+pub fn generate_grammar_cards(grammar: &[JapaneseGrammar], details: &HashMap<i64, GrammarDetails>, output_file: &str, language: Language) -> Result<()> {
     let mut file = File::create(output_file)?;
     
-    for item in grammar {
+    for (index, item) in grammar.iter().enumerate() {
         // 生成正面内容：语法表达和假名用｜隔开，如果重复则省略
         let front_content = if item.word == item.kana {
             item.word.clone()
@@ -491,14 +3519,536 @@ pub fn generate_grammar_cards(grammar: &[JapaneseGrammar], output_file: &str) ->
         let audio_tag = format!("[sound:japanese_word_{}.wav]", item.id);
         let front = format!("{} {}", front_content, audio_tag);
         
-        // CSV 格式：id:正面:背面:标签（语法标签为"语法"）
-        writeln!(file, "{}:\"{}\":\"{}\":\"语法\"", 
+        // 没有结构化解析的语法（旧数据或手动编辑过的解析）直接使用保存的 HTML
+        let (back, fields) = match details.get(&item.id) {
+            Some(details) => (
+                render_grammar_details(&item.word, details, language),
+                [
+                    details.meaning.clone(),
+                    details.register.label_in(language).to_string(),
+                    details.connections.iter().map(|r| render_connection(&item.word, r, language)).collect::<Vec<_>>().join("<br>"),
+                    details.examples.iter().map(render_example).collect::<Vec<_>>().join("<br>"),
+                    details.related.iter().map(|r| render_related(&item.word, r, language)).collect::<Vec<_>>().join("<br>"),
+                ],
+            ),
+            None => (item.analysis.clone(), Default::default()),
+        };
+        let fields: Vec<String> = fields.iter().map(|f| format!("\"{}\"", f.replace("\"", "\"\""))).collect();
+        
+        // CSV 格式：id:正面:背面:标签:学习顺序:意思:语体:接续:例句:相关语法（语法标签为"语法"）
+        writeln!(file, "{}:\"{}\":\"{}\":\"{}\":{}:{}", 
             item.id,
             front.replace("\"", "\"\""),
-            item.analysis.replace("\"", "\"\"")
+            back.replace("\"", "\"\""),
+            card_tags("语法", &entry_tags(item.frequency_rank, item.jlpt_level.as_deref(), &item.tags)),
+            sort_field(index),
+            fields.join(":")
         )?;
     }
     
     println!("✅ 语法卡片已生成：{}", output_file);
     Ok(())
 }
+
+// 生成活用练习卡片：正面为单词和活用形名称，背面为活用结果，ID 为 "单词ID-活用形"
+pub fn generate_conjugation_cards(words: &[JapaneseWord], forms: &[ConjugationForm], output_file: &str) -> Result<()> {
+    let mut file = File::create(output_file)?;
+    let mut index = 0;
+    
+    for word in words {
+        let Some(class) = classify(&word.word, &word.kana, &word.part_of_speech) else {
+            continue;
+        };
+        
+        for conjugation in conjugate(&word.word, &word.kana, class) {
+            if !forms.is_empty() && !forms.contains(&conjugation.form) {
+                continue;
+            }
+            
+            let front = format!(
+                "<div style=\"font-size: 20px; font-weight: bold;\">{}</div><div style=\"font-size: 14px; color: #666;\">{} → {}</div>",
+                word.word, class.label(), conjugation.form.label()
+            );
+            let back = if conjugation.word == conjugation.kana {
+                conjugation.word.clone()
+            } else {
+                format!("{}（{}）", conjugation.word, conjugation.kana)
+            };
+            
+            // CSV 格式：id:正面:背面:标签:学习顺序（活用标签为"活用"）
+            writeln!(file, "{}-{}:\"{}\":\"{}\":\"{}\":{}", 
+                word.id,
+                conjugation.form.key(),
+                front.replace("\"", "\"\""),
+                back.replace("\"", "\"\""),
+                card_tags("活用", &format!("活用::{}", conjugation.form.key())),
+                sort_field(index)
+            )?;
+            index += 1;
+        }
+    }
+    
+    println!("✅ 活用练习卡片已生成：{}", output_file);
+    Ok(())
+}
+
+// 生成汉字 Anki 卡片：正面为汉字，背面为读音、意思、部件、记忆口诀和例词
+pub fn generate_kanji_cards(kanji: &[Kanji], examples: &HashMap<i64, Vec<&JapaneseWord>>, output_file: &str) -> Result<()> {
+    let mut file = File::create(output_file)?;
+    
+    for (index, item) in kanji.iter().enumerate() {
+        let front = match item.stroke_count {
+            Some(strokes) => format!(
+                "<div style=\"font-size: 48px;\">{}</div><div style=\"font-size: 14px; color: #666;\">{}画</div>",
+                item.character, strokes
+            ),
+            None => format!("<div style=\"font-size: 48px;\">{}</div>", item.character),
+        };
+        
+        let mut back = String::new();
+        if !item.on_readings.is_empty() {
+            back.push_str(&format!("<div><b>音读：</b>{}</div>", item.on_readings));
+        }
+        if !item.kun_readings.is_empty() {
+            back.push_str(&format!("<div><b>训读：</b>{}</div>", item.kun_readings));
+        }
+        if !item.meanings.is_empty() {
+            back.push_str(&format!("<div><b>意思：</b>{}</div>", item.meanings));
+        }
+        if !item.components.is_empty() {
+            back.push_str(&format!("<div><b>部件：</b>{}</div>", item.components));
+        }
+        // 记忆口诀由 AI 生成的纯文本，转义后再写入卡片
+        if !item.mnemonic.is_empty() {
+            back.push_str(&format!("<div><b>记忆：</b>{}</div>", escape_html(&item.mnemonic)));
+        }
+        
+        // 最多列出 5 个例词
+        let words = examples.get(&item.id).map(Vec::as_slice).unwrap_or_default();
+        if !words.is_empty() {
+            let list: Vec<String> = words
+                .iter()
+                .take(5)
+                .map(|w| if w.word == w.kana { w.word.clone() } else { format!("{}（{}）", w.word, w.kana) })
+                .collect();
+            back.push_str(&format!("<div><b>例词：</b>{}</div>", list.join("、")));
+        }
+        
+        // CSV 格式：id:正面:背面:标签:学习顺序（汉字标签为"汉字"）
+        writeln!(file, "{}:\"{}\":\"{}\":\"汉字\":{}", 
+            item.id,
+            front.replace("\"", "\"\""),
+            back.replace("\"", "\"\""),
+            sort_field(index)
+        )?;
+    }
+    
+    println!("✅ 汉字卡片已生成：{}", output_file);
+    Ok(())
+}
+
+// 生成例句 Anki 卡片，背面列出例句中出现的单词和语法
+pub fn generate_sentence_cards(sentences: &[Sentence], words: &HashMap<i64, Vec<&JapaneseWord>>, grammar: &HashMap<i64, Vec<&JapaneseGrammar>>, output_file: &str) -> Result<()> {
+    let mut file = File::create(output_file)?;
+    
+    for (index, sentence) in sentences.iter().enumerate() {
+        // 例句、读音和翻译都是纯文本（来自输入文本或 AI），转义后再写入卡片
+        let front = format!(
+            "<div style=\"font-size: 20px;\">{} [sound:japanese_sentence_{}.wav]</div>",
+            escape_html(&sentence.japanese), sentence.id
+        );
+        
+        let mut back = String::new();
+        if !sentence.reading.is_empty() {
+            back.push_str(&format!("<div><b>读音：</b>{}</div>", escape_html(&sentence.reading)));
+        }
+        if !sentence.translation.is_empty() {
+            back.push_str(&format!("<div><b>翻译：</b>{}</div>", escape_html(&sentence.translation)));
+        }
+        
+        let linked_words = words.get(&sentence.id).map(Vec::as_slice).unwrap_or_default();
+        if !linked_words.is_empty() {
+            let list: Vec<String> = linked_words
+                .iter()
+                .map(|w| if w.word == w.kana { w.word.clone() } else { format!("{}（{}）", w.word, w.kana) })
+                .collect();
+            back.push_str(&format!("<div><b>单词：</b>{}</div>", list.join("、")));
+        }
+        let linked_grammar = grammar.get(&sentence.id).map(Vec::as_slice).unwrap_or_default();
+        if !linked_grammar.is_empty() {
+            let list: Vec<&str> = linked_grammar.iter().map(|g| g.word.as_str()).collect();
+            back.push_str(&format!("<div><b>语法：</b>{}</div>", list.join("、")));
+        }
+        
+        // CSV 格式：id:正面:背面:标签:学习顺序（例句标签为"例句"）
+        writeln!(file, "{}:\"{}\":\"{}\":\"例句\":{}", 
+            sentence.id,
+            front.replace("\"", "\"\""),
+            back.replace("\"", "\"\""),
+            sort_field(index)
+        )?;
+    }
+    
+    println!("✅ 例句卡片已生成：{}", output_file);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 单连接的内存数据库（每个连接各自是一个独立的内存数据库）
+    async fn memory_storage() -> DatabaseManager {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let manager = DatabaseManager { pool };
+        manager.initialize_tables().await.unwrap();
+        manager
+    }
+
+    fn word(word: &str, kana: &str, pitch: &str, pos: &str) -> WordAnalysis {
+        WordAnalysis {
+            word: word.to_string(),
+            kana: kana.to_string(),
+            pitch: pitch.to_string(),
+            part_of_speech: pos.to_string(),
+            analysis: format!("<div>{}</div>", word),
+            details: None,
+            prompt_version: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn pitch_conflict_moves_dependents_to_kept_word() {
+        let storage = memory_storage().await;
+        storage
+            .save_words(&[word("時計", "とけい", "0", "名词"), word("時計", "とけい", "1", "名词")])
+            .await
+            .unwrap();
+        let keeper = storage.get_existing_word("時計", "とけい", "0").await.unwrap().unwrap();
+        let duplicate = storage.get_existing_word("時計", "とけい", "1").await.unwrap().unwrap();
+        storage.save_entry_analysis(EntryKind::Word, duplicate.id, "en", "<div>clock</div>", 1).await.unwrap();
+        let sentence = ExampleSentence { japanese: "時計が止まった。".to_string(), ..Default::default() };
+        let sentence_id = storage.save_sentence(&sentence, SentenceSource::Example, None).await.unwrap();
+        storage.link_sentence(sentence_id, EntryKind::Word, duplicate.id).await.unwrap();
+        let kanji_id = storage
+            .save_kanji(&KanjiInfo { character: "計".to_string(), ..Default::default() })
+            .await
+            .unwrap();
+        storage.link_kanji_words(kanji_id, &[duplicate.id]).await.unwrap();
+        storage.record_occurrence(EntryKind::Word, duplicate.id, "clock.txt").await.unwrap();
+
+        storage.update_word_pitch_and_pos(keeper.id, "1", "名词｜自动词").await.unwrap();
+
+        assert!(storage.get_word_by_id(duplicate.id).await.unwrap().is_none());
+        let kept = storage.get_word_by_id(keeper.id).await.unwrap().unwrap();
+        assert_eq!((kept.pitch.as_str(), kept.part_of_speech.as_str()), ("1", "名词｜自动词"));
+        let english = storage.get_entry_analyses(EntryKind::Word, "en").await.unwrap();
+        assert_eq!(english.get(&keeper.id).map(String::as_str), Some("<div>clock</div>"));
+        assert!(!english.contains_key(&duplicate.id));
+        assert_eq!(storage.get_sentence_links().await.unwrap(), vec![(sentence_id, "word".to_string(), keeper.id)]);
+        assert_eq!(storage.get_kanji_word_links().await.unwrap(), vec![(kanji_id, keeper.id)]);
+        let occurrences = storage.get_occurrence_counts(EntryKind::Word).await.unwrap();
+        assert_eq!(occurrences.get(&keeper.id), Some(&1));
+        assert!(!occurrences.contains_key(&duplicate.id));
+    }
+
+    #[tokio::test]
+    async fn pitch_conflict_keeps_existing_data_of_kept_word() {
+        let storage = memory_storage().await;
+        storage
+            .save_words(&[word("時計", "とけい", "0", "名词"), word("時計", "とけい", "1", "名词")])
+            .await
+            .unwrap();
+        let keeper = storage.get_existing_word("時計", "とけい", "0").await.unwrap().unwrap();
+        let duplicate = storage.get_existing_word("時計", "とけい", "1").await.unwrap().unwrap();
+        storage.save_entry_analysis(EntryKind::Word, keeper.id, "en", "<div>kept</div>", 1).await.unwrap();
+        storage.save_entry_analysis(EntryKind::Word, duplicate.id, "en", "<div>dropped</div>", 1).await.unwrap();
+
+        storage.update_word_pitch_and_pos(keeper.id, "1", "名词").await.unwrap();
+
+        let english = storage.get_entry_analyses(EntryKind::Word, "en").await.unwrap();
+        assert_eq!(english.len(), 1);
+        assert_eq!(english.get(&keeper.id).map(String::as_str), Some("<div>kept</div>"));
+    }
+
+    async fn word_pos_keys(storage: &DatabaseManager, id: i64) -> Vec<String> {
+        sqlx::query_scalar("SELECT pos FROM word_pos WHERE word_id = ? ORDER BY pos")
+            .bind(id)
+            .fetch_all(&storage.pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn word_pos_follows_part_of_speech_writes() {
+        let storage = memory_storage().await;
+        storage.save_words(&[word("開く", "あく", "0", "自动词｜五段动词")]).await.unwrap();
+        let id = storage.get_existing_word("開く", "あく", "0").await.unwrap().unwrap().id;
+        assert_eq!(word_pos_keys(&storage, id).await, vec!["verb_intransitive"]);
+
+        storage.save_words(&[word("開く", "あく", "0", "自他动词")]).await.unwrap();
+        assert_eq!(word_pos_keys(&storage, id).await, vec!["verb_intransitive", "verb_transitive"]);
+
+        storage.update_word_part_of_speech(id, "他动词").await.unwrap();
+        assert_eq!(word_pos_keys(&storage, id).await, vec!["verb_transitive"]);
+
+        storage.update_entry_field(EntryKind::Word, id, LockField::PartOfSpeech, "名词").await.unwrap();
+        assert_eq!(word_pos_keys(&storage, id).await, vec!["noun"]);
+
+        storage.rebuild_search_index().await.unwrap();
+        assert_eq!(word_pos_keys(&storage, id).await, vec!["noun"]);
+    }
+
+    #[tokio::test]
+    async fn short_search_treats_like_wildcards_literally() {
+        let storage = memory_storage().await;
+        let mut percent = word("割", "わり", "0", "名词");
+        percent.analysis = "<div>10%</div>".to_string();
+        storage.save_words(&[percent, word("帯", "おび", "1", "名词")]).await.unwrap();
+        let search = |text: &str| SearchQuery { text: text.to_string(), ..Default::default() };
+
+        assert!(storage.search(&search("%")).await.unwrap().iter().all(|hit| hit.word == "割"));
+        assert_eq!(storage.search(&search("0%")).await.unwrap().len(), 1);
+        assert!(storage.search(&search("_")).await.unwrap().is_empty());
+        assert!(storage.search(&search("\\")).await.unwrap().is_empty());
+        assert_eq!(storage.search(&search("帯")).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn grammar_merge_moves_sentence_links_to_kept_entry() {
+        let storage = memory_storage().await;
+        for word in ["〜ている", "ていた"] {
+            sqlx::query("INSERT INTO grammar (word, kana, analysis) VALUES (?, ?, '<div>进行</div>')")
+                .bind(word)
+                .bind(word)
+                .execute(&storage.pool)
+                .await
+                .unwrap();
+        }
+        let keeper = storage.get_grammar_by_word("〜ている").await.unwrap().unwrap();
+        let duplicate = storage.get_grammar_by_word("ていた").await.unwrap().unwrap();
+        let sentence = |japanese: &str| ExampleSentence { japanese: japanese.to_string(), ..Default::default() };
+        let shared = storage.save_sentence(&sentence("雨が降っている。"), SentenceSource::Example, None).await.unwrap();
+        let moved = storage.save_sentence(&sentence("本を読んでいた。"), SentenceSource::Example, None).await.unwrap();
+        storage.link_sentence(shared, EntryKind::Grammar, keeper.id).await.unwrap();
+        storage.link_sentence(shared, EntryKind::Grammar, duplicate.id).await.unwrap();
+        storage.link_sentence(moved, EntryKind::Grammar, duplicate.id).await.unwrap();
+
+        assert_eq!(storage.merge_grammar_variants().await.unwrap(), 1);
+
+        assert!(storage.get_grammar_by_id(duplicate.id).await.unwrap().is_none());
+        let sentences: Vec<i64> = storage
+            .get_sentences_for_entry(EntryKind::Grammar, keeper.id)
+            .await
+            .unwrap()
+            .iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(sentences, vec![shared, moved]);
+        assert!(storage.get_sentence_links().await.unwrap().iter().all(|(_, _, id)| *id == keeper.id));
+    }
+
+    #[tokio::test]
+    async fn review_cards_and_history_survive_export_round_trip() {
+        let source = memory_storage().await;
+        source.save_words(&[word("時計", "とけい", "0", "名词")]).await.unwrap();
+        let clock = source.get_existing_word("時計", "とけい", "0").await.unwrap().unwrap();
+        let user_id = source.ensure_user("alice").await.unwrap();
+        let card = ReviewCard {
+            id: 0,
+            user_id,
+            entry_kind: "word".to_string(),
+            entry_id: clock.id,
+            state: "review".to_string(),
+            due: "2026-10-20 08:00:00".to_string(),
+            stability: 3.5,
+            difficulty: 5.2,
+            reps: 2,
+            lapses: 0,
+            last_review: Some("2026-10-17 08:00:00".to_string()),
+        };
+        for (i, ease) in [3, 4].into_iter().enumerate() {
+            let log = ReviewLogEntry {
+                reviewed_at: format!("2026-10-1{} 08:00:00", 6 + i),
+                reviewed_at_ms: 1_760_600_000_000 + i as i64 * 86_400_000,
+                ease,
+                ivl: 1,
+                last_ivl: 0,
+                time_ms: 4000,
+                review_type: 1,
+                elapsed_days: i as f64,
+            };
+            source.save_review(&card, &log).await.unwrap();
+        }
+
+        let target = memory_storage().await;
+        for exported in source.export_words().await.unwrap() {
+            target.import_word(&exported, ConflictPolicy::KeepExisting).await.unwrap();
+        }
+        let cards = source.export_review_cards().await.unwrap();
+        let history = source.export_review_history().await.unwrap();
+        assert_eq!((cards.len(), history.len()), (1, 2));
+        for card in &cards {
+            assert_eq!(target.import_review_card(card, ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Inserted);
+        }
+        for review in &history {
+            assert_eq!(target.import_review_history(review).await.unwrap(), ImportOutcome::Inserted);
+            assert_eq!(target.import_review_history(review).await.unwrap(), ImportOutcome::Skipped);
+        }
+        assert_eq!(target.import_review_card(&cards[0], ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Skipped);
+
+        let alice = target.ensure_user("alice").await.unwrap();
+        let imported = target.get_review_cards(alice).await.unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!((imported[0].state.as_str(), imported[0].due.as_str(), imported[0].reps), ("review", "2026-10-20 08:00:00", 2));
+        assert_eq!(imported[0].stability, 3.5);
+        let eases: Vec<i64> = target.export_review_log(alice).await.unwrap().iter().map(|r| r.ease).collect();
+        assert_eq!(eases, vec![3, 4]);
+    }
+
+    #[tokio::test]
+    async fn kanji_mnemonics_and_links_survive_export_round_trip() {
+        let source = memory_storage().await;
+        source.save_words(&[word("時計", "とけい", "0", "名词")]).await.unwrap();
+        let clock = source.get_existing_word("時計", "とけい", "0").await.unwrap().unwrap();
+        let info = KanjiInfo { character: "計".to_string(), stroke_count: Some(9), ..Default::default() };
+        let kanji_id = source.save_kanji(&info).await.unwrap();
+        source.update_kanji_mnemonic(kanji_id, "<p>言十为计</p>").await.unwrap();
+        source.link_kanji_words(kanji_id, &[clock.id]).await.unwrap();
+
+        let target = memory_storage().await;
+        for exported in source.export_words().await.unwrap() {
+            target.import_word(&exported, ConflictPolicy::KeepExisting).await.unwrap();
+        }
+        let exported = source.export_kanji().await.unwrap();
+        assert_eq!(exported.len(), 1);
+        assert_eq!(target.import_kanji(&exported[0], ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Inserted);
+        assert_eq!(target.import_kanji(&exported[0], ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Skipped);
+
+        let kanji = target.get_all_kanji().await.unwrap();
+        assert_eq!((kanji[0].character.as_str(), kanji[0].mnemonic.as_str(), kanji[0].stroke_count), ("計", "<p>言十为计</p>", Some(9)));
+        let imported_clock = target.get_existing_word("時計", "とけい", "0").await.unwrap().unwrap();
+        assert_eq!(target.get_kanji_word_links().await.unwrap(), vec![(kanji[0].id, imported_clock.id)]);
+    }
+
+    #[tokio::test]
+    async fn grammar_aliases_survive_export_round_trip() {
+        let grammar = |word: &str| GrammarAnalysis {
+            grammar: word.to_string(),
+            kana: word.trim_start_matches('〜').to_string(),
+            analysis: "<div>进行</div>".to_string(),
+            prompt_version: 1,
+            details: None,
+        };
+        let source = memory_storage().await;
+        source.save_grammar(&[grammar("〜ている"), grammar("ていた")]).await.unwrap();
+        let exported = source.export_grammar().await.unwrap();
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].aliases, vec!["ていた".to_string()]);
+
+        let target = memory_storage().await;
+        assert_eq!(target.import_grammar(&exported[0], ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Inserted);
+        let kept = target.get_grammar_by_word("〜ている").await.unwrap().unwrap();
+        assert_eq!(target.get_grammar_aliases(kept.id).await.unwrap(), vec!["ていた".to_string()]);
+
+        // 再次提取到其他写法时合并到已有条目，不产生重复
+        target.save_grammar(&[grammar("ていた")]).await.unwrap();
+        assert_eq!(target.get_all_grammar().await.unwrap().len(), 1);
+
+        // 跳过已有条目时仍然合并别名
+        let extra = ExportedGrammar { aliases: vec!["ていました".to_string()], ..exported[0].clone() };
+        assert_eq!(target.import_grammar(&extra, ConflictPolicy::KeepExisting).await.unwrap(), ImportOutcome::Skipped);
+        assert_eq!(target.get_grammar_by_word("ていました").await.unwrap().map(|g| g.id), Some(kept.id));
+    }
+
+    #[tokio::test]
+    async fn sentences_and_links_survive_export_round_trip() {
+        let teiru = GrammarAnalysis {
+            grammar: "〜ている".to_string(),
+            kana: "ている".to_string(),
+            analysis: "<div>进行</div>".to_string(),
+            prompt_version: 1,
+            details: None,
+        };
+        let source = memory_storage().await;
+        source.save_words(&[word("時計", "とけい", "0", "名词")]).await.unwrap();
+        source.save_grammar(std::slice::from_ref(&teiru)).await.unwrap();
+        let clock = source.get_existing_word("時計", "とけい", "0").await.unwrap().unwrap();
+        let grammar = source.get_grammar_by_word("〜ている").await.unwrap().unwrap();
+        let sentence = ExampleSentence {
+            japanese: "時計が動いている。".to_string(),
+            reading: "とけいがうごいている。".to_string(),
+            translation: "钟在走。".to_string(),
+        };
+        let sentence_id = source.save_sentence(&sentence, SentenceSource::Input, Some("clock.txt")).await.unwrap();
+        source.link_sentence(sentence_id, EntryKind::Word, clock.id).await.unwrap();
+        source.link_sentence(sentence_id, EntryKind::Grammar, grammar.id).await.unwrap();
+
+        let target = memory_storage().await;
+        target.save_words(&[word("時計", "とけい", "0", "名词")]).await.unwrap();
+        target.save_grammar(&[teiru]).await.unwrap();
+        let exported = source.export_sentences().await.unwrap();
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].entries.len(), 2);
+        assert_eq!(target.import_sentence(&exported[0], ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Inserted);
+        assert_eq!(target.import_sentence(&exported[0], ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Skipped);
+
+        let sentences = target.get_all_sentences().await.unwrap();
+        assert_eq!(sentences.len(), 1);
+        let imported = &sentences[0];
+        assert_eq!((imported.reading.as_str(), imported.translation.as_str()), ("とけいがうごいている。", "钟在走。"));
+        assert_eq!((imported.source.as_str(), imported.document.as_deref()), ("input", Some("clock.txt")));
+        let clock = target.get_existing_word("時計", "とけい", "0").await.unwrap().unwrap();
+        let grammar = target.get_grammar_by_word("〜ている").await.unwrap().unwrap();
+        assert_eq!(target.get_sentences_for_entry(EntryKind::Word, clock.id).await.unwrap().len(), 1);
+        assert_eq!(target.get_sentences_for_entry(EntryKind::Grammar, grammar.id).await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn kanji_cards_escape_mnemonic() {
+        let kanji = Kanji {
+            id: 1,
+            character: "計".to_string(),
+            on_readings: "ケイ".to_string(),
+            kun_readings: String::new(),
+            meanings: "measure".to_string(),
+            stroke_count: Some(9),
+            components: String::new(),
+            mnemonic: "言<img src=x onerror=alert(1)>十 & \"计\"".to_string(),
+            updated_at: None,
+        };
+        let path = std::env::temp_dir().join(format!("anki_creator_test_{}_kanji_cards.txt", std::process::id()));
+        generate_kanji_cards(&[kanji], &HashMap::new(), path.to_str().unwrap()).unwrap();
+        let cards = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!cards.contains("<img"));
+        assert!(cards.contains("<div><b>记忆：</b>言&lt;img src=x onerror=alert(1)&gt;十 &amp; &quot;计&quot;</div>"));
+    }
+
+    #[test]
+    fn sentence_cards_escape_reading_and_translation() {
+        let sentence = Sentence {
+            id: 7,
+            japanese: "1<2だ。".to_string(),
+            reading: "<b onclick=x>いち</b>".to_string(),
+            translation: "<script>alert(1)</script>一小于二".to_string(),
+            source: "input".to_string(),
+            document: None,
+            updated_at: None,
+        };
+        let path = std::env::temp_dir().join(format!("anki_creator_test_{}_sentence_cards.txt", std::process::id()));
+        generate_sentence_cards(&[sentence], &HashMap::new(), &HashMap::new(), path.to_str().unwrap()).unwrap();
+        let cards = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!cards.contains("<script") && !cards.contains("onclick=x>"));
+        assert!(cards.contains(">1&lt;2だ。 [sound:japanese_sentence_7.wav]<"));
+        assert!(cards.contains("<div><b>读音：</b>&lt;b onclick=x&gt;いち&lt;/b&gt;</div>"));
+        assert!(cards.contains("<div><b>翻译：</b>&lt;script&gt;alert(1)&lt;/script&gt;一小于二</div>"));
+    }
+}
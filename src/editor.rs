@@ -0,0 +1,63 @@
+use anyhow::Result;
+use std::io::{self, Write};
+use std::process::Command;
+
+/// 使用 $VISUAL / $EDITOR 编辑文本，返回编辑后的内容
+pub fn edit_in_editor(initial: &str, extension: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    // 编辑器命令可能带参数，例如 "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("编辑器命令为空"))?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_nanos();
+    let temp_path = std::env::temp_dir().join(format!(
+        "anki_creator_edit_{}_{}.{}",
+        std::process::id(),
+        timestamp,
+        extension
+    ));
+
+    std::fs::write(&temp_path, initial)
+        .map_err(|e| anyhow::anyhow!("无法创建临时文件 {}: {}", temp_path.display(), e))?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(&temp_path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("无法启动编辑器 {}: {}", editor, e));
+
+    let result = match status {
+        Ok(status) if status.success() => std::fs::read_to_string(&temp_path)
+            .map_err(|e| anyhow::anyhow!("无法读取临时文件 {}: {}", temp_path.display(), e)),
+        Ok(status) => Err(anyhow::anyhow!("编辑器异常退出: {}", status)),
+        Err(e) => Err(e),
+    };
+
+    let _ = std::fs::remove_file(&temp_path);
+
+    result
+}
+
+/// 在终端中读取一行新值，直接回车表示保留当前值
+pub fn prompt_line(label: &str, current: &str) -> Result<String> {
+    println!("当前{}: {}", label, current);
+    print!("请输入新的{}（直接回车保留原值）: ", label);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let input = input.trim();
+    if input.is_empty() {
+        Ok(current.to_string())
+    } else {
+        Ok(input.to_string())
+    }
+}
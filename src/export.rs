@@ -0,0 +1,225 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+use crate::models::*;
+
+// 导出文件格式版本，格式发生不兼容变化时递增
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// 导出文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Ndjson,
+}
+
+impl ExportFormat {
+    /// 根据文件扩展名判断格式，.ndjson / .jsonl 为 NDJSON，其余为 JSON
+    pub fn from_path(path: &str) -> Self {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".ndjson") || lower.ends_with(".jsonl") {
+            ExportFormat::Ndjson
+        } else {
+            ExportFormat::Json
+        }
+    }
+}
+
+/// 导出元数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportMetadata {
+    pub format_version: u32,
+    pub app_version: String,
+    pub exported_at: String,
+    pub source_db: String,
+    pub word_count: usize,
+    pub grammar_count: usize,
+    pub occurrence_count: usize,
+}
+
+/// 完整的知识库导出内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeBase {
+    pub metadata: ExportMetadata,
+    #[serde(default)]
+    pub words: Vec<ExportedWord>,
+    #[serde(default)]
+    pub grammar: Vec<ExportedGrammar>,
+    #[serde(default)]
+    pub occurrences: Vec<ExportedOccurrence>,
+    #[serde(default)]
+    pub statuses: Vec<ExportedStatus>,
+    #[serde(default)]
+    pub analyses: Vec<ExportedAnalysis>,
+    #[serde(default)]
+    pub word_details: Vec<ExportedWordDetails>,
+    #[serde(default)]
+    pub grammar_details: Vec<ExportedGrammarDetails>,
+    #[serde(default)]
+    pub kanji: Vec<ExportedKanji>,
+    #[serde(default)]
+    pub sentences: Vec<ExportedSentence>,
+    #[serde(default)]
+    pub review_cards: Vec<ExportedReviewCard>,
+    #[serde(default)]
+    pub review_log: Vec<ExportedReviewLog>,
+}
+
+// NDJSON 中的单行记录，用 type 字段区分
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Record {
+    Metadata(ExportMetadata),
+    Word(ExportedWord),
+    Grammar(ExportedGrammar),
+    Occurrence(ExportedOccurrence),
+    Status(ExportedStatus),
+    Analysis(ExportedAnalysis),
+    #[serde(rename = "word_details")]
+    WordDetails(ExportedWordDetails),
+    #[serde(rename = "grammar_details")]
+    GrammarDetails(ExportedGrammarDetails),
+    Kanji(ExportedKanji),
+    Sentence(ExportedSentence),
+    #[serde(rename = "review_card")]
+    ReviewCard(ExportedReviewCard),
+    #[serde(rename = "review_log")]
+    ReviewLog(ExportedReviewLog),
+}
+
+impl KnowledgeBase {
+    /// 写入文件
+    pub fn write_to_file(&self, path: &str, format: ExportFormat) -> Result<()> {
+        let file = File::create(path)
+            .map_err(|e| anyhow::anyhow!("无法创建导出文件 {}: {}", path, e))?;
+        let mut writer = BufWriter::new(file);
+
+        match format {
+            ExportFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, self)?;
+                writeln!(writer)?;
+            }
+            ExportFormat::Ndjson => {
+                write_record(&mut writer, &Record::Metadata(self.metadata.clone()))?;
+                for word in &self.words {
+                    write_record(&mut writer, &Record::Word(word.clone()))?;
+                }
+                for grammar in &self.grammar {
+                    write_record(&mut writer, &Record::Grammar(grammar.clone()))?;
+                }
+                for occurrence in &self.occurrences {
+                    write_record(&mut writer, &Record::Occurrence(occurrence.clone()))?;
+                }
+                for status in &self.statuses {
+                    write_record(&mut writer, &Record::Status(status.clone()))?;
+                }
+                for analysis in &self.analyses {
+                    write_record(&mut writer, &Record::Analysis(analysis.clone()))?;
+                }
+                for details in &self.word_details {
+                    write_record(&mut writer, &Record::WordDetails(details.clone()))?;
+                }
+                for details in &self.grammar_details {
+                    write_record(&mut writer, &Record::GrammarDetails(details.clone()))?;
+                }
+                for kanji in &self.kanji {
+                    write_record(&mut writer, &Record::Kanji(kanji.clone()))?;
+                }
+                for sentence in &self.sentences {
+                    write_record(&mut writer, &Record::Sentence(sentence.clone()))?;
+                }
+                for card in &self.review_cards {
+                    write_record(&mut writer, &Record::ReviewCard(card.clone()))?;
+                }
+                for review in &self.review_log {
+                    write_record(&mut writer, &Record::ReviewLog(review.clone()))?;
+                }
+            }
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// 从文件读取
+    pub fn read_from_file(path: &str, format: ExportFormat) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| anyhow::anyhow!("无法读取导入文件 {}: {}", path, e))?;
+        let reader = BufReader::new(file);
+
+        let knowledge_base = match format {
+            ExportFormat::Json => serde_json::from_reader(reader)
+                .map_err(|e| anyhow::anyhow!("解析 JSON 失败: {}", e))?,
+            ExportFormat::Ndjson => {
+                let mut metadata = None;
+                let mut words = Vec::new();
+                let mut grammar = Vec::new();
+                let mut occurrences = Vec::new();
+                let mut statuses = Vec::new();
+                let mut analyses = Vec::new();
+                let mut word_details = Vec::new();
+                let mut grammar_details = Vec::new();
+                let mut kanji = Vec::new();
+                let mut sentences = Vec::new();
+                let mut review_cards = Vec::new();
+                let mut review_log = Vec::new();
+
+                for (line_number, line) in reader.lines().enumerate() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let record: Record = serde_json::from_str(&line)
+                        .map_err(|e| anyhow::anyhow!("第 {} 行解析失败: {}", line_number + 1, e))?;
+                    match record {
+                        Record::Metadata(m) => metadata = Some(m),
+                        Record::Word(w) => words.push(w),
+                        Record::Grammar(g) => grammar.push(g),
+                        Record::Occurrence(o) => occurrences.push(o),
+                        Record::Status(s) => statuses.push(s),
+                        Record::Analysis(a) => analyses.push(a),
+                        Record::WordDetails(d) => word_details.push(d),
+                        Record::GrammarDetails(d) => grammar_details.push(d),
+                        Record::Kanji(k) => kanji.push(k),
+                        Record::Sentence(s) => sentences.push(s),
+                        Record::ReviewCard(c) => review_cards.push(c),
+                        Record::ReviewLog(r) => review_log.push(r),
+                    }
+                }
+
+                KnowledgeBase {
+                    metadata: metadata.ok_or_else(|| anyhow::anyhow!("导入文件缺少 metadata 记录"))?,
+                    words,
+                    grammar,
+                    occurrences,
+                    statuses,
+                    analyses,
+                    word_details,
+                    grammar_details,
+                    kanji,
+                    sentences,
+                    review_cards,
+                    review_log,
+                }
+            }
+        };
+
+        if knowledge_base.metadata.format_version > EXPORT_FORMAT_VERSION {
+            return Err(anyhow::anyhow!(
+                "导入文件格式版本 {} 高于当前支持的版本 {}",
+                knowledge_base.metadata.format_version,
+                EXPORT_FORMAT_VERSION
+            ));
+        }
+
+        Ok(knowledge_base)
+    }
+}
+
+fn write_record<W: Write>(writer: &mut W, record: &Record) -> Result<()> {
+    serde_json::to_writer(&mut *writer, record)?;
+    writeln!(writer)?;
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use crate::models::{CardState, Grade};
+
+/// FSRS-4.5 默认参数
+pub const DEFAULT_WEIGHTS: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461, 2.1072,
+    0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+
+const DECAY: f64 = -0.5;
+const FACTOR: f64 = 19.0 / 81.0;
+
+// 学习阶段（重来/困难）的间隔：10 分钟
+const LEARNING_STEP_DAYS: f64 = 10.0 / 1440.0;
+
+/// 卡片的记忆状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryState {
+    pub stability: f64,
+    pub difficulty: f64,
+}
+
+/// 评分后的调度结果
+#[derive(Debug, Clone, Copy)]
+pub struct Scheduled {
+    pub memory: MemoryState,
+    pub state: CardState,
+    pub interval_days: f64,
+}
+
+/// FSRS 调度器
+#[derive(Debug, Clone)]
+pub struct Fsrs {
+    w: [f64; 17],
+    desired_retention: f64,
+    maximum_interval: f64,
+}
+
+impl Fsrs {
+    /// 创建调度器，weights 长度不是 17 时使用默认参数
+    pub fn new(weights: &[f64], desired_retention: f64, maximum_interval: f64) -> Self {
+        let w = <[f64; 17]>::try_from(weights).unwrap_or(DEFAULT_WEIGHTS);
+        Self {
+            w,
+            desired_retention: desired_retention.clamp(0.7, 0.99),
+            maximum_interval: maximum_interval.max(1.0),
+        }
+    }
+
+    /// 经过 elapsed_days 天后的回忆概率
+    pub fn retrievability(&self, elapsed_days: f64, stability: f64) -> f64 {
+        (1.0 + FACTOR * elapsed_days / stability).powf(DECAY)
+    }
+
+    /// 根据评分计算新的记忆状态、卡片状态和下次间隔
+    pub fn schedule(&self, state: CardState, memory: Option<MemoryState>, elapsed_days: f64, grade: Grade) -> Scheduled {
+        let memory = match memory.filter(|_| state != CardState::New) {
+            None => MemoryState {
+                stability: self.initial_stability(grade),
+                difficulty: self.initial_difficulty(grade),
+            },
+            Some(memory) => {
+                let r = self.retrievability(elapsed_days.max(0.0), memory.stability);
+                let stability = if grade == Grade::Again {
+                    self.forget_stability(memory, r)
+                } else {
+                    self.recall_stability(memory, r, grade)
+                };
+                MemoryState {
+                    stability: stability.max(0.01),
+                    difficulty: self.next_difficulty(memory.difficulty, grade),
+                }
+            }
+        };
+
+        // 重来进入（重新）学习阶段；学习阶段中的困难留在学习阶段
+        let next_state = match (state, grade) {
+            (CardState::Review, Grade::Again) => CardState::Relearning,
+            (CardState::Relearning, Grade::Again | Grade::Hard) => CardState::Relearning,
+            (_, Grade::Again) => CardState::Learning,
+            (CardState::New | CardState::Learning, Grade::Hard) => CardState::Learning,
+            _ => CardState::Review,
+        };
+
+        let interval_days = if next_state == CardState::Review {
+            self.next_interval(memory.stability)
+        } else {
+            LEARNING_STEP_DAYS
+        };
+
+        Scheduled { memory, state: next_state, interval_days }
+    }
+
+    // 达到期望回忆概率所需的间隔（整天，1 到 maximum_interval）
+    fn next_interval(&self, stability: f64) -> f64 {
+        let interval = stability / FACTOR * (self.desired_retention.powf(1.0 / DECAY) - 1.0);
+        interval.round().clamp(1.0, self.maximum_interval)
+    }
+
+    fn initial_stability(&self, grade: Grade) -> f64 {
+        self.w[grade as usize - 1].max(0.1)
+    }
+
+    fn initial_difficulty(&self, grade: Grade) -> f64 {
+        (self.w[4] - (grade as i32 - 3) as f64 * self.w[5]).clamp(1.0, 10.0)
+    }
+
+    fn next_difficulty(&self, difficulty: f64, grade: Grade) -> f64 {
+        let next = difficulty - self.w[6] * (grade as i32 - 3) as f64;
+        // 向初始难度回归，避免难度持续累积
+        let reverted = self.w[7] * self.initial_difficulty(Grade::Good) + (1.0 - self.w[7]) * next;
+        reverted.clamp(1.0, 10.0)
+    }
+
+    fn recall_stability(&self, memory: MemoryState, r: f64, grade: Grade) -> f64 {
+        let hard_penalty = if grade == Grade::Hard { self.w[15] } else { 1.0 };
+        let easy_bonus = if grade == Grade::Easy { self.w[16] } else { 1.0 };
+        memory.stability
+            * (1.0
+                + self.w[8].exp()
+                    * (11.0 - memory.difficulty)
+                    * memory.stability.powf(-self.w[9])
+                    * ((self.w[10] * (1.0 - r)).exp() - 1.0)
+                    * hard_penalty
+                    * easy_bonus)
+    }
+
+    fn forget_stability(&self, memory: MemoryState, r: f64) -> f64 {
+        let stability = self.w[11]
+            * memory.difficulty.powf(-self.w[12])
+            * ((memory.stability + 1.0).powf(self.w[13]) - 1.0)
+            * (self.w[14] * (1.0 - r)).exp();
+        // 遗忘后的稳定性不应超过遗忘前
+        stability.min(memory.stability)
+    }
+}
+
+/// 将间隔（天）转换为 Anki revlog 的 ivl：一天以上为天数，否则为负的秒数
+pub fn anki_interval(interval_days: f64) -> i64 {
+    if interval_days >= 1.0 {
+        interval_days.round() as i64
+    } else {
+        -((interval_days * 86400.0).round() as i64)
+    }
+}
+
+/// 间隔的简短显示，例如 "10分钟"、"3天"、"2.5月"
+pub fn format_interval(interval_days: f64) -> String {
+    if interval_days < 1.0 {
+        format!("{}分钟", (interval_days * 1440.0).round() as i64)
+    } else if interval_days < 30.0 {
+        format!("{}天", interval_days.round() as i64)
+    } else if interval_days < 365.0 {
+        format!("{:.1}月", interval_days / 30.0)
+    } else {
+        format!("{:.1}年", interval_days / 365.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 期望值按 FSRS-4.5 公式和默认参数独立计算
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+    }
+
+    fn fsrs() -> Fsrs {
+        Fsrs::new(&DEFAULT_WEIGHTS, 0.9, 36500.0)
+    }
+
+    #[test]
+    fn new_cards_start_from_initial_stability_and_difficulty() {
+        let fsrs = fsrs();
+        for (grade, stability, difficulty) in [
+            (Grade::Again, 0.4872, 7.6214),
+            (Grade::Hard, 1.4003, 6.3916),
+            (Grade::Good, 3.7145, 5.1618),
+            (Grade::Easy, 13.8206, 3.932),
+        ] {
+            let scheduled = fsrs.schedule(CardState::New, None, 0.0, grade);
+            assert_close(scheduled.memory.stability, stability);
+            assert_close(scheduled.memory.difficulty, difficulty);
+        }
+
+        let good = fsrs.schedule(CardState::New, None, 0.0, Grade::Good);
+        assert_eq!((good.state, good.interval_days), (CardState::Review, 4.0));
+        let easy = fsrs.schedule(CardState::New, None, 0.0, Grade::Easy);
+        assert_eq!((easy.state, easy.interval_days), (CardState::Review, 14.0));
+        let again = fsrs.schedule(CardState::New, None, 0.0, Grade::Again);
+        assert_eq!((again.state, again.interval_days), (CardState::Learning, LEARNING_STEP_DAYS));
+        let hard = fsrs.schedule(CardState::New, None, 0.0, Grade::Hard);
+        assert_eq!(hard.state, CardState::Learning);
+    }
+
+    #[test]
+    fn review_updates_stability_and_difficulty() {
+        let fsrs = fsrs();
+        let memory = Some(MemoryState { stability: 3.7145, difficulty: 5.1618 });
+        assert_close(fsrs.retrievability(4.0, 3.7145), 0.8934995006528037);
+
+        let good = fsrs.schedule(CardState::Review, memory, 4.0, Grade::Good);
+        assert_close(good.memory.stability, 14.808100506496405);
+        assert_close(good.memory.difficulty, 5.1618);
+        assert_eq!((good.state, good.interval_days), (CardState::Review, 15.0));
+
+        let hard = fsrs.schedule(CardState::Review, memory, 4.0, Grade::Hard);
+        assert_close(hard.memory.stability, 6.234966035075983);
+        assert_close(hard.memory.difficulty, 6.0314775);
+        assert_eq!(hard.interval_days, 6.0);
+
+        let easy = fsrs.schedule(CardState::Review, memory, 4.0, Grade::Easy);
+        assert_close(easy.memory.stability, 35.61414825643041);
+        assert_close(easy.memory.difficulty, 4.2921225);
+        assert_eq!(easy.interval_days, 36.0);
+
+        let again = fsrs.schedule(CardState::Review, memory, 4.0, Grade::Again);
+        assert_close(again.memory.stability, 1.4332344897795595);
+        assert_close(again.memory.difficulty, 6.901155);
+        assert_eq!((again.state, again.interval_days), (CardState::Relearning, LEARNING_STEP_DAYS));
+    }
+
+    #[test]
+    fn difficulty_stays_within_bounds() {
+        let fsrs = fsrs();
+        let mut memory = MemoryState { stability: 1.0, difficulty: 9.9 };
+        for _ in 0..20 {
+            memory = fsrs.schedule(CardState::Review, Some(memory), 1.0, Grade::Again).memory;
+            assert!((1.0..=10.0).contains(&memory.difficulty));
+        }
+        let mut memory = MemoryState { stability: 1.0, difficulty: 1.1 };
+        for _ in 0..20 {
+            memory = fsrs.schedule(CardState::Review, Some(memory), 1.0, Grade::Easy).memory;
+            assert!((1.0..=10.0).contains(&memory.difficulty));
+        }
+    }
+
+    #[test]
+    fn interval_follows_desired_retention_and_limits() {
+        // 期望回忆概率 90% 时间隔等于稳定性
+        assert_close(fsrs().retrievability(100.0, 100.0), 0.9);
+        assert_eq!(fsrs().next_interval(100.0), 100.0);
+        assert_eq!(Fsrs::new(&DEFAULT_WEIGHTS, 0.8, 36500.0).next_interval(100.0), 240.0);
+        assert_eq!(Fsrs::new(&DEFAULT_WEIGHTS, 0.95, 36500.0).next_interval(100.0), 46.0);
+        assert_eq!(Fsrs::new(&DEFAULT_WEIGHTS, 0.9, 30.0).next_interval(100.0), 30.0);
+        assert_eq!(fsrs().next_interval(0.1), 1.0);
+    }
+
+    #[test]
+    fn intervals_convert_for_anki_and_display() {
+        assert_eq!(anki_interval(LEARNING_STEP_DAYS), -600);
+        assert_eq!(anki_interval(3.4), 3);
+        assert_eq!(format_interval(LEARNING_STEP_DAYS), "10分钟");
+        assert_eq!(format_interval(3.0), "3天");
+        assert_eq!(format_interval(75.0), "2.5月");
+        assert_eq!(format_interval(730.0), "2.0年");
+    }
+}
@@ -0,0 +1,357 @@
+// 语法解析中使用的类型（接续的词类和语体），以及语法写法的规范化
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::conjugation::{ConjugationForm, classify, conjugate};
+use crate::prompts::Language;
+
+// 表示前项的占位写法，统一替换为 〜（较长的写法在前）
+const PLACEHOLDER_WORDS: &[&str] = &[
+    "い形容詞", "な形容詞", "形容詞", "形容动词", "形容動詞", "動詞", "动词", "名詞", "名词", "普通形", "辞書形", "ます形",
+];
+
+// 占位写法后的活用形名称，保留其中属于语法本身的假名
+const FORM_NAMES: &[(&str, &str)] = &[("て形", "て"), ("た形", "た"), ("ない形", "ない"), ("ば形", "ば"), ("意向形", "よう")];
+
+// 语法末尾可以活用的补助动词：（前接的部分，补助动词）
+const AUXILIARIES: &[(&[&str], &str)] = &[
+    (&["て", "で"], "いる"),
+    (&["て", "で"], "ある"),
+    (&["て", "で"], "おく"),
+    (&["て", "で"], "しまう"),
+    (&["て", "で"], "みる"),
+    (&["て", "で"], "くる"),
+    (&["て", "で"], "いく"),
+    (&["て", "で"], "もらう"),
+    (&["て", "で"], "くれる"),
+    (&["て", "で"], "あげる"),
+    (&["ように", "ことに"], "なる"),
+    (&["ように", "ことに"], "する"),
+];
+
+// 口语缩约形：（缩约形，原形）
+const CONTRACTIONS: &[(&str, &str)] = &[
+    ("てる", "ている"),
+    ("てた", "ていた"),
+    ("ちゃう", "てしまう"),
+    ("ちゃった", "てしまった"),
+];
+
+/// 语法接续的前项词类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConnectionBase {
+    Verb,
+    IAdjective,
+    NaAdjective,
+    Noun,
+    // 接在句子或普通形后面
+    Clause,
+    #[default]
+    Other,
+}
+
+impl ConnectionBase {
+    pub const ALL: [ConnectionBase; 6] = [
+        ConnectionBase::Verb,
+        ConnectionBase::IAdjective,
+        ConnectionBase::NaAdjective,
+        ConnectionBase::Noun,
+        ConnectionBase::Clause,
+        ConnectionBase::Other,
+    ];
+
+    /// 数据库和 JSON 中使用的名称
+    pub fn key(&self) -> &'static str {
+        match self {
+            ConnectionBase::Verb => "verb",
+            ConnectionBase::IAdjective => "i_adjective",
+            ConnectionBase::NaAdjective => "na_adjective",
+            ConnectionBase::Noun => "noun",
+            ConnectionBase::Clause => "clause",
+            ConnectionBase::Other => "other",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|base| base.key() == key.trim())
+    }
+
+    /// 指定语言的词类名称
+    pub fn label_in(&self, language: Language) -> &'static str {
+        match language {
+            Language::Chinese => match self {
+                ConnectionBase::Verb => "动词",
+                ConnectionBase::IAdjective => "一类形容词",
+                ConnectionBase::NaAdjective => "二类形容词",
+                ConnectionBase::Noun => "名词",
+                ConnectionBase::Clause => "句子",
+                ConnectionBase::Other => "其他",
+            },
+            Language::English => match self {
+                ConnectionBase::Verb => "verb",
+                ConnectionBase::IAdjective => "i-adjective",
+                ConnectionBase::NaAdjective => "na-adjective",
+                ConnectionBase::Noun => "noun",
+                ConnectionBase::Clause => "clause",
+                ConnectionBase::Other => "other",
+            },
+            Language::Japanese => match self {
+                ConnectionBase::Verb => "動詞",
+                ConnectionBase::IAdjective => "い形容詞",
+                ConnectionBase::NaAdjective => "な形容詞",
+                ConnectionBase::Noun => "名詞",
+                ConnectionBase::Clause => "文",
+                ConnectionBase::Other => "その他",
+            },
+        }
+    }
+}
+
+/// 语法的语体
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GrammarRegister {
+    #[default]
+    Neutral,
+    Formal,
+    Casual,
+    Written,
+}
+
+impl GrammarRegister {
+    pub const ALL: [GrammarRegister; 4] = [
+        GrammarRegister::Neutral,
+        GrammarRegister::Formal,
+        GrammarRegister::Casual,
+        GrammarRegister::Written,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            GrammarRegister::Neutral => "neutral",
+            GrammarRegister::Formal => "formal",
+            GrammarRegister::Casual => "casual",
+            GrammarRegister::Written => "written",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|register| register.key() == key.trim())
+    }
+
+    pub fn label_in(&self, language: Language) -> &'static str {
+        match language {
+            Language::Chinese => match self {
+                GrammarRegister::Neutral => "通用",
+                GrammarRegister::Formal => "正式",
+                GrammarRegister::Casual => "口语",
+                GrammarRegister::Written => "书面语",
+            },
+            Language::English => match self {
+                GrammarRegister::Neutral => "neutral",
+                GrammarRegister::Formal => "formal",
+                GrammarRegister::Casual => "casual",
+                GrammarRegister::Written => "written",
+            },
+            Language::Japanese => match self {
+                GrammarRegister::Neutral => "一般",
+                GrammarRegister::Formal => "改まった表現",
+                GrammarRegister::Casual => "くだけた表現",
+                GrammarRegister::Written => "書き言葉",
+            },
+        }
+    }
+}
+
+// JSON 中使用 key，模型返回未知的值时退回到默认值而不是整条解析失败
+impl Serialize for ConnectionBase {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.key())
+    }
+}
+
+impl<'de> Deserialize<'de> for ConnectionBase {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        Ok(Self::from_key(&key).unwrap_or_default())
+    }
+}
+
+impl Serialize for GrammarRegister {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.key())
+    }
+}
+
+impl<'de> Deserialize<'de> for GrammarRegister {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        Ok(Self::from_key(&key).unwrap_or_default())
+    }
+}
+
+/// 语法的规范写法：统一 〜/～/~ 和 V/N 等占位写法，把末尾活用的补助动词还原为辞书形，并以 〜 开头
+/// 例如 "～ている"、"Vている"、"ていた" 都规范为 "〜ている"
+pub fn canonical_grammar(pattern: &str) -> String {
+    let key = grammar_key(pattern);
+    if key.is_empty() {
+        return pattern.trim().to_string();
+    }
+    format!("〜{}", key)
+}
+
+/// 比较语法时使用的键：规范写法去掉开头的 〜
+pub fn grammar_key(pattern: &str) -> String {
+    let notation = normalize_notation(pattern);
+    base_form(&notation)
+}
+
+// 全角字母数字转为半角，占位写法和各种波浪号统一为 〜，去掉空白和首尾的 〜
+fn normalize_notation(pattern: &str) -> String {
+    let mut text: String = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .collect();
+    for word in PLACEHOLDER_WORDS {
+        text = text.replace(word, "〜");
+    }
+    for (name, kana) in FORM_NAMES {
+        text = text.replace(&format!("{}+", name), kana).replace(name, kana);
+    }
+
+    // V、N1、Adj、V-masu 这类占位写法和连接用的 + - ・ 都视为占位，OK、SNS 等其他字母数字按原样保留
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_alphanumeric() {
+            let mut token = c.to_string();
+            while let Some(n) = chars.next_if(char::is_ascii_alphanumeric) {
+                token.push(n);
+            }
+            if !is_placeholder(&token) {
+                output.push_str(&token);
+                continue;
+            }
+            while chars.next_if(|n| n.is_ascii_alphanumeric() || matches!(n, '-' | '+' | '＋' | '・')).is_some() {}
+            if !output.ends_with('〜') {
+                output.push('〜');
+            }
+        } else if matches!(c, '~' | '〜' | '∼' | '…' | '+' | '＋') {
+            if !output.ends_with('〜') {
+                output.push('〜');
+            }
+        } else {
+            output.push(c);
+        }
+    }
+    output.trim_matches('〜').to_string()
+}
+
+// 以大写 V/N/A/S 开头、其余为小写字母或数字的写法（V、N1、Adj、Vte）表示前项
+fn is_placeholder(token: &str) -> bool {
+    let mut chars = token.chars();
+    chars.next().is_some_and(|c| matches!(c, 'V' | 'N' | 'A' | 'S'))
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+}
+
+// 把末尾的缩约形和补助动词的活用形（ます形、た形、ない形等）还原为辞书形，取最长的匹配
+fn base_form(pattern: &str) -> String {
+    let mut pattern = pattern.to_string();
+    if let Some((contracted, full)) = CONTRACTIONS.iter().find(|(c, _)| pattern.ends_with(c))
+        && !pattern.ends_with(full)
+    {
+        pattern = format!("{}{}", &pattern[..pattern.len() - contracted.len()], full);
+    }
+
+    let mut best: Option<(usize, String)> = None;
+    for (leads, auxiliary) in AUXILIARIES {
+        for inflected in inflections(auxiliary) {
+            for lead in *leads {
+                let suffix = format!("{}{}", lead, inflected);
+                if pattern.ends_with(&suffix) && best.as_ref().is_none_or(|(len, _)| suffix.len() > *len) {
+                    let stem = &pattern[..pattern.len() - suffix.len()];
+                    best = Some((suffix.len(), format!("{}{}{}", stem, lead, auxiliary)));
+                }
+            }
+        }
+    }
+    best.map(|(_, base)| base).unwrap_or(pattern)
+}
+
+// 补助动词的活用形（不含辞书形）；ある的否定形是 ない，与其他语法混淆，不算在内
+fn inflections(auxiliary: &str) -> Vec<String> {
+    let Some(class) = classify(auxiliary, auxiliary, "动词") else {
+        return Vec::new();
+    };
+    let mut forms = Vec::new();
+    for conjugation in conjugate(auxiliary, auxiliary, class) {
+        match conjugation.form {
+            ConjugationForm::Masu => {
+                let stem = conjugation.kana.trim_end_matches("ます");
+                for ending in ["ました", "ません", "ませんでした"] {
+                    forms.push(format!("{}{}", stem, ending));
+                }
+                forms.push(conjugation.kana);
+            },
+            ConjugationForm::Ta => forms.push(conjugation.kana),
+            ConjugationForm::Nai if conjugation.kana != "ない" => {
+                forms.push(format!("{}かった", conjugation.kana.trim_end_matches('い')));
+                forms.push(conjugation.kana);
+            },
+            _ => {},
+        }
+    }
+    forms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notation_variants_share_one_canonical_form() {
+        for pattern in ["〜ている", "～ている", "~ている", "ている", " 〜 ている ", "Vている", "V-ている", "V+ている", "動詞て形+いる", "Ｖている"] {
+            assert_eq!(canonical_grammar(pattern), "〜ている", "{}", pattern);
+        }
+        assert_eq!(canonical_grammar("N1はN2より"), "〜は〜より");
+        assert_eq!(canonical_grammar("Adjすぎる"), "〜すぎる");
+        assert_eq!(canonical_grammar("V-masuながら"), "〜ながら");
+        assert_eq!(canonical_grammar("名詞+の+ために"), "〜の〜ために");
+    }
+
+    #[test]
+    fn leading_tilde_is_optional() {
+        assert_eq!(canonical_grammar("でも"), "〜でも");
+        assert_eq!(canonical_grammar("〜でも"), "〜でも");
+        assert_eq!(grammar_key("でも"), grammar_key("～でも"));
+        assert_ne!(canonical_grammar("でも"), canonical_grammar("ても"));
+    }
+
+    #[test]
+    fn real_ascii_is_kept() {
+        assert_eq!(canonical_grammar("〜をOKする"), "〜をOKする");
+        assert_eq!(canonical_grammar("SNSに上げる"), "〜SNSに上げる");
+        assert_eq!(canonical_grammar("Ｔシャツを着ている"), "〜Tシャツを着ている");
+        assert_ne!(canonical_grammar("〜をOKする"), canonical_grammar("〜をする"));
+    }
+
+    #[test]
+    fn inflected_auxiliaries_return_to_dictionary_form() {
+        for pattern in ["ていた", "ています", "ていました", "ていない", "ていなかった", "てる", "〜てた"] {
+            assert_eq!(canonical_grammar(pattern), "〜ている", "{}", pattern);
+        }
+        assert_eq!(canonical_grammar("ちゃった"), "〜てしまう");
+        assert_eq!(canonical_grammar("ようになった"), "〜ようになる");
+        // ある的否定形 ない 与其他语法混淆，不还原
+        assert_eq!(canonical_grammar("てない"), "〜てない");
+    }
+
+    #[test]
+    fn empty_patterns_are_kept_as_is() {
+        assert_eq!(canonical_grammar(" 〜 "), "〜");
+        assert_eq!(canonical_grammar("V"), "V");
+    }
+}
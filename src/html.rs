@@ -0,0 +1,399 @@
+// 清理 AI 返回的解析：去掉代码块标记、转换 markdown 强调、按白名单过滤标签并补全未闭合的标签
+
+use crate::models::{AnalysisReview, GrammarDetails, WordDetails};
+use crate::text::strip_html;
+
+// 允许保留的标签
+const ALLOWED_TAGS: &[&str] = &[
+    "div", "p", "br", "hr", "span", "b", "strong", "i", "em", "u", "small", "sup", "sub",
+    "ul", "ol", "li", "table", "thead", "tbody", "tr", "th", "td", "ruby", "rb", "rt", "rp",
+];
+
+// 没有结束标签的标签
+const VOID_TAGS: &[&str] = &["br", "hr"];
+
+// 连同内容一起删除的标签
+const DROPPED_WITH_CONTENT: &[&str] = &["script", "style", "iframe", "object", "embed", "noscript", "template"];
+
+/// 清理解析时发现的问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtmlIssue {
+    CodeFence,
+    MarkdownEmphasis,
+    DisallowedTag(String),
+    DisallowedAttribute(String),
+    UnclosedTag(String),
+    StrayClosingTag(String),
+    Empty,
+}
+
+impl HtmlIssue {
+    /// 是否算作校验失败：格式修正不算失败，标签或内容有问题的解析需要重新分析
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, HtmlIssue::CodeFence | HtmlIssue::MarkdownEmphasis)
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            HtmlIssue::CodeFence => "去掉了 markdown 代码块标记".to_string(),
+            HtmlIssue::MarkdownEmphasis => "转换了 markdown 强调".to_string(),
+            HtmlIssue::DisallowedTag(tag) => format!("删除了不允许的标签 <{}>", tag),
+            HtmlIssue::DisallowedAttribute(attr) => format!("删除了不允许的属性 {}", attr),
+            HtmlIssue::UnclosedTag(tag) => format!("补全了未闭合的标签 <{}>", tag),
+            HtmlIssue::StrayClosingTag(tag) => format!("删除了多余的结束标签 </{}>", tag),
+            HtmlIssue::Empty => "解析内容为空".to_string(),
+        }
+    }
+}
+
+/// 清理后的解析和发现的问题
+#[derive(Debug, Clone)]
+pub struct SanitizedHtml {
+    pub html: String,
+    pub issues: Vec<HtmlIssue>,
+    // 审查模型的审查结果，未配置审查模型或未审查时为 None
+    pub review: Option<AnalysisReview>,
+}
+
+impl SanitizedHtml {
+    /// 校验失败的原因，通过校验时为 None
+    pub fn failure_reason(&self) -> Option<String> {
+        let failures: Vec<String> = self.issues.iter().filter(|i| i.is_failure()).map(|i| i.label()).collect();
+        (!failures.is_empty()).then(|| failures.join("；"))
+    }
+}
+
+/// 清理 AI 返回的解析
+pub fn sanitize_analysis(raw: &str) -> SanitizedHtml {
+    let mut issues = Vec::new();
+    let text = strip_code_fences(raw.trim(), &mut issues);
+    let text = convert_markdown_emphasis(&text, &mut issues);
+    let html = filter_tags(&text, &mut issues).trim().to_string();
+    if strip_html(&html).is_empty() {
+        issues.push(HtmlIssue::Empty);
+    }
+    SanitizedHtml { html, issues, review: None }
+}
+
+/// 清理结构化单词解析中的每个文本字段，核心意思为空时算作内容为空
+pub fn sanitize_word_details(details: &mut WordDetails) -> Vec<HtmlIssue> {
+    let mut fields: Vec<&mut String> = vec![&mut details.etymology, &mut details.summary];
+    for sense in &mut details.senses {
+        fields.push(&mut sense.meaning);
+        fields.push(&mut sense.explanation);
+        for example in &mut sense.examples {
+            fields.extend([&mut example.japanese, &mut example.reading, &mut example.translation]);
+        }
+    }
+    for comparison in &mut details.comparisons {
+        fields.extend([&mut comparison.word, &mut comparison.difference]);
+    }
+    sanitize_fields(&mut details.core_meaning, fields)
+}
+
+/// 清理结构化语法解析中的每个文本字段，意思为空时算作内容为空
+pub fn sanitize_grammar_details(details: &mut GrammarDetails) -> Vec<HtmlIssue> {
+    let mut fields: Vec<&mut String> = vec![&mut details.usage];
+    for rule in &mut details.connections {
+        fields.extend([&mut rule.form, &mut rule.note]);
+    }
+    for example in &mut details.examples {
+        fields.extend([&mut example.japanese, &mut example.reading, &mut example.translation]);
+    }
+    for related in &mut details.related {
+        fields.extend([&mut related.grammar, &mut related.difference]);
+    }
+    sanitize_fields(&mut details.meaning, fields)
+}
+
+// 清理必填字段和可选字段，可选字段允许为空
+fn sanitize_fields(required: &mut String, optional: Vec<&mut String>) -> Vec<HtmlIssue> {
+    let mut issues = Vec::new();
+    let sanitized = sanitize_analysis(required);
+    *required = sanitized.html;
+    for issue in sanitized.issues {
+        push_issue(&mut issues, issue);
+    }
+    for field in optional {
+        let sanitized = sanitize_analysis(field);
+        *field = sanitized.html;
+        for issue in sanitized.issues.into_iter().filter(|i| *i != HtmlIssue::Empty) {
+            push_issue(&mut issues, issue);
+        }
+    }
+    issues
+}
+
+fn push_issue(issues: &mut Vec<HtmlIssue>, issue: HtmlIssue) {
+    if !issues.contains(&issue) {
+        issues.push(issue);
+    }
+}
+
+// 去掉 ```html 之类的代码块标记行，以及首尾紧贴内容的标记
+fn strip_code_fences(text: &str, issues: &mut Vec<HtmlIssue>) -> String {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let Some(rest) = trimmed.strip_prefix("```") else {
+            lines.push(line);
+            continue;
+        };
+        push_issue(issues, HtmlIssue::CodeFence);
+        // ```html<div>... 这种标记和内容在同一行的情况
+        let content = rest.trim_start_matches(|c: char| c.is_ascii_alphanumeric());
+        let content = content.strip_suffix("```").unwrap_or(content);
+        if !content.trim().is_empty() {
+            lines.push(content);
+        }
+    }
+    let mut text = lines.join("\n");
+    if let Some(stripped) = text.strip_suffix("```") {
+        push_issue(issues, HtmlIssue::CodeFence);
+        text = stripped.to_string();
+    }
+    text
+}
+
+// **粗体** 转为 <b>，*斜体* 转为 <i>，只处理同一行内成对出现的标记
+fn convert_markdown_emphasis(text: &str, issues: &mut Vec<HtmlIssue>) -> String {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            let line = replace_pairs(line, "**", "b", issues);
+            replace_pairs(&line, "*", "i", issues)
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn replace_pairs(line: &str, marker: &str, tag: &str, issues: &mut Vec<HtmlIssue>) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find(marker) {
+        let after = &rest[start + marker.len()..];
+        let end = after.find(marker).filter(|&end| {
+            let inner = &after[..end];
+            !inner.is_empty()
+                && !inner.starts_with(char::is_whitespace)
+                && !inner.ends_with(char::is_whitespace)
+        });
+        match end {
+            Some(end) => {
+                output.push_str(&rest[..start]);
+                output.push_str(&format!("<{}>{}</{}>", tag, &after[..end], tag));
+                rest = &after[end + marker.len()..];
+                push_issue(issues, HtmlIssue::MarkdownEmphasis);
+            },
+            None => {
+                output.push_str(&rest[..start + marker.len()]);
+                rest = after;
+            },
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+// 按白名单过滤标签和属性，删除多余的结束标签并补全未闭合的标签
+fn filter_tags(text: &str, issues: &mut Vec<HtmlIssue>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut open: Vec<String> = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        // 注释直接删除
+        if let Some(comment) = after.strip_prefix("!--") {
+            rest = comment.find("-->").map(|end| &comment[end + 3..]).unwrap_or("");
+            continue;
+        }
+
+        let closing = after.starts_with('/');
+        let name_start = if closing { 1 } else { 0 };
+        let name_len = after[name_start..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(after.len() - name_start);
+        let (Some(end), true) = (after.find('>'), name_len > 0) else {
+            // 不是标签的 < 作为文本保留
+            output.push_str("&lt;");
+            rest = after;
+            continue;
+        };
+        let name = after[name_start..name_start + name_len].to_lowercase();
+        let attributes = after[name_start + name_len..end].trim_end_matches('/');
+        rest = &after[end + 1..];
+
+        if DROPPED_WITH_CONTENT.contains(&name.as_str()) {
+            push_issue(issues, HtmlIssue::DisallowedTag(name.clone()));
+            if !closing {
+                let lower = rest.to_ascii_lowercase();
+                rest = match lower.find(&format!("</{}", name)) {
+                    Some(close) => rest[close..].find('>').map(|e| &rest[close + e + 1..]).unwrap_or(""),
+                    None => "",
+                };
+            }
+            continue;
+        }
+        if !ALLOWED_TAGS.contains(&name.as_str()) {
+            push_issue(issues, HtmlIssue::DisallowedTag(name));
+            continue;
+        }
+        if VOID_TAGS.contains(&name.as_str()) {
+            if !closing {
+                output.push_str(&format!("<{}>", name));
+            }
+            continue;
+        }
+
+        if closing {
+            match open.iter().rposition(|tag| *tag == name) {
+                Some(index) => {
+                    for tag in open.drain(index..).rev() {
+                        if tag != name {
+                            push_issue(issues, HtmlIssue::UnclosedTag(tag.clone()));
+                        }
+                        output.push_str(&format!("</{}>", tag));
+                    }
+                },
+                None => push_issue(issues, HtmlIssue::StrayClosingTag(name)),
+            }
+        } else {
+            output.push('<');
+            output.push_str(&name);
+            output.push_str(&filter_attributes(attributes, issues));
+            output.push('>');
+            open.push(name);
+        }
+    }
+    output.push_str(rest);
+
+    for tag in open.into_iter().rev() {
+        push_issue(issues, HtmlIssue::UnclosedTag(tag.clone()));
+        output.push_str(&format!("</{}>", tag));
+    }
+    output
+}
+
+// 只保留不含脚本和外部资源的 style 属性；含字符实体或 CSS 转义的值可能绕过检查，一并删除
+fn filter_attributes(attributes: &str, issues: &mut Vec<HtmlIssue>) -> String {
+    let mut kept = String::new();
+    for (name, value) in parse_attributes(attributes) {
+        let lower = value.to_lowercase();
+        let safe = name == "style"
+            && !lower.contains("url(")
+            && !lower.contains("expression")
+            && !lower.contains("javascript:")
+            && !lower.contains(['&', '\\']);
+        if safe {
+            kept.push_str(&format!(" style=\"{}\"", value.replace('"', "&quot;")));
+        } else {
+            push_issue(issues, HtmlIssue::DisallowedAttribute(name));
+        }
+    }
+    kept
+}
+
+// 解析 name="value" / name='value' / name=value / name 形式的属性
+fn parse_attributes(attributes: &str) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+    let mut rest = attributes.trim_start();
+    while !rest.is_empty() {
+        let name_end = rest.find(|c: char| c == '=' || c.is_whitespace()).unwrap_or(rest.len());
+        let name = rest[..name_end].to_lowercase();
+        rest = rest[name_end..].trim_start();
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (parsed_value, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after[1..];
+                    match inner.find(quote) {
+                        Some(end) => (&inner[..end], &inner[end + 1..]),
+                        None => (inner, ""),
+                    }
+                },
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                },
+            };
+            value = parsed_value.to_string();
+            rest = remaining;
+        }
+        if !name.is_empty() {
+            parsed.push((name, value));
+        }
+        rest = rest.trim_start();
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_and_styles_are_dropped_with_content() {
+        let sanitized = sanitize_analysis("<div>前<script>alert(1)</script>后</div><style>div { color: red }</style>");
+        assert_eq!(sanitized.html, "<div>前后</div>");
+        assert!(sanitized.issues.contains(&HtmlIssue::DisallowedTag("script".to_string())));
+        assert!(sanitized.issues.contains(&HtmlIssue::DisallowedTag("style".to_string())));
+        assert!(sanitized.failure_reason().is_some());
+
+        assert_eq!(sanitize_analysis("<div>意思</div><SCRIPT type=\"text/javascript\">alert(1)</Script >").html, "<div>意思</div>");
+        // 没有结束标签时删除到末尾
+        assert_eq!(sanitize_analysis("<div>意思</div><script>alert(1)").html, "<div>意思</div>");
+        assert_eq!(sanitize_analysis("<div>意思<iframe src=\"https://example.com\">x</iframe></div>").html, "<div>意思</div>");
+        // 大小写转换会改变部分字符的字节长度，查找结束标签时不能因此错位
+        assert_eq!(sanitize_analysis("<div><script>İİİİİİİİİİ</script>漢字テスト</div>").html, "<div>漢字テスト</div>");
+    }
+
+    #[test]
+    fn event_handler_attributes_are_removed() {
+        let sanitized = sanitize_analysis("<span onclick=\"alert(1)\" style=\"color: red\" ONMOUSEOVER=alert(2)>赤</span>");
+        assert_eq!(sanitized.html, "<span style=\"color: red\">赤</span>");
+        assert!(sanitized.issues.contains(&HtmlIssue::DisallowedAttribute("onclick".to_string())));
+        assert!(sanitized.issues.contains(&HtmlIssue::DisallowedAttribute("onmouseover".to_string())));
+
+        assert_eq!(sanitize_analysis("<b/onmouseover=alert(1)>重点</b>").html, "<b>重点</b>");
+        assert_eq!(sanitize_analysis("<div>图<img src=x onerror=alert(1)></div>").html, "<div>图</div>");
+    }
+
+    #[test]
+    fn javascript_urls_are_removed() {
+        let sanitized = sanitize_analysis("<div><a href=\"javascript:alert(1)\">链接</a></div>");
+        assert_eq!(sanitized.html, "<div>链接</div>");
+        assert!(sanitized.issues.contains(&HtmlIssue::DisallowedTag("a".to_string())));
+
+        for style in [
+            "background: url(javascript:alert(1))",
+            "background: URL('https://example.com/x.png')",
+            "width: expression(alert(1))",
+            "background: u&#114;l(https://example.com/x.png)",
+            "background: \\75 rl(https://example.com/x.png)",
+        ] {
+            let sanitized = sanitize_analysis(&format!("<span style=\"{}\">字</span>", style));
+            assert_eq!(sanitized.html, "<span>字</span>", "{}", style);
+        }
+    }
+
+    #[test]
+    fn markdown_and_broken_markup_are_repaired() {
+        let sanitized = sanitize_analysis("```html\n<div>**重点**和*例子*\n```");
+        assert_eq!(sanitized.html, "<div><b>重点</b>和<i>例子</i></div>");
+        assert!(sanitized.issues.contains(&HtmlIssue::CodeFence));
+        assert!(sanitized.issues.contains(&HtmlIssue::MarkdownEmphasis));
+        assert!(sanitized.issues.contains(&HtmlIssue::UnclosedTag("div".to_string())));
+
+        let sanitized = sanitize_analysis("<div>1 < 2</div></p><!-- 注释 -->");
+        assert_eq!(sanitized.html, "<div>1 &lt; 2</div>");
+        assert_eq!(sanitized.issues, vec![HtmlIssue::StrayClosingTag("p".to_string())]);
+
+        let formatting_only = sanitize_analysis("```\n<div>意思</div>\n```");
+        assert_eq!(formatting_only.failure_reason(), None);
+        assert_eq!(sanitize_analysis("<script>x</script>").issues.last(), Some(&HtmlIssue::Empty));
+    }
+}
@@ -0,0 +1,260 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::models::{JapaneseWord, KanjiInfo};
+use crate::text::is_kanji;
+
+/// 本地汉字数据：KANJIDIC 格式的读音/意思/笔画数和 KRADFILE 格式的部件
+#[derive(Debug, Default)]
+pub struct KanjiDictionary {
+    entries: HashMap<char, KanjiInfo>,
+    components: HashMap<char, Vec<String>>,
+}
+
+impl KanjiDictionary {
+    /// 加载数据文件（UTF-8 编码），未配置的文件跳过
+    pub fn load(kanjidic_file: Option<&str>, kradfile: Option<&str>) -> Result<Self> {
+        let mut dictionary = KanjiDictionary::default();
+
+        if let Some(path) = kanjidic_file {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("无法读取 KANJIDIC 文件 {}（需要 UTF-8 编码）: {}", path, e))?;
+            for info in content.lines().filter_map(parse_kanjidic_line) {
+                let character = info.character.chars().next().unwrap_or_default();
+                dictionary.entries.insert(character, info);
+            }
+        }
+
+        if let Some(path) = kradfile {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("无法读取 KRADFILE 文件 {}（需要 UTF-8 编码）: {}", path, e))?;
+            for (character, parts) in content.lines().filter_map(parse_kradfile_line) {
+                dictionary.components.insert(character, parts);
+            }
+        }
+
+        Ok(dictionary)
+    }
+
+    /// 查询汉字信息，数据中没有的汉字只填写字本身
+    pub fn lookup(&self, character: char) -> KanjiInfo {
+        let mut info = self.entries.get(&character).cloned().unwrap_or_else(|| KanjiInfo {
+            character: character.to_string(),
+            ..Default::default()
+        });
+        if let Some(parts) = self.components.get(&character) {
+            info.components = parts.clone();
+        }
+        info
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.components.is_empty()
+    }
+}
+
+/// 收集单词中出现的汉字，按首次出现的顺序返回每个汉字和使用它的单词ID
+pub fn collect_kanji(words: &[JapaneseWord]) -> Vec<(char, Vec<i64>)> {
+    let mut order: Vec<char> = Vec::new();
+    let mut word_ids: HashMap<char, Vec<i64>> = HashMap::new();
+
+    for word in words {
+        for c in word.word.chars().filter(|c| is_kanji(*c)) {
+            let ids = word_ids.entry(c).or_insert_with(|| {
+                order.push(c);
+                Vec::new()
+            });
+            if !ids.contains(&word.id) {
+                ids.push(word.id);
+            }
+        }
+    }
+
+    order
+        .into_iter()
+        .map(|c| {
+            let ids = word_ids.remove(&c).unwrap_or_default();
+            (c, ids)
+        })
+        .collect()
+}
+
+// KANJIDIC 格式：亜 3021 U4e9c B1 C7 G8 S7 ... ア つ.ぐ T1 や つぎ {Asia} {rank next}
+// 片假名为音读，平假名为训读，T1/T2 之后是名字读法（忽略），{} 中为意思，第一个 S 开头的数字为笔画数
+fn parse_kanjidic_line(line: &str) -> Option<KanjiInfo> {
+    if line.starts_with('#') || line.trim().is_empty() {
+        return None;
+    }
+
+    let (head, meanings_part) = match line.find('{') {
+        Some(index) => line.split_at(index),
+        None => (line, ""),
+    };
+    let mut tokens = head.split_whitespace();
+    let character = tokens.next()?;
+    if character.chars().count() != 1 || !character.chars().all(is_kanji) {
+        return None;
+    }
+
+    let mut info = KanjiInfo {
+        character: character.to_string(),
+        ..Default::default()
+    };
+    let mut in_nanori = false;
+
+    for token in tokens {
+        let first = token.chars().next()?;
+        if token == "T1" || token == "T2" {
+            in_nanori = true;
+        } else if first == 'S' && info.stroke_count.is_none() {
+            info.stroke_count = token[1..].parse().ok();
+        } else if in_nanori || token.trim_start_matches('-').starts_with(|c: char| c.is_ascii()) {
+            continue;
+        } else if is_katakana_reading(token) {
+            info.on_readings.push(token.to_string());
+        } else {
+            info.kun_readings.push(token.to_string());
+        }
+    }
+
+    info.meanings = meanings_part
+        .split('}')
+        .filter_map(|part| part.trim().strip_prefix('{'))
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
+
+    Some(info)
+}
+
+fn is_katakana_reading(token: &str) -> bool {
+    token
+        .trim_matches('-')
+        .chars()
+        .all(|c| matches!(c, '\u{30A0}'..='\u{30FF}'))
+}
+
+// KRADFILE 格式：亜 : ｜ 一 口
+fn parse_kradfile_line(line: &str) -> Option<(char, Vec<String>)> {
+    if line.starts_with('#') {
+        return None;
+    }
+    let (character, parts) = line.split_once(':')?;
+    let character = character.trim();
+    if character.chars().count() != 1 {
+        return None;
+    }
+
+    Some((
+        character.chars().next()?,
+        parts.split_whitespace().map(|p| p.to_string()).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KANJIDIC: &str = "\
+# KANJIDIC JIS X 0208 Kanji Dictionary
+亜 3021 U4e9c B1 C7 G8 S7 XJ13D0B F1509 J1 N43 V81 H3540 DK2204 L1809 P4-7-1 I0a7.14 Q1010.6 MY21 Yya4 Wa ア つ.ぐ T1 や つぎ つぐ {Asia} {rank next} {come after} {-ous}
+食 3F29 U98df B184 G2 S9 S10 F328 N5180 ショク ジキ く.う く.らう た.べる は.む -く.う T1 け {eat} {food}
+X 0000 ア {not kanji}
+";
+
+    const KRADFILE: &str = "\
+# KRADFILE
+亜 : ｜ 一 口
+食 : 人 良 食
+";
+
+    // 把内联的测试数据写入临时文件
+    fn fixture(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("anki_creator_test_{}_{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn word(id: i64, text: &str) -> JapaneseWord {
+        JapaneseWord {
+            id,
+            word: text.to_string(),
+            kana: String::new(),
+            pitch: String::new(),
+            part_of_speech: String::new(),
+            analysis: String::new(),
+            updated_at: None,
+            analysis_locked: false,
+            pos_locked: false,
+            pitch_locked: false,
+            kana_locked: false,
+            tags: String::new(),
+            frequency_rank: None,
+            jlpt_level: None,
+            prompt_version: 0,
+        }
+    }
+
+    #[test]
+    fn kanjidic_lines_split_readings_meanings_and_strokes() {
+        let info = parse_kanjidic_line(KANJIDIC.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(info.character, "亜");
+        assert_eq!(info.on_readings, vec!["ア"]);
+        // T1 之后的名字读法不算训读
+        assert_eq!(info.kun_readings, vec!["つ.ぐ"]);
+        assert_eq!(info.meanings, vec!["Asia", "rank next", "come after", "-ous"]);
+        assert_eq!(info.stroke_count, Some(7));
+
+        let info = parse_kanjidic_line(KANJIDIC.lines().nth(2).unwrap()).unwrap();
+        assert_eq!(info.on_readings, vec!["ショク", "ジキ"]);
+        assert_eq!(info.kun_readings, vec!["く.う", "く.らう", "た.べる", "は.む", "-く.う"]);
+        // 多个笔画数时取第一个
+        assert_eq!(info.stroke_count, Some(9));
+
+        assert!(parse_kanjidic_line("# comment").is_none());
+        assert!(parse_kanjidic_line("").is_none());
+        assert!(parse_kanjidic_line("X 0000 ア {not kanji}").is_none());
+    }
+
+    #[test]
+    fn kradfile_lines_list_components() {
+        assert_eq!(parse_kradfile_line("亜 : ｜ 一 口"), Some(('亜', vec!["｜".to_string(), "一".to_string(), "口".to_string()])));
+        assert_eq!(parse_kradfile_line("# 亜 : 一"), None);
+        assert_eq!(parse_kradfile_line("亜亜 : 一"), None);
+        assert_eq!(parse_kradfile_line("亜 一 口"), None);
+    }
+
+    #[test]
+    fn dictionary_combines_both_files() {
+        let kanjidic = fixture("kanjidic.txt", KANJIDIC);
+        let kradfile = fixture("kradfile.txt", KRADFILE);
+        let dictionary = KanjiDictionary::load(Some(&kanjidic), Some(&kradfile)).unwrap();
+        std::fs::remove_file(&kanjidic).unwrap();
+        std::fs::remove_file(&kradfile).unwrap();
+
+        assert_eq!(dictionary.len(), 2);
+        let info = dictionary.lookup('食');
+        assert_eq!(info.meanings, vec!["eat", "food"]);
+        assert_eq!(info.components, vec!["人", "良", "食"]);
+        // 数据中没有的汉字只填写字本身
+        let unknown = dictionary.lookup('鬱');
+        assert_eq!((unknown.character.as_str(), unknown.stroke_count), ("鬱", None));
+        assert!(unknown.meanings.is_empty() && unknown.components.is_empty());
+
+        assert!(KanjiDictionary::load(None, None).unwrap().is_empty());
+        assert!(KanjiDictionary::load(Some("/nonexistent/kanjidic"), None).is_err());
+    }
+
+    #[test]
+    fn kanji_are_collected_in_first_seen_order() {
+        let words = [word(1, "食べ物"), word(2, "物語"), word(3, "食物"), word(4, "たべる")];
+        assert_eq!(
+            collect_kanji(&words),
+            vec![('食', vec![1, 3]), ('物', vec![1, 2, 3]), ('語', vec![2])]
+        );
+    }
+}
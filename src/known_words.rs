@@ -0,0 +1,235 @@
+use anyhow::Result;
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::text::strip_html;
+
+/// 已掌握单词的来源
+#[derive(Debug, Clone)]
+pub enum KnownWordSource {
+    /// 纯文本单词表，每行一个单词，# 开头为注释
+    WordList { path: String },
+    /// CSV / TSV 文件中的某一列（从 0 开始）
+    Csv { path: String, column: usize, has_header: bool },
+    /// Anki 牌组：collection.anki2 / .apkg 中指定笔记类型的指定字段
+    Anki { path: String, note_type: String, field: String },
+}
+
+impl KnownWordSource {
+    pub fn path(&self) -> &str {
+        match self {
+            KnownWordSource::WordList { path }
+            | KnownWordSource::Csv { path, .. }
+            | KnownWordSource::Anki { path, .. } => path,
+        }
+    }
+}
+
+/// 读取已掌握单词，返回去重后的辞书形（保持原有顺序）
+pub async fn read_known_words(source: &KnownWordSource) -> Result<Vec<String>> {
+    let raw = match source {
+        KnownWordSource::WordList { path } => read_word_list(path)?,
+        KnownWordSource::Csv { path, column, has_header } => read_csv_column(path, *column, *has_header)?,
+        KnownWordSource::Anki { path, note_type, field } => read_anki_field(path, note_type, field).await?,
+    };
+
+    let mut seen = HashSet::new();
+    Ok(raw
+        .iter()
+        .map(|value| normalize_lemma(value))
+        .filter(|word| !word.is_empty() && seen.insert(word.clone()))
+        .collect())
+}
+
+/// 规范化字段内容：去掉 HTML、Anki 注音 `漢字[かんじ]` 中的读音和多余空白
+pub fn normalize_lemma(value: &str) -> String {
+    let text = strip_html(value);
+    let mut lemma = String::new();
+    let mut in_reading = false;
+
+    for c in text.lines().next().unwrap_or("").chars() {
+        match c {
+            '[' => in_reading = true,
+            ']' => in_reading = false,
+            _ if in_reading || c.is_whitespace() => {}
+            _ => lemma.push(c),
+        }
+    }
+
+    lemma
+}
+
+fn read_word_list(path: &str) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("无法读取单词表 {}: {}", path, e))?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect())
+}
+
+fn read_csv_column(path: &str, column: usize, has_header: bool) -> Result<Vec<String>> {
+    let delimiter = if path.to_lowercase().ends_with(".tsv") { b'\t' } else { b',' };
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(has_header)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| anyhow::anyhow!("无法读取 CSV 文件 {}: {}", path, e))?;
+
+    let mut words = Vec::new();
+    for (line_number, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| anyhow::anyhow!("第 {} 行解析失败: {}", line_number + 1, e))?;
+        if let Some(value) = record.get(column) {
+            words.push(value.to_string());
+        }
+    }
+
+    Ok(words)
+}
+
+async fn read_anki_field(path: &str, note_type: &str, field: &str) -> Result<Vec<String>> {
+    // .apkg 是 zip 包，先把其中的数据库解压到临时文件
+    let extracted = if path.to_lowercase().ends_with(".apkg") {
+        Some(extract_apkg_collection(path)?)
+    } else {
+        None
+    };
+    let db_path = extracted.as_deref().unwrap_or(Path::new(path));
+
+    let result = read_collection_field(db_path, note_type, field).await;
+
+    if let Some(temp_path) = &extracted {
+        let _ = std::fs::remove_file(temp_path);
+    }
+
+    result
+}
+
+fn extract_apkg_collection(path: &str) -> Result<PathBuf> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow::anyhow!("无法读取 Anki 牌组 {}: {}", path, e))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| anyhow::anyhow!("无法解析 .apkg 文件 {}: {}", path, e))?;
+
+    // 新版 Anki 同时写入 collection.anki21 和兼容用的 collection.anki2
+    let name = ["collection.anki21", "collection.anki2"]
+        .into_iter()
+        .find(|name| archive.by_name(name).is_ok())
+        .ok_or_else(|| anyhow::anyhow!(
+            "{} 中没有找到 collection.anki2（不支持仅含 collection.anki21b 的新格式，请在 Anki 中勾选“兼容旧版本”后重新导出）",
+            path
+        ))?;
+
+    let mut data = Vec::new();
+    archive.by_name(name)?.read_to_end(&mut data)?;
+
+    let temp_path = std::env::temp_dir().join(format!(
+        "anki_creator_known_{}_{}.anki2",
+        std::process::id(),
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_nanos()
+    ));
+    std::fs::write(&temp_path, data)
+        .map_err(|e| anyhow::anyhow!("无法创建临时文件 {}: {}", temp_path.display(), e))?;
+
+    Ok(temp_path)
+}
+
+async fn read_collection_field(db_path: &Path, note_type: &str, field: &str) -> Result<Vec<String>> {
+    let options = SqliteConnectOptions::new()
+        .filename(db_path)
+        .read_only(true);
+    let pool = SqlitePool::connect_with(options)
+        .await
+        .map_err(|e| anyhow::anyhow!("无法打开 Anki 数据库 {}: {}", db_path.display(), e))?;
+
+    let result = async {
+        let (note_type_id, field_index) = find_note_type_field(&pool, note_type, field).await?;
+
+        let notes: Vec<(String,)> = sqlx::query_as("SELECT flds FROM notes WHERE mid = ?")
+            .bind(note_type_id)
+            .fetch_all(&pool)
+            .await?;
+
+        // 笔记字段之间用 0x1f 分隔
+        Ok(notes
+            .into_iter()
+            .filter_map(|(fields,)| fields.split('\x1f').nth(field_index).map(|v| v.to_string()))
+            .collect())
+    }
+    .await;
+
+    pool.close().await;
+    result
+}
+
+// 查找笔记类型ID和字段序号：Anki 2.1.28 以后使用 notetypes / fields 表，旧版本存在 col.models JSON 中
+async fn find_note_type_field(pool: &SqlitePool, note_type: &str, field: &str) -> Result<(i64, usize)> {
+    let has_notetypes_table: Option<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'notetypes'"
+    ).fetch_optional(pool).await?;
+
+    if has_notetypes_table.is_some() {
+        let note_types: Vec<(i64, String)> = sqlx::query_as("SELECT id, name FROM notetypes")
+            .fetch_all(pool)
+            .await?;
+        let (id, _) = note_types
+            .iter()
+            .find(|(_, name)| name == note_type)
+            .ok_or_else(|| not_found_note_type(note_type, note_types.iter().map(|(_, n)| n.as_str())))?;
+
+        let fields: Vec<(i64, String)> = sqlx::query_as("SELECT ord, name FROM fields WHERE ntid = ? ORDER BY ord")
+            .bind(id)
+            .fetch_all(pool)
+            .await?;
+        let (ord, _) = fields
+            .iter()
+            .find(|(_, name)| name == field)
+            .ok_or_else(|| not_found_field(field, fields.iter().map(|(_, n)| n.as_str())))?;
+
+        return Ok((*id, *ord as usize));
+    }
+
+    let (models,): (String,) = sqlx::query_as("SELECT models FROM col LIMIT 1")
+        .fetch_one(pool)
+        .await?;
+    let models: Value = serde_json::from_str(&models)
+        .map_err(|e| anyhow::anyhow!("解析 Anki 笔记类型失败: {}", e))?;
+    let models = models
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("Anki 笔记类型格式不正确"))?;
+
+    let model = models
+        .values()
+        .find(|m| m["name"].as_str() == Some(note_type))
+        .ok_or_else(|| not_found_note_type(note_type, models.values().filter_map(|m| m["name"].as_str())))?;
+    let id = model["id"]
+        .as_i64()
+        .or_else(|| model["id"].as_str().and_then(|s| s.parse().ok()))
+        .ok_or_else(|| anyhow::anyhow!("笔记类型 {} 缺少ID", note_type))?;
+
+    let field_names: Vec<&str> = model["flds"]
+        .as_array()
+        .map(|fields| fields.iter().filter_map(|f| f["name"].as_str()).collect())
+        .unwrap_or_default();
+    let index = field_names
+        .iter()
+        .position(|name| *name == field)
+        .ok_or_else(|| not_found_field(field, field_names.iter().copied()))?;
+
+    Ok((id, index))
+}
+
+fn not_found_note_type<'a>(note_type: &str, available: impl Iterator<Item = &'a str>) -> anyhow::Error {
+    anyhow::anyhow!("未找到笔记类型 {}，可用的笔记类型: {}", note_type, available.collect::<Vec<_>>().join("、"))
+}
+
+fn not_found_field<'a>(field: &str, available: impl Iterator<Item = &'a str>) -> anyhow::Error {
+    anyhow::anyhow!("未找到字段 {}，可用的字段: {}", field, available.collect::<Vec<_>>().join("、"))
+}
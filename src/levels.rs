@@ -0,0 +1,227 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::config::LevelsConfig;
+use crate::grammar::grammar_key;
+
+/// 从本地文件加载的词频表和 JLPT 等级表
+#[derive(Debug, Default)]
+pub struct LevelLists {
+    word_frequency: HashMap<String, i64>,
+    word_jlpt: HashMap<String, String>,
+    grammar_frequency: HashMap<String, i64>,
+    grammar_jlpt: HashMap<String, String>,
+}
+
+impl LevelLists {
+    /// 加载配置中的所有列表，未配置的列表为空
+    pub fn load(config: &LevelsConfig) -> Result<Self> {
+        Ok(LevelLists {
+            word_frequency: load_optional(&config.word_frequency_file, |c| read_frequency_list(c, str::to_string))?,
+            word_jlpt: load_optional(&config.word_jlpt_file, |c| read_jlpt_list(c, str::to_string))?,
+            grammar_frequency: load_optional(&config.grammar_frequency_file, |c| read_frequency_list(c, grammar_key))?,
+            grammar_jlpt: load_optional(&config.grammar_jlpt_file, |c| read_jlpt_list(c, grammar_key))?,
+        })
+    }
+
+    /// 是否配置了任何列表
+    pub fn is_empty(&self) -> bool {
+        self.word_frequency.is_empty()
+            && self.word_jlpt.is_empty()
+            && self.grammar_frequency.is_empty()
+            && self.grammar_jlpt.is_empty()
+    }
+
+    /// 查询单词的词频排名和 JLPT 等级，先按单词查找，找不到时按假名查找
+    pub fn word_levels(&self, word: &str, kana: &str) -> (Option<i64>, Option<String>) {
+        let rank = self.word_frequency.get(word).or_else(|| self.word_frequency.get(kana)).copied();
+        let level = self.word_jlpt.get(word).or_else(|| self.word_jlpt.get(kana)).cloned();
+        (rank, level)
+    }
+
+    /// 查询语法的词频排名和 JLPT 等级（按规范写法比较，忽略 〜 和 V/N 等占位写法）
+    pub fn grammar_levels(&self, grammar: &str) -> (Option<i64>, Option<String>) {
+        let key = grammar_key(grammar);
+        (self.grammar_frequency.get(&key).copied(), self.grammar_jlpt.get(&key).cloned())
+    }
+}
+
+/// 将 "n3"、"3"、"JLPT N3" 等写法统一为 "N3"
+pub fn normalize_jlpt_level(value: &str) -> Option<String> {
+    let digits: String = value.chars().filter(|c| c.is_ascii_digit()).collect();
+    match digits.parse::<u8>() {
+        Ok(level @ 1..=5) => Some(format!("N{}", level)),
+        _ => None,
+    }
+}
+
+/// 词频排名所属的区间标签
+fn frequency_bucket(rank: i64) -> &'static str {
+    match rank {
+        ..=1000 => "1k",
+        1001..=2000 => "2k",
+        2001..=5000 => "5k",
+        5001..=10000 => "10k",
+        10001..=20000 => "20k",
+        _ => "20k+",
+    }
+}
+
+/// 生成卡片标签：JLPT 等级、词频区间和条目自带的标签
+pub fn entry_tags(frequency_rank: Option<i64>, jlpt_level: Option<&str>, tags: &str) -> String {
+    let mut parts = Vec::new();
+    if let Some(level) = jlpt_level {
+        parts.push(format!("JLPT::{}", level));
+    }
+    if let Some(rank) = frequency_rank {
+        parts.push(format!("频率::{}", frequency_bucket(rank)));
+    }
+    if !tags.trim().is_empty() {
+        parts.push(tags.trim().to_string());
+    }
+    parts.join(" ")
+}
+
+/// 检查条目是否满足配置中的词频和 JLPT 等级筛选条件
+pub fn passes_filter(config: &LevelsConfig, frequency_rank: Option<i64>, jlpt_level: Option<&str>) -> bool {
+    if config.min_frequency_rank.is_some() || config.max_frequency_rank.is_some() {
+        let Some(rank) = frequency_rank else {
+            return false;
+        };
+        if config.min_frequency_rank.is_some_and(|min| rank < min)
+            || config.max_frequency_rank.is_some_and(|max| rank > max)
+        {
+            return false;
+        }
+    }
+
+    if !config.jlpt_levels.is_empty() {
+        let Some(level) = jlpt_level else {
+            return false;
+        };
+        if !config.jlpt_levels.iter().any(|l| normalize_jlpt_level(l).as_deref() == Some(level)) {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn load_optional<T>(path: &Option<String>, parse: impl Fn(&str) -> HashMap<String, T>) -> Result<HashMap<String, T>> {
+    match path {
+        Some(path) => {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("无法读取列表文件 {}: {}", path, e))?;
+            Ok(parse(&content))
+        }
+        None => Ok(HashMap::new()),
+    }
+}
+
+// 按制表符或逗号切分一行，忽略空行和 # 注释
+fn split_lines(content: &str) -> impl Iterator<Item = Vec<&str>> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let separator = if line.contains('\t') { '\t' } else { ',' };
+            line.split(separator).map(str::trim).collect()
+        })
+}
+
+// 词频表：每行一个单词，按频率从高到低排列；也可以写成 "单词<TAB>排名"
+fn read_frequency_list(content: &str, key: impl Fn(&str) -> String) -> HashMap<String, i64> {
+    let mut ranks = HashMap::new();
+    for (index, columns) in split_lines(content).enumerate() {
+        let rank = columns
+            .get(1)
+            .and_then(|c| c.parse::<i64>().ok())
+            .unwrap_or(index as i64 + 1);
+        ranks.entry(key(columns[0])).or_insert(rank);
+    }
+    ranks
+}
+
+// JLPT 等级表：最后一列为等级，前面各列（单词、假名）都作为查询键
+fn read_jlpt_list(content: &str, key: impl Fn(&str) -> String) -> HashMap<String, String> {
+    let mut levels = HashMap::new();
+    for columns in split_lines(content) {
+        let Some((level, names)) = columns.split_last() else {
+            continue;
+        };
+        let Some(level) = normalize_jlpt_level(level) else {
+            continue;
+        };
+        for name in names.iter().filter(|n| !n.is_empty()) {
+            levels.entry(key(name)).or_insert_with(|| level.clone());
+        }
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lists() -> LevelLists {
+        LevelLists {
+            word_frequency: read_frequency_list("# 词频表\nの\n食べる\nたべる\n\n今日\t12\n食べる\n", str::to_string),
+            word_jlpt: read_jlpt_list("食べる,たべる,N5\n今日,きょう,jlpt n5\n勉強\t4\n難しい,N0\n", str::to_string),
+            grammar_frequency: read_frequency_list("〜ている\nVてしまう\n", grammar_key),
+            grammar_jlpt: read_jlpt_list("～ている,N5\nNにとって,N3\n", grammar_key),
+        }
+    }
+
+    #[test]
+    fn words_are_looked_up_by_word_then_kana() {
+        let lists = lists();
+        assert_eq!(lists.word_levels("食べる", "たべる"), (Some(2), Some("N5".to_string())));
+        // 排名按行号计算，跳过空行和注释，重复的单词保留第一次出现的排名
+        assert_eq!(lists.word_levels("今日", "きょう"), (Some(12), Some("N5".to_string())));
+        assert_eq!(lists.word_levels("喰べる", "たべる"), (Some(3), Some("N5".to_string())));
+        assert_eq!(lists.word_levels("勉強", "べんきょう"), (None, Some("N4".to_string())));
+        assert_eq!(lists.word_levels("難しい", "むずかしい"), (None, None));
+        assert!(!lists.is_empty());
+        assert!(LevelLists::default().is_empty());
+    }
+
+    #[test]
+    fn grammar_is_looked_up_by_canonical_form() {
+        let lists = lists();
+        assert_eq!(lists.grammar_levels("ていた"), (Some(1), Some("N5".to_string())));
+        assert_eq!(lists.grammar_levels("〜てしまう"), (Some(2), None));
+        assert_eq!(lists.grammar_levels("〜にとって"), (None, Some("N3".to_string())));
+        assert_eq!(lists.grammar_levels("〜ために"), (None, None));
+    }
+
+    #[test]
+    fn jlpt_levels_are_normalized() {
+        for value in ["N3", "n3", "3", "JLPT N3", " N 3 "] {
+            assert_eq!(normalize_jlpt_level(value).as_deref(), Some("N3"), "{}", value);
+        }
+        for value in ["N0", "N6", "", "N", "N10"] {
+            assert_eq!(normalize_jlpt_level(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn tags_and_filters_use_levels() {
+        assert_eq!(entry_tags(Some(1500), Some("N4"), " 动物 "), "JLPT::N4 频率::2k 动物");
+        assert_eq!(entry_tags(Some(1000), None, ""), "频率::1k");
+        assert_eq!(entry_tags(Some(20001), None, ""), "频率::20k+");
+        assert_eq!(entry_tags(None, None, ""), "");
+
+        let config = LevelsConfig {
+            max_frequency_rank: Some(5000),
+            jlpt_levels: vec!["n5".to_string(), "N4".to_string()],
+            ..Default::default()
+        };
+        assert!(passes_filter(&config, Some(5000), Some("N5")));
+        assert!(!passes_filter(&config, Some(5001), Some("N5")));
+        assert!(!passes_filter(&config, None, Some("N4")));
+        assert!(!passes_filter(&config, Some(10), Some("N3")));
+        assert!(!passes_filter(&config, Some(10), None));
+        assert!(passes_filter(&LevelsConfig::default(), None, None));
+    }
+}
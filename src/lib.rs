@@ -1,9 +1,29 @@
 pub mod config;
 pub mod models;
 pub mod api;
+pub mod rate_limit;
+pub mod scheduler;
 pub mod database;
+pub mod storage;
+#[cfg(feature = "postgres")]
+pub mod postgres;
 pub mod analyzer;
 pub mod tts;
+pub mod editor;
+pub mod text;
+pub mod export;
+pub mod known_words;
+pub mod levels;
+pub mod study_plan;
+pub mod fsrs;
+pub mod review;
+pub mod kanji;
+pub mod conjugation;
+pub mod pos;
+pub mod grammar;
+pub mod prompts;
+pub mod html;
+pub mod sentences;
 
 pub use config::Config;
 pub use models::*;
@@ -1,5 +1,7 @@
 use anyhow::Result;
-use anki_creator::{Config, AnkiCreator};
+use anki_creator::known_words::KnownWordSource;
+use anki_creator::prompts::PromptLibrary;
+use anki_creator::{Config, AnkiCreator, ConflictPolicy, EntryKind, EntryStatus, LockField, SearchHit, SearchQuery};
 
 // 显示主菜单并获取用户选择
 fn show_menu() -> Result<i32> {
@@ -12,8 +14,26 @@ fn show_menu() -> Result<i32> {
     println!("5. 更新所有单词解析");
     println!("6. 根据ID更新单词解析");
     println!("7. 增量生成音频文件");
+    println!("8. 手动编辑条目字段");
+    println!("9. 锁定/解锁条目字段");
+    println!("10. 搜索词库");
+    println!("11. 导出知识库 (JSON/NDJSON)");
+    println!("12. 导入知识库 (JSON/NDJSON)");
+    println!("13. 设置条目学习状态");
+    println!("14. 导入已掌握单词 (单词表/CSV/Anki)");
+    println!("15. 更新词频和JLPT等级");
+    println!("16. 复习 (终端)");
+    println!("17. 导出复习记录 (Anki revlog CSV)");
+    println!("18. 更新汉字库并生成汉字卡片");
+    println!("19. 词性统计");
+    println!("20. 提示词模板 (列出/校验)");
+    println!("21. 重新分析旧版提示词生成或未通过校验的解析");
+    println!("22. 校验已有解析 (清理格式/标记问题解析)");
+    println!("23. 更新例句库并生成例句卡片");
+    println!("24. 查看包含某个单词/语法的例句");
+    println!("25. 用审查模型检查已有解析");
     println!("0. 退出程序");
-    print!("请输入选项 (0-7): ");
+    print!("请输入选项 (0-25): ");
     
     use std::io::{self, Write};
     io::stdout().flush()?;
@@ -25,10 +45,182 @@ fn show_menu() -> Result<i32> {
     Ok(choice)
 }
 
+// 读取一行用户输入
+fn read_input(prompt: &str) -> Result<String> {
+    use std::io::{self, Write};
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+// 选择要编辑或锁定的条目和字段
+fn select_entry_field() -> Result<Option<(EntryKind, i64, LockField)>> {
+    let kind = match read_input("条目类型 (1. 单词 2. 语法): ")?.as_str() {
+        "1" => EntryKind::Word,
+        "2" => EntryKind::Grammar,
+        _ => {
+            println!("❌ 无效的条目类型");
+            return Ok(None);
+        }
+    };
+    
+    let id = match read_input(&format!("请输入{}ID: ", kind.label()))?.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => {
+            println!("❌ 无效的ID，请输入一个有效的数字");
+            return Ok(None);
+        }
+    };
+    
+    let fields: Vec<LockField> = LockField::ALL.iter().copied().filter(|f| f.applies_to(kind)).collect();
+    let options: Vec<String> = fields.iter().enumerate()
+        .map(|(i, f)| format!("{}. {}", i + 1, f.label()))
+        .collect();
+    let field = match read_input(&format!("字段 ({}): ", options.join(" ")))?.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= fields.len() => fields[n - 1],
+        _ => {
+            println!("❌ 无效的字段");
+            return Ok(None);
+        }
+    };
+    
+    Ok(Some((kind, id, field)))
+}
+
+// 根据文件扩展名选择已掌握单词的来源
+fn read_known_word_source() -> Result<Option<KnownWordSource>> {
+    let path = read_input("文件路径（.txt 单词表 / .csv / .tsv / collection.anki2 / .apkg）: ")?;
+    if path.is_empty() {
+        println!("❌ 文件路径不能为空");
+        return Ok(None);
+    }
+    
+    let lower = path.to_lowercase();
+    let source = if lower.ends_with(".anki2") || lower.ends_with(".anki21") || lower.ends_with(".apkg") {
+        let note_type = read_input("笔记类型名称: ")?;
+        let field = read_input("单词所在字段名称: ")?;
+        KnownWordSource::Anki { path, note_type, field }
+    } else if lower.ends_with(".csv") || lower.ends_with(".tsv") {
+        let column = match read_input("单词所在列（从 1 开始，默认 1）: ")?.parse::<usize>() {
+            Ok(n) if n >= 1 => n - 1,
+            _ => 0,
+        };
+        let has_header = read_input("第一行是否为表头？(y/N): ")?.to_lowercase() == "y";
+        KnownWordSource::Csv { path, column, has_header }
+    } else {
+        KnownWordSource::WordList { path }
+    };
+    
+    Ok(Some(source))
+}
+
+// 读取可选的过滤条件，直接回车表示不过滤
+fn read_optional(prompt: &str) -> Result<Option<String>> {
+    let input = read_input(prompt)?;
+    Ok(if input.is_empty() { None } else { Some(input) })
+}
+
+// 读取搜索关键词和过滤条件
+fn read_search_query() -> Result<SearchQuery> {
+    let text = read_input("请输入关键词（单词/假名/罗马字/词性/解析内容）: ")?;
+    println!("以下过滤条件可直接回车跳过：");
+    let kind = match read_input("条目类型 (1. 单词 2. 语法): ")?.as_str() {
+        "1" => Some(EntryKind::Word),
+        "2" => Some(EntryKind::Grammar),
+        _ => None,
+    };
+    
+    Ok(SearchQuery {
+        text,
+        kind,
+        part_of_speech: read_optional("词性: ")?,
+        date_from: read_optional("更新日期起 (YYYY-MM-DD): ")?,
+        date_to: read_optional("更新日期止 (YYYY-MM-DD): ")?,
+        document: read_optional("来源文档: ")?,
+        tag: read_optional("标签: ")?,
+        limit: read_input("最多显示条数 (默认 50): ")?.parse().unwrap_or(50),
+    })
+}
+
+// 以表格形式输出搜索结果
+fn print_search_table(hits: &[SearchHit]) {
+    println!("{:<6} {:<6} {:<12} {:<12} {:<16} {:<12} 摘要", "类型", "ID", "单词", "假名", "罗马字", "词性");
+    for hit in hits {
+        println!("{:<6} {:<6} {:<12} {:<12} {:<16} {:<12} {}",
+            hit.kind.label(), hit.id, hit.word, hit.kana, hit.romaji, hit.part_of_speech, hit.snippet);
+    }
+}
+
+// 列出提示词模板并校验模板目录中的文件
+fn report_prompt_templates(dir: &str) -> Result<()> {
+    match PromptLibrary::load(dir) {
+        Ok(library) => {
+            println!("📝 当前使用的提示词模板（模板目录: {}）：", dir);
+            for template in library.templates() {
+                let language = template.language.map(|l| l.code()).unwrap_or("-");
+                let source = template.path.as_ref()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_else(|| "内置".to_string());
+                println!("  {:<18} {:<3} v{:<3} {}", template.kind.key(), language, template.version, source);
+            }
+        },
+        Err(e) => println!("❌ {}", e),
+    }
+
+    let checked = PromptLibrary::check_dir(dir)?;
+    if checked.is_empty() {
+        println!("ℹ️  模板目录中没有模板文件，全部使用内置模板");
+        return Ok(());
+    }
+
+    println!("🔍 校验模板目录中的 {} 个文件：", checked.len());
+    let mut invalid = 0;
+    for (path, template) in &checked {
+        match template {
+            Ok(template) => println!("  ✅ {} (v{})", path.display(), template.version),
+            Err(e) => {
+                invalid += 1;
+                println!("  ❌ {}: {}", path.display(), e);
+            }
+        }
+    }
+    if invalid > 0 {
+        println!("⚠️  {} 个模板文件无效，修正前程序无法启动", invalid);
+    } else {
+        println!("✅ 所有模板文件有效，修改后重新启动程序生效");
+    }
+    Ok(())
+}
+
+// 把内置模板写入模板目录，作为自定义模板的起点
+fn write_builtin_prompts(dir: &str) -> Result<()> {
+    let written = PromptLibrary::write_builtin(dir)?;
+    if written.is_empty() {
+        println!("ℹ️  模板目录 {} 中已有所有模板文件，没有写入新文件", dir);
+        return Ok(());
+    }
+    for path in &written {
+        println!("  📄 {}", path.display());
+    }
+    println!("✅ 写入了 {} 个模板文件，修改模板内容后请递增文件头中的版本号", written.len());
+    Ok(())
+}
+
+// 退出前打印本次运行的请求用量
+async fn say_goodbye(creator: &AnkiCreator) {
+    if let Err(e) = creator.print_usage_summary().await {
+        println!("⚠️  读取请求用量失败: {}", e);
+    }
+    println!("👋 再见！");
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // 加载配置文件
-    let config = Config::load().map_err(|e| {
+    let mut config = Config::load().map_err(|e| {
         println!("❌ 配置文件加载失败: {}", e);
         println!("💡 请确保 config.toml 文件存在并包含必要的配置");
         e
@@ -37,12 +229,40 @@ async fn main() -> Result<()> {
     println!("✅ 配置文件加载成功");
     println!("   并发请求数: {}", config.processing.concurrent_requests);
     println!("   数据库文件: {}", config.database.db_file);
+    println!("   当前学习者: {}", config.user.name);
+
+    // `--no-cache` 本次运行忽略已缓存的模型回复（新的回复仍会写入缓存）
+    let args: Vec<String> = std::env::args().skip(1).filter(|a| a != "--no-cache").collect();
+    if args.len() + 1 < std::env::args().count() {
+        config.cache.bypass = true;
+        println!("   请求缓存: 本次运行不使用");
+    }
+
+    // `anki-creator prompts` 列出并校验提示词模板，`anki-creator prompts init` 写出内置模板
+    if args.first().map(String::as_str) == Some("prompts") {
+        return match args.get(1).map(String::as_str) {
+            Some("init") => write_builtin_prompts(&config.prompts.dir),
+            _ => report_prompt_templates(&config.prompts.dir),
+        };
+    }
 
     // 创建 Anki 卡片生成器
     let creator = AnkiCreator::new(config).await?;
 
+    // `anki-creator review` 直接进入复习模式
+    if args.first().map(String::as_str) == Some("review") {
+        return creator.run_review_session().await;
+    }
+
     loop {
-        match show_menu()? {
+        // 在菜单按 Ctrl-C 与选择 0 相同
+        let choice = tokio::select! {
+            biased;
+            _ = creator.wait_for_exit() => 0,
+            choice = tokio::task::spawn_blocking(show_menu) => choice??,
+        };
+        creator.reset_cancellation();
+        match choice {
             1 => {
                 // 解析单词
                 println!("\n📖 读取输入文件: {}", creator.config.input.text_file);
@@ -246,24 +466,249 @@ async fn main() -> Result<()> {
                     }
                 }
             },
+            8 => {
+                // 手动编辑条目字段
+                println!("\n✏️  手动编辑条目字段...");
+                if let Some((kind, id, field)) = select_entry_field()?
+                    && let Err(e) = creator.edit_entry_field(kind, id, field).await
+                {
+                    println!("❌ 编辑失败: {}", e);
+                }
+            },
+            9 => {
+                // 锁定/解锁条目字段
+                println!("\n🔒 锁定/解锁条目字段...");
+                if let Some((kind, id, field)) = select_entry_field()? {
+                    let locked = match read_input("操作 (1. 锁定 2. 解锁): ")?.as_str() {
+                        "1" => true,
+                        "2" => false,
+                        _ => {
+                            println!("❌ 无效的操作");
+                            continue;
+                        }
+                    };
+                    if let Err(e) = creator.set_entry_lock(kind, id, field, locked).await {
+                        println!("❌ 设置锁定失败: {}", e);
+                    }
+                }
+            },
+            10 => {
+                // 搜索词库
+                println!("\n🔍 搜索词库...");
+                let query = read_search_query()?;
+                let as_json = read_input("输出格式 (1. 表格 2. JSON，默认表格): ")? == "2";
+                
+                match creator.search(&query).await {
+                    Ok(hits) => {
+                        if as_json {
+                            println!("{}", serde_json::to_string_pretty(&hits)?);
+                        } else if hits.is_empty() {
+                            println!("⚠️  没有找到匹配的条目");
+                        } else {
+                            print_search_table(&hits);
+                            println!("📊 共找到 {} 个条目", hits.len());
+                        }
+                    },
+                    Err(e) => println!("❌ 搜索失败: {}", e),
+                }
+            },
+            11 => {
+                // 导出知识库
+                println!("\n📤 导出知识库...");
+                let path = read_input("导出文件路径（.json 或 .ndjson，默认 knowledge_base.json）: ")?;
+                let path = if path.is_empty() { "knowledge_base.json".to_string() } else { path };
+                if let Err(e) = creator.export_knowledge_base(&path).await {
+                    println!("❌ 导出失败: {}", e);
+                }
+            },
+            12 => {
+                // 导入知识库
+                println!("\n📥 导入知识库...");
+                let path = read_input("导入文件路径（.json 或 .ndjson）: ")?;
+                let policy = match read_input("冲突处理 (1. 保留现有 2. 覆盖 3. 保留较新的，默认 1): ")?.as_str() {
+                    "2" => ConflictPolicy::Overwrite,
+                    "3" => ConflictPolicy::KeepNewer,
+                    _ => ConflictPolicy::KeepExisting,
+                };
+                if let Err(e) = creator.import_knowledge_base(&path, policy).await {
+                    println!("❌ 导入失败: {}", e);
+                }
+            },
+            13 => {
+                // 设置当前学习者的条目学习状态
+                println!("\n🎓 设置 {} 的条目学习状态...", creator.user_name());
+                let kind = match read_input("条目类型 (1. 单词 2. 语法): ")?.as_str() {
+                    "1" => EntryKind::Word,
+                    "2" => EntryKind::Grammar,
+                    _ => {
+                        println!("❌ 无效的条目类型");
+                        continue;
+                    }
+                };
+                let id = match read_input(&format!("请输入{}ID: ", kind.label()))?.parse::<i64>() {
+                    Ok(id) => id,
+                    Err(_) => {
+                        println!("❌ 无效的ID，请输入一个有效的数字");
+                        continue;
+                    }
+                };
+                let options: Vec<String> = EntryStatus::ALL.iter().enumerate()
+                    .map(|(i, s)| format!("{}. {}", i + 1, s.label()))
+                    .collect();
+                let status = match read_input(&format!("学习状态 ({}): ", options.join(" ")))?.parse::<usize>() {
+                    Ok(n) if n >= 1 && n <= EntryStatus::ALL.len() => EntryStatus::ALL[n - 1],
+                    _ => {
+                        println!("❌ 无效的学习状态");
+                        continue;
+                    }
+                };
+                if let Err(e) = creator.set_entry_status(kind, id, status).await {
+                    println!("❌ 设置学习状态失败: {}", e);
+                }
+            },
+            14 => {
+                // 导入已掌握单词
+                println!("\n📚 导入 {} 已掌握的单词...", creator.user_name());
+                if let Some(source) = read_known_word_source()?
+                    && let Err(e) = creator.import_known_words(&source).await
+                {
+                    println!("❌ 导入已掌握单词失败: {}", e);
+                }
+            },
+            15 => {
+                // 根据本地列表更新词频和 JLPT 等级
+                println!("\n🏷️  更新词频和JLPT等级...");
+                match creator.annotate_levels().await {
+                    Ok(0) => println!("ℹ️  没有需要更新的条目（未配置 [levels] 列表文件时不会标注）"),
+                    Ok(count) => println!("✅ 更新了 {} 个条目的词频和 JLPT 等级，重新生成卡片后生效", count),
+                    Err(e) => println!("❌ 更新词频和JLPT等级失败: {}", e),
+                }
+            },
+            16 => {
+                // 终端复习
+                println!("\n🧠 开始复习...");
+                if let Err(e) = creator.run_review_session().await {
+                    println!("❌ 复习时出错: {}", e);
+                }
+            },
+            17 => {
+                // 导出复习记录
+                println!("\n📤 导出复习记录...");
+                let path = read_input("导出文件路径（默认 revlog.csv）: ")?;
+                let path = if path.is_empty() { "revlog.csv".to_string() } else { path };
+                if let Err(e) = creator.export_review_log(&path).await {
+                    println!("❌ 导出复习记录失败: {}", e);
+                }
+            },
+            18 => {
+                // 更新汉字库并生成汉字卡片
+                println!("\n🈶 更新汉字库...");
+                let generate_mnemonics = read_input("是否用 AI 为还没有记忆口诀的汉字生成口诀？(y/N): ")?.to_lowercase() == "y";
+                if let Err(e) = creator.update_kanji(generate_mnemonics).await {
+                    println!("❌ 更新汉字库失败: {}", e);
+                }
+            },
+            19 => {
+                // 词性统计和无法识别的词性标签
+                println!("\n🏷️  词性统计...");
+                if let Err(e) = creator.report_parts_of_speech().await {
+                    println!("❌ 词性统计失败: {}", e);
+                }
+            },
+            20 => {
+                // 列出并校验提示词模板
+                println!();
+                if let Err(e) = report_prompt_templates(&creator.config.prompts.dir) {
+                    println!("❌ 校验提示词模板失败: {}", e);
+                }
+                if read_input("是否将内置模板写入模板目录（不覆盖已有文件）？(y/N): ")?.to_lowercase() == "y"
+                    && let Err(e) = write_builtin_prompts(&creator.config.prompts.dir)
+                {
+                    println!("❌ 写入模板文件失败: {}", e);
+                }
+            },
+            21 => {
+                // 重新分析旧版提示词生成或未通过校验的解析
+                println!();
+                if let Err(e) = creator.reanalyze_outdated().await {
+                    println!("❌ 重新分析失败: {}", e);
+                }
+            },
+            22 => {
+                // 清理并校验已保存的解析
+                println!();
+                if let Err(e) = creator.validate_existing_analyses().await {
+                    println!("❌ 校验解析失败: {}", e);
+                }
+            },
+            23 => {
+                // 更新例句库并生成例句卡片
+                println!("\n📝 更新例句库...");
+                let translate = read_input("是否用 AI 为缺少读音或翻译的例句补全？(y/N): ")?.to_lowercase() == "y";
+                if let Err(e) = creator.update_sentences(translate).await {
+                    println!("❌ 更新例句库失败: {}", e);
+                }
+            },
+            24 => {
+                // 查看包含某个单词或语法的例句
+                let kind = match read_input("条目类型 (1. 单词 2. 语法): ")?.as_str() {
+                    "1" => EntryKind::Word,
+                    "2" => EntryKind::Grammar,
+                    _ => {
+                        println!("❌ 无效的条目类型");
+                        continue;
+                    }
+                };
+                let query = read_input(&format!("请输入{}ID或{}本身: ", kind.label(), kind.label()))?;
+                if let Err(e) = creator.show_sentences_for_entry(kind, &query).await {
+                    println!("❌ 查看例句失败: {}", e);
+                }
+            },
+            25 => {
+                // 用审查模型检查还没有审查结果的解析
+                println!();
+                if let Err(e) = creator.review_existing_analyses().await {
+                    println!("❌ 审查解析失败: {}", e);
+                }
+            },
             0 => {
-                println!("👋 再见！");
+                say_goodbye(&creator).await;
                 break;
             },
             _ => {
-                println!("❌ 无效选项，请输入 0-7 之间的数字");
+                println!("❌ 无效选项，请输入 0-25 之间的数字");
             }
         }
         
+        // 操作中按了 Ctrl-C 时保存完结果后退出
+        if creator.exit_requested() {
+            say_goodbye(&creator).await;
+            break;
+        }
+        
         println!("\n📋 使用说明：");
         println!("1. 在 Anki 中导入 CSV 文件");
         println!("2. 确保字段映射正确（ID 字段用于更新现有卡片）");
         println!("3. 单词和语法会创建为不同的卡组");
         
         println!("\n按 Enter 键继续...");
-        let mut _input = String::new();
-        std::io::stdin().read_line(&mut _input)?;
+        let wait_enter = tokio::task::spawn_blocking(|| {
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input).map(|_| ())
+        });
+        tokio::select! {
+            biased;
+            _ = creator.wait_for_exit() => {
+                say_goodbye(&creator).await;
+                break;
+            },
+            read = wait_enter => read??,
+        }
     }
     
+    // 按 Ctrl-C 退出时，读取标准输入的线程可能仍在阻塞，直接结束进程而不等待它
+    if creator.exit_requested() {
+        std::process::exit(130);
+    }
     Ok(())
 }
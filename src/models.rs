@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::grammar::{ConnectionBase, GrammarRegister};
+
 // 数据结构定义
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
 pub struct JapaneseWord {
@@ -10,6 +12,17 @@ pub struct JapaneseWord {
     pub part_of_speech: String,
     pub analysis: String,
     pub updated_at: Option<String>, // 更新时间，使用Option以兼容旧数据
+    // 字段锁定标记：锁定的字段不会被批量更新覆盖
+    pub analysis_locked: bool,
+    pub pos_locked: bool,
+    pub pitch_locked: bool,
+    pub kana_locked: bool,
+    pub tags: String, // 附加的 Anki 标签，空格分隔
+    // 根据本地词频表和 JLPT 等级表标注，未收录时为空
+    pub frequency_rank: Option<i64>,
+    pub jlpt_level: Option<String>,
+    // 生成解析所用提示词模板的版本，0 表示记录版本之前生成的解析
+    pub prompt_version: i64,
 }
 
 // 用于支持多词性合并的结构
@@ -29,6 +42,401 @@ pub struct JapaneseGrammar {
     pub word: String,
     pub kana: String,
     pub analysis: String,
+    // 语法只有假名和解析两个可锁定字段
+    pub analysis_locked: bool,
+    pub kana_locked: bool,
+    pub tags: String,
+    pub updated_at: Option<String>,
+    pub frequency_rank: Option<i64>,
+    pub jlpt_level: Option<String>,
+    pub prompt_version: i64,
+}
+
+// 条目类型：单词或语法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    Word,
+    Grammar,
+}
+
+impl EntryKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntryKind::Word => "单词",
+            EntryKind::Grammar => "语法",
+        }
+    }
+
+    // 在 occurrences、search_index 等表中存储的类型名
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::Word => "word",
+            EntryKind::Grammar => "grammar",
+        }
+    }
+
+    // 条目所在的数据表
+    pub fn table(&self) -> &'static str {
+        match self {
+            EntryKind::Word => "words",
+            EntryKind::Grammar => "grammar",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<EntryKind> {
+        match s {
+            "word" => Some(EntryKind::Word),
+            "grammar" => Some(EntryKind::Grammar),
+            _ => None,
+        }
+    }
+}
+
+// 学习者对单词/语法的学习状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryStatus {
+    New,
+    Learning,
+    Known,
+    Ignored,
+    Blacklisted,
+}
+
+impl EntryStatus {
+    pub const ALL: [EntryStatus; 5] = [
+        EntryStatus::New,
+        EntryStatus::Learning,
+        EntryStatus::Known,
+        EntryStatus::Ignored,
+        EntryStatus::Blacklisted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryStatus::New => "new",
+            EntryStatus::Learning => "learning",
+            EntryStatus::Known => "known",
+            EntryStatus::Ignored => "ignored",
+            EntryStatus::Blacklisted => "blacklisted",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<EntryStatus> {
+        EntryStatus::ALL.into_iter().find(|status| status.as_str() == s)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntryStatus::New => "新词",
+            EntryStatus::Learning => "学习中",
+            EntryStatus::Known => "已掌握",
+            EntryStatus::Ignored => "忽略",
+            EntryStatus::Blacklisted => "黑名单",
+        }
+    }
+
+    // 已掌握、忽略和黑名单的条目不再分析，也不生成卡片
+    pub fn is_excluded(&self) -> bool {
+        matches!(self, EntryStatus::Known | EntryStatus::Ignored | EntryStatus::Blacklisted)
+    }
+}
+
+// 汉字卡片（读音、意思、部件来自本地数据，记忆口诀由 LLM 生成）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Kanji {
+    pub id: i64,
+    pub character: String,
+    pub on_readings: String,  // 音读，、分隔
+    pub kun_readings: String, // 训读，、分隔
+    pub meanings: String,     // 意思，; 分隔
+    pub stroke_count: Option<i64>,
+    pub components: String,   // 部件，空格分隔
+    pub mnemonic: String,
+    pub updated_at: Option<String>,
+}
+
+// 例句库中的例句（来自解析中的例句和输入文本中的句子）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Sentence {
+    pub id: i64,
+    pub japanese: String,
+    pub reading: String,
+    pub translation: String,
+    pub source: String,           // example（解析中的例句）或 input（输入文本）
+    pub document: Option<String>, // 来自输入文本时的文档路径
+    pub updated_at: Option<String>,
+}
+
+// 例句的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SentenceSource {
+    Example,
+    Input,
+}
+
+impl SentenceSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SentenceSource::Example => "example",
+            SentenceSource::Input => "input",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SentenceSource::Example => "解析例句",
+            SentenceSource::Input => "输入文本",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<SentenceSource> {
+        match s {
+            "example" => Some(SentenceSource::Example),
+            "input" => Some(SentenceSource::Input),
+            _ => None,
+        }
+    }
+}
+
+// 从本地数据读取的汉字信息
+#[derive(Debug, Clone, Default)]
+pub struct KanjiInfo {
+    pub character: String,
+    pub on_readings: Vec<String>,
+    pub kun_readings: Vec<String>,
+    pub meanings: Vec<String>,
+    pub stroke_count: Option<i64>,
+    pub components: Vec<String>,
+}
+
+// 复习卡片的调度状态，取值与 Anki revlog 的 type 字段对应
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardState {
+    New,
+    Learning,
+    Review,
+    Relearning,
+}
+
+impl CardState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CardState::New => "new",
+            CardState::Learning => "learning",
+            CardState::Review => "review",
+            CardState::Relearning => "relearning",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<CardState> {
+        [CardState::New, CardState::Learning, CardState::Review, CardState::Relearning]
+            .into_iter()
+            .find(|state| state.as_str() == s)
+    }
+
+    // Anki revlog.type：0 学习，1 复习，2 重新学习
+    pub fn revlog_type(&self) -> i64 {
+        match self {
+            CardState::New | CardState::Learning => 0,
+            CardState::Review => 1,
+            CardState::Relearning => 2,
+        }
+    }
+}
+
+// 复习评分，数值与 Anki 的 ease 相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grade {
+    Again = 1,
+    Hard = 2,
+    Good = 3,
+    Easy = 4,
+}
+
+impl Grade {
+    pub const ALL: [Grade; 4] = [Grade::Again, Grade::Hard, Grade::Good, Grade::Easy];
+
+    pub fn from_number(n: u8) -> Option<Grade> {
+        Grade::ALL.into_iter().find(|g| *g as u8 == n)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Grade::Again => "重来",
+            Grade::Hard => "困难",
+            Grade::Good => "良好",
+            Grade::Easy => "简单",
+        }
+    }
+}
+
+// 某个学习者的一张复习卡片（FSRS 记忆状态）
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ReviewCard {
+    pub id: i64,
+    pub user_id: i64,
+    pub entry_kind: String,
+    pub entry_id: i64,
+    pub state: String,
+    pub due: String, // UTC，YYYY-MM-DD HH:MM:SS
+    pub stability: f64,
+    pub difficulty: f64,
+    pub reps: i64,
+    pub lapses: i64,
+    pub last_review: Option<String>,
+}
+
+impl ReviewCard {
+    pub fn state(&self) -> CardState {
+        CardState::parse(&self.state).unwrap_or(CardState::New)
+    }
+}
+
+// 一次复习记录，按 Anki revlog 的约定保存到 review_log
+#[derive(Debug, Clone)]
+pub struct ReviewLogEntry {
+    pub reviewed_at: String,
+    pub reviewed_at_ms: i64,
+    pub ease: i64,
+    pub ivl: i64,        // 正数为天，负数为秒
+    pub last_ivl: i64,
+    pub time_ms: i64,
+    pub review_type: i64,
+    pub elapsed_days: f64,
+}
+
+// 导出的复习记录，前几列与 Anki revlog 表一致
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RevlogRecord {
+    pub id: i64,       // 复习时间（毫秒时间戳）
+    pub cid: i64,      // 卡片ID
+    pub usn: i64,      // 固定为 -1（未同步）
+    pub ease: i64,     // 1-4
+    pub ivl: i64,      // 正数为天，负数为秒
+    #[serde(rename = "lastIvl")]
+    pub last_ivl: i64,
+    pub factor: i64,   // FSRS 不使用难度系数，固定为 0
+    pub time: i64,     // 用时（毫秒，最多 60000）
+    #[serde(rename = "type")]
+    pub review_type: i64,
+    pub entry_kind: String,
+    pub word: String,
+    pub stability: f64,
+    pub difficulty: f64,
+}
+
+// 词库搜索条件，除关键词外均为可选过滤条件
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    pub text: String,
+    pub kind: Option<EntryKind>,
+    pub part_of_speech: Option<String>,
+    pub date_from: Option<String>, // YYYY-MM-DD，按更新时间过滤
+    pub date_to: Option<String>,
+    pub document: Option<String>,
+    pub tag: Option<String>,
+    pub limit: u32,
+}
+
+// 搜索结果
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub kind: EntryKind,
+    pub id: i64,
+    pub word: String,
+    pub kana: String,
+    pub romaji: String,
+    pub part_of_speech: String,
+    pub snippet: String,
+    pub tags: String,
+    pub updated_at: Option<String>,
+}
+
+// 可手动编辑并锁定的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockField {
+    Analysis,
+    PartOfSpeech,
+    Pitch,
+    Kana,
+}
+
+impl LockField {
+    pub const ALL: [LockField; 4] = [
+        LockField::Analysis,
+        LockField::PartOfSpeech,
+        LockField::Pitch,
+        LockField::Kana,
+    ];
+
+    // 字段对应的数据列
+    pub fn value_column(&self) -> &'static str {
+        match self {
+            LockField::Analysis => "analysis",
+            LockField::PartOfSpeech => "part_of_speech",
+            LockField::Pitch => "pitch",
+            LockField::Kana => "kana",
+        }
+    }
+
+    // 字段对应的锁定标记列
+    pub fn lock_column(&self) -> &'static str {
+        match self {
+            LockField::Analysis => "analysis_locked",
+            LockField::PartOfSpeech => "pos_locked",
+            LockField::Pitch => "pitch_locked",
+            LockField::Kana => "kana_locked",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LockField::Analysis => "解析",
+            LockField::PartOfSpeech => "词性",
+            LockField::Pitch => "音调",
+            LockField::Kana => "假名",
+        }
+    }
+
+    // 该字段是否适用于指定的条目类型（语法没有词性和音调）
+    pub fn applies_to(&self, kind: EntryKind) -> bool {
+        match kind {
+            EntryKind::Word => true,
+            EntryKind::Grammar => matches!(self, LockField::Analysis | LockField::Kana),
+        }
+    }
+}
+
+impl JapaneseWord {
+    pub fn is_locked(&self, field: LockField) -> bool {
+        match field {
+            LockField::Analysis => self.analysis_locked,
+            LockField::PartOfSpeech => self.pos_locked,
+            LockField::Pitch => self.pitch_locked,
+            LockField::Kana => self.kana_locked,
+        }
+    }
+
+    pub fn field_value(&self, field: LockField) -> &str {
+        match field {
+            LockField::Analysis => &self.analysis,
+            LockField::PartOfSpeech => &self.part_of_speech,
+            LockField::Pitch => &self.pitch,
+            LockField::Kana => &self.kana,
+        }
+    }
+}
+
+impl JapaneseGrammar {
+    pub fn is_locked(&self, field: LockField) -> bool {
+        match field {
+            LockField::Analysis => self.analysis_locked,
+            LockField::Kana => self.kana_locked,
+            LockField::PartOfSpeech | LockField::Pitch => false,
+        }
+    }
 }
 
 // 第一步提取结构
@@ -52,6 +460,245 @@ pub struct BasicGrammarInfo {
     pub kana: String,
 }
 
+// 结构化的单词解析，卡片生成时渲染为 HTML
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct WordDetails {
+    pub core_meaning: String,
+    #[serde(default)]
+    pub senses: Vec<WordSense>,
+    // 词源或汉字说明
+    #[serde(default)]
+    pub etymology: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub comparisons: Vec<WordComparison>,
+}
+
+// 单词的一个义项
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct WordSense {
+    pub meaning: String,
+    #[serde(default)]
+    pub explanation: String,
+    #[serde(default)]
+    pub examples: Vec<ExampleSentence>,
+}
+
+// 例句，reading 为罗马字或假名读音，translation 按配置的翻译语言，可以为空
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ExampleSentence {
+    pub japanese: String,
+    #[serde(default)]
+    pub reading: String,
+    #[serde(default)]
+    pub translation: String,
+}
+
+// 审查模型发现的问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewIssueKind {
+    Reading,
+    Pitch,
+    PartOfSpeech,
+    Meaning,
+    Example,
+    #[serde(other)]
+    Other,
+}
+
+impl ReviewIssueKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReviewIssueKind::Reading => "读音错误",
+            ReviewIssueKind::Pitch => "音调错误",
+            ReviewIssueKind::PartOfSpeech => "词性错误",
+            ReviewIssueKind::Meaning => "释义错误",
+            ReviewIssueKind::Example => "例句有误",
+            ReviewIssueKind::Other => "其他问题",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewIssue {
+    pub kind: ReviewIssueKind,
+    #[serde(default)]
+    pub description: String,
+}
+
+// 审查模型对一份解析的审查结果，score 为 0-100
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalysisReview {
+    pub score: i64,
+    #[serde(default)]
+    pub issues: Vec<ReviewIssue>,
+}
+
+impl AnalysisReview {
+    /// 未通过审查的原因：分数低于 min_score，或发现了读音、音调、词性、释义、例句方面的错误
+    pub fn failure_reason(&self, min_score: i64) -> Option<String> {
+        let errors: Vec<String> = self.issues.iter()
+            .filter(|i| i.kind != ReviewIssueKind::Other)
+            .map(|i| format!("{}：{}", i.kind.label(), i.description))
+            .collect();
+        if self.score >= min_score && errors.is_empty() {
+            return None;
+        }
+        let mut reason = format!("审查 {} 分", self.score);
+        if !errors.is_empty() {
+            reason.push_str(&format!("，{}", errors.join("；")));
+        }
+        Some(reason)
+    }
+}
+
+// 缓存的模型回复，key 为请求内容的哈希
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub key: String,
+    pub provider: String,
+    pub model: String,
+    pub temperature: f64,
+    pub max_tokens: i64,
+    pub response: String,
+}
+
+// 模型请求的用途，记录在 api_calls 表中
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiOperation {
+    Extraction,
+    WordAnalysis,
+    PartOfSpeech,
+    GrammarAnalysis,
+    AnalysisReview,
+    KanjiMnemonic,
+    SentenceTranslation,
+}
+
+impl ApiOperation {
+    const ALL: [ApiOperation; 7] = [
+        ApiOperation::Extraction,
+        ApiOperation::WordAnalysis,
+        ApiOperation::PartOfSpeech,
+        ApiOperation::GrammarAnalysis,
+        ApiOperation::AnalysisReview,
+        ApiOperation::KanjiMnemonic,
+        ApiOperation::SentenceTranslation,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ApiOperation::Extraction => "提取单词和语法",
+            ApiOperation::WordAnalysis => "单词解析",
+            ApiOperation::PartOfSpeech => "更新词性",
+            ApiOperation::GrammarAnalysis => "语法解析",
+            ApiOperation::AnalysisReview => "解析审查",
+            ApiOperation::KanjiMnemonic => "汉字记忆口诀",
+            ApiOperation::SentenceTranslation => "例句翻译",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiOperation::Extraction => "extraction",
+            ApiOperation::WordAnalysis => "word_analysis",
+            ApiOperation::PartOfSpeech => "part_of_speech",
+            ApiOperation::GrammarAnalysis => "grammar_analysis",
+            ApiOperation::AnalysisReview => "analysis_review",
+            ApiOperation::KanjiMnemonic => "kanji_mnemonic",
+            ApiOperation::SentenceTranslation => "sentence_translation",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<ApiOperation> {
+        ApiOperation::ALL.into_iter().find(|op| op.as_str() == s)
+    }
+
+    /// 配置中没有指定时的 max_tokens
+    pub fn default_max_tokens(&self) -> u32 {
+        match self {
+            ApiOperation::Extraction => 32000,
+            ApiOperation::WordAnalysis | ApiOperation::GrammarAnalysis => 16000,
+            ApiOperation::SentenceTranslation => 16000,
+            ApiOperation::AnalysisReview => 4000,
+            ApiOperation::PartOfSpeech | ApiOperation::KanjiMnemonic => 1000,
+        }
+    }
+}
+
+// 一次实际发出的模型请求的用量，run_id 区分每次运行
+#[derive(Debug, Clone)]
+pub struct ApiCall {
+    pub run_id: String,
+    pub operation: String,
+    pub model: String,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    // 服务商没有返回费用时为 None
+    pub cost: Option<f64>,
+}
+
+// 按用途汇总的请求用量
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct ApiUsageSummary {
+    pub operation: String,
+    pub requests: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub cost: Option<f64>,
+}
+
+// 例句翻译的返回结构
+#[derive(Debug, Deserialize)]
+pub struct SentenceTranslationResult {
+    pub sentences: Vec<ExampleSentence>,
+}
+
+// 与近义词的比较
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct WordComparison {
+    pub word: String,
+    pub difference: String,
+}
+
+// 结构化的语法解析，卡片生成时渲染为 HTML
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct GrammarDetails {
+    pub meaning: String,
+    #[serde(default)]
+    pub register: GrammarRegister,
+    #[serde(default)]
+    pub connections: Vec<ConnectionRule>,
+    #[serde(default)]
+    pub usage: String,
+    #[serde(default)]
+    pub examples: Vec<ExampleSentence>,
+    #[serde(default)]
+    pub related: Vec<RelatedGrammar>,
+}
+
+// 接续规则：前项词类和要求的活用形，例如 动词 + て形
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ConnectionRule {
+    pub base: ConnectionBase,
+    #[serde(default)]
+    pub form: String,
+    #[serde(default)]
+    pub note: String,
+}
+
+// 相关语法，grammar_id 为语法库中对应条目的ID（读取时按语法表达关联，未收录时为 None）
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct RelatedGrammar {
+    pub grammar: String,
+    #[serde(default)]
+    pub difference: String,
+    #[serde(skip)]
+    pub grammar_id: Option<i64>,
+}
+
 // 最终分析结果结构
 #[derive(Debug, Clone)]
 pub struct WordAnalysis {
@@ -60,6 +707,9 @@ pub struct WordAnalysis {
     pub pitch: String,
     pub part_of_speech: String,
     pub analysis: String,
+    // 结构化解析，自定义模板返回 HTML 时为 None
+    pub details: Option<WordDetails>,
+    pub prompt_version: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -67,4 +717,251 @@ pub struct GrammarAnalysis {
     pub grammar: String,
     pub kana: String,
     pub analysis: String,
+    // 结构化解析，自定义模板返回 HTML 时为 None
+    pub details: Option<GrammarDetails>,
+    pub prompt_version: i64,
+}
+
+// 导出/导入使用的完整单词记录（不含自增ID，按 word+kana+pitch 合并）
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct ExportedWord {
+    pub word: String,
+    pub kana: String,
+    pub pitch: String,
+    pub part_of_speech: String,
+    pub analysis: String,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub analysis_locked: bool,
+    #[serde(default)]
+    pub pos_locked: bool,
+    #[serde(default)]
+    pub pitch_locked: bool,
+    #[serde(default)]
+    pub kana_locked: bool,
+    #[serde(default)]
+    pub tags: String,
+    #[serde(default)]
+    pub frequency_rank: Option<i64>,
+    #[serde(default)]
+    pub jlpt_level: Option<String>,
+    #[serde(default)]
+    pub prompt_version: i64,
+}
+
+// 导出/导入使用的完整语法记录（按语法表达合并）
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct ExportedGrammar {
+    pub word: String,
+    pub kana: String,
+    pub analysis: String,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub analysis_locked: bool,
+    #[serde(default)]
+    pub kana_locked: bool,
+    #[serde(default)]
+    pub tags: String,
+    #[serde(default)]
+    pub frequency_rank: Option<i64>,
+    #[serde(default)]
+    pub jlpt_level: Option<String>,
+    #[serde(default)]
+    pub prompt_version: i64,
+    // 指向该语法的其他写法（变体和活用形）
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub aliases: Vec<String>,
+}
+
+// 导出的出现记录，用自然键引用条目，以便合并到其他数据库
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct ExportedOccurrence {
+    pub entry_kind: String,
+    pub word: String,
+    pub kana: String,
+    #[serde(default)]
+    pub pitch: Option<String>, // 语法没有音调
+    pub document: String,
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+// 导出的学习状态，用学习者名称和辞书形引用
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct ExportedStatus {
+    pub user_name: String,
+    pub entry_kind: String,
+    pub word: String,
+    pub status: String,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+// 导出记录中对单词或语法的引用：单词按 (word, kana, pitch)，语法按语法表达
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct ExportedEntryRef {
+    pub entry_kind: String,
+    pub word: String,
+    pub kana: String,
+    #[serde(default)]
+    pub pitch: Option<String>, // 语法没有音调
+}
+
+// 导出的汉字（含记忆口诀）和包含该汉字的单词
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportedKanji {
+    pub character: String,
+    #[serde(default)]
+    pub on_readings: String,
+    #[serde(default)]
+    pub kun_readings: String,
+    #[serde(default)]
+    pub meanings: String,
+    #[serde(default)]
+    pub stroke_count: Option<i64>,
+    #[serde(default)]
+    pub components: String,
+    #[serde(default)]
+    pub mnemonic: String,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub words: Vec<ExportedEntryRef>,
+}
+
+// 导出的例句（含翻译和读音）和例句中出现的单词、语法
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportedSentence {
+    pub japanese: String,
+    #[serde(default)]
+    pub reading: String,
+    #[serde(default)]
+    pub translation: String,
+    pub source: String,
+    #[serde(default)]
+    pub document: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub entries: Vec<ExportedEntryRef>,
+}
+
+// 导出的复习卡片（FSRS 记忆状态），用学习者名称和条目的自然键引用
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct ExportedReviewCard {
+    pub user_name: String,
+    pub entry_kind: String,
+    pub word: String,
+    pub kana: String,
+    #[serde(default)]
+    pub pitch: Option<String>, // 语法没有音调
+    pub state: String,
+    pub due: String,
+    pub stability: f64,
+    pub difficulty: f64,
+    #[serde(default)]
+    pub reps: i64,
+    #[serde(default)]
+    pub lapses: i64,
+    #[serde(default)]
+    pub last_review: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+// 导出的复习记录，用学习者名称和条目的自然键引用所属卡片
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct ExportedReviewLog {
+    pub user_name: String,
+    pub entry_kind: String,
+    pub word: String,
+    pub kana: String,
+    #[serde(default)]
+    pub pitch: Option<String>,
+    pub ease: i64,
+    pub ivl: i64,
+    pub last_ivl: i64,
+    pub time_ms: i64,
+    pub review_type: i64,
+    pub stability: f64,
+    pub difficulty: f64,
+    pub elapsed_days: f64,
+    pub reviewed_at: String,
+    pub reviewed_at_ms: i64,
+}
+
+// 导出的其他语言解析，用辞书形和假名引用
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct ExportedAnalysis {
+    pub entry_kind: String,
+    pub word: String,
+    pub kana: String,
+    pub language: String,
+    pub analysis: String,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub prompt_version: i64,
+}
+
+// 导出的结构化单词解析，用辞书形和假名引用
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportedWordDetails {
+    pub word: String,
+    pub kana: String,
+    pub language: String,
+    pub details: WordDetails,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+// 导出的结构化语法解析，用语法表达引用
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportedGrammarDetails {
+    pub grammar: String,
+    pub language: String,
+    pub details: GrammarDetails,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+// 导入时遇到已存在条目的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    KeepExisting, // 保留现有条目
+    Overwrite,    // 用导入的条目覆盖
+    KeepNewer,    // 按 updated_at 保留较新的条目
+}
+
+// 单条记录的导入结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportOutcome {
+    Inserted,
+    Updated,
+    Skipped,
+}
+
+// 导入统计
+#[derive(Debug, Clone, Default)]
+pub struct ImportStats {
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+impl ImportStats {
+    pub fn record(&mut self, outcome: ImportOutcome) {
+        match outcome {
+            ImportOutcome::Inserted => self.inserted += 1,
+            ImportOutcome::Updated => self.updated += 1,
+            ImportOutcome::Skipped => self.skipped += 1,
+        }
+    }
 }
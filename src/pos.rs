@@ -0,0 +1,333 @@
+use crate::prompts::Language;
+
+/// 标准词性
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PartOfSpeech {
+    Noun,
+    ProperNoun,
+    Pronoun,
+    Numeral,
+    Counter,
+    IntransitiveVerb,
+    TransitiveVerb,
+    // 未区分自他的动词（如サ变动词）
+    Verb,
+    IAdjective,
+    NaAdjective,
+    // 未区分一类/二类的形容词
+    Adjective,
+    Adverb,
+    Adnominal,
+    Conjunction,
+    Particle,
+    AuxiliaryVerb,
+    Interjection,
+    Prefix,
+    Suffix,
+    Expression,
+}
+
+impl PartOfSpeech {
+    pub const ALL: [PartOfSpeech; 20] = [
+        PartOfSpeech::Noun,
+        PartOfSpeech::ProperNoun,
+        PartOfSpeech::Pronoun,
+        PartOfSpeech::Numeral,
+        PartOfSpeech::Counter,
+        PartOfSpeech::IntransitiveVerb,
+        PartOfSpeech::TransitiveVerb,
+        PartOfSpeech::Verb,
+        PartOfSpeech::IAdjective,
+        PartOfSpeech::NaAdjective,
+        PartOfSpeech::Adjective,
+        PartOfSpeech::Adverb,
+        PartOfSpeech::Adnominal,
+        PartOfSpeech::Conjunction,
+        PartOfSpeech::Particle,
+        PartOfSpeech::AuxiliaryVerb,
+        PartOfSpeech::Interjection,
+        PartOfSpeech::Prefix,
+        PartOfSpeech::Suffix,
+        PartOfSpeech::Expression,
+    ];
+
+    /// 数据库 word_pos 表中使用的名称
+    pub fn key(&self) -> &'static str {
+        match self {
+            PartOfSpeech::Noun => "noun",
+            PartOfSpeech::ProperNoun => "proper_noun",
+            PartOfSpeech::Pronoun => "pronoun",
+            PartOfSpeech::Numeral => "numeral",
+            PartOfSpeech::Counter => "counter",
+            PartOfSpeech::IntransitiveVerb => "verb_intransitive",
+            PartOfSpeech::TransitiveVerb => "verb_transitive",
+            PartOfSpeech::Verb => "verb",
+            PartOfSpeech::IAdjective => "adj_i",
+            PartOfSpeech::NaAdjective => "adj_na",
+            PartOfSpeech::Adjective => "adjective",
+            PartOfSpeech::Adverb => "adverb",
+            PartOfSpeech::Adnominal => "adnominal",
+            PartOfSpeech::Conjunction => "conjunction",
+            PartOfSpeech::Particle => "particle",
+            PartOfSpeech::AuxiliaryVerb => "auxiliary_verb",
+            PartOfSpeech::Interjection => "interjection",
+            PartOfSpeech::Prefix => "prefix",
+            PartOfSpeech::Suffix => "suffix",
+            PartOfSpeech::Expression => "expression",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|pos| pos.key() == key)
+    }
+
+    /// 卡片和数据库 part_of_speech 字段中使用的简体中文名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            PartOfSpeech::Noun => "名词",
+            PartOfSpeech::ProperNoun => "专有名词",
+            PartOfSpeech::Pronoun => "代词",
+            PartOfSpeech::Numeral => "数词",
+            PartOfSpeech::Counter => "量词",
+            PartOfSpeech::IntransitiveVerb => "自动词",
+            PartOfSpeech::TransitiveVerb => "他动词",
+            PartOfSpeech::Verb => "动词",
+            PartOfSpeech::IAdjective => "一类形容词",
+            PartOfSpeech::NaAdjective => "二类形容词",
+            PartOfSpeech::Adjective => "形容词",
+            PartOfSpeech::Adverb => "副词",
+            PartOfSpeech::Adnominal => "连体词",
+            PartOfSpeech::Conjunction => "连词",
+            PartOfSpeech::Particle => "助词",
+            PartOfSpeech::AuxiliaryVerb => "助动词",
+            PartOfSpeech::Interjection => "感叹词",
+            PartOfSpeech::Prefix => "接头词",
+            PartOfSpeech::Suffix => "接尾词",
+            PartOfSpeech::Expression => "惯用语",
+        }
+    }
+
+    /// 指定语言的词性名称
+    pub fn label_in(&self, language: Language) -> &'static str {
+        match language {
+            Language::Chinese => self.label(),
+            Language::English => match self {
+                PartOfSpeech::Noun => "noun",
+                PartOfSpeech::ProperNoun => "proper noun",
+                PartOfSpeech::Pronoun => "pronoun",
+                PartOfSpeech::Numeral => "numeral",
+                PartOfSpeech::Counter => "counter",
+                PartOfSpeech::IntransitiveVerb => "intransitive verb",
+                PartOfSpeech::TransitiveVerb => "transitive verb",
+                PartOfSpeech::Verb => "verb",
+                PartOfSpeech::IAdjective => "i-adjective",
+                PartOfSpeech::NaAdjective => "na-adjective",
+                PartOfSpeech::Adjective => "adjective",
+                PartOfSpeech::Adverb => "adverb",
+                PartOfSpeech::Adnominal => "adnominal",
+                PartOfSpeech::Conjunction => "conjunction",
+                PartOfSpeech::Particle => "particle",
+                PartOfSpeech::AuxiliaryVerb => "auxiliary verb",
+                PartOfSpeech::Interjection => "interjection",
+                PartOfSpeech::Prefix => "prefix",
+                PartOfSpeech::Suffix => "suffix",
+                PartOfSpeech::Expression => "expression",
+            },
+            Language::Japanese => match self {
+                PartOfSpeech::Noun => "名詞",
+                PartOfSpeech::ProperNoun => "固有名詞",
+                PartOfSpeech::Pronoun => "代名詞",
+                PartOfSpeech::Numeral => "数詞",
+                PartOfSpeech::Counter => "助数詞",
+                PartOfSpeech::IntransitiveVerb => "自動詞",
+                PartOfSpeech::TransitiveVerb => "他動詞",
+                PartOfSpeech::Verb => "動詞",
+                PartOfSpeech::IAdjective => "い形容詞",
+                PartOfSpeech::NaAdjective => "な形容詞",
+                PartOfSpeech::Adjective => "形容詞",
+                PartOfSpeech::Adverb => "副詞",
+                PartOfSpeech::Adnominal => "連体詞",
+                PartOfSpeech::Conjunction => "接続詞",
+                PartOfSpeech::Particle => "助詞",
+                PartOfSpeech::AuxiliaryVerb => "助動詞",
+                PartOfSpeech::Interjection => "感動詞",
+                PartOfSpeech::Prefix => "接頭辞",
+                PartOfSpeech::Suffix => "接尾辞",
+                PartOfSpeech::Expression => "連語",
+            },
+        }
+    }
+
+    fn is_specific_verb(&self) -> bool {
+        matches!(self, PartOfSpeech::IntransitiveVerb | PartOfSpeech::TransitiveVerb)
+    }
+
+    fn is_specific_adjective(&self) -> bool {
+        matches!(self, PartOfSpeech::IAdjective | PartOfSpeech::NaAdjective)
+    }
+}
+
+// 各词性的日语、中文、英文写法和 AI 常见的变体（比较前去掉空格并转为小写）
+const ALIASES: &[(&str, &[PartOfSpeech])] = {
+    use PartOfSpeech::*;
+    &[
+        ("名词", &[Noun]), ("名詞", &[Noun]), ("noun", &[Noun]), ("n", &[Noun]), ("普通名词", &[Noun]),
+        ("专有名词", &[ProperNoun]), ("固有名詞", &[ProperNoun]), ("propernoun", &[ProperNoun]),
+        ("代词", &[Pronoun]), ("代名词", &[Pronoun]), ("代名詞", &[Pronoun]), ("pronoun", &[Pronoun]),
+        ("数词", &[Numeral]), ("数詞", &[Numeral]), ("numeral", &[Numeral]), ("number", &[Numeral]),
+        ("量词", &[Counter]), ("助数词", &[Counter]), ("助数詞", &[Counter]), ("counter", &[Counter]),
+        ("自动词", &[IntransitiveVerb]), ("自動詞", &[IntransitiveVerb]), ("自动", &[IntransitiveVerb]),
+        ("intransitiveverb", &[IntransitiveVerb]), ("vi", &[IntransitiveVerb]),
+        ("他动词", &[TransitiveVerb]), ("他動詞", &[TransitiveVerb]), ("他动", &[TransitiveVerb]),
+        ("transitiveverb", &[TransitiveVerb]), ("vt", &[TransitiveVerb]),
+        ("自他动词", &[IntransitiveVerb, TransitiveVerb]), ("自他動詞", &[IntransitiveVerb, TransitiveVerb]),
+        ("动词", &[Verb]), ("動詞", &[Verb]), ("verb", &[Verb]), ("v", &[Verb]),
+        ("サ变动词", &[Verb]), ("サ変動詞", &[Verb]), ("する动词", &[Verb]), ("suruverb", &[Verb]),
+        ("一段动词", &[Verb]), ("一段動詞", &[Verb]), ("五段动词", &[Verb]), ("五段動詞", &[Verb]),
+        ("一类形容词", &[IAdjective]), ("い形容词", &[IAdjective]), ("い形容詞", &[IAdjective]),
+        ("イ形容詞", &[IAdjective]), ("形容詞", &[IAdjective]), ("i-adjective", &[IAdjective]),
+        ("iadjective", &[IAdjective]), ("adj-i", &[IAdjective]),
+        ("二类形容词", &[NaAdjective]), ("な形容词", &[NaAdjective]), ("な形容詞", &[NaAdjective]),
+        ("ナ形容詞", &[NaAdjective]), ("形容动词", &[NaAdjective]), ("形容動詞", &[NaAdjective]),
+        ("na-adjective", &[NaAdjective]), ("naadjective", &[NaAdjective]), ("adj-na", &[NaAdjective]),
+        ("形容词", &[Adjective]), ("adjective", &[Adjective]), ("adj", &[Adjective]),
+        ("副词", &[Adverb]), ("副詞", &[Adverb]), ("adverb", &[Adverb]), ("adv", &[Adverb]),
+        ("连体词", &[Adnominal]), ("連体詞", &[Adnominal]), ("adnominal", &[Adnominal]),
+        ("连词", &[Conjunction]), ("接续词", &[Conjunction]), ("接続詞", &[Conjunction]),
+        ("conjunction", &[Conjunction]), ("conj", &[Conjunction]),
+        ("助词", &[Particle]), ("助詞", &[Particle]), ("particle", &[Particle]), ("prt", &[Particle]),
+        ("助动词", &[AuxiliaryVerb]), ("助動詞", &[AuxiliaryVerb]), ("auxiliaryverb", &[AuxiliaryVerb]),
+        ("感叹词", &[Interjection]), ("感动词", &[Interjection]), ("感動詞", &[Interjection]),
+        ("叹词", &[Interjection]), ("interjection", &[Interjection]), ("int", &[Interjection]),
+        ("接头词", &[Prefix]), ("接头辞", &[Prefix]), ("接頭辞", &[Prefix]), ("接頭語", &[Prefix]), ("prefix", &[Prefix]),
+        ("接尾词", &[Suffix]), ("接尾辞", &[Suffix]), ("接尾語", &[Suffix]), ("suffix", &[Suffix]),
+        ("惯用语", &[Expression]), ("惯用句", &[Expression]), ("慣用句", &[Expression]), ("连语", &[Expression]),
+        ("連語", &[Expression]), ("短语", &[Expression]), ("表达", &[Expression]), ("expression", &[Expression]),
+        ("phrase", &[Expression]), ("exp", &[Expression]),
+    ]
+};
+
+/// 将单个词性标签映射为标准词性，无法识别时返回空列表
+pub fn normalize_label(label: &str) -> Vec<PartOfSpeech> {
+    let cleaned: String = label
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '"' | '\'' | '「' | '」' | '【' | '】' | '[' | ']'))
+        .collect::<String>()
+        .to_lowercase();
+    // 去掉括号中的补充说明，例如 "他动词（サ变）"
+    let cleaned = cleaned
+        .split(['(', '（'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(['.', '。']);
+
+    if cleaned.is_empty() {
+        return Vec::new();
+    }
+    if let Some((_, parts)) = ALIASES.iter().find(|(alias, _)| *alias == cleaned) {
+        return parts.to_vec();
+    }
+    Vec::new()
+}
+
+/// 规范化后的词性列表
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NormalizedPos {
+    pub parts: Vec<PartOfSpeech>,
+    // 无法识别的标签，原样保留
+    pub unrecognized: Vec<String>,
+}
+
+impl NormalizedPos {
+    /// 标准词性的中文名称在前，无法识别的标签在后
+    pub fn labels(&self) -> Vec<String> {
+        self.labels_in(Language::Chinese)
+    }
+
+    /// 指定语言的词性名称，无法识别的标签原样保留
+    pub fn labels_in(&self, language: Language) -> Vec<String> {
+        self.parts
+            .iter()
+            .map(|p| p.label_in(language).to_string())
+            .chain(self.unrecognized.iter().cloned())
+            .collect()
+    }
+
+    /// 数据库 part_of_speech 字段的写法，用｜分隔
+    pub fn to_field(&self) -> String {
+        self.labels().join("｜")
+    }
+}
+
+/// 规范化词性标签列表：按固定顺序去重，有具体的自/他动词或一类/二类形容词时去掉笼统的"动词"/"形容词"
+pub fn normalize_labels<S: AsRef<str>>(labels: &[S]) -> NormalizedPos {
+    let mut result = NormalizedPos::default();
+
+    for label in labels.iter().flat_map(|l| l.as_ref().split(['｜', '|', '、', ',', '，', '/', '・', ';', '；'])) {
+        let label = label.trim();
+        if label.is_empty() {
+            continue;
+        }
+        let parts = normalize_label(label);
+        if parts.is_empty() {
+            if !result.unrecognized.iter().any(|u| u == label) {
+                result.unrecognized.push(label.to_string());
+            }
+        } else {
+            result.parts.extend(parts);
+        }
+    }
+
+    result.parts.sort();
+    result.parts.dedup();
+    if result.parts.iter().any(PartOfSpeech::is_specific_verb) {
+        result.parts.retain(|p| *p != PartOfSpeech::Verb);
+    }
+    if result.parts.iter().any(PartOfSpeech::is_specific_adjective) {
+        result.parts.retain(|p| *p != PartOfSpeech::Adjective);
+    }
+    result
+}
+
+/// 规范化数据库中以｜分隔的词性字段
+pub fn normalize_field(field: &str) -> NormalizedPos {
+    normalize_labels(&[field])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PartOfSpeech::*;
+
+    #[test]
+    fn specific_verbs_drop_generic_verb() {
+        assert_eq!(normalize_labels(&["动词", "他动词"]).parts, vec![TransitiveVerb]);
+        assert_eq!(normalize_labels(&["自他動詞｜動詞"]).parts, vec![IntransitiveVerb, TransitiveVerb]);
+        assert_eq!(normalize_labels(&["名词", "动词"]).parts, vec![Noun, Verb]);
+    }
+
+    #[test]
+    fn specific_adjectives_drop_generic_adjective() {
+        assert_eq!(normalize_labels(&["形容词", "二类形容词"]).parts, vec![NaAdjective]);
+        assert_eq!(normalize_labels(&["adjective｜い形容詞"]).parts, vec![IAdjective]);
+        assert_eq!(normalize_labels(&["形容词"]).parts, vec![Adjective]);
+    }
+
+    #[test]
+    fn conjugation_classes_collapse_to_verb() {
+        for label in ["サ变动词", "サ変動詞", "一段动词", "一段動詞", "五段动词", "五段動詞"] {
+            assert_eq!(normalize_labels(&[label]).parts, vec![Verb], "{}", label);
+        }
+        assert_eq!(normalize_labels(&["名词｜サ变动词"]).parts, vec![Noun, Verb]);
+        assert_eq!(normalize_labels(&["五段动词", "自动词"]).parts, vec![IntransitiveVerb]);
+        assert_eq!(normalize_labels(&["他动词（サ变）"]).parts, vec![TransitiveVerb]);
+    }
+
+    #[test]
+    fn duplicates_merge_and_unrecognized_labels_are_kept() {
+        let normalized = normalize_labels(&["名詞、noun", "拟声词", "拟声词"]);
+        assert_eq!(normalized.parts, vec![Noun]);
+        assert_eq!(normalized.unrecognized, vec!["拟声词".to_string()]);
+        assert_eq!(normalized.to_field(), "名词｜拟声词");
+    }
+}
@@ -0,0 +1,2817 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
+
+use crate::database::{ANALYSES_QUERY, REVIEW_CARDS_QUERY, REVIEW_HISTORY_QUERY, REVLOG_QUERY, SENTENCE_ENTRIES_QUERY, escape_like, make_snippet, should_replace};
+use crate::models::*;
+use crate::grammar::{ConnectionBase, GrammarRegister, canonical_grammar};
+use crate::pos::{normalize_field, normalize_label};
+use crate::storage::Storage;
+use crate::text::{kana_to_romaji, strip_html};
+
+// 查询单词和语法时使用的列
+const WORD_COLUMNS: &str = "id, word, kana, pitch, part_of_speech, analysis, updated_at, analysis_locked, pos_locked, pitch_locked, kana_locked, tags, frequency_rank, jlpt_level, prompt_version";
+const GRAMMAR_COLUMNS: &str = "id, word, kana, analysis, analysis_locked, kana_locked, tags, updated_at, frequency_rank, jlpt_level, prompt_version";
+
+// 时间统一存为 UTC 的 'YYYY-MM-DD HH:MM:SS' 文本，与 SQLite 后端格式一致
+const NOW: &str = "to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')";
+
+// PostgreSQL 存储后端，供多人共享同一词库
+pub struct PostgresStorage {
+    pool: PgPool,
+}
+
+impl PostgresStorage {
+    pub async fn connect(url: &str) -> Result<Self> {
+        println!("💾 连接 PostgreSQL 数据库...");
+        let pool = PgPool::connect(url).await?;
+
+        let storage = PostgresStorage { pool };
+        storage.initialize_tables().await?;
+
+        Ok(storage)
+    }
+
+    async fn initialize_tables(&self) -> Result<()> {
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS words (
+                id BIGSERIAL PRIMARY KEY,
+                word TEXT NOT NULL,
+                kana TEXT NOT NULL,
+                pitch TEXT NOT NULL DEFAULT '0',
+                part_of_speech TEXT NOT NULL,
+                analysis TEXT NOT NULL,
+                created_at TEXT DEFAULT {now},
+                updated_at TEXT DEFAULT {now},
+                analysis_locked BOOLEAN NOT NULL DEFAULT FALSE,
+                pos_locked BOOLEAN NOT NULL DEFAULT FALSE,
+                pitch_locked BOOLEAN NOT NULL DEFAULT FALSE,
+                kana_locked BOOLEAN NOT NULL DEFAULT FALSE,
+                tags TEXT NOT NULL DEFAULT '',
+                frequency_rank BIGINT,
+                jlpt_level TEXT,
+                UNIQUE(word, kana, pitch)
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS grammar (
+                id BIGSERIAL PRIMARY KEY,
+                word TEXT NOT NULL UNIQUE,
+                kana TEXT NOT NULL,
+                analysis TEXT NOT NULL,
+                created_at TEXT DEFAULT {now},
+                updated_at TEXT DEFAULT {now},
+                analysis_locked BOOLEAN NOT NULL DEFAULT FALSE,
+                kana_locked BOOLEAN NOT NULL DEFAULT FALSE,
+                tags TEXT NOT NULL DEFAULT '',
+                frequency_rank BIGINT,
+                jlpt_level TEXT
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS occurrences (
+                id BIGSERIAL PRIMARY KEY,
+                entry_kind TEXT NOT NULL,
+                entry_id BIGINT NOT NULL,
+                document TEXT NOT NULL,
+                created_at TEXT DEFAULT {now}
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_occurrences_entry ON occurrences (entry_kind, entry_id)")
+            .execute(&self.pool)
+            .await?;
+
+        // 旧版本创建的表补充词频和 JLPT 等级列
+        for table in ["words", "grammar"] {
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN IF NOT EXISTS frequency_rank BIGINT, ADD COLUMN IF NOT EXISTS jlpt_level TEXT",
+                table
+            )).execute(&self.pool).await?;
+        }
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS users (
+                id BIGSERIAL PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                created_at TEXT DEFAULT {now}
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS entry_status (
+                user_id BIGINT NOT NULL REFERENCES users(id),
+                entry_kind TEXT NOT NULL,
+                word TEXT NOT NULL,
+                status TEXT NOT NULL,
+                updated_at TEXT DEFAULT {now},
+                PRIMARY KEY (user_id, entry_kind, word)
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS entry_analyses (
+                entry_kind TEXT NOT NULL,
+                entry_id BIGINT NOT NULL,
+                language TEXT NOT NULL,
+                analysis TEXT NOT NULL,
+                updated_at TEXT DEFAULT {now},
+                PRIMARY KEY (entry_kind, entry_id, language)
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS analysis_flags (
+                entry_kind TEXT NOT NULL,
+                entry_id BIGINT NOT NULL,
+                language TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                created_at TEXT DEFAULT {now},
+                PRIMARY KEY (entry_kind, entry_id, language)
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS analysis_reviews (
+                entry_kind TEXT NOT NULL,
+                entry_id BIGINT NOT NULL,
+                language TEXT NOT NULL,
+                score BIGINT NOT NULL,
+                issues TEXT NOT NULL DEFAULT '[]',
+                model TEXT NOT NULL,
+                created_at TEXT DEFAULT {now},
+                PRIMARY KEY (entry_kind, entry_id, language)
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS word_details (
+                word_id BIGINT NOT NULL,
+                language TEXT NOT NULL,
+                core_meaning TEXT NOT NULL,
+                etymology TEXT NOT NULL DEFAULT '',
+                summary TEXT NOT NULL DEFAULT '',
+                updated_at TEXT DEFAULT {now},
+                PRIMARY KEY (word_id, language)
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS word_senses (
+                word_id BIGINT NOT NULL,
+                language TEXT NOT NULL,
+                position BIGINT NOT NULL,
+                meaning TEXT NOT NULL,
+                explanation TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (word_id, language, position)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS word_examples (
+                word_id BIGINT NOT NULL,
+                language TEXT NOT NULL,
+                sense_position BIGINT NOT NULL,
+                position BIGINT NOT NULL,
+                japanese TEXT NOT NULL,
+                reading TEXT NOT NULL DEFAULT '',
+                translation TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (word_id, language, sense_position, position)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS word_comparisons (
+                word_id BIGINT NOT NULL,
+                language TEXT NOT NULL,
+                position BIGINT NOT NULL,
+                other_word TEXT NOT NULL,
+                difference TEXT NOT NULL,
+                PRIMARY KEY (word_id, language, position)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS grammar_details (
+                grammar_id BIGINT NOT NULL,
+                language TEXT NOT NULL,
+                meaning TEXT NOT NULL,
+                register TEXT NOT NULL DEFAULT 'neutral',
+                usage TEXT NOT NULL DEFAULT '',
+                updated_at TEXT DEFAULT {now},
+                PRIMARY KEY (grammar_id, language)
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS grammar_connections (
+                grammar_id BIGINT NOT NULL,
+                language TEXT NOT NULL,
+                position BIGINT NOT NULL,
+                base TEXT NOT NULL,
+                form TEXT NOT NULL DEFAULT '',
+                note TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (grammar_id, language, position)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS grammar_examples (
+                grammar_id BIGINT NOT NULL,
+                language TEXT NOT NULL,
+                position BIGINT NOT NULL,
+                japanese TEXT NOT NULL,
+                reading TEXT NOT NULL DEFAULT '',
+                translation TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (grammar_id, language, position)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS grammar_related (
+                grammar_id BIGINT NOT NULL,
+                language TEXT NOT NULL,
+                position BIGINT NOT NULL,
+                related_grammar TEXT NOT NULL,
+                difference TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (grammar_id, language, position)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        // 记录生成解析所用的提示词模板版本，0 表示记录版本之前生成的解析
+        for table in ["words", "grammar", "entry_analyses"] {
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN IF NOT EXISTS prompt_version BIGINT NOT NULL DEFAULT 0",
+                table
+            )).execute(&self.pool).await?;
+        }
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS kanji (
+                id BIGSERIAL PRIMARY KEY,
+                character TEXT NOT NULL UNIQUE,
+                on_readings TEXT NOT NULL DEFAULT '',
+                kun_readings TEXT NOT NULL DEFAULT '',
+                meanings TEXT NOT NULL DEFAULT '',
+                stroke_count BIGINT,
+                components TEXT NOT NULL DEFAULT '',
+                mnemonic TEXT NOT NULL DEFAULT '',
+                created_at TEXT DEFAULT {now},
+                updated_at TEXT DEFAULT {now}
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS kanji_words (
+                kanji_id BIGINT NOT NULL REFERENCES kanji(id),
+                word_id BIGINT NOT NULL,
+                PRIMARY KEY (kanji_id, word_id)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS sentences (
+                id BIGSERIAL PRIMARY KEY,
+                japanese TEXT NOT NULL UNIQUE,
+                reading TEXT NOT NULL DEFAULT '',
+                translation TEXT NOT NULL DEFAULT '',
+                source TEXT NOT NULL,
+                document TEXT,
+                created_at TEXT DEFAULT {now},
+                updated_at TEXT DEFAULT {now}
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS sentence_entries (
+                sentence_id BIGINT NOT NULL REFERENCES sentences(id),
+                entry_kind TEXT NOT NULL,
+                entry_id BIGINT NOT NULL,
+                PRIMARY KEY (sentence_id, entry_kind, entry_id)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_sentence_entries_entry ON sentence_entries(entry_kind, entry_id)")
+            .execute(&self.pool).await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS response_cache (
+                key TEXT PRIMARY KEY,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                temperature DOUBLE PRECISION NOT NULL,
+                max_tokens BIGINT NOT NULL,
+                response TEXT NOT NULL,
+                created_at TEXT DEFAULT {now}
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS api_calls (
+                id BIGSERIAL PRIMARY KEY,
+                run_id TEXT NOT NULL,
+                operation TEXT NOT NULL,
+                model TEXT NOT NULL,
+                prompt_tokens BIGINT NOT NULL,
+                completion_tokens BIGINT NOT NULL,
+                cost DOUBLE PRECISION,
+                created_at TEXT DEFAULT {now}
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_api_calls_run ON api_calls(run_id)")
+            .execute(&self.pool).await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS review_cards (
+                id BIGSERIAL PRIMARY KEY,
+                user_id BIGINT NOT NULL REFERENCES users(id),
+                entry_kind TEXT NOT NULL,
+                entry_id BIGINT NOT NULL,
+                state TEXT NOT NULL,
+                due TEXT NOT NULL,
+                stability DOUBLE PRECISION NOT NULL,
+                difficulty DOUBLE PRECISION NOT NULL,
+                reps BIGINT NOT NULL DEFAULT 0,
+                lapses BIGINT NOT NULL DEFAULT 0,
+                last_review TEXT,
+                created_at TEXT DEFAULT {now},
+                UNIQUE(user_id, entry_kind, entry_id)
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS review_log (
+                id BIGSERIAL PRIMARY KEY,
+                card_id BIGINT NOT NULL REFERENCES review_cards(id),
+                user_id BIGINT NOT NULL,
+                ease BIGINT NOT NULL,
+                ivl BIGINT NOT NULL,
+                last_ivl BIGINT NOT NULL,
+                time_ms BIGINT NOT NULL,
+                review_type BIGINT NOT NULL,
+                stability DOUBLE PRECISION NOT NULL,
+                difficulty DOUBLE PRECISION NOT NULL,
+                elapsed_days DOUBLE PRECISION NOT NULL,
+                reviewed_at TEXT NOT NULL,
+                reviewed_at_ms BIGINT NOT NULL
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_review_log_user ON review_log(user_id, reviewed_at_ms)")
+            .execute(&self.pool)
+            .await?;
+
+        // 搜索用的冗余表：罗马字和去掉 HTML 的解析文本由程序生成
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS search_index (
+                kind TEXT NOT NULL,
+                entry_id BIGINT NOT NULL,
+                word TEXT NOT NULL,
+                kana TEXT NOT NULL,
+                romaji TEXT NOT NULL,
+                part_of_speech TEXT NOT NULL,
+                analysis_text TEXT NOT NULL,
+                tags TEXT NOT NULL,
+                updated_at TEXT,
+                PRIMARY KEY (kind, entry_id)
+            )
+            "#
+        ).execute(&self.pool).await?;
+
+        // 单词和标准词性的关联（由 part_of_speech 字段规范化得到）
+        let pos_table_exists = sqlx::query("SELECT word_id FROM word_pos LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await
+            .is_ok();
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS word_pos (
+                word_id BIGINT NOT NULL,
+                pos TEXT NOT NULL,
+                PRIMARY KEY (word_id, pos)
+            )
+            "#
+        ).execute(&self.pool).await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_word_pos_pos ON word_pos(pos)")
+            .execute(&self.pool)
+            .await?;
+
+        if !pos_table_exists {
+            println!("🔧 规范化单词词性...");
+            let mut changed = 0;
+            for word in self.get_all_words().await? {
+                let normalized = normalize_field(&word.part_of_speech).to_field();
+                if normalized != word.part_of_speech {
+                    sqlx::query("UPDATE words SET part_of_speech = $1 WHERE id = $2")
+                        .bind(&normalized)
+                        .bind(word.id)
+                        .execute(&self.pool)
+                        .await?;
+                    changed += 1;
+                }
+            }
+            println!("   ✅ 已规范化 {} 个单词的词性", changed);
+            let filled = self.fill_word_pos().await?;
+            println!("   ✅ 已填充 {} 个单词的词性关联", filled);
+            sqlx::query("DELETE FROM search_index").execute(&self.pool).await?;
+        }
+
+        // 语法的其他写法（变体和活用形），指向规范写法的语法条目
+        let aliases_table_exists = sqlx::query("SELECT alias FROM grammar_aliases LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await
+            .is_ok();
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS grammar_aliases (
+                alias TEXT PRIMARY KEY,
+                grammar_id BIGINT NOT NULL,
+                created_at TEXT DEFAULT {now}
+            )
+            "#,
+            now = NOW
+        )).execute(&self.pool).await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_grammar_aliases_grammar ON grammar_aliases(grammar_id)")
+            .execute(&self.pool)
+            .await?;
+
+        if !aliases_table_exists {
+            println!("🔧 合并语法的不同写法...");
+            let merged = self.merge_grammar_variants().await?;
+            println!("   ✅ 已合并 {} 个重复的语法条目", merged);
+        }
+
+        let (indexed,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM search_index")
+            .fetch_one(&self.pool)
+            .await?;
+        let (entries,): (i64,) = sqlx::query_as("SELECT (SELECT COUNT(*) FROM words) + (SELECT COUNT(*) FROM grammar)")
+            .fetch_one(&self.pool)
+            .await?;
+
+        if indexed != entries {
+            println!("🔧 重建搜索索引...");
+            sqlx::query("DELETE FROM search_index").execute(&self.pool).await?;
+            for word in self.get_all_words().await? {
+                self.reindex_entry(EntryKind::Word, word.id).await?;
+            }
+            for grammar in self.get_all_grammar().await? {
+                self.reindex_entry(EntryKind::Grammar, grammar.id).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // 按所有单词的词性字段重新填充词性关联，返回单词数
+    async fn fill_word_pos(&self) -> Result<usize> {
+        let words = self.get_all_words().await?;
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM word_pos").execute(&mut *tx).await?;
+        for word in &words {
+            Self::write_word_pos(&mut tx, word.id, &word.part_of_speech).await?;
+        }
+        tx.commit().await?;
+        Ok(words.len())
+    }
+
+    // 按词性字段重写单词的词性关联，和单词本身的修改放在同一个事务中
+    async fn write_word_pos(conn: &mut PgConnection, word_id: i64, part_of_speech: &str) -> Result<()> {
+        sqlx::query("DELETE FROM word_pos WHERE word_id = $1")
+            .bind(word_id)
+            .execute(&mut *conn)
+            .await?;
+        for pos in normalize_field(part_of_speech).parts {
+            sqlx::query("INSERT INTO word_pos (word_id, pos) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+                .bind(word_id)
+                .bind(pos.key())
+                .execute(&mut *conn)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // 把规范写法相同的语法合并为一个条目，其余写法记为别名，返回删除的重复条目数
+    async fn merge_grammar_variants(&self) -> Result<usize> {
+        let mut groups: HashMap<String, Vec<JapaneseGrammar>> = HashMap::new();
+        for grammar in self.get_all_grammar().await? {
+            groups.entry(canonical_grammar(&grammar.word)).or_default().push(grammar);
+        }
+
+        let mut merged = 0;
+        for (canonical, mut entries) in groups {
+            if entries.len() == 1 && entries[0].word == canonical {
+                continue;
+            }
+            // 保留已是规范写法的条目，其次是解析被锁定的，再其次是最早收录的
+            entries.sort_by_key(|g| (g.word != canonical, !g.analysis_locked, g.id));
+            let keeper = entries.remove(0);
+            for duplicate in &entries {
+                self.merge_grammar_into(duplicate, keeper.id, &canonical).await?;
+                merged += 1;
+            }
+            if keeper.word != canonical {
+                self.rename_grammar(&keeper, &canonical).await?;
+            }
+            self.reindex_entry(EntryKind::Grammar, keeper.id).await?;
+        }
+        Ok(merged)
+    }
+
+    // 把重复语法的出现记录、学习状态、复习卡片、其他语言解析和结构化解析移到保留的条目，然后删除重复条目
+    // 两边都有的数据（同一语言的解析、同一学习者的卡片）以保留的条目为准
+    async fn merge_grammar_into(&self, duplicate: &JapaneseGrammar, keeper_id: i64, canonical: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE occurrences SET entry_id = $1 WHERE entry_kind = 'grammar' AND entry_id = $2")
+            .bind(keeper_id)
+            .bind(duplicate.id)
+            .execute(&mut *tx)
+            .await?;
+
+        for (table, key) in [
+            ("entry_analyses", "language"),
+            ("analysis_flags", "language"),
+            ("analysis_reviews", "language"),
+            ("review_cards", "user_id"),
+            ("sentence_entries", "sentence_id"),
+        ] {
+            sqlx::query(&format!(
+                "UPDATE {table} SET entry_id = $1 WHERE entry_kind = 'grammar' AND entry_id = $2 AND {key} NOT IN (SELECT {key} FROM {table} WHERE entry_kind = 'grammar' AND entry_id = $1)"
+            ))
+            .bind(keeper_id)
+            .bind(duplicate.id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query("DELETE FROM review_log WHERE card_id IN (SELECT id FROM review_cards WHERE entry_kind = 'grammar' AND entry_id = $1)")
+            .bind(duplicate.id)
+            .execute(&mut *tx)
+            .await?;
+        for table in ["entry_analyses", "analysis_flags", "analysis_reviews", "review_cards", "sentence_entries"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE entry_kind = 'grammar' AND entry_id = $1", table))
+                .bind(duplicate.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("DELETE FROM search_index WHERE kind = 'grammar' AND entry_id = $1")
+            .bind(duplicate.id)
+            .execute(&mut *tx)
+            .await?;
+
+        // 结构化解析按语言整体移动，grammar_details 放在最后，移动子表时仍按保留条目原有的语言判断
+        for table in ["grammar_connections", "grammar_examples", "grammar_related", "grammar_details"] {
+            sqlx::query(&format!(
+                "UPDATE {} SET grammar_id = $1 WHERE grammar_id = $2 AND language NOT IN (SELECT language FROM grammar_details WHERE grammar_id = $1)",
+                table
+            ))
+            .bind(keeper_id)
+            .bind(duplicate.id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        for table in ["grammar_connections", "grammar_examples", "grammar_related", "grammar_details"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE grammar_id = $1", table))
+                .bind(duplicate.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        // 学习状态按语法写法记录
+        sqlx::query(
+            "UPDATE entry_status SET word = $1 WHERE entry_kind = 'grammar' AND word = $2 AND user_id NOT IN (SELECT user_id FROM entry_status WHERE entry_kind = 'grammar' AND word = $1)"
+        )
+        .bind(canonical)
+        .bind(&duplicate.word)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM entry_status WHERE entry_kind = 'grammar' AND word = $1")
+            .bind(&duplicate.word)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE grammar_aliases SET grammar_id = $1 WHERE grammar_id = $2")
+            .bind(keeper_id)
+            .bind(duplicate.id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM grammar WHERE id = $1")
+            .bind(duplicate.id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO grammar_aliases (alias, grammar_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(&duplicate.word)
+            .bind(keeper_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // 把语法改为规范写法，原写法记为别名
+    async fn rename_grammar(&self, grammar: &JapaneseGrammar, canonical: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE grammar SET word = $1 WHERE id = $2")
+            .bind(canonical)
+            .bind(grammar.id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "UPDATE entry_status SET word = $1 WHERE entry_kind = 'grammar' AND word = $2 AND user_id NOT IN (SELECT user_id FROM entry_status WHERE entry_kind = 'grammar' AND word = $1)"
+        )
+        .bind(canonical)
+        .bind(&grammar.word)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM entry_status WHERE entry_kind = 'grammar' AND word = $1")
+            .bind(&grammar.word)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO grammar_aliases (alias, grammar_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(&grammar.word)
+            .bind(grammar.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // 记录语法的其他写法，与条目写法相同或已记录时跳过
+    async fn add_grammar_alias(&self, grammar: &JapaneseGrammar, alias: &str) -> Result<()> {
+        let alias = alias.trim();
+        if alias.is_empty() || alias == grammar.word {
+            return Ok(());
+        }
+        sqlx::query("INSERT INTO grammar_aliases (alias, grammar_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(alias)
+            .bind(grammar.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // 条目修改后刷新其搜索索引
+    async fn reindex_entry(&self, kind: EntryKind, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM search_index WHERE kind = $1 AND entry_id = $2")
+            .bind(kind.as_str())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        let row = match kind {
+            EntryKind::Word => self.get_word_by_id(id).await?.map(|w| {
+                (w.word, w.kana, w.part_of_speech, w.analysis, w.tags, w.updated_at)
+            }),
+            EntryKind::Grammar => self.get_grammar_by_id(id).await?.map(|g| {
+                (g.word, g.kana, String::new(), g.analysis, g.tags, g.updated_at)
+            }),
+        };
+
+        if let Some((word, kana, part_of_speech, analysis, tags, updated_at)) = row {
+            sqlx::query(
+                "INSERT INTO search_index (kind, entry_id, word, kana, romaji, part_of_speech, analysis_text, tags, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+            )
+            .bind(kind.as_str())
+            .bind(id)
+            .bind(&word)
+            .bind(&kana)
+            .bind(kana_to_romaji(&kana))
+            .bind(&part_of_speech)
+            .bind(strip_html(&analysis))
+            .bind(&tags)
+            .bind(&updated_at)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    // 替换语法某种语言的结构化解析（None 时只删除），updated_at 为空时使用当前时间
+    async fn write_grammar_details(&self, id: i64, language: &str, details: Option<&GrammarDetails>, updated_at: Option<&str>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for table in ["grammar_details", "grammar_connections", "grammar_examples", "grammar_related"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE grammar_id = $1 AND language = $2", table))
+                .bind(id)
+                .bind(language)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let Some(details) = details else {
+            tx.commit().await?;
+            return Ok(());
+        };
+
+        sqlx::query(&format!(
+            "INSERT INTO grammar_details (grammar_id, language, meaning, register, usage, updated_at) VALUES ($1, $2, $3, $4, $5, COALESCE($6, {}))",
+            NOW
+        ))
+        .bind(id)
+        .bind(language)
+        .bind(&details.meaning)
+        .bind(details.register.key())
+        .bind(&details.usage)
+        .bind(updated_at)
+        .execute(&mut *tx)
+        .await?;
+
+        for (position, rule) in details.connections.iter().enumerate() {
+            sqlx::query("INSERT INTO grammar_connections (grammar_id, language, position, base, form, note) VALUES ($1, $2, $3, $4, $5, $6)")
+                .bind(id)
+                .bind(language)
+                .bind(position as i64)
+                .bind(rule.base.key())
+                .bind(&rule.form)
+                .bind(&rule.note)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for (position, example) in details.examples.iter().enumerate() {
+            sqlx::query("INSERT INTO grammar_examples (grammar_id, language, position, japanese, reading, translation) VALUES ($1, $2, $3, $4, $5, $6)")
+                .bind(id)
+                .bind(language)
+                .bind(position as i64)
+                .bind(&example.japanese)
+                .bind(&example.reading)
+                .bind(&example.translation)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for (position, related) in details.related.iter().enumerate() {
+            sqlx::query("INSERT INTO grammar_related (grammar_id, language, position, related_grammar, difference) VALUES ($1, $2, $3, $4, $5)")
+                .bind(id)
+                .bind(language)
+                .bind(position as i64)
+                .bind(&related.grammar)
+                .bind(&related.difference)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // 替换单词某种语言的结构化解析（None 时只删除），updated_at 为空时使用当前时间
+    async fn write_word_details(&self, id: i64, language: &str, details: Option<&WordDetails>, updated_at: Option<&str>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for table in ["word_details", "word_senses", "word_examples", "word_comparisons"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE word_id = $1 AND language = $2", table))
+                .bind(id)
+                .bind(language)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let Some(details) = details else {
+            tx.commit().await?;
+            return Ok(());
+        };
+
+        sqlx::query(&format!(
+            "INSERT INTO word_details (word_id, language, core_meaning, etymology, summary, updated_at) VALUES ($1, $2, $3, $4, $5, COALESCE($6, {}))",
+            NOW
+        ))
+        .bind(id)
+        .bind(language)
+        .bind(&details.core_meaning)
+        .bind(&details.etymology)
+        .bind(&details.summary)
+        .bind(updated_at)
+        .execute(&mut *tx)
+        .await?;
+
+        for (sense_position, sense) in details.senses.iter().enumerate() {
+            sqlx::query("INSERT INTO word_senses (word_id, language, position, meaning, explanation) VALUES ($1, $2, $3, $4, $5)")
+                .bind(id)
+                .bind(language)
+                .bind(sense_position as i64)
+                .bind(&sense.meaning)
+                .bind(&sense.explanation)
+                .execute(&mut *tx)
+                .await?;
+
+            for (position, example) in sense.examples.iter().enumerate() {
+                sqlx::query(
+                    "INSERT INTO word_examples (word_id, language, sense_position, position, japanese, reading, translation) VALUES ($1, $2, $3, $4, $5, $6, $7)"
+                )
+                .bind(id)
+                .bind(language)
+                .bind(sense_position as i64)
+                .bind(position as i64)
+                .bind(&example.japanese)
+                .bind(&example.reading)
+                .bind(&example.translation)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        for (position, comparison) in details.comparisons.iter().enumerate() {
+            sqlx::query("INSERT INTO word_comparisons (word_id, language, position, other_word, difference) VALUES ($1, $2, $3, $4, $5)")
+                .bind(id)
+                .bind(language)
+                .bind(position as i64)
+                .bind(&comparison.word)
+                .bind(&comparison.difference)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // 把重复单词的出现记录、复习卡片、其他语言解析、结构化解析、汉字和例句关联移到保留的条目，删除重复条目后更新保留条目的音调和词性
+    // 两边都有的数据（同一语言的解析、同一学习者的卡片、同一例句）以保留的条目为准，全部在一个事务中完成
+    async fn merge_word_into(&self, duplicate_id: i64, keeper_id: i64, pitch: &str, part_of_speech: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE occurrences SET entry_id = $1 WHERE entry_kind = 'word' AND entry_id = $2")
+            .bind(keeper_id)
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let entry_tables = [
+            ("entry_analyses", "language"),
+            ("analysis_flags", "language"),
+            ("analysis_reviews", "language"),
+            ("review_cards", "user_id"),
+            ("sentence_entries", "sentence_id"),
+        ];
+        for (table, key) in entry_tables {
+            sqlx::query(&format!(
+                "UPDATE {table} SET entry_id = $1 WHERE entry_kind = 'word' AND entry_id = $2 AND {key} NOT IN (SELECT {key} FROM {table} WHERE entry_kind = 'word' AND entry_id = $1)"
+            ))
+            .bind(keeper_id)
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query("DELETE FROM review_log WHERE card_id IN (SELECT id FROM review_cards WHERE entry_kind = 'word' AND entry_id = $1)")
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+        for (table, _) in entry_tables {
+            sqlx::query(&format!("DELETE FROM {} WHERE entry_kind = 'word' AND entry_id = $1", table))
+                .bind(duplicate_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("DELETE FROM search_index WHERE kind = 'word' AND entry_id = $1")
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // 结构化解析按语言整体移动，word_details 放在最后，移动子表时仍按保留条目原有的语言判断
+        for table in ["word_senses", "word_examples", "word_comparisons", "word_details"] {
+            sqlx::query(&format!(
+                "UPDATE {} SET word_id = $1 WHERE word_id = $2 AND language NOT IN (SELECT language FROM word_details WHERE word_id = $1)",
+                table
+            ))
+            .bind(keeper_id)
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query("UPDATE kanji_words SET word_id = $1 WHERE word_id = $2 AND kanji_id NOT IN (SELECT kanji_id FROM kanji_words WHERE word_id = $1)")
+            .bind(keeper_id)
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+        for table in ["word_senses", "word_examples", "word_comparisons", "word_details", "kanji_words", "word_pos"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE word_id = $1", table))
+                .bind(duplicate_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query("DELETE FROM words WHERE id = $1")
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!("UPDATE words SET pitch = $1, part_of_speech = $2, updated_at = {} WHERE id = $3", NOW))
+            .bind(pitch)
+            .bind(part_of_speech)
+            .bind(keeper_id)
+            .execute(&mut *tx)
+            .await?;
+        Self::write_word_pos(&mut tx, keeper_id, part_of_speech).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn update_word_fields(&self, id: i64, pitch: &str, part_of_speech: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(&format!(
+            "UPDATE words SET pitch = $1, part_of_speech = $2, updated_at = {} WHERE id = $3",
+            NOW
+        ))
+        .bind(pitch)
+        .bind(part_of_speech)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        Self::write_word_pos(&mut tx, id, part_of_speech).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // 按导出文件中的自然键查找条目ID：单词按 (word, kana, pitch)，语法按语法表达
+    async fn find_entry_id(&self, entry_kind: &str, word: &str, kana: &str, pitch: Option<&str>) -> Result<Option<i64>> {
+        match EntryKind::parse(entry_kind) {
+            Some(EntryKind::Word) => {
+                let row: Option<(i64,)> = sqlx::query_as("SELECT id FROM words WHERE word = $1 AND kana = $2 AND pitch = $3")
+                    .bind(word)
+                    .bind(kana)
+                    .bind(pitch.unwrap_or("0"))
+                    .fetch_optional(&self.pool)
+                    .await?;
+                Ok(row.map(|(id,)| id))
+            },
+            Some(EntryKind::Grammar) => Ok(self.get_grammar_by_word(word).await?.map(|g| g.id)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for PostgresStorage {
+    async fn check_word_exists(&self, word: &str, kana: &str) -> Result<bool> {
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM words WHERE word = $1 AND kana = $2")
+            .bind(word)
+            .bind(kana)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count.0 > 0)
+    }
+
+    async fn get_existing_word_by_word_kana(&self, word: &str, kana: &str) -> Result<Option<JapaneseWord>> {
+        let result = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words WHERE word = $1 AND kana = $2 LIMIT 1", WORD_COLUMNS)
+        )
+        .bind(word)
+        .bind(kana)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    async fn get_existing_word(&self, word: &str, kana: &str, pitch: &str) -> Result<Option<MergedWord>> {
+        let result = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words WHERE word = $1 AND kana = $2 AND pitch = $3 LIMIT 1", WORD_COLUMNS)
+        )
+        .bind(word)
+        .bind(kana)
+        .bind(pitch)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result.map(|existing| MergedWord {
+            id: existing.id,
+            parts_of_speech: vec![existing.part_of_speech.clone()],
+            word: existing.word,
+            kana: existing.kana,
+            pitch: existing.pitch,
+            analysis: existing.analysis,
+        }))
+    }
+
+    async fn get_kana_locked_word(&self, word: &str) -> Result<Option<JapaneseWord>> {
+        let result = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words WHERE word = $1 AND kana_locked LIMIT 1", WORD_COLUMNS)
+        )
+        .bind(word)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    async fn get_all_words(&self) -> Result<Vec<JapaneseWord>> {
+        let words = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words ORDER BY id", WORD_COLUMNS)
+        ).fetch_all(&self.pool).await?;
+
+        Ok(words)
+    }
+
+    async fn get_word_by_id(&self, id: i64) -> Result<Option<JapaneseWord>> {
+        let word = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words WHERE id = $1", WORD_COLUMNS)
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(word)
+    }
+
+    async fn save_words(&self, words: &[WordAnalysis]) -> Result<()> {
+        for word in words {
+            let existing = sqlx::query_as::<_, JapaneseWord>(
+                &format!("SELECT {} FROM words WHERE word = $1 AND kana = $2 AND pitch = $3 LIMIT 1", WORD_COLUMNS)
+            )
+            .bind(&word.word)
+            .bind(&word.kana)
+            .bind(&word.pitch)
+            .fetch_optional(&self.pool)
+            .await?;
+
+            match existing {
+                Some(existing_word) => {
+                    if existing_word.part_of_speech == word.part_of_speech {
+                        println!("  ✅ 单词词性未变化，跳过更新: {} ({})", word.word, word.kana);
+                        continue;
+                    }
+                    if existing_word.pos_locked {
+                        println!("  🔒 单词词性已锁定，跳过更新: {} ({}) - {}",
+                            word.word, word.kana, existing_word.part_of_speech);
+                        continue;
+                    }
+
+                    println!("  🔄 更新单词词性: {} ({}) - {} -> {}",
+                        word.word, word.kana, existing_word.part_of_speech, word.part_of_speech);
+
+                    let (analysis, prompt_version) = if existing_word.analysis_locked {
+                        println!("  🔒 单词解析已锁定，保留原解析: {} ({})", word.word, word.kana);
+                        (&existing_word.analysis, existing_word.prompt_version)
+                    } else if word.analysis.is_empty() {
+                        // 只保存了其他语言解析的单词，保留原有的中文解析
+                        (&existing_word.analysis, existing_word.prompt_version)
+                    } else {
+                        (&word.analysis, word.prompt_version)
+                    };
+                    let mut tx = self.pool.begin().await?;
+                    sqlx::query(&format!(
+                        "UPDATE words SET part_of_speech = $1, analysis = $2, prompt_version = $3, updated_at = {} WHERE id = $4",
+                        NOW
+                    ))
+                    .bind(&word.part_of_speech)
+                    .bind(analysis)
+                    .bind(prompt_version)
+                    .bind(existing_word.id)
+                    .execute(&mut *tx)
+                    .await?;
+                    Self::write_word_pos(&mut tx, existing_word.id, &word.part_of_speech).await?;
+                    tx.commit().await?;
+
+                    self.reindex_entry(EntryKind::Word, existing_word.id).await?;
+                },
+                None => {
+                    println!("  ➕ 新增单词: {} ({}) - {}", word.word, word.kana, word.part_of_speech);
+                    let mut tx = self.pool.begin().await?;
+                    let (id,): (i64,) = sqlx::query_as(
+                        "INSERT INTO words (word, kana, pitch, part_of_speech, analysis, prompt_version) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id"
+                    )
+                    .bind(&word.word)
+                    .bind(&word.kana)
+                    .bind(&word.pitch)
+                    .bind(&word.part_of_speech)
+                    .bind(&word.analysis)
+                    .bind(word.prompt_version)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                    Self::write_word_pos(&mut tx, id, &word.part_of_speech).await?;
+                    tx.commit().await?;
+
+                    self.reindex_entry(EntryKind::Word, id).await?;
+                },
+            }
+        }
+        Ok(())
+    }
+
+    async fn update_word_part_of_speech(&self, id: i64, new_pos: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(&format!("UPDATE words SET part_of_speech = $1, updated_at = {} WHERE id = $2", NOW))
+            .bind(new_pos)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        Self::write_word_pos(&mut tx, id, new_pos).await?;
+        tx.commit().await?;
+
+        self.reindex_entry(EntryKind::Word, id).await?;
+        Ok(())
+    }
+
+    async fn update_word_pitch_and_pos(&self, id: i64, new_pitch: &str, new_pos: &str) -> Result<()> {
+        let current = self.get_word_by_id(id).await?
+            .ok_or_else(|| anyhow::anyhow!("单词 ID {} 不存在", id))?;
+
+        let existing_conflict = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words WHERE word = $1 AND kana = $2 AND pitch = $3 AND id != $4 LIMIT 1", WORD_COLUMNS)
+        )
+        .bind(&current.word)
+        .bind(&current.kana)
+        .bind(new_pitch)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(conflict_record) = existing_conflict {
+            if LockField::ALL.iter().any(|field| conflict_record.is_locked(*field)) {
+                println!("  🔒 冲突记录 ID {} 含有锁定字段，跳过更新 ID {}", conflict_record.id, id);
+                return Ok(());
+            }
+
+            println!("  🔄 发现冲突记录，删除旧记录 ID {}，更新当前记录 ID {}", conflict_record.id, id);
+
+            // 关联数据归并、删除冲突记录和更新当前记录放在同一个事务中，避免多人同时修改时出现中间状态
+            self.merge_word_into(conflict_record.id, id, new_pitch, new_pos).await?;
+
+            println!("  ✅ 冲突处理完成: {} ({}) - pitch: {}->{}, pos: {}->{}",
+                current.word, current.kana, current.pitch, new_pitch, current.part_of_speech, new_pos);
+        } else {
+            self.update_word_fields(id, new_pitch, new_pos).await?;
+        }
+
+        self.reindex_entry(EntryKind::Word, id).await?;
+        Ok(())
+    }
+
+    async fn update_word_analysis(&self, id: i64, new_analysis: &str, prompt_version: i64) -> Result<()> {
+        sqlx::query(&format!("UPDATE words SET analysis = $1, prompt_version = $2, updated_at = {} WHERE id = $3", NOW))
+            .bind(new_analysis)
+            .bind(prompt_version)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        self.reindex_entry(EntryKind::Word, id).await?;
+        Ok(())
+    }
+
+    async fn update_grammar_analysis(&self, id: i64, new_analysis: &str, prompt_version: i64) -> Result<()> {
+        sqlx::query(&format!("UPDATE grammar SET analysis = $1, prompt_version = $2, updated_at = {} WHERE id = $3", NOW))
+            .bind(new_analysis)
+            .bind(prompt_version)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        self.reindex_entry(EntryKind::Grammar, id).await?;
+        Ok(())
+    }
+
+    async fn save_grammar(&self, grammar: &[GrammarAnalysis]) -> Result<()> {
+        for item in grammar {
+            // 按规范写法和已记录的别名查找，变体和活用形合并到同一条目
+            let id = match self.get_grammar_by_word(&item.grammar).await? {
+                Some(existing) => {
+                    if existing.analysis_locked || existing.kana_locked {
+                        println!("  🔒 语法存在锁定字段，保留手动编辑内容: {}", item.grammar);
+                    }
+                    let kana = if existing.kana_locked { &existing.kana } else { &item.kana };
+                    // 解析锁定或只保存了其他语言解析时保留原有的中文解析
+                    let (analysis, prompt_version) = if existing.analysis_locked || item.analysis.is_empty() {
+                        (&existing.analysis, existing.prompt_version)
+                    } else {
+                        (&item.analysis, item.prompt_version)
+                    };
+
+                    sqlx::query(&format!(
+                        "UPDATE grammar SET kana = $1, analysis = $2, prompt_version = $3, updated_at = {} WHERE id = $4",
+                        NOW
+                    ))
+                    .bind(kana)
+                    .bind(analysis)
+                    .bind(prompt_version)
+                    .bind(existing.id)
+                    .execute(&self.pool)
+                    .await?;
+
+                    existing.id
+                },
+                None => {
+                    let (id,): (i64,) = sqlx::query_as(
+                        "INSERT INTO grammar (word, kana, analysis, prompt_version) VALUES ($1, $2, $3, $4) RETURNING id"
+                    )
+                    .bind(canonical_grammar(&item.grammar))
+                    .bind(&item.kana)
+                    .bind(&item.analysis)
+                    .bind(item.prompt_version)
+                    .fetch_one(&self.pool)
+                    .await?;
+
+                    id
+                },
+            };
+
+            self.reindex_entry(EntryKind::Grammar, id).await?;
+            if let Some(entry) = self.get_grammar_by_id(id).await? {
+                self.add_grammar_alias(&entry, &item.grammar).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_all_grammar(&self) -> Result<Vec<JapaneseGrammar>> {
+        let grammar = sqlx::query_as::<_, JapaneseGrammar>(
+            &format!("SELECT {} FROM grammar ORDER BY id", GRAMMAR_COLUMNS)
+        ).fetch_all(&self.pool).await?;
+
+        Ok(grammar)
+    }
+
+    async fn get_grammar_by_id(&self, id: i64) -> Result<Option<JapaneseGrammar>> {
+        let grammar = sqlx::query_as::<_, JapaneseGrammar>(
+            &format!("SELECT {} FROM grammar WHERE id = $1", GRAMMAR_COLUMNS)
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(grammar)
+    }
+
+    async fn get_grammar_by_word(&self, word: &str) -> Result<Option<JapaneseGrammar>> {
+        let grammar = sqlx::query_as::<_, JapaneseGrammar>(
+            &format!(
+                "SELECT {} FROM grammar WHERE word = $1 OR word = $2 OR id IN (SELECT grammar_id FROM grammar_aliases WHERE alias = $1) ORDER BY word = $1 DESC, word = $2 DESC LIMIT 1",
+                GRAMMAR_COLUMNS
+            )
+        )
+        .bind(word.trim())
+        .bind(canonical_grammar(word))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(grammar)
+    }
+
+    async fn get_grammar_aliases(&self, id: i64) -> Result<Vec<String>> {
+        let aliases: Vec<(String,)> = sqlx::query_as("SELECT alias FROM grammar_aliases WHERE grammar_id = $1 ORDER BY alias")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(aliases.into_iter().map(|(alias,)| alias).collect())
+    }
+
+    async fn update_entry_field(&self, kind: EntryKind, id: i64, field: LockField, value: &str) -> Result<()> {
+        if !field.applies_to(kind) {
+            return Err(anyhow::anyhow!("{}没有{}字段", kind.label(), field.label()));
+        }
+
+        let table = match kind {
+            EntryKind::Word => "words",
+            EntryKind::Grammar => "grammar",
+        };
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(&format!(
+            "UPDATE {} SET {} = $1, {} = TRUE, updated_at = {} WHERE id = $2",
+            table, field.value_column(), field.lock_column(), NOW
+        ))
+        .bind(value)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("{} ID {} 不存在", kind.label(), id));
+        }
+        if kind == EntryKind::Word && field == LockField::PartOfSpeech {
+            Self::write_word_pos(&mut tx, id, value).await?;
+        }
+        tx.commit().await?;
+
+        self.reindex_entry(kind, id).await?;
+        Ok(())
+    }
+
+    async fn set_entry_lock(&self, kind: EntryKind, id: i64, field: LockField, locked: bool) -> Result<()> {
+        if !field.applies_to(kind) {
+            return Err(anyhow::anyhow!("{}没有{}字段", kind.label(), field.label()));
+        }
+
+        let table = match kind {
+            EntryKind::Word => "words",
+            EntryKind::Grammar => "grammar",
+        };
+        let result = sqlx::query(&format!("UPDATE {} SET {} = $1 WHERE id = $2", table, field.lock_column()))
+            .bind(locked)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("{} ID {} 不存在", kind.label(), id));
+        }
+
+        Ok(())
+    }
+
+    async fn update_entry_levels(&self, kind: EntryKind, id: i64, frequency_rank: Option<i64>, jlpt_level: Option<&str>) -> Result<()> {
+        sqlx::query(&format!("UPDATE {} SET frequency_rank = $1, jlpt_level = $2 WHERE id = $3", kind.table()))
+            .bind(frequency_rank)
+            .bind(jlpt_level)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn ensure_user(&self, name: &str) -> Result<i64> {
+        sqlx::query("INSERT INTO users (name) VALUES ($1) ON CONFLICT (name) DO NOTHING")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+
+        let (id,): (i64,) = sqlx::query_as("SELECT id FROM users WHERE name = $1")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(id)
+    }
+
+    async fn set_entry_status(&self, user_id: i64, kind: EntryKind, word: &str, status: EntryStatus) -> Result<()> {
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO entry_status (user_id, entry_kind, word, status, updated_at)
+            VALUES ($1, $2, $3, $4, {})
+            ON CONFLICT (user_id, entry_kind, word) DO UPDATE SET status = excluded.status, updated_at = excluded.updated_at
+            "#,
+            NOW
+        ))
+        .bind(user_id)
+        .bind(kind.as_str())
+        .bind(word)
+        .bind(status.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_entry_statuses(&self, user_id: i64, kind: EntryKind) -> Result<HashMap<String, EntryStatus>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT word, status FROM entry_status WHERE user_id = $1 AND entry_kind = $2"
+        )
+        .bind(user_id)
+        .bind(kind.as_str())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(word, status)| Some((word, EntryStatus::parse(&status)?)))
+            .collect())
+    }
+
+    async fn get_entry_analyses(&self, kind: EntryKind, language: &str) -> Result<HashMap<i64, String>> {
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT entry_id, analysis FROM entry_analyses WHERE entry_kind = $1 AND language = $2"
+        )
+        .bind(kind.as_str())
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    async fn get_entry_analysis_versions(&self, kind: EntryKind, language: &str) -> Result<HashMap<i64, i64>> {
+        let rows: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT entry_id, prompt_version FROM entry_analyses WHERE entry_kind = $1 AND language = $2"
+        )
+        .bind(kind.as_str())
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    async fn save_entry_analysis(&self, kind: EntryKind, id: i64, language: &str, analysis: &str, prompt_version: i64) -> Result<()> {
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO entry_analyses (entry_kind, entry_id, language, analysis, prompt_version, updated_at)
+            VALUES ($1, $2, $3, $4, $5, {})
+            ON CONFLICT (entry_kind, entry_id, language) DO UPDATE SET analysis = excluded.analysis,
+                prompt_version = excluded.prompt_version, updated_at = excluded.updated_at
+            "#,
+            NOW
+        ))
+        .bind(kind.as_str())
+        .bind(id)
+        .bind(language)
+        .bind(analysis)
+        .bind(prompt_version)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_analysis_flag(&self, kind: EntryKind, id: i64, language: &str, reason: Option<&str>) -> Result<()> {
+        let query = match reason {
+            Some(_) => format!(
+                r#"
+                INSERT INTO analysis_flags (entry_kind, entry_id, language, reason, created_at)
+                VALUES ($1, $2, $3, $4, {})
+                ON CONFLICT (entry_kind, entry_id, language) DO UPDATE SET reason = excluded.reason,
+                    created_at = excluded.created_at
+                "#,
+                NOW
+            ),
+            None => "DELETE FROM analysis_flags WHERE entry_kind = $1 AND entry_id = $2 AND language = $3".to_string(),
+        };
+        let mut query = sqlx::query(&query).bind(kind.as_str()).bind(id).bind(language);
+        if let Some(reason) = reason {
+            query = query.bind(reason);
+        }
+        query.execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    async fn get_analysis_flags(&self, kind: EntryKind, language: &str) -> Result<HashMap<i64, String>> {
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT entry_id, reason FROM analysis_flags WHERE entry_kind = $1 AND language = $2"
+        )
+        .bind(kind.as_str())
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    async fn save_analysis_review(&self, kind: EntryKind, id: i64, language: &str, review: &AnalysisReview, model: &str) -> Result<()> {
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO analysis_reviews (entry_kind, entry_id, language, score, issues, model, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, {})
+            ON CONFLICT (entry_kind, entry_id, language) DO UPDATE SET score = excluded.score,
+                issues = excluded.issues, model = excluded.model, created_at = excluded.created_at
+            "#,
+            NOW
+        ))
+        .bind(kind.as_str())
+        .bind(id)
+        .bind(language)
+        .bind(review.score)
+        .bind(serde_json::to_string(&review.issues)?)
+        .bind(model)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_analysis_reviews(&self, kind: EntryKind, language: &str) -> Result<HashMap<i64, AnalysisReview>> {
+        let rows: Vec<(i64, i64, String)> = sqlx::query_as(
+            "SELECT entry_id, score, issues FROM analysis_reviews WHERE entry_kind = $1 AND language = $2"
+        )
+        .bind(kind.as_str())
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(id, score, issues)| {
+                let issues = serde_json::from_str(&issues)
+                    .map_err(|e| anyhow::anyhow!("审查结果格式错误: {}", e))?;
+                Ok((id, AnalysisReview { score, issues }))
+            })
+            .collect()
+    }
+
+    async fn save_word_details(&self, id: i64, language: &str, details: Option<&WordDetails>) -> Result<()> {
+        self.write_word_details(id, language, details, None).await
+    }
+
+    async fn get_word_details(&self, language: &str) -> Result<HashMap<i64, WordDetails>> {
+        let rows: Vec<(i64, String, String, String)> = sqlx::query_as(
+            "SELECT word_id, core_meaning, etymology, summary FROM word_details WHERE language = $1"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut details: HashMap<i64, WordDetails> = rows
+            .into_iter()
+            .map(|(id, core_meaning, etymology, summary)| (id, WordDetails {
+                core_meaning,
+                etymology,
+                summary,
+                ..Default::default()
+            }))
+            .collect();
+
+        let senses: Vec<(i64, String, String)> = sqlx::query_as(
+            "SELECT word_id, meaning, explanation FROM word_senses WHERE language = $1 ORDER BY word_id, position"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, meaning, explanation) in senses {
+            if let Some(entry) = details.get_mut(&id) {
+                entry.senses.push(WordSense { meaning, explanation, examples: Vec::new() });
+            }
+        }
+
+        let examples: Vec<(i64, i64, String, String, String)> = sqlx::query_as(
+            "SELECT word_id, sense_position, japanese, reading, translation FROM word_examples WHERE language = $1 ORDER BY word_id, sense_position, position"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, sense_position, japanese, reading, translation) in examples {
+            if let Some(sense) = details.get_mut(&id).and_then(|d| d.senses.get_mut(sense_position as usize)) {
+                sense.examples.push(ExampleSentence { japanese, reading, translation });
+            }
+        }
+
+        let comparisons: Vec<(i64, String, String)> = sqlx::query_as(
+            "SELECT word_id, other_word, difference FROM word_comparisons WHERE language = $1 ORDER BY word_id, position"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, word, difference) in comparisons {
+            if let Some(entry) = details.get_mut(&id) {
+                entry.comparisons.push(WordComparison { word, difference });
+            }
+        }
+
+        Ok(details)
+    }
+
+    async fn export_word_details(&self) -> Result<Vec<ExportedWordDetails>> {
+        let rows: Vec<(i64, String, String, String, Option<String>)> = sqlx::query_as(
+            "SELECT d.word_id, w.word, w.kana, d.language, d.updated_at FROM word_details d JOIN words w ON w.id = d.word_id ORDER BY d.word_id, d.language"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut by_language: HashMap<String, HashMap<i64, WordDetails>> = HashMap::new();
+        let mut exported = Vec::new();
+        for (id, word, kana, language, updated_at) in rows {
+            if !by_language.contains_key(&language) {
+                by_language.insert(language.clone(), self.get_word_details(&language).await?);
+            }
+            if let Some(details) = by_language.get_mut(&language).and_then(|d| d.remove(&id)) {
+                exported.push(ExportedWordDetails { word, kana, language, details, updated_at });
+            }
+        }
+
+        Ok(exported)
+    }
+
+    async fn import_word_details(&self, details: &ExportedWordDetails, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let word_id: Option<(i64,)> = sqlx::query_as("SELECT id FROM words WHERE word = $1 AND kana = $2 LIMIT 1")
+            .bind(&details.word)
+            .bind(&details.kana)
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some((word_id,)) = word_id else {
+            return Ok(ImportOutcome::Skipped);
+        };
+
+        let existing: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT updated_at FROM word_details WHERE word_id = $1 AND language = $2"
+        )
+        .bind(word_id)
+        .bind(&details.language)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let outcome = match &existing {
+            Some((updated_at,)) => {
+                if !should_replace(policy, updated_at.as_deref(), details.updated_at.as_deref()) {
+                    return Ok(ImportOutcome::Skipped);
+                }
+                ImportOutcome::Updated
+            },
+            None => ImportOutcome::Inserted,
+        };
+
+        self.write_word_details(word_id, &details.language, Some(&details.details), details.updated_at.as_deref()).await?;
+        Ok(outcome)
+    }
+
+    async fn save_grammar_details(&self, id: i64, language: &str, details: Option<&GrammarDetails>) -> Result<()> {
+        self.write_grammar_details(id, language, details, None).await
+    }
+
+    async fn get_grammar_details(&self, language: &str) -> Result<HashMap<i64, GrammarDetails>> {
+        let rows: Vec<(i64, String, String, String)> = sqlx::query_as(
+            "SELECT grammar_id, meaning, register, usage FROM grammar_details WHERE language = $1"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut details: HashMap<i64, GrammarDetails> = rows
+            .into_iter()
+            .map(|(id, meaning, register, usage)| (id, GrammarDetails {
+                meaning,
+                register: GrammarRegister::from_key(&register).unwrap_or_default(),
+                usage,
+                ..Default::default()
+            }))
+            .collect();
+
+        let connections: Vec<(i64, String, String, String)> = sqlx::query_as(
+            "SELECT grammar_id, base, form, note FROM grammar_connections WHERE language = $1 ORDER BY grammar_id, position"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, base, form, note) in connections {
+            if let Some(entry) = details.get_mut(&id) {
+                let base = ConnectionBase::from_key(&base).unwrap_or_default();
+                entry.connections.push(ConnectionRule { base, form, note });
+            }
+        }
+
+        let examples: Vec<(i64, String, String, String)> = sqlx::query_as(
+            "SELECT grammar_id, japanese, reading, translation FROM grammar_examples WHERE language = $1 ORDER BY grammar_id, position"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, japanese, reading, translation) in examples {
+            if let Some(entry) = details.get_mut(&id) {
+                entry.examples.push(ExampleSentence { japanese, reading, translation });
+            }
+        }
+
+        let related: Vec<(i64, String, String, Option<i64>)> = sqlx::query_as(
+            "SELECT r.grammar_id, r.related_grammar, r.difference, COALESCE(g.id, a.grammar_id) FROM grammar_related r LEFT JOIN grammar g ON g.word = r.related_grammar LEFT JOIN grammar_aliases a ON a.alias = r.related_grammar WHERE r.language = $1 ORDER BY r.grammar_id, r.position"
+        )
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, grammar, difference, grammar_id) in related {
+            if let Some(entry) = details.get_mut(&id) {
+                entry.related.push(RelatedGrammar { grammar, difference, grammar_id });
+            }
+        }
+
+        Ok(details)
+    }
+
+    async fn export_grammar_details(&self) -> Result<Vec<ExportedGrammarDetails>> {
+        let rows: Vec<(i64, String, String, Option<String>)> = sqlx::query_as(
+            "SELECT d.grammar_id, g.word, d.language, d.updated_at FROM grammar_details d JOIN grammar g ON g.id = d.grammar_id ORDER BY d.grammar_id, d.language"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut by_language: HashMap<String, HashMap<i64, GrammarDetails>> = HashMap::new();
+        let mut exported = Vec::new();
+        for (id, grammar, language, updated_at) in rows {
+            if !by_language.contains_key(&language) {
+                by_language.insert(language.clone(), self.get_grammar_details(&language).await?);
+            }
+            if let Some(details) = by_language.get_mut(&language).and_then(|d| d.remove(&id)) {
+                exported.push(ExportedGrammarDetails { grammar, language, details, updated_at });
+            }
+        }
+
+        Ok(exported)
+    }
+
+    async fn import_grammar_details(&self, details: &ExportedGrammarDetails, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let Some(grammar_id) = self.get_grammar_by_word(&details.grammar).await?.map(|g| g.id) else {
+            return Ok(ImportOutcome::Skipped);
+        };
+
+        let existing: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT updated_at FROM grammar_details WHERE grammar_id = $1 AND language = $2"
+        )
+        .bind(grammar_id)
+        .bind(&details.language)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let outcome = match &existing {
+            Some((updated_at,)) => {
+                if !should_replace(policy, updated_at.as_deref(), details.updated_at.as_deref()) {
+                    return Ok(ImportOutcome::Skipped);
+                }
+                ImportOutcome::Updated
+            },
+            None => ImportOutcome::Inserted,
+        };
+
+        self.write_grammar_details(grammar_id, &details.language, Some(&details.details), details.updated_at.as_deref()).await?;
+        Ok(outcome)
+    }
+
+    async fn export_analyses(&self) -> Result<Vec<ExportedAnalysis>> {
+        let analyses = sqlx::query_as::<_, ExportedAnalysis>(ANALYSES_QUERY)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(analyses)
+    }
+
+    async fn import_analysis(&self, analysis: &ExportedAnalysis, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let entry_id: Option<(i64,)> = if analysis.entry_kind == EntryKind::Word.as_str() {
+            sqlx::query_as("SELECT id FROM words WHERE word = $1 AND kana = $2 LIMIT 1")
+                .bind(&analysis.word)
+                .bind(&analysis.kana)
+                .fetch_optional(&self.pool)
+                .await?
+        } else {
+            self.get_grammar_by_word(&analysis.word).await?.map(|g| (g.id,))
+        };
+        let Some((entry_id,)) = entry_id else {
+            return Ok(ImportOutcome::Skipped);
+        };
+
+        let existing: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT updated_at FROM entry_analyses WHERE entry_kind = $1 AND entry_id = $2 AND language = $3"
+        )
+        .bind(&analysis.entry_kind)
+        .bind(entry_id)
+        .bind(&analysis.language)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let outcome = match &existing {
+            Some((updated_at,)) => {
+                if !should_replace(policy, updated_at.as_deref(), analysis.updated_at.as_deref()) {
+                    return Ok(ImportOutcome::Skipped);
+                }
+                ImportOutcome::Updated
+            },
+            None => ImportOutcome::Inserted,
+        };
+
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO entry_analyses (entry_kind, entry_id, language, analysis, updated_at, prompt_version)
+            VALUES ($1, $2, $3, $4, COALESCE($5, {}), $6)
+            ON CONFLICT (entry_kind, entry_id, language) DO UPDATE SET analysis = excluded.analysis,
+                updated_at = excluded.updated_at, prompt_version = excluded.prompt_version
+            "#,
+            NOW
+        ))
+        .bind(&analysis.entry_kind)
+        .bind(entry_id)
+        .bind(&analysis.language)
+        .bind(&analysis.analysis)
+        .bind(&analysis.updated_at)
+        .bind(analysis.prompt_version)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(outcome)
+    }
+
+    async fn save_kanji(&self, info: &KanjiInfo) -> Result<i64> {
+        let (id,): (i64,) = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO kanji (character, on_readings, kun_readings, meanings, stroke_count, components)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (character) DO UPDATE SET
+                on_readings = excluded.on_readings, kun_readings = excluded.kun_readings,
+                meanings = excluded.meanings, stroke_count = excluded.stroke_count,
+                components = excluded.components, updated_at = {}
+            RETURNING id
+            "#,
+            NOW
+        ))
+        .bind(&info.character)
+        .bind(info.on_readings.join("、"))
+        .bind(info.kun_readings.join("、"))
+        .bind(info.meanings.join("; "))
+        .bind(info.stroke_count)
+        .bind(info.components.join(" "))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn link_kanji_words(&self, kanji_id: i64, word_ids: &[i64]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM kanji_words WHERE kanji_id = $1")
+            .bind(kanji_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for word_id in word_ids {
+            sqlx::query("INSERT INTO kanji_words (kanji_id, word_id) VALUES ($1, $2)")
+                .bind(kanji_id)
+                .bind(word_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_all_kanji(&self) -> Result<Vec<Kanji>> {
+        let kanji = sqlx::query_as::<_, Kanji>(
+            "SELECT id, character, on_readings, kun_readings, meanings, stroke_count, components, mnemonic, updated_at FROM kanji ORDER BY id"
+        ).fetch_all(&self.pool).await?;
+
+        Ok(kanji)
+    }
+
+    async fn get_kanji_word_links(&self) -> Result<Vec<(i64, i64)>> {
+        let links = sqlx::query_as("SELECT kanji_id, word_id FROM kanji_words ORDER BY kanji_id, word_id")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(links)
+    }
+
+    async fn update_kanji_mnemonic(&self, id: i64, mnemonic: &str) -> Result<()> {
+        sqlx::query(&format!("UPDATE kanji SET mnemonic = $1, updated_at = {} WHERE id = $2", NOW))
+            .bind(mnemonic)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn export_kanji(&self) -> Result<Vec<ExportedKanji>> {
+        let links: Vec<(i64, String, String, String)> = sqlx::query_as(
+            "SELECT kw.kanji_id, w.word, w.kana, w.pitch FROM kanji_words kw JOIN words w ON w.id = kw.word_id ORDER BY kw.kanji_id, w.id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut words: HashMap<i64, Vec<ExportedEntryRef>> = HashMap::new();
+        for (kanji_id, word, kana, pitch) in links {
+            words.entry(kanji_id).or_default().push(ExportedEntryRef {
+                entry_kind: EntryKind::Word.as_str().to_string(),
+                word,
+                kana,
+                pitch: Some(pitch),
+            });
+        }
+
+        Ok(self.get_all_kanji().await?.into_iter().map(|k| ExportedKanji {
+            words: words.remove(&k.id).unwrap_or_default(),
+            character: k.character,
+            on_readings: k.on_readings,
+            kun_readings: k.kun_readings,
+            meanings: k.meanings,
+            stroke_count: k.stroke_count,
+            components: k.components,
+            mnemonic: k.mnemonic,
+            updated_at: k.updated_at,
+        }).collect())
+    }
+
+    async fn import_kanji(&self, kanji: &ExportedKanji, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let existing: Option<(i64, Option<String>)> = sqlx::query_as("SELECT id, updated_at FROM kanji WHERE character = $1")
+            .bind(&kanji.character)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let (kanji_id, outcome) = match existing {
+            Some((id, updated_at)) if !should_replace(policy, updated_at.as_deref(), kanji.updated_at.as_deref()) => {
+                (id, ImportOutcome::Skipped)
+            },
+            existing => {
+                let (id,): (i64,) = sqlx::query_as(&format!(
+                    r#"
+                    INSERT INTO kanji (character, on_readings, kun_readings, meanings, stroke_count, components, mnemonic, updated_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, COALESCE($8, {}))
+                    ON CONFLICT (character) DO UPDATE SET
+                        on_readings = excluded.on_readings, kun_readings = excluded.kun_readings,
+                        meanings = excluded.meanings, stroke_count = excluded.stroke_count,
+                        components = excluded.components, mnemonic = excluded.mnemonic, updated_at = excluded.updated_at
+                    RETURNING id
+                    "#,
+                    NOW
+                ))
+                .bind(&kanji.character)
+                .bind(&kanji.on_readings)
+                .bind(&kanji.kun_readings)
+                .bind(&kanji.meanings)
+                .bind(kanji.stroke_count)
+                .bind(&kanji.components)
+                .bind(&kanji.mnemonic)
+                .bind(&kanji.updated_at)
+                .fetch_one(&self.pool)
+                .await?;
+                (id, if existing.is_some() { ImportOutcome::Updated } else { ImportOutcome::Inserted })
+            },
+        };
+
+        for word in &kanji.words {
+            if let Some(word_id) = self.find_entry_id(&word.entry_kind, &word.word, &word.kana, word.pitch.as_deref()).await? {
+                sqlx::query("INSERT INTO kanji_words (kanji_id, word_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+                    .bind(kanji_id)
+                    .bind(word_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    async fn save_sentence(&self, sentence: &ExampleSentence, source: SentenceSource, document: Option<&str>) -> Result<i64> {
+        let (id,): (i64,) = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO sentences (japanese, reading, translation, source, document)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (japanese) DO UPDATE SET
+                reading = CASE WHEN sentences.reading = '' THEN excluded.reading ELSE sentences.reading END,
+                translation = CASE WHEN sentences.translation = '' THEN excluded.translation ELSE sentences.translation END,
+                updated_at = {}
+            RETURNING id
+            "#,
+            NOW
+        ))
+        .bind(sentence.japanese.trim())
+        .bind(sentence.reading.trim())
+        .bind(sentence.translation.trim())
+        .bind(source.as_str())
+        .bind(document)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn link_sentence(&self, sentence_id: i64, kind: EntryKind, entry_id: i64) -> Result<bool> {
+        let result = sqlx::query("INSERT INTO sentence_entries (sentence_id, entry_kind, entry_id) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING")
+            .bind(sentence_id)
+            .bind(kind.as_str())
+            .bind(entry_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn update_sentence_translation(&self, id: i64, reading: &str, translation: &str) -> Result<()> {
+        sqlx::query(&format!("UPDATE sentences SET reading = $1, translation = $2, updated_at = {} WHERE id = $3", NOW))
+            .bind(reading)
+            .bind(translation)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_all_sentences(&self) -> Result<Vec<Sentence>> {
+        let sentences = sqlx::query_as::<_, Sentence>(
+            "SELECT id, japanese, reading, translation, source, document, updated_at FROM sentences ORDER BY id"
+        ).fetch_all(&self.pool).await?;
+
+        Ok(sentences)
+    }
+
+    async fn get_sentence_links(&self) -> Result<Vec<(i64, String, i64)>> {
+        let links = sqlx::query_as("SELECT sentence_id, entry_kind, entry_id FROM sentence_entries ORDER BY sentence_id, entry_kind, entry_id")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(links)
+    }
+
+    async fn get_sentences_for_entry(&self, kind: EntryKind, id: i64) -> Result<Vec<Sentence>> {
+        let sentences = sqlx::query_as::<_, Sentence>(
+            r#"
+            SELECT s.id, s.japanese, s.reading, s.translation, s.source, s.document, s.updated_at
+            FROM sentences s
+            JOIN sentence_entries e ON e.sentence_id = s.id
+            WHERE e.entry_kind = $1 AND e.entry_id = $2
+            ORDER BY s.id
+            "#
+        )
+        .bind(kind.as_str())
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sentences)
+    }
+
+    async fn export_sentences(&self) -> Result<Vec<ExportedSentence>> {
+        let links: Vec<(i64, String, String, String, Option<String>)> = sqlx::query_as(SENTENCE_ENTRIES_QUERY)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut entries: HashMap<i64, Vec<ExportedEntryRef>> = HashMap::new();
+        for (sentence_id, entry_kind, word, kana, pitch) in links {
+            entries.entry(sentence_id).or_default().push(ExportedEntryRef { entry_kind, word, kana, pitch });
+        }
+
+        Ok(self.get_all_sentences().await?.into_iter().map(|s| ExportedSentence {
+            entries: entries.remove(&s.id).unwrap_or_default(),
+            japanese: s.japanese,
+            reading: s.reading,
+            translation: s.translation,
+            source: s.source,
+            document: s.document,
+            updated_at: s.updated_at,
+        }).collect())
+    }
+
+    async fn import_sentence(&self, sentence: &ExportedSentence, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let existing: Option<(i64, Option<String>)> = sqlx::query_as("SELECT id, updated_at FROM sentences WHERE japanese = $1")
+            .bind(&sentence.japanese)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let (sentence_id, outcome) = match existing {
+            Some((id, updated_at)) if !should_replace(policy, updated_at.as_deref(), sentence.updated_at.as_deref()) => {
+                (id, ImportOutcome::Skipped)
+            },
+            existing => {
+                let (id,): (i64,) = sqlx::query_as(&format!(
+                    r#"
+                    INSERT INTO sentences (japanese, reading, translation, source, document, updated_at)
+                    VALUES ($1, $2, $3, $4, $5, COALESCE($6, {}))
+                    ON CONFLICT (japanese) DO UPDATE SET
+                        reading = excluded.reading, translation = excluded.translation, source = excluded.source,
+                        document = excluded.document, updated_at = excluded.updated_at
+                    RETURNING id
+                    "#,
+                    NOW
+                ))
+                .bind(&sentence.japanese)
+                .bind(&sentence.reading)
+                .bind(&sentence.translation)
+                .bind(&sentence.source)
+                .bind(&sentence.document)
+                .bind(&sentence.updated_at)
+                .fetch_one(&self.pool)
+                .await?;
+                (id, if existing.is_some() { ImportOutcome::Updated } else { ImportOutcome::Inserted })
+            },
+        };
+
+        for entry in &sentence.entries {
+            if let Some(entry_id) = self.find_entry_id(&entry.entry_kind, &entry.word, &entry.kana, entry.pitch.as_deref()).await? {
+                sqlx::query("INSERT INTO sentence_entries (sentence_id, entry_kind, entry_id) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING")
+                    .bind(sentence_id)
+                    .bind(&entry.entry_kind)
+                    .bind(entry_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    async fn get_review_cards(&self, user_id: i64) -> Result<Vec<ReviewCard>> {
+        let cards = sqlx::query_as::<_, ReviewCard>(
+            "SELECT id, user_id, entry_kind, entry_id, state, due, stability, difficulty, reps, lapses, last_review FROM review_cards WHERE user_id = $1 ORDER BY due"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(cards)
+    }
+
+    async fn count_cards_introduced(&self, user_id: i64, since: &str) -> Result<i64> {
+        let (count,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM review_cards WHERE user_id = $1 AND created_at >= $2"
+        )
+        .bind(user_id)
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn save_review(&self, card: &ReviewCard, log: &ReviewLogEntry) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+
+        let (card_id,): (i64,) = sqlx::query_as(
+            r#"
+            INSERT INTO review_cards (user_id, entry_kind, entry_id, state, due, stability, difficulty, reps, lapses, last_review)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (user_id, entry_kind, entry_id) DO UPDATE SET
+                state = excluded.state, due = excluded.due, stability = excluded.stability,
+                difficulty = excluded.difficulty, reps = excluded.reps, lapses = excluded.lapses,
+                last_review = excluded.last_review
+            RETURNING id
+            "#
+        )
+        .bind(card.user_id)
+        .bind(&card.entry_kind)
+        .bind(card.entry_id)
+        .bind(&card.state)
+        .bind(&card.due)
+        .bind(card.stability)
+        .bind(card.difficulty)
+        .bind(card.reps)
+        .bind(card.lapses)
+        .bind(&card.last_review)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO review_log (card_id, user_id, ease, ivl, last_ivl, time_ms, review_type,
+                stability, difficulty, elapsed_days, reviewed_at, reviewed_at_ms)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            "#
+        )
+        .bind(card_id)
+        .bind(card.user_id)
+        .bind(log.ease)
+        .bind(log.ivl)
+        .bind(log.last_ivl)
+        .bind(log.time_ms)
+        .bind(log.review_type)
+        .bind(card.stability)
+        .bind(card.difficulty)
+        .bind(log.elapsed_days)
+        .bind(&log.reviewed_at)
+        .bind(log.reviewed_at_ms)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(card_id)
+    }
+
+    async fn export_review_log(&self, user_id: i64) -> Result<Vec<RevlogRecord>> {
+        let records = sqlx::query_as::<_, RevlogRecord>(REVLOG_QUERY)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(records)
+    }
+
+    async fn get_cached_response(&self, key: &str, since: Option<&str>) -> Result<Option<String>> {
+        let row: Option<(String,)> = sqlx::query_as(
+            "SELECT response FROM response_cache WHERE key = $1 AND created_at >= $2"
+        )
+        .bind(key)
+        .bind(since.unwrap_or(""))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|(response,)| response))
+    }
+
+    async fn save_cached_response(&self, entry: &CachedResponse) -> Result<()> {
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO response_cache (key, provider, model, temperature, max_tokens, response, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, {})
+            ON CONFLICT (key) DO UPDATE SET response = excluded.response, created_at = excluded.created_at
+            "#,
+            NOW
+        ))
+        .bind(&entry.key)
+        .bind(&entry.provider)
+        .bind(&entry.model)
+        .bind(entry.temperature)
+        .bind(entry.max_tokens)
+        .bind(&entry.response)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn purge_cached_responses(&self, before: &str) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM response_cache WHERE created_at < $1")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn save_api_call(&self, call: &ApiCall) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO api_calls (run_id, operation, model, prompt_tokens, completion_tokens, cost)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#
+        )
+        .bind(&call.run_id)
+        .bind(&call.operation)
+        .bind(&call.model)
+        .bind(call.prompt_tokens)
+        .bind(call.completion_tokens)
+        .bind(call.cost)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_api_usage(&self, run_id: Option<&str>) -> Result<Vec<ApiUsageSummary>> {
+        let summaries = sqlx::query_as::<_, ApiUsageSummary>(
+            r#"
+            SELECT operation, COUNT(*) AS requests,
+                   COALESCE(SUM(prompt_tokens), 0)::BIGINT AS prompt_tokens,
+                   COALESCE(SUM(completion_tokens), 0)::BIGINT AS completion_tokens,
+                   SUM(cost) AS cost
+            FROM api_calls
+            WHERE $1::TEXT IS NULL OR run_id = $1
+            GROUP BY operation
+            ORDER BY operation
+            "#
+        )
+        .bind(run_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(summaries)
+    }
+
+    async fn record_occurrence(&self, kind: EntryKind, id: i64, document: &str) -> Result<()> {
+        sqlx::query("INSERT INTO occurrences (entry_kind, entry_id, document) VALUES ($1, $2, $3)")
+            .bind(kind.as_str())
+            .bind(id)
+            .bind(document)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_occurrence_counts(&self, kind: EntryKind) -> Result<HashMap<i64, i64>> {
+        let rows: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT entry_id, COUNT(*) FROM occurrences WHERE entry_kind = $1 GROUP BY entry_id"
+        )
+        .bind(kind.as_str())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        let text = query.text.trim();
+        let mut sql = String::from(
+            "SELECT kind, entry_id, word, kana, romaji, part_of_speech, analysis_text, tags, updated_at FROM search_index WHERE TRUE"
+        );
+        let mut binds: Vec<String> = Vec::new();
+
+        // PostgreSQL 后端使用 ILIKE 子串匹配
+        if !text.is_empty() {
+            sql.push_str(&format!(
+                " AND (word ILIKE {p} OR kana ILIKE {p} OR romaji ILIKE {p} OR part_of_speech ILIKE {p} OR analysis_text ILIKE {p} OR tags ILIKE {p})",
+                p = "$1 ESCAPE '\\'"
+            ));
+            binds.push(format!("%{}%", escape_like(text)));
+        }
+
+        let mut filters: Vec<(&str, String)> = Vec::new();
+        if let Some(kind) = query.kind {
+            filters.push(("kind = {}", kind.as_str().to_string()));
+        }
+        if let Some(pos) = &query.part_of_speech {
+            // 能识别的词性按关联表精确过滤，否则按文本匹配
+            match normalize_label(pos).first() {
+                Some(pos) => filters.push((
+                    "kind = 'word' AND entry_id IN (SELECT word_id FROM word_pos WHERE pos = {})",
+                    pos.key().to_string(),
+                )),
+                None => filters.push(("part_of_speech ILIKE {} ESCAPE '\\'", format!("%{}%", escape_like(pos)))),
+            }
+        }
+        if let Some(from) = &query.date_from {
+            filters.push(("substr(updated_at, 1, 10) >= {}", from.clone()));
+        }
+        if let Some(to) = &query.date_to {
+            filters.push(("substr(updated_at, 1, 10) <= {}", to.clone()));
+        }
+        if let Some(tag) = &query.tag {
+            filters.push(("(' ' || tags || ' ') LIKE {} ESCAPE '\\'", format!("% {} %", escape_like(tag))));
+        }
+        if let Some(document) = &query.document {
+            filters.push((
+                "EXISTS (SELECT 1 FROM occurrences o WHERE o.entry_kind = search_index.kind AND o.entry_id = search_index.entry_id AND o.document ILIKE {} ESCAPE '\\')",
+                format!("%{}%", escape_like(document)),
+            ));
+        }
+        for (condition, value) in filters {
+            let p = format!("${}", binds.len() + 1);
+            sql.push_str(" AND ");
+            sql.push_str(&condition.replace("{}", &p));
+            binds.push(value);
+        }
+
+        sql.push_str(&format!(
+            " ORDER BY kind DESC, entry_id LIMIT {}",
+            if query.limit == 0 { 50 } else { query.limit }
+        ));
+
+        let mut db_query = sqlx::query_as::<_, (String, i64, String, String, String, String, String, String, Option<String>)>(&sql);
+        for value in &binds {
+            db_query = db_query.bind(value);
+        }
+        let rows = db_query.fetch_all(&self.pool).await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(kind, id, word, kana, romaji, part_of_speech, analysis_text, tags, updated_at)| {
+                Some(SearchHit {
+                    kind: EntryKind::parse(&kind)?,
+                    id,
+                    word,
+                    kana,
+                    romaji,
+                    part_of_speech,
+                    snippet: make_snippet(&analysis_text, text),
+                    tags,
+                    updated_at,
+                })
+            })
+            .collect())
+    }
+
+    async fn export_words(&self) -> Result<Vec<ExportedWord>> {
+        let words = sqlx::query_as::<_, ExportedWord>(
+            "SELECT word, kana, pitch, part_of_speech, analysis, created_at, updated_at, analysis_locked, pos_locked, pitch_locked, kana_locked, tags, frequency_rank, jlpt_level, prompt_version FROM words ORDER BY id"
+        ).fetch_all(&self.pool).await?;
+
+        Ok(words)
+    }
+
+    async fn export_grammar(&self) -> Result<Vec<ExportedGrammar>> {
+        let grammar = sqlx::query_as::<_, ExportedGrammar>(
+            "SELECT word, kana, analysis, created_at, updated_at, analysis_locked, kana_locked, tags, frequency_rank, jlpt_level, prompt_version FROM grammar ORDER BY id"
+        ).fetch_all(&self.pool).await?;
+
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT g.word, a.alias FROM grammar_aliases a JOIN grammar g ON g.id = a.grammar_id ORDER BY a.alias"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut aliases: HashMap<String, Vec<String>> = HashMap::new();
+        for (word, alias) in rows {
+            aliases.entry(word).or_default().push(alias);
+        }
+
+        Ok(grammar.into_iter().map(|g| ExportedGrammar { aliases: aliases.remove(&g.word).unwrap_or_default(), ..g }).collect())
+    }
+
+    async fn export_occurrences(&self) -> Result<Vec<ExportedOccurrence>> {
+        let occurrences = sqlx::query_as::<_, ExportedOccurrence>(
+            r#"
+            SELECT o.entry_kind, w.word, w.kana, w.pitch, o.document, o.created_at
+            FROM occurrences o JOIN words w ON o.entry_kind = 'word' AND o.entry_id = w.id
+            UNION ALL
+            SELECT o.entry_kind, g.word, g.kana, NULL, o.document, o.created_at
+            FROM occurrences o JOIN grammar g ON o.entry_kind = 'grammar' AND o.entry_id = g.id
+            "#
+        ).fetch_all(&self.pool).await?;
+
+        Ok(occurrences)
+    }
+
+    async fn import_word(&self, word: &ExportedWord, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let existing = sqlx::query_as::<_, JapaneseWord>(
+            &format!("SELECT {} FROM words WHERE word = $1 AND kana = $2 AND pitch = $3 LIMIT 1", WORD_COLUMNS)
+        )
+        .bind(&word.word)
+        .bind(&word.kana)
+        .bind(&word.pitch)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match existing {
+            Some(existing) => {
+                if !should_replace(policy, existing.updated_at.as_deref(), word.updated_at.as_deref()) {
+                    return Ok(ImportOutcome::Skipped);
+                }
+
+                let mut tx = self.pool.begin().await?;
+                sqlx::query(&format!(
+                    r#"
+                    UPDATE words SET part_of_speech = $1, analysis = $2, updated_at = COALESCE($3, {now}),
+                        analysis_locked = $4, pos_locked = $5, pitch_locked = $6, kana_locked = $7, tags = $8,
+                        frequency_rank = $9, jlpt_level = $10, prompt_version = $11
+                    WHERE id = $12
+                    "#,
+                    now = NOW
+                ))
+                .bind(&word.part_of_speech)
+                .bind(&word.analysis)
+                .bind(&word.updated_at)
+                .bind(word.analysis_locked)
+                .bind(word.pos_locked)
+                .bind(word.pitch_locked)
+                .bind(word.kana_locked)
+                .bind(&word.tags)
+                .bind(word.frequency_rank)
+                .bind(&word.jlpt_level)
+                .bind(word.prompt_version)
+                .bind(existing.id)
+                .execute(&mut *tx)
+                .await?;
+                Self::write_word_pos(&mut tx, existing.id, &word.part_of_speech).await?;
+                tx.commit().await?;
+
+                self.reindex_entry(EntryKind::Word, existing.id).await?;
+                Ok(ImportOutcome::Updated)
+            },
+            None => {
+                let mut tx = self.pool.begin().await?;
+                let (id,): (i64,) = sqlx::query_as(&format!(
+                    r#"
+                    INSERT INTO words (word, kana, pitch, part_of_speech, analysis, created_at, updated_at,
+                        analysis_locked, pos_locked, pitch_locked, kana_locked, tags, frequency_rank, jlpt_level, prompt_version)
+                    VALUES ($1, $2, $3, $4, $5, COALESCE($6, {now}), COALESCE($7, {now}), $8, $9, $10, $11, $12, $13, $14, $15)
+                    RETURNING id
+                    "#,
+                    now = NOW
+                ))
+                .bind(&word.word)
+                .bind(&word.kana)
+                .bind(&word.pitch)
+                .bind(&word.part_of_speech)
+                .bind(&word.analysis)
+                .bind(&word.created_at)
+                .bind(&word.updated_at)
+                .bind(word.analysis_locked)
+                .bind(word.pos_locked)
+                .bind(word.pitch_locked)
+                .bind(word.kana_locked)
+                .bind(&word.tags)
+                .bind(word.frequency_rank)
+                .bind(&word.jlpt_level)
+                .bind(word.prompt_version)
+                .fetch_one(&mut *tx)
+                .await?;
+                Self::write_word_pos(&mut tx, id, &word.part_of_speech).await?;
+                tx.commit().await?;
+
+                self.reindex_entry(EntryKind::Word, id).await?;
+                Ok(ImportOutcome::Inserted)
+            },
+        }
+    }
+
+    async fn import_grammar(&self, grammar: &ExportedGrammar, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let outcome = match self.get_grammar_by_word(&grammar.word).await? {
+            Some(existing) if !should_replace(policy, existing.updated_at.as_deref(), grammar.updated_at.as_deref()) => {
+                ImportOutcome::Skipped
+            },
+            Some(existing) => {
+                sqlx::query(&format!(
+                    r#"
+                    UPDATE grammar SET kana = $1, analysis = $2, updated_at = COALESCE($3, {now}),
+                        analysis_locked = $4, kana_locked = $5, tags = $6, frequency_rank = $7, jlpt_level = $8,
+                        prompt_version = $9
+                    WHERE id = $10
+                    "#,
+                    now = NOW
+                ))
+                .bind(&grammar.kana)
+                .bind(&grammar.analysis)
+                .bind(&grammar.updated_at)
+                .bind(grammar.analysis_locked)
+                .bind(grammar.kana_locked)
+                .bind(&grammar.tags)
+                .bind(grammar.frequency_rank)
+                .bind(&grammar.jlpt_level)
+                .bind(grammar.prompt_version)
+                .bind(existing.id)
+                .execute(&self.pool)
+                .await?;
+
+                self.reindex_entry(EntryKind::Grammar, existing.id).await?;
+                ImportOutcome::Updated
+            },
+            None => {
+                let (id,): (i64,) = sqlx::query_as(&format!(
+                    r#"
+                    INSERT INTO grammar (word, kana, analysis, created_at, updated_at, analysis_locked, kana_locked, tags,
+                        frequency_rank, jlpt_level, prompt_version)
+                    VALUES ($1, $2, $3, COALESCE($4, {now}), COALESCE($5, {now}), $6, $7, $8, $9, $10, $11)
+                    RETURNING id
+                    "#,
+                    now = NOW
+                ))
+                .bind(canonical_grammar(&grammar.word))
+                .bind(&grammar.kana)
+                .bind(&grammar.analysis)
+                .bind(&grammar.created_at)
+                .bind(&grammar.updated_at)
+                .bind(grammar.analysis_locked)
+                .bind(grammar.kana_locked)
+                .bind(&grammar.tags)
+                .bind(grammar.frequency_rank)
+                .bind(&grammar.jlpt_level)
+                .bind(grammar.prompt_version)
+                .fetch_one(&self.pool)
+                .await?;
+
+                self.reindex_entry(EntryKind::Grammar, id).await?;
+                ImportOutcome::Inserted
+            },
+        };
+
+        // 导入的写法和其他写法都指向合并后的条目，不受冲突策略影响
+        if let Some(entry) = self.get_grammar_by_word(&grammar.word).await? {
+            for alias in std::iter::once(&grammar.word).chain(&grammar.aliases) {
+                self.add_grammar_alias(&entry, alias).await?;
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    async fn import_occurrence(&self, occurrence: &ExportedOccurrence) -> Result<ImportOutcome> {
+        let entry_id = self.find_entry_id(&occurrence.entry_kind, &occurrence.word, &occurrence.kana, occurrence.pitch.as_deref()).await?;
+        let Some(entry_id) = entry_id else {
+            return Ok(ImportOutcome::Skipped);
+        };
+
+        let (duplicates,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM occurrences WHERE entry_kind = $1 AND entry_id = $2 AND document = $3 AND created_at IS NOT DISTINCT FROM $4"
+        )
+        .bind(&occurrence.entry_kind)
+        .bind(entry_id)
+        .bind(&occurrence.document)
+        .bind(&occurrence.created_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if duplicates > 0 {
+            return Ok(ImportOutcome::Skipped);
+        }
+
+        sqlx::query(&format!(
+            "INSERT INTO occurrences (entry_kind, entry_id, document, created_at) VALUES ($1, $2, $3, COALESCE($4, {}))",
+            NOW
+        ))
+        .bind(&occurrence.entry_kind)
+        .bind(entry_id)
+        .bind(&occurrence.document)
+        .bind(&occurrence.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(ImportOutcome::Inserted)
+    }
+
+    async fn export_statuses(&self) -> Result<Vec<ExportedStatus>> {
+        let statuses = sqlx::query_as::<_, ExportedStatus>(
+            r#"
+            SELECT u.name AS user_name, s.entry_kind, s.word, s.status, s.updated_at
+            FROM entry_status s JOIN users u ON s.user_id = u.id
+            ORDER BY u.name, s.entry_kind, s.word
+            "#
+        ).fetch_all(&self.pool).await?;
+
+        Ok(statuses)
+    }
+
+    async fn import_status(&self, status: &ExportedStatus, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let user_id = self.ensure_user(&status.user_name).await?;
+
+        let existing: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT updated_at FROM entry_status WHERE user_id = $1 AND entry_kind = $2 AND word = $3"
+        )
+        .bind(user_id)
+        .bind(&status.entry_kind)
+        .bind(&status.word)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let outcome = match &existing {
+            Some((updated_at,)) => {
+                if !should_replace(policy, updated_at.as_deref(), status.updated_at.as_deref()) {
+                    return Ok(ImportOutcome::Skipped);
+                }
+                ImportOutcome::Updated
+            },
+            None => ImportOutcome::Inserted,
+        };
+
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO entry_status (user_id, entry_kind, word, status, updated_at)
+            VALUES ($1, $2, $3, $4, COALESCE($5, {}))
+            ON CONFLICT (user_id, entry_kind, word) DO UPDATE SET status = excluded.status, updated_at = excluded.updated_at
+            "#,
+            NOW
+        ))
+        .bind(user_id)
+        .bind(&status.entry_kind)
+        .bind(&status.word)
+        .bind(&status.status)
+        .bind(&status.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(outcome)
+    }
+
+    async fn export_review_cards(&self) -> Result<Vec<ExportedReviewCard>> {
+        let cards = sqlx::query_as::<_, ExportedReviewCard>(REVIEW_CARDS_QUERY)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(cards)
+    }
+
+    async fn import_review_card(&self, card: &ExportedReviewCard, policy: ConflictPolicy) -> Result<ImportOutcome> {
+        let Some(entry_id) = self.find_entry_id(&card.entry_kind, &card.word, &card.kana, card.pitch.as_deref()).await? else {
+            return Ok(ImportOutcome::Skipped);
+        };
+        let user_id = self.ensure_user(&card.user_name).await?;
+
+        let existing: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT last_review FROM review_cards WHERE user_id = $1 AND entry_kind = $2 AND entry_id = $3"
+        )
+        .bind(user_id)
+        .bind(&card.entry_kind)
+        .bind(entry_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let outcome = match &existing {
+            Some((last_review,)) => {
+                if !should_replace(policy, last_review.as_deref(), card.last_review.as_deref()) {
+                    return Ok(ImportOutcome::Skipped);
+                }
+                ImportOutcome::Updated
+            },
+            None => ImportOutcome::Inserted,
+        };
+
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO review_cards (user_id, entry_kind, entry_id, state, due, stability, difficulty, reps, lapses, last_review, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, COALESCE($11, {}))
+            ON CONFLICT (user_id, entry_kind, entry_id) DO UPDATE SET
+                state = excluded.state, due = excluded.due, stability = excluded.stability,
+                difficulty = excluded.difficulty, reps = excluded.reps, lapses = excluded.lapses,
+                last_review = excluded.last_review
+            "#,
+            NOW
+        ))
+        .bind(user_id)
+        .bind(&card.entry_kind)
+        .bind(entry_id)
+        .bind(&card.state)
+        .bind(&card.due)
+        .bind(card.stability)
+        .bind(card.difficulty)
+        .bind(card.reps)
+        .bind(card.lapses)
+        .bind(&card.last_review)
+        .bind(&card.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(outcome)
+    }
+
+    async fn export_review_history(&self) -> Result<Vec<ExportedReviewLog>> {
+        let history = sqlx::query_as::<_, ExportedReviewLog>(REVIEW_HISTORY_QUERY)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(history)
+    }
+
+    async fn import_review_history(&self, review: &ExportedReviewLog) -> Result<ImportOutcome> {
+        let Some(entry_id) = self.find_entry_id(&review.entry_kind, &review.word, &review.kana, review.pitch.as_deref()).await? else {
+            return Ok(ImportOutcome::Skipped);
+        };
+        let card: Option<(i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT c.id, c.user_id FROM review_cards c JOIN users u ON c.user_id = u.id
+            WHERE u.name = $1 AND c.entry_kind = $2 AND c.entry_id = $3
+            "#
+        )
+        .bind(&review.user_name)
+        .bind(&review.entry_kind)
+        .bind(entry_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some((card_id, user_id)) = card else {
+            return Ok(ImportOutcome::Skipped);
+        };
+
+        let (duplicates,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM review_log WHERE card_id = $1 AND reviewed_at_ms = $2"
+        )
+        .bind(card_id)
+        .bind(review.reviewed_at_ms)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if duplicates > 0 {
+            return Ok(ImportOutcome::Skipped);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO review_log (card_id, user_id, ease, ivl, last_ivl, time_ms, review_type,
+                stability, difficulty, elapsed_days, reviewed_at, reviewed_at_ms)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            "#
+        )
+        .bind(card_id)
+        .bind(user_id)
+        .bind(review.ease)
+        .bind(review.ivl)
+        .bind(review.last_ivl)
+        .bind(review.time_ms)
+        .bind(review.review_type)
+        .bind(review.stability)
+        .bind(review.difficulty)
+        .bind(review.elapsed_days)
+        .bind(&review.reviewed_at)
+        .bind(review.reviewed_at_ms)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(ImportOutcome::Inserted)
+    }
+}
@@ -0,0 +1,922 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::config::LanguageConfig;
+
+/// 解析、词性名称和例句翻译使用的语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Default)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "zh")]
+    Chinese,
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "ja")]
+    Japanese,
+}
+
+impl Language {
+    /// 数据库 words/grammar 表 analysis 列保存的语言，其他语言的解析保存在 entry_analyses 表
+    pub const PRIMARY: Language = Language::Chinese;
+
+    pub const ALL: [Language; 3] = [Language::Chinese, Language::English, Language::Japanese];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::Chinese => "zh",
+            Language::English => "en",
+            Language::Japanese => "ja",
+        }
+    }
+
+    pub fn parse(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|l| l.code() == code.trim())
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Language::Chinese => "中文",
+            Language::English => "英语",
+            Language::Japanese => "日语",
+        }
+    }
+
+    // 用 explanation 语言书写的本语言名称
+    fn name_in(&self, explanation: Language) -> &'static str {
+        match (explanation, self) {
+            (Language::Chinese, Language::Chinese) => "中文",
+            (Language::Chinese, Language::English) => "英语",
+            (Language::Chinese, Language::Japanese) => "日语",
+            (Language::English, Language::Chinese) => "Chinese",
+            (Language::English, Language::English) => "English",
+            (Language::English, Language::Japanese) => "Japanese",
+            (Language::Japanese, Language::Chinese) => "中国語",
+            (Language::Japanese, Language::English) => "英語",
+            (Language::Japanese, Language::Japanese) => "日本語",
+        }
+    }
+}
+
+/// 提示词模板的用途
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    Extraction,
+    PartOfSpeech,
+    WordAnalysis,
+    GrammarAnalysis,
+    KanjiMnemonic,
+    SentenceTranslation,
+    AnalysisReview,
+}
+
+impl PromptKind {
+    pub const ALL: [PromptKind; 7] = [
+        PromptKind::Extraction,
+        PromptKind::PartOfSpeech,
+        PromptKind::WordAnalysis,
+        PromptKind::GrammarAnalysis,
+        PromptKind::KanjiMnemonic,
+        PromptKind::SentenceTranslation,
+        PromptKind::AnalysisReview,
+    ];
+
+    /// 模板文件名（不含语言和扩展名）
+    pub fn key(&self) -> &'static str {
+        match self {
+            PromptKind::Extraction => "extraction",
+            PromptKind::PartOfSpeech => "part_of_speech",
+            PromptKind::WordAnalysis => "word_analysis",
+            PromptKind::GrammarAnalysis => "grammar_analysis",
+            PromptKind::KanjiMnemonic => "kanji_mnemonic",
+            PromptKind::SentenceTranslation => "sentence_translation",
+            PromptKind::AnalysisReview => "analysis_review",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.key() == key)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PromptKind::Extraction => "提取单词和语法",
+            PromptKind::PartOfSpeech => "词性标注",
+            PromptKind::WordAnalysis => "单词解析",
+            PromptKind::GrammarAnalysis => "语法解析",
+            PromptKind::KanjiMnemonic => "汉字记忆口诀",
+            PromptKind::SentenceTranslation => "例句翻译",
+            PromptKind::AnalysisReview => "解析审查",
+        }
+    }
+
+    /// 模板可以使用的变量，第一个变量必须出现在模板中
+    pub fn variables(&self) -> &'static [&'static str] {
+        match self {
+            PromptKind::Extraction => &["text"],
+            PromptKind::PartOfSpeech => &["word", "kana", "pitch"],
+            PromptKind::WordAnalysis => &["word", "kana", "pitch", "pos", "translation"],
+            PromptKind::GrammarAnalysis => &["grammar", "kana", "translation"],
+            PromptKind::KanjiMnemonic => &["kanji", "components", "meanings", "on", "kun"],
+            PromptKind::SentenceTranslation => &["sentences", "translation"],
+            PromptKind::AnalysisReview => &["analysis", "entry"],
+        }
+    }
+
+    /// 是否按解析语言区分模板；提取、词性标注和解析审查返回 JSON，只有一个模板
+    pub fn is_localized(&self) -> bool {
+        !matches!(self, PromptKind::Extraction | PromptKind::PartOfSpeech | PromptKind::AnalysisReview)
+    }
+
+    /// 内置模板的版本，修改内置模板的内容时递增（单词和语法解析 v2 起返回结构化 JSON）
+    pub fn builtin_version(&self) -> i64 {
+        match self {
+            PromptKind::WordAnalysis | PromptKind::GrammarAnalysis => 2,
+            _ => 1,
+        }
+    }
+
+    fn builtin(&self, language: Language) -> &'static str {
+        match (self, language) {
+            (PromptKind::Extraction, _) => EXTRACTION,
+            (PromptKind::PartOfSpeech, _) => PART_OF_SPEECH,
+            (PromptKind::AnalysisReview, _) => ANALYSIS_REVIEW,
+            (PromptKind::WordAnalysis, Language::Chinese) => WORD_ANALYSIS_ZH,
+            (PromptKind::WordAnalysis, Language::English) => WORD_ANALYSIS_EN,
+            (PromptKind::WordAnalysis, Language::Japanese) => WORD_ANALYSIS_JA,
+            (PromptKind::GrammarAnalysis, Language::Chinese) => GRAMMAR_ANALYSIS_ZH,
+            (PromptKind::GrammarAnalysis, Language::English) => GRAMMAR_ANALYSIS_EN,
+            (PromptKind::GrammarAnalysis, Language::Japanese) => GRAMMAR_ANALYSIS_JA,
+            (PromptKind::KanjiMnemonic, Language::Chinese) => KANJI_MNEMONIC_ZH,
+            (PromptKind::KanjiMnemonic, Language::English) => KANJI_MNEMONIC_EN,
+            (PromptKind::KanjiMnemonic, Language::Japanese) => KANJI_MNEMONIC_JA,
+            (PromptKind::SentenceTranslation, Language::Chinese) => SENTENCE_TRANSLATION_ZH,
+            (PromptKind::SentenceTranslation, Language::English) => SENTENCE_TRANSLATION_EN,
+            (PromptKind::SentenceTranslation, Language::Japanese) => SENTENCE_TRANSLATION_JA,
+        }
+    }
+}
+
+// ---- 提取单词和语法 ----
+
+const EXTRACTION: &str = r#"
+请分析以下日语文本，提取出所有单词和语法点的基本信息：
+
+1. 单词部分：
+   - 将所有单词转换为辞书形（原形）
+   - 提供假名读音
+   - 提供音调（用0-9数字表示）
+   - 确定词性（请精确标注）
+
+2. 语法部分：
+   - 识别语法结构和表达方式
+   - 提供假名读音
+
+重要词性标注规则：
+- 动词必须明确标注为"自动词"或"他动词"，不要只写"动词"
+- 如果一个词既是自动词又是他动词，就标注["自动词", "他动词"]
+- 形容词分为"一类形容词"和"二类形容词"
+- 一律使用简体中文词性：名词、自动词、他动词、一类形容词、二类形容词、副词、连词、助词、感叹词等
+- 不要出现重复标注如"動詞｜自動詞"
+
+请用以下JSON格式返回结果（只需要基本信息，不需要详细解释）：
+{
+  "words": [
+    {
+      "word": "単语辞书形",
+      "kana": "かな",
+      "pitch": "0",
+      "part_of_speech": ["名词", "他动词"]
+    }
+  ],
+  "grammar": [
+    {
+      "grammar": "语法表达",
+      "kana": "かな"
+    }
+  ]
+}
+
+要分析的文本：
+{text}
+"#;
+
+// ---- 词性标注 ----
+
+const PART_OF_SPEECH: &str = r#"
+请重新分析这个日语单词的词性，只需要返回准确的词性信息：
+
+单词：{word}
+假名：{kana}
+音调：{pitch}
+
+请用以下JSON格式返回结果（只需要词性信息）：
+{
+  "part_of_speech": ["词性1", "词性2"]
+}
+
+重要词性标注规则：
+1. 动词必须明确标注为"自动词"或"他动词"，不要只写"动词"
+2. 如果一个词既是自动词又是他动词，就标注["自动词", "他动词"]
+3. 形容词分为"一类形容词"和"二类形容词"
+4. 一律使用简体中文词性标注：名词、自动词、他动词、一类形容词、二类形容词、副词、连词、助词、感叹词等
+5. 不要出现"動詞｜自動詞"这种重复标注
+6. 只返回JSON格式，不要添加其他内容
+"#;
+
+// ---- 单词解析 ----
+
+const WORD_ANALYSIS_ZH: &str = r#"
+请分析这个日语单词的用法，用JSON格式回复，参考以下示例：
+
+示例（单词：帯，假名：おび，音调：1，词性：名词）：
+{
+  "core_meaning": "「帯」（おび、obi）是一个日语名词，意思是<b>\"腰带\"、\"带子\"或\"地带\"</b>。根据语境有不同的含义，但核心都与\"带状物\"或\"区域\"有关。",
+  "senses": [
+    {
+      "meaning": "服饰上的\"腰带\" 👘",
+      "explanation": "最常见、最核心的用法，特指系在和服、浴衣等传统日本服饰上的宽腰带。",
+      "examples": [
+        {"japanese": "帯を締める", "reading": "obi o shimeru", "translation": "系腰带。"},
+        {"japanese": "着物と帯", "reading": "kimono to obi", "translation": "和服和腰带。"}
+      ]
+    },
+    {
+      "meaning": "\"地带\"、\"区域\" 🗺️",
+      "explanation": "带有比喻色彩，指某个具有特定特征的带状区域。",
+      "examples": [
+        {"japanese": "火山帯", "reading": "kazan tai", "translation": "火山带。"}
+      ]
+    }
+  ],
+  "etymology": "「帯」这个汉字本身就带有<b>\"束缚\"、\"带状\"</b>的含义，日语完整保留了这些核心概念。",
+  "summary": "总的来说，「帯」的核心概念是<b>\"带状物\"或\"带状区域\"</b>，既可以指实际的物品，也可以指抽象的区域。",
+  "comparisons": [
+    {"word": "ベルト", "difference": "「ベルト」是外来词，多指现代服饰的皮带，而「帯」更偏向传统文化，如和服腰带。"},
+    {"word": "紐", "difference": "「紐」通常指细绳、细带，「帯」则指较宽的带状物，且更正式。"}
+  ]
+}
+
+现在请按照上述格式分析：
+
+单词：{word}
+假名：{kana}
+音调：{pitch}
+词性：{pos}
+
+重要事项：
+1. 只返回JSON，不要使用markdown代码块，不要添加其他内容
+2. 文字中需要强调的部分使用<b></b>标签，绝不要使用**符号
+3. 如果有多个词性，请在senses中全面分析所有词性的用法
+4. etymology 为词源或汉字说明，没有可写的内容时留空字符串
+5. {translation}
+"#;
+
+const WORD_ANALYSIS_EN: &str = r#"
+Explain how this Japanese word is used. Reply in JSON, following this example:
+
+Example (word: 帯, kana: おび, pitch: 1, part of speech: noun):
+{
+  "core_meaning": "「帯」(おび, obi) is a Japanese noun meaning <b>\"sash\", \"belt\" or \"zone\"</b>. Its meanings vary with context, but all of them revolve around \"something band-shaped\".",
+  "senses": [
+    {
+      "meaning": "A sash worn with traditional clothing 👘",
+      "explanation": "The most common and central meaning: the wide sash tied around a kimono or yukata.",
+      "examples": [
+        {"japanese": "帯を締める", "reading": "obi o shimeru", "translation": "to tie a sash."}
+      ]
+    },
+    {
+      "meaning": "A zone or belt 🗺️",
+      "explanation": "A figurative use for a band-shaped area with particular features.",
+      "examples": [
+        {"japanese": "火山帯", "reading": "kazan tai", "translation": "volcanic belt."}
+      ]
+    }
+  ],
+  "etymology": "The kanji 帯 itself carries the idea of <b>binding</b> and <b>band-like shapes</b>.",
+  "summary": "In short, the core idea of 「帯」 is <b>\"a band\" or \"a band-shaped area\"</b>, covering both concrete objects and abstract regions.",
+  "comparisons": [
+    {"word": "ベルト", "difference": "「ベルト」 is a loanword for modern belts, while 「帯」 leans towards traditional clothing."},
+    {"word": "紐", "difference": "「紐」 is a thin string or cord; 「帯」 is wider and more formal."}
+  ]
+}
+
+Now analyse this word in the same format:
+
+Word: {word}
+Kana: {kana}
+Pitch accent: {pitch}
+Part of speech: {pos}
+
+Important:
+1. Reply with JSON only, do not use markdown code blocks or add anything else
+2. Use <b></b> for emphasis inside the text, never use **
+3. If the word has several parts of speech, cover all of them in senses
+4. etymology explains the origin or the kanji; leave it as an empty string if there is nothing to say
+5. {translation}
+"#;
+
+const WORD_ANALYSIS_JA: &str = r#"
+次の日本語の単語の使い方を、日本語で説明してください。次の例の形式に従って、JSONだけで回答してください。
+
+例（単語：帯、かな：おび、アクセント：1、品詞：名詞）：
+{
+  "core_meaning": "「帯」（おび）は<b>「着物の上から腰に巻く布」「帯状の区域」</b>を表す名詞です。どの意味も「帯状のもの」という中心的なイメージを持っています。",
+  "senses": [
+    {
+      "meaning": "和服の帯 👘",
+      "explanation": "最も基本的な意味で、着物や浴衣の上から締める幅の広い布を指します。",
+      "examples": [
+        {"japanese": "帯を締める", "reading": "おびをしめる", "translation": ""}
+      ]
+    },
+    {
+      "meaning": "帯状の区域 🗺️",
+      "explanation": "特定の性質を持つ細長い区域を比喩的に表します。",
+      "examples": [
+        {"japanese": "火山帯", "reading": "かざんたい", "translation": ""}
+      ]
+    }
+  ],
+  "etymology": "",
+  "summary": "まとめると、「帯」の中心的な意味は<b>「帯状のもの・区域」</b>で、具体的な物から抽象的な区域まで表します。",
+  "comparisons": [
+    {"word": "ベルト", "difference": "「ベルト」は洋服に使う外来語で、「帯」は主に和服に使います。"},
+    {"word": "紐", "difference": "「紐」は細いもの、「帯」は幅が広いものを指します。"}
+  ]
+}
+
+次の単語を同じ形式で説明してください：
+
+単語：{word}
+かな：{kana}
+アクセント：{pitch}
+品詞：{pos}
+
+注意事項：
+1. JSONだけを返し、markdownのコードブロックやその他の内容を付けない
+2. 文中の強調には<b></b>タグを使い、**は使わない
+3. 品詞が複数ある場合は、senses ですべての品詞の使い方を説明する
+4. etymology には語源や漢字の説明を書き、書くことがなければ空文字列にする
+5. {translation}
+"#;
+
+// ---- 语法解析 ----
+
+const GRAMMAR_ANALYSIS_ZH: &str = r#"
+请详细分析这个日语语法点，用JSON格式回复，参考以下示例：
+
+示例（语法：〜ている，假名：ている）：
+{
+  "meaning": "表示动作正在进行，或动作结果的状态持续。",
+  "register": "neutral",
+  "connections": [
+    {"base": "verb", "form": "て形", "note": ""}
+  ],
+  "usage": "持续动词接「ている」表示正在进行；瞬间动词接「ている」表示结果状态。口语中常省略为「てる」。",
+  "examples": [
+    {"japanese": "今、本を読んでいる。", "reading": "いま、ほんをよんでいる。", "translation": "现在正在看书。"},
+    {"japanese": "窓が開いている。", "reading": "まどがあいている。", "translation": "窗户开着。"}
+  ],
+  "related": [
+    {"grammar": "〜てある", "difference": "「てある」强调有人有意做了某事后留下的状态，多接他动词。"}
+  ]
+}
+
+现在请按照上述格式分析：
+
+语法：{grammar}
+假名：{kana}
+
+重要事项：
+1. 只返回JSON，不要使用markdown代码块，不要添加其他内容
+2. register 只能是 neutral（通用）、formal（正式）、casual（口语）、written（书面语）之一
+3. connections 中每条接续规则的 base 只能是 verb、i_adjective、na_adjective、noun、clause、other 之一，form 写要求的活用形（如て形、辞书形、普通形）
+4. related 中的 grammar 使用语法点的标准写法
+5. 文字中需要强调的部分使用<b></b>标签，绝不要使用**符号
+6. {translation}
+"#;
+
+const GRAMMAR_ANALYSIS_EN: &str = r#"
+Explain this Japanese grammar point in detail, in English. Reply in JSON, following this example:
+
+Example (grammar: 〜ている, kana: ている):
+{
+  "meaning": "Expresses an ongoing action, or a state that results from a completed action.",
+  "register": "neutral",
+  "connections": [
+    {"base": "verb", "form": "te-form", "note": ""}
+  ],
+  "usage": "With durative verbs it means the action is in progress; with instantaneous verbs it describes the resulting state. Often shortened to 「てる」 in speech.",
+  "examples": [
+    {"japanese": "今、本を読んでいる。", "reading": "いま、ほんをよんでいる。", "translation": "I am reading a book now."},
+    {"japanese": "窓が開いている。", "reading": "まどがあいている。", "translation": "The window is open."}
+  ],
+  "related": [
+    {"grammar": "〜てある", "difference": "「てある」 stresses a state left behind by someone's deliberate action and usually follows transitive verbs."}
+  ]
+}
+
+Now analyse this grammar point in the same format:
+
+Grammar: {grammar}
+Kana: {kana}
+
+Important:
+1. Reply with JSON only, do not use markdown code blocks or add anything else
+2. register must be one of neutral, formal, casual, written
+3. base in each connection rule must be one of verb, i_adjective, na_adjective, noun, clause, other; form is the required conjugated form (e.g. te-form, dictionary form, plain form)
+4. Use the standard notation of each grammar point in related
+5. Use <b></b> for emphasis inside the text, never use **
+6. {translation}
+"#;
+
+const GRAMMAR_ANALYSIS_JA: &str = r#"
+次の日本語の文法項目を、日本語で詳しく説明してください。次の例の形式に従って、JSONだけで回答してください。
+
+例（文法：〜ている、かな：ている）：
+{
+  "meaning": "動作が進行中であること、または動作の結果の状態が続いていることを表す。",
+  "register": "neutral",
+  "connections": [
+    {"base": "verb", "form": "て形", "note": ""}
+  ],
+  "usage": "継続動詞に付くと進行中、瞬間動詞に付くと結果の状態を表す。話し言葉では「てる」になることが多い。",
+  "examples": [
+    {"japanese": "今、本を読んでいる。", "reading": "いま、ほんをよんでいる。", "translation": ""},
+    {"japanese": "窓が開いている。", "reading": "まどがあいている。", "translation": ""}
+  ],
+  "related": [
+    {"grammar": "〜てある", "difference": "「てある」は誰かが意図的にした結果の状態を表し、主に他動詞に付く。"}
+  ]
+}
+
+次の文法項目を同じ形式で説明してください：
+
+文法：{grammar}
+かな：{kana}
+
+注意事項：
+1. JSONだけを返し、markdownのコードブロックやその他の内容を付けない
+2. register は neutral、formal、casual、written のいずれか
+3. connections の base は verb、i_adjective、na_adjective、noun、clause、other のいずれかで、form には必要な活用形（て形、辞書形、普通形など）を書く
+4. related の grammar には文法項目の標準的な表記を使う
+5. 文中の強調には<b></b>タグを使い、**は使わない
+6. {translation}
+"#;
+
+// ---- 汉字记忆口诀 ----
+
+const KANJI_MNEMONIC_ZH: &str = r#"
+请为这个日语汉字编写一句简短的中文记忆口诀，帮助记住字形和意思：
+
+汉字：{kanji}
+部件：{components}
+意思：{meanings}
+音读：{on}
+训读：{kun}
+
+要求：
+1. 结合部件讲一个容易记住的小故事或联想，不超过50字
+2. 直接回复口诀内容，不要添加标题、解释或markdown格式
+"#;
+
+const KANJI_MNEMONIC_EN: &str = r#"
+Write a short English mnemonic for this Japanese kanji that helps remember its shape and meaning:
+
+Kanji: {kanji}
+Components: {components}
+Meanings: {meanings}
+On readings: {on}
+Kun readings: {kun}
+
+Requirements:
+1. Build a memorable little story or association from the components, in at most 30 words
+2. Reply with the mnemonic only, without headings, explanations or markdown
+"#;
+
+const KANJI_MNEMONIC_JA: &str = r#"
+次の漢字の形と意味を覚えるための、短い日本語の覚え方を書いてください：
+
+漢字：{kanji}
+部品：{components}
+意味：{meanings}
+音読み：{on}
+訓読み：{kun}
+
+条件：
+1. 部品を使って覚えやすい短いストーリーや連想を作り、50字以内にする
+2. 覚え方だけを返し、見出し・説明・markdownは付けない
+"#;
+
+// ---- 解析审查 ----
+
+const ANALYSIS_REVIEW: &str = r#"
+请以日语教师的身份审查下面这份由 AI 生成的日语学习解析，找出其中的错误：
+
+{entry}
+
+解析内容：
+{analysis}
+
+请逐项检查：
+1. 读音（假名）是否正确
+2. 音调是否正确
+3. 词性是否正确
+4. 释义和用法说明是否正确
+5. 例句是否自然、是否确实用到了该单词或语法，例句的读音和翻译是否正确，是否有编造的用法
+
+请用以下JSON格式返回结果：
+{
+  "score": 85,
+  "issues": [
+    {"kind": "reading", "description": "问题说明"}
+  ]
+}
+
+重要事项：
+1. 只返回JSON，不要使用markdown代码块，不要添加其他内容
+2. score 为 0-100 的整数，表示解析整体的准确程度；有读音、词性错误或编造的例句时不超过 60 分
+3. kind 只能是 reading（读音）、pitch（音调）、part_of_speech（词性）、meaning（释义）、example（例句）、other（其他）之一
+4. 措辞、排版等不影响正确性的小问题使用 other
+5. 没有发现问题时 issues 为空数组，不要报告没有把握的问题
+"#;
+
+// ---- 例句翻译 ----
+
+const SENTENCE_TRANSLATION_ZH: &str = r#"
+请为以下日语句子标注平假名读音并翻译，每行一句：
+
+{sentences}
+
+请用以下JSON格式返回结果，句子的顺序和原文保持一致：
+{
+  "sentences": [
+    {"japanese": "原句", "reading": "全部用平假名书写的读音", "translation": "翻译"}
+  ]
+}
+
+重要事项：
+1. 只返回JSON，不要使用markdown代码块，不要添加其他内容
+2. japanese 原样照抄，不要修改
+3. {translation}
+"#;
+
+const SENTENCE_TRANSLATION_EN: &str = r#"
+Give the hiragana reading and a translation for each of the following Japanese sentences, one per line:
+
+{sentences}
+
+Reply in the following JSON format, keeping the sentences in the original order:
+{
+  "sentences": [
+    {"japanese": "original sentence", "reading": "reading written entirely in hiragana", "translation": "translation"}
+  ]
+}
+
+Important:
+1. Return only JSON, without markdown code blocks or anything else
+2. Copy japanese exactly as given, without changes
+3. {translation}
+"#;
+
+const SENTENCE_TRANSLATION_JA: &str = r#"
+次の日本語の文に、ひらがなの読みと訳を付けてください（1行に1文）：
+
+{sentences}
+
+次のJSON形式で、元の順番のまま返してください：
+{
+  "sentences": [
+    {"japanese": "元の文", "reading": "すべてひらがなで書いた読み", "translation": "訳"}
+  ]
+}
+
+注意事項：
+1. JSONだけを返し、markdownのコードブロックや他の内容は付けない
+2. japanese は元の文をそのまま写し、変更しない
+3. {translation}
+"#;
+
+
+/// 提示词模板
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    pub kind: PromptKind,
+    // 按解析语言区分的模板所属的语言
+    pub language: Option<Language>,
+    pub version: i64,
+    pub text: String,
+    // 模板文件路径，内置模板为 None
+    pub path: Option<PathBuf>,
+}
+
+impl PromptTemplate {
+    fn builtin(kind: PromptKind, language: Option<Language>) -> Self {
+        PromptTemplate {
+            kind,
+            language,
+            version: kind.builtin_version(),
+            text: kind.builtin(language.unwrap_or(Language::PRIMARY)).to_string(),
+            path: None,
+        }
+    }
+
+    /// 模板文件名，例如 word_analysis.en.txt、extraction.txt
+    pub fn file_name(kind: PromptKind, language: Option<Language>) -> String {
+        match language {
+            Some(language) => format!("{}.{}.txt", kind.key(), language.code()),
+            None => format!("{}.txt", kind.key()),
+        }
+    }
+
+    /// 根据文件名识别模板用途和语言
+    fn identify(file_name: &str) -> Option<(PromptKind, Option<Language>)> {
+        let stem = file_name.strip_suffix(".txt")?;
+        let (kind, language) = match stem.split_once('.') {
+            Some((key, code)) => (PromptKind::from_key(key)?, Some(Language::parse(code)?)),
+            None => (PromptKind::from_key(stem)?, None),
+        };
+        (kind.is_localized() == language.is_some()).then_some((kind, language))
+    }
+
+    /// 读取模板文件：开头以 # 开始的行为文件头，其中 `# version: N` 指定版本，其余为模板正文
+    pub fn load(path: &Path) -> Result<Self> {
+        let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let (kind, language) = Self::identify(file_name)
+            .ok_or_else(|| anyhow::anyhow!("无法识别的模板文件名: {}", file_name))?;
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("无法读取模板文件 {}: {}", path.display(), e))?;
+
+        let mut version = None;
+        let mut body_start = 0;
+        for line in content.split_inclusive('\n') {
+            let Some(header) = line.strip_prefix('#') else {
+                break;
+            };
+            body_start += line.len();
+            if let Some(value) = header.trim().strip_prefix("version:") {
+                version = Some(value.trim().parse::<i64>().ok().filter(|v| *v > 0)
+                    .ok_or_else(|| anyhow::anyhow!("版本号无效: {}", value.trim()))?);
+            }
+        }
+
+        let template = PromptTemplate {
+            kind,
+            language,
+            version: version.ok_or_else(|| anyhow::anyhow!("缺少 # version: N 文件头"))?,
+            text: content[body_start..].to_string(),
+            path: Some(path.to_path_buf()),
+        };
+        template.validate()?;
+        Ok(template)
+    }
+
+    /// 检查模板中的变量：不能使用未知变量，必须包含第一个变量
+    pub fn validate(&self) -> Result<()> {
+        let variables = self.kind.variables();
+        let used = placeholders(&self.text);
+        if let Some(unknown) = used.iter().find(|name| !variables.contains(name)) {
+            return Err(anyhow::anyhow!(
+                "未知变量 {{{}}}，可用变量: {}", unknown, format_variables(variables)
+            ));
+        }
+        if !used.contains(&variables[0]) {
+            return Err(anyhow::anyhow!("缺少必需的变量 {{{}}}", variables[0]));
+        }
+        Ok(())
+    }
+
+    /// 写入模板文件的内容
+    pub fn to_file_content(&self) -> String {
+        format!(
+            "# version: {}\n# {}，可用变量: {}\n{}",
+            self.version,
+            self.kind.label(),
+            format_variables(self.kind.variables()),
+            self.text
+        )
+    }
+
+    fn render(&self, values: &[(&str, &str)]) -> String {
+        render(&self.text, values)
+    }
+}
+
+/// 所有提示词模板：模板目录中的文件覆盖对应的内置模板
+#[derive(Debug, Clone)]
+pub struct PromptLibrary {
+    templates: Vec<PromptTemplate>,
+}
+
+impl PromptLibrary {
+    /// 只使用内置模板
+    pub fn builtin() -> Self {
+        let templates = PromptKind::ALL
+            .into_iter()
+            .flat_map(|kind| {
+                let languages: Vec<Option<Language>> = if kind.is_localized() {
+                    Language::ALL.into_iter().map(Some).collect()
+                } else {
+                    vec![None]
+                };
+                languages.into_iter().map(move |language| PromptTemplate::builtin(kind, language))
+            })
+            .collect();
+        PromptLibrary { templates }
+    }
+
+    /// 加载模板目录，目录不存在时只使用内置模板，模板文件无效时报错
+    pub fn load(dir: &str) -> Result<Self> {
+        let mut library = Self::builtin();
+        for (path, template) in Self::check_dir(dir)? {
+            let template = template
+                .map_err(|e| anyhow::anyhow!("提示词模板 {} 无效: {}", path.display(), e))?;
+            if let Some(existing) = library.templates.iter_mut()
+                .find(|t| t.kind == template.kind && t.language == template.language)
+            {
+                *existing = template;
+            }
+        }
+        Ok(library)
+    }
+
+    /// 逐个读取模板目录中的 .txt 文件，返回每个文件的校验结果
+    pub fn check_dir(dir: &str) -> Result<Vec<(PathBuf, Result<PromptTemplate>)>> {
+        let dir = Path::new(dir);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map_err(|e| anyhow::anyhow!("无法读取模板目录 {}: {}", dir.display(), e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+            .collect();
+        paths.sort();
+        Ok(paths.into_iter().map(|path| {
+            let template = PromptTemplate::load(&path);
+            (path, template)
+        }).collect())
+    }
+
+    /// 把内置模板写入模板目录（不覆盖已有文件），返回写入的文件
+    pub fn write_builtin(dir: &str) -> Result<Vec<PathBuf>> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| anyhow::anyhow!("无法创建模板目录 {}: {}", dir, e))?;
+        let mut written = Vec::new();
+        for template in Self::builtin().templates {
+            let path = Path::new(dir).join(PromptTemplate::file_name(template.kind, template.language));
+            if path.exists() {
+                continue;
+            }
+            std::fs::write(&path, template.to_file_content())
+                .map_err(|e| anyhow::anyhow!("无法写入模板文件 {}: {}", path.display(), e))?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    pub fn templates(&self) -> &[PromptTemplate] {
+        &self.templates
+    }
+
+    /// 某种用途在指定解析语言下使用的模板
+    pub fn get(&self, kind: PromptKind, language: Language) -> &PromptTemplate {
+        let language = kind.is_localized().then_some(language);
+        self.templates
+            .iter()
+            .find(|t| t.kind == kind && t.language == language)
+            .expect("内置模板覆盖所有用途和语言")
+    }
+
+    /// 某种用途在指定解析语言下的模板版本
+    pub fn version(&self, kind: PromptKind, language: Language) -> i64 {
+        self.get(kind, language).version
+    }
+
+    /// 提取单词和语法的提示词
+    pub fn extraction_prompt(&self, text: &str) -> String {
+        self.get(PromptKind::Extraction, Language::PRIMARY).render(&[("text", text)])
+    }
+
+    /// 重新标注词性的提示词
+    pub fn part_of_speech_prompt(&self, word: &str, kana: &str, pitch: &str) -> String {
+        self.get(PromptKind::PartOfSpeech, Language::PRIMARY).render(&[
+            ("word", word),
+            ("kana", kana),
+            ("pitch", pitch),
+        ])
+    }
+
+    /// 单词解析提示词，parts_of_speech 为已按 pos_labels 语言显示的词性
+    pub fn word_analysis_prompt(&self, config: &LanguageConfig, word: &str, kana: &str, pitch: &str, parts_of_speech: &[String]) -> String {
+        let separator = match config.explanation {
+            Language::English => ", ",
+            _ => "、",
+        };
+        self.get(PromptKind::WordAnalysis, config.explanation).render(&[
+            ("word", word),
+            ("kana", kana),
+            ("pitch", pitch),
+            ("pos", &parts_of_speech.join(separator)),
+            ("translation", &translation_instruction(config)),
+        ])
+    }
+
+    /// 语法解析提示词
+    pub fn grammar_analysis_prompt(&self, config: &LanguageConfig, grammar: &str, kana: &str) -> String {
+        self.get(PromptKind::GrammarAnalysis, config.explanation).render(&[
+            ("grammar", grammar),
+            ("kana", kana),
+            ("translation", &translation_instruction(config)),
+        ])
+    }
+
+    /// 汉字记忆口诀提示词
+    pub fn kanji_mnemonic_prompt(&self, config: &LanguageConfig, kanji: &crate::models::Kanji) -> String {
+        self.get(PromptKind::KanjiMnemonic, config.explanation).render(&[
+            ("kanji", &kanji.character),
+            ("components", &kanji.components),
+            ("meanings", &kanji.meanings),
+            ("on", &kanji.on_readings),
+            ("kun", &kanji.kun_readings),
+        ])
+    }
+
+    /// 解析审查提示词，entry 为条目的基本信息（每行一项），analysis 为去掉标签的解析文本
+    pub fn analysis_review_prompt(&self, entry: &str, analysis: &str) -> String {
+        self.get(PromptKind::AnalysisReview, Language::PRIMARY).render(&[
+            ("analysis", analysis),
+            ("entry", entry),
+        ])
+    }
+
+    /// 例句读音和翻译提示词，每行一句
+    pub fn sentence_translation_prompt(&self, config: &LanguageConfig, sentences: &[String]) -> String {
+        self.get(PromptKind::SentenceTranslation, config.explanation).render(&[
+            ("sentences", &sentences.join("\n")),
+            ("translation", &translation_instruction(config)),
+        ])
+    }
+}
+
+fn format_variables(variables: &[&str]) -> String {
+    variables.iter().map(|name| format!("{{{}}}", name)).collect::<Vec<_>>().join(" ")
+}
+
+// 模板中使用的 {name} 变量，JSON 示例中的花括号不算变量
+fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        if let Some(end) = rest.find('}') {
+            let name = &rest[..end];
+            if !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_') {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+// 将模板中的 {name} 替换为对应的值，替换后的内容不再展开
+fn render(template: &str, values: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            let name = &after[..end];
+            values.iter().find(|(n, _)| *n == name).map(|(_, value)| (end, *value))
+        });
+        match value {
+            Some((end, value)) => {
+                output.push_str(value);
+                rest = &after[end + 1..];
+            },
+            None => {
+                output.push('{');
+                rest = after;
+            },
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+// 例句翻译要求，用解析语言书写
+fn translation_instruction(config: &LanguageConfig) -> String {
+    let explanation = config.explanation;
+    let target = config.translation.name_in(explanation);
+    match (explanation, config.translation) {
+        (Language::Chinese, Language::Japanese) => "例句不需要翻译".to_string(),
+        (Language::Chinese, _) => format!("例句的翻译使用{}", target),
+        (Language::English, Language::Japanese) => "Do not translate example sentences".to_string(),
+        (Language::English, _) => format!("Translate example sentences into {}", target),
+        (Language::Japanese, Language::Japanese) => "例文に訳は付けない".to_string(),
+        (Language::Japanese, _) => format!("例文には{}訳を付ける", target),
+    }
+}
@@ -0,0 +1,263 @@
+// 模型请求的限流和重试策略：错误分类、指数退避、限流响应头解析和共享的令牌桶
+
+use rand::Rng;
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::{Instant, sleep};
+
+/// 请求失败后的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// 触发限流（429），等待后重试，同时暂停所有并发请求
+    RateLimited,
+    /// 临时错误（超时、网络错误、5xx 等），退避后重试
+    Retryable,
+    /// 请求本身有误（400、401、402 等），重试也不会成功
+    Fatal,
+}
+
+impl Failure {
+    pub fn from_status(status: StatusCode) -> Failure {
+        match status.as_u16() {
+            429 => Failure::RateLimited,
+            408 | 409 | 425 => Failure::Retryable,
+            code if code >= 500 => Failure::Retryable,
+            _ => Failure::Fatal,
+        }
+    }
+}
+
+/// 第 attempt 次重试（从 1 开始）前的退避时间：base_ms 按 2 的幂增长、不超过 max_ms，再随机取其一半到全部
+pub fn backoff_delay(base_ms: u64, max_ms: u64, attempt: u32) -> Duration {
+    let exp = base_ms
+        .saturating_mul(1u64 << attempt.saturating_sub(1).min(20))
+        .min(max_ms);
+    let jitter = rand::thread_rng().gen_range(0..=exp / 2);
+    Duration::from_millis(exp - exp / 2 + jitter)
+}
+
+/// 响应头要求的等待时间：Retry-After（秒数或 HTTP 日期），或 X-RateLimit-Reset
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    if let Some(value) = header_str(headers, "retry-after") {
+        if let Some(seconds) = parse_number(value) {
+            if let Some(wait) = seconds_hint(seconds) {
+                return Some(wait);
+            }
+        } else if let Ok(date) = chrono::DateTime::parse_from_rfc2822(value) {
+            return Some(until_millis(date.timestamp_millis()));
+        }
+    }
+    rate_limit_reset(headers)
+}
+
+/// 服务商返回剩余请求数为 0 时，距离额度重置还需要等待的时间
+pub fn quota_exhausted(headers: &HeaderMap) -> Option<Duration> {
+    let remaining = parse_number(header_str(headers, "x-ratelimit-remaining")?)?;
+    if remaining > 0.0 {
+        return None;
+    }
+    rate_limit_reset(headers)
+}
+
+// X-RateLimit-Reset：OpenRouter 为毫秒时间戳，也兼容秒时间戳和剩余秒数
+fn rate_limit_reset(headers: &HeaderMap) -> Option<Duration> {
+    let reset = parse_number(header_str(headers, "x-ratelimit-reset")?)?;
+    if reset > 1e12 {
+        Some(until_millis(reset as i64))
+    } else if reset > 1e9 {
+        Some(until_millis((reset * 1000.0) as i64))
+    } else {
+        seconds_hint(reset)
+    }
+}
+
+// 响应头来自服务商，inf、NaN 等非有限值按无法解析处理
+fn parse_number(value: &str) -> Option<f64> {
+    value.parse::<f64>().ok().filter(|number| number.is_finite())
+}
+
+// 剩余秒数，负数视为 0，超出 Duration 范围（如 1e300）时视为没有给出
+fn seconds_hint(seconds: f64) -> Option<Duration> {
+    Duration::try_from_secs_f64(seconds.max(0.0)).ok()
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok().map(str::trim)
+}
+
+// 距离某个 Unix 毫秒时间戳的时间，已经过去时为 0
+fn until_millis(timestamp_ms: i64) -> Duration {
+    let remaining = timestamp_ms - chrono::Utc::now().timestamp_millis();
+    Duration::from_millis(remaining.max(0) as u64)
+}
+
+/// 所有并发请求共享的令牌桶：每分钟最多 per_minute 个请求（0 表示不限制），触发限流时所有请求一起暂停
+pub struct RateLimiter {
+    per_minute: u32,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    tokens: f64,
+    updated: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        RateLimiter {
+            per_minute,
+            state: Mutex::new(BucketState {
+                tokens: per_minute as f64,
+                updated: Instant::now(),
+                paused_until: None,
+            }),
+        }
+    }
+
+    /// 等待到可以发出下一个请求
+    pub async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            sleep(wait).await;
+        }
+    }
+
+    /// 在 wait 之内不再放行任何请求
+    pub fn pause(&self, wait: Duration) {
+        // 等待时间过长、超出 Instant 范围时不暂停
+        let Some(until) = Instant::now().checked_add(wait) else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap();
+        if state.paused_until.is_none_or(|current| current < until) {
+            state.paused_until = Some(until);
+        }
+    }
+
+    // 取得一个令牌，取不到时返回需要等待的时间
+    fn try_acquire(&self) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        if let Some(until) = state.paused_until {
+            if until > now {
+                return Some(until - now);
+            }
+            state.paused_until = None;
+        }
+        if self.per_minute == 0 {
+            return None;
+        }
+
+        // 每秒补充 per_minute / 60 个令牌，最多积攒一分钟的量
+        let rate = self.per_minute as f64 / 60.0;
+        let elapsed = now.duration_since(state.updated).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * rate).min(self.per_minute as f64);
+        state.updated = now;
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - state.tokens) / rate))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, String)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn assert_between(delay: Option<Duration>, min_secs: f64, max_secs: f64) {
+        let secs = delay.expect("应返回等待时间").as_secs_f64();
+        assert!((min_secs..=max_secs).contains(&secs), "{} 不在 {}..={} 之间", secs, min_secs, max_secs);
+    }
+
+    #[test]
+    fn status_codes_are_classified() {
+        assert_eq!(Failure::from_status(StatusCode::TOO_MANY_REQUESTS), Failure::RateLimited);
+        for code in [408, 409, 425, 500, 502, 503, 504, 529] {
+            assert_eq!(Failure::from_status(StatusCode::from_u16(code).unwrap()), Failure::Retryable, "{}", code);
+        }
+        for code in [400, 401, 402, 403, 404, 413, 422] {
+            assert_eq!(Failure::from_status(StatusCode::from_u16(code).unwrap()), Failure::Fatal, "{}", code);
+        }
+    }
+
+    #[test]
+    fn backoff_stays_between_half_and_full_exponential_delay() {
+        for _ in 0..200 {
+            for (attempt, exp) in [(1, 100), (2, 200), (3, 400), (4, 800), (5, 1000), (10, 1000), (u32::MAX, 1000)] {
+                let delay = backoff_delay(100, 1000, attempt).as_millis() as u64;
+                assert!((exp / 2..=exp).contains(&delay), "第 {} 次重试等待 {}ms", attempt, delay);
+            }
+        }
+        assert_eq!(backoff_delay(0, 1000, 3), Duration::ZERO);
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_http_dates() {
+        assert_eq!(retry_after(&headers(&[("retry-after", "120".to_string())])), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after(&headers(&[("retry-after", " 1.5 ".to_string())])), Some(Duration::from_millis(1500)));
+
+        let later = chrono::Utc::now() + chrono::Duration::seconds(30);
+        assert_between(retry_after(&headers(&[("retry-after", later.to_rfc2822())])), 28.0, 30.0);
+        let earlier = chrono::Utc::now() - chrono::Duration::seconds(30);
+        assert_eq!(retry_after(&headers(&[("retry-after", earlier.to_rfc2822())])), Some(Duration::ZERO));
+
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+        assert_eq!(retry_after(&headers(&[("retry-after", "soon".to_string())])), None);
+        assert_eq!(retry_after(&headers(&[("retry-after", "-5".to_string())])), Some(Duration::ZERO));
+        // 超出范围的值不会 panic，视为没有给出
+        for value in ["1e300", "inf", "-inf", "NaN"] {
+            assert_eq!(retry_after(&headers(&[("retry-after", value.to_string())])), None, "{}", value);
+        }
+        let huge = headers(&[("retry-after", "1e300".to_string()), ("x-ratelimit-reset", "7".to_string())]);
+        assert_eq!(retry_after(&huge), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn rate_limit_reset_accepts_millis_seconds_and_deltas() {
+        let now = chrono::Utc::now();
+        let millis = (now + chrono::Duration::seconds(5)).timestamp_millis();
+        assert_between(retry_after(&headers(&[("x-ratelimit-reset", millis.to_string())])), 3.0, 5.0);
+        let seconds = (now + chrono::Duration::seconds(10)).timestamp();
+        assert_between(retry_after(&headers(&[("x-ratelimit-reset", seconds.to_string())])), 8.0, 10.0);
+        assert_eq!(retry_after(&headers(&[("x-ratelimit-reset", "7".to_string())])), Some(Duration::from_secs(7)));
+        for value in ["inf", "NaN"] {
+            assert_eq!(retry_after(&headers(&[("x-ratelimit-reset", value.to_string())])), None, "{}", value);
+            let exhausted = headers(&[("x-ratelimit-remaining", "0".to_string()), ("x-ratelimit-reset", value.to_string())]);
+            assert_eq!(quota_exhausted(&exhausted), None, "{}", value);
+        }
+
+        // Retry-After 优先，无法解析时改用 X-RateLimit-Reset
+        let both = headers(&[("retry-after", "3".to_string()), ("x-ratelimit-reset", "7".to_string())]);
+        assert_eq!(retry_after(&both), Some(Duration::from_secs(3)));
+        let invalid = headers(&[("retry-after", "soon".to_string()), ("x-ratelimit-reset", "7".to_string())]);
+        assert_eq!(retry_after(&invalid), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn quota_is_exhausted_only_when_nothing_remains() {
+        let exhausted = headers(&[("x-ratelimit-remaining", "0".to_string()), ("x-ratelimit-reset", "7".to_string())]);
+        assert_eq!(quota_exhausted(&exhausted), Some(Duration::from_secs(7)));
+        let remaining = headers(&[("x-ratelimit-remaining", "3".to_string()), ("x-ratelimit-reset", "7".to_string())]);
+        assert_eq!(quota_exhausted(&remaining), None);
+    }
+
+    #[test]
+    fn pause_ignores_waits_beyond_instant_range() {
+        let limiter = RateLimiter::new(0);
+        limiter.pause(Duration::MAX);
+        assert_eq!(limiter.try_acquire(), None);
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::database::pitch_to_superscript;
+use crate::fsrs::{Fsrs, MemoryState, format_interval};
+use crate::models::*;
+use crate::text::strip_html;
+
+// 数据库中的时间格式（UTC）
+pub const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 复习队列中的一项
+#[derive(Debug, Clone)]
+pub struct ReviewItem {
+    pub kind: EntryKind,
+    pub entry_id: i64,
+    pub front: String,
+    pub answer: String,
+    pub card: Option<ReviewCard>,
+}
+
+impl ReviewItem {
+    pub fn from_word(word: &JapaneseWord, card: Option<ReviewCard>) -> Self {
+        let mut front = format!("{}{}", word.word, pitch_to_superscript(&word.pitch));
+        if word.kana != word.word {
+            front.push_str(&format!("  {}", word.kana));
+        }
+        front.push_str(&format!("  [{}]", word.part_of_speech.replace('｜', "·")));
+
+        ReviewItem {
+            kind: EntryKind::Word,
+            entry_id: word.id,
+            front,
+            answer: strip_html(&word.analysis),
+            card,
+        }
+    }
+
+    pub fn from_grammar(grammar: &JapaneseGrammar, card: Option<ReviewCard>) -> Self {
+        let front = if grammar.word == grammar.kana {
+            grammar.word.clone()
+        } else {
+            format!("{}｜{}", grammar.word, grammar.kana)
+        };
+
+        ReviewItem {
+            kind: EntryKind::Grammar,
+            entry_id: grammar.id,
+            front,
+            answer: strip_html(&grammar.analysis),
+            card,
+        }
+    }
+
+    /// 当前卡片状态、记忆状态和距上次复习的天数
+    pub fn memory(&self, now: NaiveDateTime) -> (CardState, Option<MemoryState>, f64) {
+        match &self.card {
+            Some(card) => {
+                let elapsed_days = card
+                    .last_review
+                    .as_deref()
+                    .and_then(|t| NaiveDateTime::parse_from_str(t, TIME_FORMAT).ok())
+                    .map(|last| (now - last).num_seconds() as f64 / 86400.0)
+                    .unwrap_or(0.0);
+                let memory = MemoryState {
+                    stability: card.stability,
+                    difficulty: card.difficulty,
+                };
+                (card.state(), Some(memory), elapsed_days)
+            }
+            None => (CardState::New, None, 0.0),
+        }
+    }
+}
+
+/// 当前 UTC 时间（去掉秒以下的部分）
+pub fn now_utc() -> NaiveDateTime {
+    let now = Utc::now().naive_utc();
+    NaiveDateTime::parse_from_str(&now.format(TIME_FORMAT).to_string(), TIME_FORMAT).unwrap_or(now)
+}
+
+/// 读取一行输入，遇到文件结尾时返回 None
+pub fn read_line(prompt: &str) -> Result<Option<String>> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(input.trim().to_string()))
+}
+
+/// 显示各评分对应的下次间隔
+pub fn grade_options(fsrs: &Fsrs, item: &ReviewItem, now: NaiveDateTime) -> String {
+    let (state, memory, elapsed_days) = item.memory(now);
+    Grade::ALL
+        .iter()
+        .map(|grade| {
+            let scheduled = fsrs.schedule(state, memory, elapsed_days, *grade);
+            format!("{}. {}({})", *grade as u8, grade.label(), format_interval(scheduled.interval_days))
+        })
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+/// 使用外部播放器播放音频，不等待播放结束
+pub fn play_audio(player: &str, path: &Path) {
+    let mut parts = player.split_whitespace();
+    let Some(program) = parts.next() else {
+        return;
+    };
+
+    let result = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    if let Err(e) = result {
+        println!("⚠️  无法启动音频播放器 {}: {}", player, e);
+    }
+}
@@ -0,0 +1,295 @@
+// 任务调度：所有流程共用的并发上限、每分钟请求数上限和 Ctrl-C 取消
+
+use futures::stream::{self, StreamExt};
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Notify, Semaphore, SemaphorePermit};
+
+use crate::config::Config;
+use crate::rate_limit::RateLimiter;
+
+/// 任务占用的资源，每种资源有独立的并发上限和每分钟请求数上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    /// 模型服务商
+    Llm,
+    /// 语音合成
+    Tts,
+    /// 数据库写入
+    DbWrite,
+}
+
+impl Resource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Resource::Llm => "模型请求",
+            Resource::Tts => "语音合成",
+            Resource::DbWrite => "数据库写入",
+        }
+    }
+}
+
+// 一种资源的并发名额和请求额度
+struct Lane {
+    concurrency: usize,
+    semaphore: Semaphore,
+    limiter: RateLimiter,
+}
+
+impl Lane {
+    fn new(concurrency: usize, requests_per_minute: u32) -> Self {
+        let concurrency = concurrency.max(1);
+        Lane {
+            concurrency,
+            semaphore: Semaphore::new(concurrency),
+            limiter: RateLimiter::new(requests_per_minute),
+        }
+    }
+}
+
+pub struct Scheduler {
+    llm: Lane,
+    tts: Lane,
+    db_write: Lane,
+    // 收到 Ctrl-C 后不再开始新任务
+    cancelled: AtomicBool,
+    // 正在执行的 run 数量，没有任务时 Ctrl-C 请求退出
+    active_runs: AtomicUsize,
+    // 请求退出：放弃进行中的任务，当前操作保存已完成的结果后主循环退出
+    exit_requested: AtomicBool,
+    exit: Notify,
+}
+
+impl Scheduler {
+    pub fn new(config: &Config) -> Self {
+        let limits = &config.scheduler;
+        let processing = &config.processing;
+        Scheduler {
+            llm: Lane::new(
+                limits.llm.concurrency.unwrap_or(processing.concurrent_requests),
+                limits.llm.requests_per_minute.unwrap_or(processing.requests_per_minute),
+            ),
+            tts: Lane::new(
+                limits.tts.concurrency.unwrap_or(processing.concurrent_requests),
+                limits.tts.requests_per_minute.unwrap_or(0),
+            ),
+            db_write: Lane::new(
+                limits.db_write.concurrency.unwrap_or(1),
+                limits.db_write.requests_per_minute.unwrap_or(0),
+            ),
+            cancelled: AtomicBool::new(false),
+            active_runs: AtomicUsize::new(0),
+            exit_requested: AtomicBool::new(false),
+            exit: Notify::new(),
+        }
+    }
+
+    fn lane(&self, resource: Resource) -> &Lane {
+        match resource {
+            Resource::Llm => &self.llm,
+            Resource::Tts => &self.tts,
+            Resource::DbWrite => &self.db_write,
+        }
+    }
+
+    /// 资源的并发上限
+    pub fn concurrency(&self, resource: Resource) -> usize {
+        self.lane(resource).concurrency
+    }
+
+    /// 等待资源的每分钟请求额度（每次实际请求前调用）
+    pub async fn throttle(&self, resource: Resource) {
+        self.lane(resource).limiter.acquire().await;
+    }
+
+    /// 在 wait 之内暂停该资源的所有请求
+    pub fn pause(&self, resource: Resource, wait: Duration) {
+        self.lane(resource).limiter.pause(wait);
+    }
+
+    /// 取得资源的一个并发名额和请求额度，名额在返回值释放时归还；取消后仍可取得，保证进行中的任务能保存结果
+    pub async fn acquire(&self, resource: Resource) -> SemaphorePermit<'_> {
+        let permit = self.lane(resource).semaphore.acquire().await.unwrap();
+        self.throttle(resource).await;
+        permit
+    }
+
+    /// 并发执行一组任务，同时执行的任务数不超过资源的并发上限；
+    /// 收到 Ctrl-C 后不再开始新任务，已开始的任务正常完成，只返回已执行任务的结果；
+    /// 请求退出时放弃进行中的任务，返回已完成任务的结果
+    pub async fn run<I, F, Fut, R>(&self, resource: Resource, items: I, task: F) -> Vec<R>
+    where
+        I: IntoIterator,
+        F: Fn(I::Item) -> Fut,
+        Fut: Future<Output = R>,
+    {
+        let lane = self.lane(resource);
+        let skipped = AtomicUsize::new(0);
+        self.active_runs.fetch_add(1, Ordering::SeqCst);
+        let mut tasks = stream::iter(items)
+            .map(|item| {
+                let skipped = &skipped;
+                let task = &task;
+                async move {
+                    let _permit = lane.semaphore.acquire().await.unwrap();
+                    if self.is_cancelled() {
+                        skipped.fetch_add(1, Ordering::SeqCst);
+                        return None;
+                    }
+                    Some(task(item).await)
+                }
+            })
+            .buffer_unordered(lane.concurrency);
+        
+        let mut results = Vec::new();
+        let mut aborted = false;
+        let exit = self.exit.notified();
+        tokio::pin!(exit);
+        exit.as_mut().enable();
+        while !self.exit_requested() {
+            tokio::select! {
+                next = tasks.next() => match next {
+                    Some(Some(result)) => results.push(result),
+                    Some(None) => {},
+                    None => break,
+                },
+                _ = &mut exit => aborted = true,
+            }
+        }
+        aborted |= self.exit_requested();
+        drop(tasks);
+        self.active_runs.fetch_sub(1, Ordering::SeqCst);
+
+        let skipped = skipped.into_inner();
+        if aborted {
+            println!("⏹️  已放弃进行中和未开始的{}任务，已完成的 {} 个结果会照常保存", resource.label(), results.len());
+        } else if skipped > 0 {
+            println!("⏹️  已停止：跳过 {} 个未开始的{}任务，已完成的结果会照常保存", skipped, resource.label());
+        }
+        results
+    }
+
+    /// 是否收到了 Ctrl-C
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 不再开始新任务（预算用完时调用），返回之前是否已经停止
+    pub fn cancel(&self) -> bool {
+        self.cancelled.swap(true, Ordering::SeqCst)
+    }
+
+    /// 开始新的操作前清除取消状态（已请求退出时保留）
+    pub fn reset_cancellation(&self) {
+        if !self.exit_requested() {
+            self.cancelled.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// 是否请求了退出
+    pub fn exit_requested(&self) -> bool {
+        self.exit_requested.load(Ordering::SeqCst)
+    }
+
+    /// 等待退出请求
+    pub async fn wait_for_exit(&self) {
+        let exit = self.exit.notified();
+        tokio::pin!(exit);
+        exit.as_mut().enable();
+        if self.exit_requested() {
+            return;
+        }
+        exit.await;
+    }
+
+    /// 监听 Ctrl-C：有任务在执行时第一次只停止开始新任务；第二次或没有任务时请求退出，
+    /// 由主循环在当前操作保存完已完成的结果后退出（不在这里结束进程，避免打断数据库写入）
+    pub fn listen_for_ctrl_c(self: &Arc<Self>) {
+        let scheduler = Arc::clone(self);
+        tokio::spawn(async move {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if scheduler.active_runs.load(Ordering::SeqCst) == 0 || scheduler.cancelled.swap(true, Ordering::SeqCst) {
+                    scheduler.cancelled.store(true, Ordering::SeqCst);
+                    scheduler.exit_requested.store(true, Ordering::SeqCst);
+                    scheduler.exit.notify_waiters();
+                    println!("\n👋 收到 Ctrl-C：保存已完成的结果后退出");
+                    continue;
+                }
+                println!("\n⏹️  收到 Ctrl-C：不再开始新任务，等待进行中的任务完成并保存（再按一次放弃进行中的任务并退出）");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(concurrency: usize) -> Scheduler {
+        Scheduler {
+            llm: Lane::new(concurrency, 0),
+            tts: Lane::new(concurrency, 0),
+            db_write: Lane::new(1, 0),
+            cancelled: AtomicBool::new(false),
+            active_runs: AtomicUsize::new(0),
+            exit_requested: AtomicBool::new(false),
+            exit: Notify::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelled_run_keeps_finished_results() {
+        let scheduler = scheduler(1);
+        let results = scheduler
+            .run(Resource::Llm, 0..5, |i| {
+                let scheduler = &scheduler;
+                async move {
+                    if i == 1 {
+                        scheduler.cancel();
+                    }
+                    i
+                }
+            })
+            .await;
+        assert_eq!(results, vec![0, 1]);
+    }
+
+    #[tokio::test]
+    async fn exit_request_abandons_running_tasks() {
+        let scheduler = scheduler(2);
+        let results = scheduler
+            .run(Resource::Llm, 0..4, |i| {
+                let scheduler = &scheduler;
+                async move {
+                    if i == 0 {
+                        return i;
+                    }
+                    if i == 1 {
+                        scheduler.exit_requested.store(true, Ordering::SeqCst);
+                        scheduler.exit.notify_waiters();
+                    }
+                    std::future::pending::<()>().await;
+                    i
+                }
+            })
+            .await;
+        assert_eq!(results, vec![0]);
+        assert_eq!(scheduler.active_runs.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn reset_keeps_cancellation_after_exit_request() {
+        let scheduler = scheduler(1);
+        scheduler.cancel();
+        scheduler.reset_cancellation();
+        assert!(!scheduler.is_cancelled());
+
+        scheduler.cancel();
+        scheduler.exit_requested.store(true, Ordering::SeqCst);
+        scheduler.reset_cancellation();
+        assert!(scheduler.is_cancelled());
+        scheduler.wait_for_exit().await;
+    }
+}
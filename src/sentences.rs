@@ -0,0 +1,122 @@
+// 例句库：从解析和输入文本中取出例句，判断例句中出现了哪些单词和语法
+
+use crate::conjugation::classify;
+use crate::grammar::grammar_key;
+use crate::models::{ExampleSentence, JapaneseWord};
+use crate::text::{is_kanji, strip_html};
+
+// 解析中例句行的前缀（旧版 HTML 解析和由结构化解析渲染的卡片相同）
+const EXAMPLE_LABELS: &[&str] = &["例：", "例:", "例文：", "Ex:", "Example:"];
+
+// 句末标点，以及可以跟在句末标点后面的右括号
+const SENTENCE_ENDS: &[char] = &['。', '！', '？', '!', '?'];
+const CLOSING_BRACKETS: &[char] = &['」', '』', '）', ')', '】'];
+
+/// 把输入文本切分为句子，保留句末标点，去掉不含日文和重复的句子
+pub fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let mut current = String::new();
+        let mut ended = false;
+        for c in line.chars() {
+            // 「……。」 的右括号属于前一句
+            if ended && CLOSING_BRACKETS.contains(&c) && current.trim().is_empty() {
+                if let Some(last) = sentences.last_mut() {
+                    last.push(c);
+                }
+                continue;
+            }
+            current.push(c);
+            ended = SENTENCE_ENDS.contains(&c);
+            if ended {
+                push_sentence(&mut sentences, &current);
+                current.clear();
+            }
+        }
+        push_sentence(&mut sentences, &current);
+    }
+    sentences
+}
+
+fn push_sentence(sentences: &mut Vec<String>, text: &str) {
+    let text = text.trim();
+    if has_japanese(text) && !sentences.iter().any(|s| s == text) {
+        sentences.push(text.to_string());
+    }
+}
+
+/// 是否包含假名或汉字
+pub fn has_japanese(text: &str) -> bool {
+    text.chars().any(|c| is_kanji(c) || matches!(c, '\u{3041}'..='\u{309F}' | '\u{30A0}'..='\u{30FF}'))
+}
+
+/// 取出 HTML 解析中 "例： 日语 (读音) - 翻译" 形式的例句
+pub fn examples_in_html(html: &str) -> Vec<ExampleSentence> {
+    strip_html(html)
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let rest = EXAMPLE_LABELS.iter().find_map(|label| line.strip_prefix(label))?;
+            parse_example(rest)
+        })
+        .collect()
+}
+
+// 解析 "日语 (读音) - 翻译"，读音和翻译可以省略
+fn parse_example(text: &str) -> Option<ExampleSentence> {
+    let (head, translation) = match text.split_once(" - ") {
+        Some((head, translation)) => (head.trim(), translation.trim()),
+        None => (text.trim(), ""),
+    };
+    let (japanese, reading) = head
+        .strip_suffix(')')
+        .and_then(|h| h.rsplit_once(" ("))
+        .or_else(|| head.strip_suffix('）').and_then(|h| h.rsplit_once('（')))
+        .map(|(japanese, reading)| (japanese.trim(), reading.trim()))
+        .unwrap_or((head, ""));
+    if !has_japanese(japanese) {
+        return None;
+    }
+    Some(ExampleSentence {
+        japanese: japanese.to_string(),
+        reading: reading.to_string(),
+        translation: translation.to_string(),
+    })
+}
+
+/// 句子中是否出现该单词：动词和形容词按去掉词尾的词干匹配活用形，只有一个假名的单词不匹配
+pub fn contains_word(sentence: &str, word: &JapaneseWord) -> bool {
+    let text = word.word.trim();
+    if text.chars().count() < 2 && !text.chars().any(is_kanji) {
+        return false;
+    }
+    let inflects = classify(&word.word, &word.kana, &word.part_of_speech).is_some();
+    let stem = match text.char_indices().last() {
+        Some((index, last)) if inflects && text.chars().any(is_kanji) && !is_kanji(last) => &text[..index],
+        _ => text,
+    };
+    sentence.contains(stem)
+}
+
+/// 句子中是否出现该语法：按规范写法中 〜 分隔的各部分依次匹配，て/で 视为相同，太短的语法不匹配
+pub fn contains_grammar(sentence: &str, grammar: &str) -> bool {
+    let key = grammar_key(grammar);
+    let parts: Vec<&str> = key.split('〜').filter(|p| !p.is_empty()).collect();
+    if parts.iter().map(|p| p.chars().count()).sum::<usize>() < 2 {
+        return false;
+    }
+    let mut rest = sentence;
+    for part in parts {
+        let voiced = part.strip_prefix('て').map(|tail| format!("で{}", tail));
+        let found = [Some(part.to_string()), voiced]
+            .into_iter()
+            .flatten()
+            .filter_map(|p| rest.find(&p).map(|index| index + p.len()))
+            .min();
+        match found {
+            Some(end) => rest = &rest[end..],
+            None => return false,
+        }
+    }
+    true
+}
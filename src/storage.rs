@@ -0,0 +1,290 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+use crate::config::Config;
+use crate::database::DatabaseManager;
+use crate::models::*;
+
+/// 存储后端接口：SQLite（默认）和 PostgreSQL（`postgres` feature）都实现该接口
+#[async_trait]
+pub trait Storage: Send + Sync {
+    // ---- 单词 ----
+
+    /// 检查单词是否已存在（根据单词、假名，不依据音调和词性）
+    async fn check_word_exists(&self, word: &str, kana: &str) -> Result<bool>;
+
+    /// 获取已存在的单词信息（只基于 word 和 kana）
+    async fn get_existing_word_by_word_kana(&self, word: &str, kana: &str) -> Result<Option<JapaneseWord>>;
+
+    /// 获取已存在的单词信息（支持多词性合并）
+    async fn get_existing_word(&self, word: &str, kana: &str, pitch: &str) -> Result<Option<MergedWord>>;
+
+    /// 查找假名被锁定的同名单词
+    async fn get_kana_locked_word(&self, word: &str) -> Result<Option<JapaneseWord>>;
+
+    /// 获取所有单词
+    async fn get_all_words(&self) -> Result<Vec<JapaneseWord>>;
+
+    /// 根据ID获取单词信息
+    async fn get_word_by_id(&self, id: i64) -> Result<Option<JapaneseWord>>;
+
+    /// 保存单词（新的词性覆盖旧的，锁定字段保留原值）
+    async fn save_words(&self, words: &[WordAnalysis]) -> Result<()>;
+
+    /// 更新单词词性
+    async fn update_word_part_of_speech(&self, id: i64, new_pos: &str) -> Result<()>;
+
+    /// 更新单词的 pitch 和词性（处理唯一约束冲突）
+    async fn update_word_pitch_and_pos(&self, id: i64, new_pitch: &str, new_pos: &str) -> Result<()>;
+
+    /// 更新单词解析
+    async fn update_word_analysis(&self, id: i64, new_analysis: &str, prompt_version: i64) -> Result<()>;
+
+    // ---- 语法 ----
+
+    /// 保存语法（锁定字段保留原值）；写法按规范写法合并到已有条目，原写法记为别名
+    async fn save_grammar(&self, grammar: &[GrammarAnalysis]) -> Result<()>;
+
+    /// 更新语法解析
+    async fn update_grammar_analysis(&self, id: i64, new_analysis: &str, prompt_version: i64) -> Result<()>;
+
+    /// 获取所有语法
+    async fn get_all_grammar(&self) -> Result<Vec<JapaneseGrammar>>;
+
+    /// 根据ID获取语法信息
+    async fn get_grammar_by_id(&self, id: i64) -> Result<Option<JapaneseGrammar>>;
+
+    /// 根据语法表达获取语法信息，按规范写法和已记录的别名匹配（〜ている、ていた、Vている 都找到同一条目）
+    async fn get_grammar_by_word(&self, word: &str) -> Result<Option<JapaneseGrammar>>;
+
+    /// 语法已记录的其他写法
+    async fn get_grammar_aliases(&self, id: i64) -> Result<Vec<String>>;
+
+    // ---- 手动编辑和锁定 ----
+
+    /// 手动编辑条目字段，编辑后自动锁定该字段
+    async fn update_entry_field(&self, kind: EntryKind, id: i64, field: LockField, value: &str) -> Result<()>;
+
+    /// 设置或解除字段锁定
+    async fn set_entry_lock(&self, kind: EntryKind, id: i64, field: LockField, locked: bool) -> Result<()>;
+
+    /// 更新条目的词频排名和 JLPT 等级
+    async fn update_entry_levels(&self, kind: EntryKind, id: i64, frequency_rank: Option<i64>, jlpt_level: Option<&str>) -> Result<()>;
+
+    // ---- 学习者和学习状态 ----
+
+    /// 获取学习者ID，不存在时创建
+    async fn ensure_user(&self, name: &str) -> Result<i64>;
+
+    /// 设置学习者对某个辞书形的学习状态
+    async fn set_entry_status(&self, user_id: i64, kind: EntryKind, word: &str, status: EntryStatus) -> Result<()>;
+
+    /// 获取学习者的所有学习状态（辞书形 -> 状态）
+    async fn get_entry_statuses(&self, user_id: i64, kind: EntryKind) -> Result<HashMap<String, EntryStatus>>;
+
+    // ---- 其他语言的解析 ----
+
+    /// 获取某种语言的所有解析（条目ID -> 解析）
+    async fn get_entry_analyses(&self, kind: EntryKind, language: &str) -> Result<HashMap<i64, String>>;
+
+    /// 获取某种语言的所有解析所用的提示词版本（条目ID -> 版本）
+    async fn get_entry_analysis_versions(&self, kind: EntryKind, language: &str) -> Result<HashMap<i64, i64>>;
+
+    /// 保存条目某种语言的解析
+    async fn save_entry_analysis(&self, kind: EntryKind, id: i64, language: &str, analysis: &str, prompt_version: i64) -> Result<()>;
+
+    /// 记录解析未通过校验的原因，reason 为 None 时清除记录
+    async fn set_analysis_flag(&self, kind: EntryKind, id: i64, language: &str, reason: Option<&str>) -> Result<()>;
+
+    /// 获取某种语言下未通过校验的解析（条目ID -> 原因）
+    async fn get_analysis_flags(&self, kind: EntryKind, language: &str) -> Result<HashMap<i64, String>>;
+
+    /// 保存审查模型对解析的审查结果（替换已有的）
+    async fn save_analysis_review(&self, kind: EntryKind, id: i64, language: &str, review: &AnalysisReview, model: &str) -> Result<()>;
+
+    /// 获取某种语言下所有解析的审查结果（条目ID -> 审查结果）
+    async fn get_analysis_reviews(&self, kind: EntryKind, language: &str) -> Result<HashMap<i64, AnalysisReview>>;
+
+    /// 导出所有其他语言的解析
+    async fn export_analyses(&self) -> Result<Vec<ExportedAnalysis>>;
+
+    /// 按冲突策略导入一条其他语言的解析，找不到对应条目时跳过
+    async fn import_analysis(&self, analysis: &ExportedAnalysis, policy: ConflictPolicy) -> Result<ImportOutcome>;
+
+    // ---- 结构化单词解析 ----
+
+    /// 保存单词某种语言的结构化解析（替换已有的），None 时删除
+    async fn save_word_details(&self, id: i64, language: &str, details: Option<&WordDetails>) -> Result<()>;
+
+    /// 获取某种语言的所有结构化单词解析（单词ID -> 解析）
+    async fn get_word_details(&self, language: &str) -> Result<HashMap<i64, WordDetails>>;
+
+    /// 导出所有结构化单词解析
+    async fn export_word_details(&self) -> Result<Vec<ExportedWordDetails>>;
+
+    /// 按冲突策略导入一条结构化单词解析，找不到对应单词时跳过
+    async fn import_word_details(&self, details: &ExportedWordDetails, policy: ConflictPolicy) -> Result<ImportOutcome>;
+
+    // ---- 结构化语法解析 ----
+
+    /// 保存语法某种语言的结构化解析（替换已有的），None 时删除
+    async fn save_grammar_details(&self, id: i64, language: &str, details: Option<&GrammarDetails>) -> Result<()>;
+
+    /// 获取某种语言的所有结构化语法解析（语法ID -> 解析），相关语法关联到语法库中的条目
+    async fn get_grammar_details(&self, language: &str) -> Result<HashMap<i64, GrammarDetails>>;
+
+    /// 导出所有结构化语法解析
+    async fn export_grammar_details(&self) -> Result<Vec<ExportedGrammarDetails>>;
+
+    /// 按冲突策略导入一条结构化语法解析，找不到对应语法时跳过
+    async fn import_grammar_details(&self, details: &ExportedGrammarDetails, policy: ConflictPolicy) -> Result<ImportOutcome>;
+
+    // ---- 汉字 ----
+
+    /// 保存汉字信息（按字合并，保留已有的记忆口诀），返回汉字ID
+    async fn save_kanji(&self, info: &KanjiInfo) -> Result<i64>;
+
+    /// 替换汉字关联的单词
+    async fn link_kanji_words(&self, kanji_id: i64, word_ids: &[i64]) -> Result<()>;
+
+    /// 获取所有汉字
+    async fn get_all_kanji(&self) -> Result<Vec<Kanji>>;
+
+    /// 获取所有 (汉字ID, 单词ID) 关联
+    async fn get_kanji_word_links(&self) -> Result<Vec<(i64, i64)>>;
+
+    /// 更新汉字记忆口诀
+    async fn update_kanji_mnemonic(&self, id: i64, mnemonic: &str) -> Result<()>;
+
+    /// 导出所有汉字（含记忆口诀）及其关联的单词
+    async fn export_kanji(&self) -> Result<Vec<ExportedKanji>>;
+
+    /// 按冲突策略导入一个汉字，按字合并；关联的单词按自然键查找，找不到的跳过
+    async fn import_kanji(&self, kanji: &ExportedKanji, policy: ConflictPolicy) -> Result<ImportOutcome>;
+
+    // ---- 例句库 ----
+
+    /// 保存例句（按日文合并，只补全空的读音和翻译），返回例句ID
+    async fn save_sentence(&self, sentence: &ExampleSentence, source: SentenceSource, document: Option<&str>) -> Result<i64>;
+
+    /// 记录例句中出现的单词或语法，返回是否新增了关联
+    async fn link_sentence(&self, sentence_id: i64, kind: EntryKind, entry_id: i64) -> Result<bool>;
+
+    /// 更新例句的读音和翻译
+    async fn update_sentence_translation(&self, id: i64, reading: &str, translation: &str) -> Result<()>;
+
+    /// 获取所有例句
+    async fn get_all_sentences(&self) -> Result<Vec<Sentence>>;
+
+    /// 获取所有 (例句ID, 条目类型, 条目ID) 关联
+    async fn get_sentence_links(&self) -> Result<Vec<(i64, String, i64)>>;
+
+    /// 获取包含某个单词或语法的所有例句
+    async fn get_sentences_for_entry(&self, kind: EntryKind, id: i64) -> Result<Vec<Sentence>>;
+
+    /// 导出所有例句及其关联的单词和语法
+    async fn export_sentences(&self) -> Result<Vec<ExportedSentence>>;
+
+    /// 按冲突策略导入一个例句，按日文合并；关联的条目按自然键查找，找不到的跳过
+    async fn import_sentence(&self, sentence: &ExportedSentence, policy: ConflictPolicy) -> Result<ImportOutcome>;
+
+    // ---- 内置复习 ----
+
+    /// 获取学习者的所有复习卡片
+    async fn get_review_cards(&self, user_id: i64) -> Result<Vec<ReviewCard>>;
+
+    /// 统计某个时间（UTC）之后第一次复习的卡片数
+    async fn count_cards_introduced(&self, user_id: i64, since: &str) -> Result<i64>;
+
+    /// 保存卡片的新状态并写入复习记录，返回卡片ID
+    async fn save_review(&self, card: &ReviewCard, log: &ReviewLogEntry) -> Result<i64>;
+
+    /// 导出学习者的复习记录（Anki revlog 格式）
+    async fn export_review_log(&self, user_id: i64) -> Result<Vec<RevlogRecord>>;
+
+    // ---- 模型回复缓存 ----
+
+    /// 读取缓存的模型回复，只返回 since（UTC）之后写入的，since 为 None 时不限时间
+    async fn get_cached_response(&self, key: &str, since: Option<&str>) -> Result<Option<String>>;
+
+    /// 写入模型回复缓存（替换同一请求的旧回复）
+    async fn save_cached_response(&self, entry: &CachedResponse) -> Result<()>;
+
+    /// 删除 before（UTC）之前写入的缓存，返回删除的条数
+    async fn purge_cached_responses(&self, before: &str) -> Result<u64>;
+
+    // ---- 请求用量 ----
+
+    /// 记录一次模型请求的用量
+    async fn save_api_call(&self, call: &ApiCall) -> Result<()>;
+
+    /// 按用途汇总请求用量，run_id 为 None 时汇总所有运行
+    async fn get_api_usage(&self, run_id: Option<&str>) -> Result<Vec<ApiUsageSummary>>;
+
+    // ---- 出现记录和搜索 ----
+
+    /// 记录条目在某个输入文档中出现
+    async fn record_occurrence(&self, kind: EntryKind, id: i64, document: &str) -> Result<()>;
+
+    /// 统计每个条目出现过的次数（条目ID -> 次数）
+    async fn get_occurrence_counts(&self, kind: EntryKind) -> Result<HashMap<i64, i64>>;
+
+    /// 搜索单词和语法
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>>;
+
+    // ---- 导出和导入 ----
+
+    async fn export_words(&self) -> Result<Vec<ExportedWord>>;
+
+    async fn export_grammar(&self) -> Result<Vec<ExportedGrammar>>;
+
+    async fn export_occurrences(&self) -> Result<Vec<ExportedOccurrence>>;
+
+    /// 导入单词，按 (word, kana, pitch) 合并
+    async fn import_word(&self, word: &ExportedWord, policy: ConflictPolicy) -> Result<ImportOutcome>;
+
+    /// 导入语法，按语法表达合并
+    async fn import_grammar(&self, grammar: &ExportedGrammar, policy: ConflictPolicy) -> Result<ImportOutcome>;
+
+    /// 导入出现记录
+    async fn import_occurrence(&self, occurrence: &ExportedOccurrence) -> Result<ImportOutcome>;
+
+    async fn export_statuses(&self) -> Result<Vec<ExportedStatus>>;
+
+    /// 导入学习状态，按 (学习者, 类型, 辞书形) 合并
+    async fn import_status(&self, status: &ExportedStatus, policy: ConflictPolicy) -> Result<ImportOutcome>;
+
+    async fn export_review_cards(&self) -> Result<Vec<ExportedReviewCard>>;
+
+    /// 导入复习卡片，按 (学习者, 条目) 合并，KeepNewer 时比较最近复习时间
+    async fn import_review_card(&self, card: &ExportedReviewCard, policy: ConflictPolicy) -> Result<ImportOutcome>;
+
+    async fn export_review_history(&self) -> Result<Vec<ExportedReviewLog>>;
+
+    /// 导入复习记录，找不到对应卡片或已存在同一时刻的记录时跳过
+    async fn import_review_history(&self, review: &ExportedReviewLog) -> Result<ImportOutcome>;
+}
+
+/// 根据配置连接存储后端：`database.url` 为 postgres:// 时使用 PostgreSQL，否则使用 SQLite 文件
+pub async fn connect(config: &Config) -> Result<Box<dyn Storage>> {
+    match config.database.url.as_deref() {
+        Some(url) if url.starts_with("postgres://") || url.starts_with("postgresql://") => {
+            connect_postgres(url).await
+        }
+        Some(url) if !url.starts_with("sqlite:") => {
+            Err(anyhow::anyhow!("不支持的数据库地址: {}", url))
+        }
+        _ => Ok(Box::new(DatabaseManager::new(config.clone()).await?)),
+    }
+}
+
+#[cfg(feature = "postgres")]
+async fn connect_postgres(url: &str) -> Result<Box<dyn Storage>> {
+    Ok(Box::new(crate::postgres::PostgresStorage::connect(url).await?))
+}
+
+#[cfg(not(feature = "postgres"))]
+async fn connect_postgres(_url: &str) -> Result<Box<dyn Storage>> {
+    Err(anyhow::anyhow!("PostgreSQL 后端未启用，请使用 `cargo build --features postgres` 重新编译"))
+}
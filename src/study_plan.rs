@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+
+use crate::config::StudyPlanConfig;
+use crate::text::is_kanji;
+
+/// 参与排序的条目
+#[derive(Debug, Clone)]
+pub struct PlanItem<'a> {
+    pub id: i64,
+    pub word: &'a str,
+    pub frequency_rank: Option<i64>,
+    pub jlpt_level: Option<&'a str>,
+    pub occurrences: i64,
+}
+
+/// 按学习价值排序，返回排好序的条目ID
+///
+/// 词频、出现次数和 JLPT 等级各自归一化到 0-1 后按权重相加；
+/// 汉字重合度在逐个挑选时计算：已排在前面的单词中出现过的汉字越多，越优先。
+/// 分数相同时按 ID 排序。
+pub fn plan_order(items: &[PlanItem], config: &StudyPlanConfig) -> Vec<i64> {
+    let max_rank = items.iter().filter_map(|i| i.frequency_rank).max().unwrap_or(0);
+    let max_occurrences = items.iter().map(|i| i.occurrences).max().unwrap_or(0);
+
+    let base_scores: Vec<f64> = items
+        .iter()
+        .map(|item| {
+            config.frequency_weight * frequency_score(item.frequency_rank, max_rank)
+                + config.occurrence_weight * occurrence_score(item.occurrences, max_occurrences)
+                + config.jlpt_weight * jlpt_score(item.jlpt_level)
+        })
+        .collect();
+
+    let kanji: Vec<Vec<char>> = items
+        .iter()
+        .map(|item| {
+            let mut chars: Vec<char> = item.word.chars().filter(|c| is_kanji(*c)).collect();
+            chars.sort_unstable();
+            chars.dedup();
+            chars
+        })
+        .collect();
+
+    let mut remaining: Vec<usize> = (0..items.len()).collect();
+    let mut seen_kanji: HashSet<char> = HashSet::new();
+    let mut order = Vec::with_capacity(items.len());
+
+    // 不考虑汉字重合度时直接排序
+    if config.kanji_overlap_weight == 0.0 {
+        remaining.sort_by(|&a, &b| {
+            base_scores[b].total_cmp(&base_scores[a]).then(items[a].id.cmp(&items[b].id))
+        });
+        return remaining.into_iter().map(|i| items[i].id).collect();
+    }
+
+    while !remaining.is_empty() {
+        let score = |i: usize| {
+            // 没有汉字的单词不加分
+            let overlap = if kanji[i].is_empty() {
+                0.0
+            } else {
+                kanji[i].iter().filter(|c| seen_kanji.contains(c)).count() as f64 / kanji[i].len() as f64
+            };
+            base_scores[i] + config.kanji_overlap_weight * overlap
+        };
+
+        let (best_position, _) = remaining
+            .iter()
+            .enumerate()
+            .map(|(position, &i)| (position, score(i)))
+            .max_by(|(pa, sa), (pb, sb)| {
+                sa.total_cmp(sb).then(items[remaining[*pb]].id.cmp(&items[remaining[*pa]].id))
+            })
+            .expect("remaining 非空");
+
+        let best = remaining.remove(best_position);
+        seen_kanji.extend(kanji[best].iter().copied());
+        order.push(items[best].id);
+    }
+
+    order
+}
+
+// 排名越靠前分数越高，使用对数刻度
+fn frequency_score(rank: Option<i64>, max_rank: i64) -> f64 {
+    match rank {
+        Some(rank) if rank > 0 && max_rank > 0 => {
+            1.0 - (rank as f64).ln() / ((max_rank + 1) as f64).ln()
+        }
+        _ => 0.0,
+    }
+}
+
+fn occurrence_score(occurrences: i64, max_occurrences: i64) -> f64 {
+    if max_occurrences <= 0 {
+        return 0.0;
+    }
+    ((1 + occurrences) as f64).ln() / ((1 + max_occurrences) as f64).ln()
+}
+
+// 越基础的等级越优先
+fn jlpt_score(level: Option<&str>) -> f64 {
+    match level {
+        Some("N5") => 1.0,
+        Some("N4") => 0.8,
+        Some("N3") => 0.6,
+        Some("N2") => 0.4,
+        Some("N1") => 0.2,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: i64, word: &'static str, frequency_rank: Option<i64>, jlpt_level: Option<&'static str>, occurrences: i64) -> PlanItem<'static> {
+        PlanItem { id, word, frequency_rank, jlpt_level, occurrences }
+    }
+
+    fn weights(frequency: f64, occurrence: f64, jlpt: f64, kanji_overlap: f64) -> StudyPlanConfig {
+        StudyPlanConfig {
+            frequency_weight: frequency,
+            occurrence_weight: occurrence,
+            jlpt_weight: jlpt,
+            kanji_overlap_weight: kanji_overlap,
+        }
+    }
+
+    #[test]
+    fn zero_weights_keep_id_order() {
+        let items = [item(3, "猫", Some(1), Some("N5"), 9), item(1, "犬", None, None, 0), item(2, "鳥", Some(5), None, 1)];
+        assert_eq!(plan_order(&items, &weights(0.0, 0.0, 0.0, 0.0)), vec![1, 2, 3]);
+        assert!(plan_order(&[], &StudyPlanConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn each_weight_ranks_its_own_signal() {
+        let items = [
+            item(1, "一", Some(3000), Some("N1"), 1),
+            item(2, "二", Some(10), Some("N3"), 0),
+            item(3, "三", None, Some("N5"), 5),
+            item(4, "四", Some(200), None, 2),
+        ];
+        // 没有排名的条目排在最后
+        assert_eq!(plan_order(&items, &weights(1.0, 0.0, 0.0, 0.0)), vec![2, 4, 1, 3]);
+        assert_eq!(plan_order(&items, &weights(0.0, 1.0, 0.0, 0.0)), vec![3, 4, 1, 2]);
+        assert_eq!(plan_order(&items, &weights(0.0, 0.0, 1.0, 0.0)), vec![3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn shared_kanji_pull_words_forward() {
+        let items = [
+            item(1, "日本", Some(1), None, 0),
+            item(2, "時計", Some(2), None, 0),
+            item(3, "日記", Some(3), None, 0),
+            item(4, "すごい", Some(2), None, 0),
+        ];
+        assert_eq!(plan_order(&items, &weights(1.0, 0.0, 0.0, 0.0)), vec![1, 2, 4, 3]);
+        // 日本 之后，共用 日 的 日記 超过分数更高的 時計；没有汉字的单词不加分
+        assert_eq!(plan_order(&items, &weights(1.0, 0.0, 0.0, 1.0)), vec![1, 3, 2, 4]);
+    }
+
+    #[test]
+    fn scores_are_normalized() {
+        assert_eq!(frequency_score(Some(1), 100), 1.0);
+        assert!(frequency_score(Some(100), 100) > 0.0);
+        assert_eq!(frequency_score(None, 100), 0.0);
+        assert_eq!(frequency_score(Some(5), 0), 0.0);
+        assert_eq!(occurrence_score(7, 7), 1.0);
+        assert_eq!(occurrence_score(0, 7), 0.0);
+        assert_eq!(occurrence_score(3, 0), 0.0);
+        assert_eq!(jlpt_score(Some("N5")), 1.0);
+        assert_eq!(jlpt_score(Some("N6")), 0.0);
+    }
+}
@@ -0,0 +1,210 @@
+// 文本处理辅助函数：HTML 转纯文本、纯文本转义、假名转罗马字
+
+/// 去掉 HTML 标签并还原常见实体，块级标签转换为换行
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut chars = html.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '<' => {
+                let mut tag = String::new();
+                for t in chars.by_ref() {
+                    if t == '>' {
+                        break;
+                    }
+                    tag.push(t);
+                }
+                let name = tag
+                    .trim_start_matches('/')
+                    .split(|c: char| c.is_whitespace() || c == '/')
+                    .next()
+                    .unwrap_or("")
+                    .to_lowercase();
+                if matches!(name.as_str(), "br" | "div" | "p" | "hr" | "li" | "tr") && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+            }
+            '&' => {
+                let mut entity = String::new();
+                while let Some(&e) = chars.peek() {
+                    if e == ';' || entity.len() > 8 {
+                        break;
+                    }
+                    entity.push(e);
+                    chars.next();
+                }
+                let decoded = match entity.as_str() {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" | "#39" => Some('\''),
+                    "nbsp" => Some(' '),
+                    _ => None,
+                };
+                match decoded {
+                    Some(d) if chars.peek() == Some(&';') => {
+                        chars.next();
+                        text.push(d);
+                    }
+                    _ => {
+                        text.push('&');
+                        text.push_str(&entity);
+                    }
+                }
+            }
+            _ => text.push(c),
+        }
+    }
+
+    // 合并多余的空行
+    let lines: Vec<&str> = text.lines().map(|l| l.trim()).collect();
+    let mut result = String::new();
+    let mut blank = false;
+    for line in lines {
+        if line.is_empty() {
+            if !blank && !result.is_empty() {
+                result.push('\n');
+            }
+            blank = true;
+        } else {
+            if !result.is_empty() {
+                result.push('\n');
+            }
+            result.push_str(line);
+            blank = false;
+        }
+    }
+    result.trim().to_string()
+}
+
+/// 转义纯文本中的 HTML 特殊字符，用于把 AI 生成的纯文本写入卡片 HTML
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 是否为汉字（CJK 统一表意文字及扩展 A 区）
+pub fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}')
+}
+
+/// 片假名转平假名，其他字符保持不变
+pub fn katakana_to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+// 单个假名对应的罗马字（平文式）
+fn kana_romaji(c: char) -> Option<&'static str> {
+    let romaji = match c {
+        'あ' => "a", 'い' => "i", 'う' => "u", 'え' => "e", 'お' => "o",
+        'か' => "ka", 'き' => "ki", 'く' => "ku", 'け' => "ke", 'こ' => "ko",
+        'が' => "ga", 'ぎ' => "gi", 'ぐ' => "gu", 'げ' => "ge", 'ご' => "go",
+        'さ' => "sa", 'し' => "shi", 'す' => "su", 'せ' => "se", 'そ' => "so",
+        'ざ' => "za", 'じ' => "ji", 'ず' => "zu", 'ぜ' => "ze", 'ぞ' => "zo",
+        'た' => "ta", 'ち' => "chi", 'つ' => "tsu", 'て' => "te", 'と' => "to",
+        'だ' => "da", 'ぢ' => "ji", 'づ' => "zu", 'で' => "de", 'ど' => "do",
+        'な' => "na", 'に' => "ni", 'ぬ' => "nu", 'ね' => "ne", 'の' => "no",
+        'は' => "ha", 'ひ' => "hi", 'ふ' => "fu", 'へ' => "he", 'ほ' => "ho",
+        'ば' => "ba", 'び' => "bi", 'ぶ' => "bu", 'べ' => "be", 'ぼ' => "bo",
+        'ぱ' => "pa", 'ぴ' => "pi", 'ぷ' => "pu", 'ぺ' => "pe", 'ぽ' => "po",
+        'ま' => "ma", 'み' => "mi", 'む' => "mu", 'め' => "me", 'も' => "mo",
+        'や' => "ya", 'ゆ' => "yu", 'よ' => "yo",
+        'ら' => "ra", 'り' => "ri", 'る' => "ru", 'れ' => "re", 'ろ' => "ro",
+        'わ' => "wa", 'ゐ' => "i", 'ゑ' => "e", 'を' => "o", 'ん' => "n",
+        'ゔ' => "vu",
+        'ぁ' => "a", 'ぃ' => "i", 'ぅ' => "u", 'ぇ' => "e", 'ぉ' => "o",
+        'ゃ' => "ya", 'ゅ' => "yu", 'ょ' => "yo", 'ゎ' => "wa",
+        _ => return None,
+    };
+    Some(romaji)
+}
+
+/// 假名转罗马字（平文式），用于搜索索引
+pub fn kana_to_romaji(kana: &str) -> String {
+    let chars: Vec<char> = katakana_to_hiragana(kana).chars().collect();
+    let mut result = String::new();
+    let mut sokuon = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c == 'っ' {
+            sokuon = true;
+            i += 1;
+            continue;
+        }
+
+        if c == 'ー' {
+            if let Some(vowel) = result.chars().rev().find(|v| "aiueo".contains(*v)) {
+                result.push(vowel);
+            }
+            i += 1;
+            continue;
+        }
+
+        let mut syllable = match kana_romaji(c) {
+            Some(r) => r.to_string(),
+            None => {
+                result.push(c);
+                sokuon = false;
+                i += 1;
+                continue;
+            }
+        };
+        i += 1;
+
+        // 拗音：き + ゃ -> kya，し + ゃ -> sha
+        if let Some(small @ ('ゃ' | 'ゅ' | 'ょ')) = next {
+            if syllable.ends_with('i') && syllable.len() > 1 {
+                let glide = &kana_romaji(small).unwrap_or("")[1..];
+                syllable.pop();
+                if matches!(syllable.as_str(), "sh" | "ch" | "j") {
+                    syllable.push_str(glide);
+                } else {
+                    syllable.push('y');
+                    syllable.push_str(glide);
+                }
+                i += 1;
+            }
+        } else if let Some(small @ ('ぁ' | 'ぃ' | 'ぇ' | 'ぉ')) = next {
+            // 外来语：ふぁ -> fa，てぃ -> ti
+            if syllable.len() > 1 {
+                syllable.pop();
+                syllable.push_str(kana_romaji(small).unwrap_or(""));
+                i += 1;
+            }
+        }
+
+        if sokuon {
+            if syllable.starts_with("ch") {
+                result.push('t');
+            } else if let Some(first) = syllable.chars().next().filter(|f| !"aiueon".contains(*f)) {
+                result.push(first);
+            }
+            sokuon = false;
+        }
+
+        result.push_str(&syllable);
+    }
+
+    result
+}
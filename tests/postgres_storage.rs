@@ -0,0 +1,491 @@
+// PostgreSQL 存储后端集成测试
+//
+// 需要本地 PostgreSQL，运行方式：
+//   ANKI_CREATOR_TEST_POSTGRES_URL=postgres://postgres@localhost/anki_test cargo test --features postgres
+// 测试会删除并重建该数据库中的表，请勿指向正在使用的数据库。
+#![cfg(feature = "postgres")]
+
+use anki_creator::grammar::{ConnectionBase, GrammarRegister};
+use anki_creator::models::*;
+use anki_creator::postgres::PostgresStorage;
+use anki_creator::storage::Storage;
+
+async fn fresh_storage() -> Option<PostgresStorage> {
+    let url = match std::env::var("ANKI_CREATOR_TEST_POSTGRES_URL") {
+        Ok(url) => url,
+        Err(_) => {
+            eprintln!("跳过：未设置 ANKI_CREATOR_TEST_POSTGRES_URL");
+            return None;
+        }
+    };
+
+    let pool = sqlx::PgPool::connect(&url).await.expect("连接 PostgreSQL 失败");
+    sqlx::query("DROP TABLE IF EXISTS words, grammar, occurrences, search_index, entry_status, review_log, review_cards, users, kanji_words, kanji, word_pos, entry_analyses, analysis_flags, word_details, word_senses, word_examples, word_comparisons, grammar_details, grammar_connections, grammar_examples, grammar_related, grammar_aliases, sentence_entries, sentences, analysis_reviews, response_cache, api_calls")
+        .execute(&pool)
+        .await
+        .expect("清理测试表失败");
+    pool.close().await;
+
+    Some(PostgresStorage::connect(&url).await.expect("初始化 PostgreSQL 后端失败"))
+}
+
+fn word(word: &str, kana: &str, pitch: &str, pos: &str, analysis: &str) -> WordAnalysis {
+    WordAnalysis {
+        word: word.to_string(),
+        kana: kana.to_string(),
+        pitch: pitch.to_string(),
+        part_of_speech: pos.to_string(),
+        analysis: analysis.to_string(),
+        details: None,
+        prompt_version: 1,
+    }
+}
+
+#[tokio::test]
+async fn postgres_storage_round_trip() {
+    let Some(storage) = fresh_storage().await else {
+        return;
+    };
+
+    // 保存和查询单词
+    storage
+        .save_words(&[
+            word("勉強", "べんきょう", "0", "名词｜他动词", "<div>学习</div>"),
+            word("帯", "おび", "1", "名词", "<div>腰带</div>"),
+        ])
+        .await
+        .unwrap();
+    assert!(storage.check_word_exists("勉強", "べんきょう").await.unwrap());
+    let merged = storage.get_existing_word("帯", "おび", "1").await.unwrap().unwrap();
+    assert_eq!(merged.parts_of_speech, vec!["名词".to_string()]);
+
+    // 锁定的词性不会被覆盖
+    let obi = storage.get_existing_word_by_word_kana("帯", "おび").await.unwrap().unwrap();
+    storage.set_entry_lock(EntryKind::Word, obi.id, LockField::PartOfSpeech, true).await.unwrap();
+    storage.save_words(&[word("帯", "おび", "1", "动词", "<div>新解析</div>")]).await.unwrap();
+    let obi = storage.get_word_by_id(obi.id).await.unwrap().unwrap();
+    assert_eq!(obi.part_of_speech, "名词");
+    assert_eq!(obi.analysis, "<div>腰带</div>");
+
+    // 手动编辑会自动锁定字段
+    storage
+        .update_entry_field(EntryKind::Word, obi.id, LockField::Analysis, "<div>手动</div>")
+        .await
+        .unwrap();
+    let obi = storage.get_word_by_id(obi.id).await.unwrap().unwrap();
+    assert!(obi.analysis_locked);
+    assert_eq!(obi.analysis, "<div>手动</div>");
+
+    // pitch 冲突时删除冲突记录
+    storage.save_words(&[word("勉強", "べんきょう", "1", "名词", "<div>重复</div>")]).await.unwrap();
+    let original = storage.get_existing_word("勉強", "べんきょう", "0").await.unwrap().unwrap();
+    storage.update_word_pitch_and_pos(original.id, "1", "名词").await.unwrap();
+    let words = storage.get_all_words().await.unwrap();
+    assert_eq!(words.iter().filter(|w| w.word == "勉強").count(), 1);
+    assert_eq!(storage.get_word_by_id(original.id).await.unwrap().unwrap().pitch, "1");
+
+    // 语法和出现记录
+    storage
+        .save_grammar(&[GrammarAnalysis {
+            grammar: "〜ている".to_string(),
+            kana: "ている".to_string(),
+            analysis: "表示进行".to_string(),
+            prompt_version: 1,
+            details: None,
+        }])
+        .await
+        .unwrap();
+    let grammar = storage.get_grammar_by_word("〜ている").await.unwrap().unwrap();
+    assert_eq!(grammar.prompt_version, 1);
+    storage.update_grammar_analysis(grammar.id, "表示动作正在进行", 2).await.unwrap();
+    let grammar = storage.get_grammar_by_id(grammar.id).await.unwrap().unwrap();
+    assert_eq!((grammar.analysis.as_str(), grammar.prompt_version), ("表示动作正在进行", 2));
+    storage.record_occurrence(EntryKind::Grammar, grammar.id, "input.txt").await.unwrap();
+
+    // 同一语法的其他写法合并到规范写法的条目，原写法记为别名
+    storage
+        .save_grammar(&[GrammarAnalysis {
+            grammar: "ていた".to_string(),
+            kana: "ていた".to_string(),
+            analysis: String::new(),
+            prompt_version: 2,
+            details: None,
+        }])
+        .await
+        .unwrap();
+    assert_eq!(storage.get_all_grammar().await.unwrap().len(), 1);
+    assert_eq!(storage.get_grammar_aliases(grammar.id).await.unwrap(), vec!["ていた".to_string()]);
+    assert_eq!(storage.get_grammar_by_word("Vている").await.unwrap().map(|g| g.id), Some(grammar.id));
+    assert_eq!(storage.get_grammar_by_word("ていた").await.unwrap().map(|g| g.id), Some(grammar.id));
+
+    // 搜索：罗马字和文档过滤
+    let hits = storage
+        .search(&SearchQuery { text: "benkyou".to_string(), ..Default::default() })
+        .await
+        .unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].word, "勉強");
+    let hits = storage
+        .search(&SearchQuery { document: Some("input".to_string()), ..Default::default() })
+        .await
+        .unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].kind, EntryKind::Grammar);
+
+    // 按标准词性过滤（名詞 与 名词 相同）
+    let hits = storage
+        .search(&SearchQuery { part_of_speech: Some("名詞".to_string()), ..Default::default() })
+        .await
+        .unwrap();
+    assert_eq!(hits.len(), 2);
+    assert!(hits.iter().all(|h| h.kind == EntryKind::Word));
+
+    // 学习者状态
+    let user_id = storage.ensure_user("alice").await.unwrap();
+    assert_eq!(storage.ensure_user("alice").await.unwrap(), user_id);
+    storage.set_entry_status(user_id, EntryKind::Word, "帯", EntryStatus::Known).await.unwrap();
+    storage.set_entry_status(user_id, EntryKind::Word, "帯", EntryStatus::Ignored).await.unwrap();
+    let statuses = storage.get_entry_statuses(user_id, EntryKind::Word).await.unwrap();
+    assert_eq!(statuses.get("帯"), Some(&EntryStatus::Ignored));
+    assert_eq!(storage.export_statuses().await.unwrap().len(), 1);
+
+    // 复习卡片和复习记录
+    let card = ReviewCard {
+        id: 0,
+        user_id,
+        entry_kind: "word".to_string(),
+        entry_id: obi.id,
+        state: "review".to_string(),
+        due: "2030-01-01 00:00:00".to_string(),
+        stability: 3.7,
+        difficulty: 5.2,
+        reps: 1,
+        lapses: 0,
+        last_review: Some("2029-12-28 00:00:00".to_string()),
+    };
+    let log = ReviewLogEntry {
+        reviewed_at: "2029-12-28 00:00:00".to_string(),
+        reviewed_at_ms: 1_893_110_400_000,
+        ease: 3,
+        ivl: 4,
+        last_ivl: 0,
+        time_ms: 5000,
+        review_type: 0,
+        elapsed_days: 0.0,
+    };
+    let card_id = storage.save_review(&card, &log).await.unwrap();
+    assert_eq!(storage.save_review(&card, &log).await.unwrap(), card_id);
+    assert_eq!(storage.get_review_cards(user_id).await.unwrap().len(), 1);
+    let revlog = storage.export_review_log(user_id).await.unwrap();
+    assert_eq!(revlog.len(), 2);
+    assert_eq!(revlog[0].word, "帯");
+    assert_eq!(revlog[0].usn, -1);
+
+    // 汉字按字合并，保留记忆口诀
+    let info = KanjiInfo {
+        character: "帯".to_string(),
+        on_readings: vec!["タイ".to_string()],
+        kun_readings: vec!["お.びる".to_string(), "おび".to_string()],
+        meanings: vec!["sash".to_string(), "belt".to_string()],
+        stroke_count: Some(10),
+        components: vec!["冖".to_string(), "巾".to_string()],
+    };
+    let kanji_id = storage.save_kanji(&info).await.unwrap();
+    storage.update_kanji_mnemonic(kanji_id, "口诀").await.unwrap();
+    assert_eq!(storage.save_kanji(&info).await.unwrap(), kanji_id);
+    storage.link_kanji_words(kanji_id, &[obi.id]).await.unwrap();
+    let kanji = storage.get_all_kanji().await.unwrap();
+    assert_eq!(kanji[0].mnemonic, "口诀");
+    assert_eq!(kanji[0].kun_readings, "お.びる、おび");
+    assert_eq!(storage.get_kanji_word_links().await.unwrap(), vec![(kanji_id, obi.id)]);
+
+    // 审查结果按条目和语言替换
+    let review = AnalysisReview {
+        score: 40,
+        issues: vec![ReviewIssue { kind: ReviewIssueKind::Reading, description: "读音应为おび".to_string() }],
+    };
+    storage.save_analysis_review(EntryKind::Word, obi.id, "zh", &review, "reviewer").await.unwrap();
+    assert_eq!(storage.get_analysis_reviews(EntryKind::Word, "zh").await.unwrap().get(&obi.id), Some(&review));
+    let passed = AnalysisReview { score: 95, issues: Vec::new() };
+    storage.save_analysis_review(EntryKind::Word, obi.id, "zh", &passed, "reviewer").await.unwrap();
+    assert_eq!(storage.get_analysis_reviews(EntryKind::Word, "zh").await.unwrap().get(&obi.id), Some(&passed));
+    assert!(storage.get_analysis_reviews(EntryKind::Word, "en").await.unwrap().is_empty());
+
+    // 模型回复缓存按键覆盖，按写入时间过期
+    let mut cached = CachedResponse {
+        key: "abc".to_string(),
+        provider: "openrouter".to_string(),
+        model: "model".to_string(),
+        temperature: 0.1,
+        max_tokens: 1000,
+        response: "旧回复".to_string(),
+    };
+    storage.save_cached_response(&cached).await.unwrap();
+    cached.response = "新回复".to_string();
+    storage.save_cached_response(&cached).await.unwrap();
+    assert_eq!(storage.get_cached_response("abc", None).await.unwrap().as_deref(), Some("新回复"));
+    assert_eq!(storage.get_cached_response("abc", Some("9999-01-01 00:00:00")).await.unwrap(), None);
+    assert_eq!(storage.get_cached_response("missing", None).await.unwrap(), None);
+    assert_eq!(storage.purge_cached_responses("2000-01-01 00:00:00").await.unwrap(), 0);
+    assert_eq!(storage.purge_cached_responses("9999-01-01 00:00:00").await.unwrap(), 1);
+    assert_eq!(storage.get_cached_response("abc", None).await.unwrap(), None);
+
+    // 请求用量按运行和用途汇总，没有返回费用的请求不计入费用
+    let call = |run_id: &str, operation: &str, cost: Option<f64>| ApiCall {
+        run_id: run_id.to_string(),
+        operation: operation.to_string(),
+        model: "model".to_string(),
+        prompt_tokens: 100,
+        completion_tokens: 20,
+        cost,
+    };
+    storage.save_api_call(&call("run1", "word_analysis", Some(0.5))).await.unwrap();
+    storage.save_api_call(&call("run1", "word_analysis", None)).await.unwrap();
+    storage.save_api_call(&call("run1", "extraction", None)).await.unwrap();
+    storage.save_api_call(&call("run2", "word_analysis", Some(0.25))).await.unwrap();
+    assert_eq!(
+        storage.get_api_usage(Some("run1")).await.unwrap(),
+        vec![
+            ApiUsageSummary { operation: "extraction".to_string(), requests: 1, prompt_tokens: 100, completion_tokens: 20, cost: None },
+            ApiUsageSummary { operation: "word_analysis".to_string(), requests: 2, prompt_tokens: 200, completion_tokens: 40, cost: Some(0.5) },
+        ]
+    );
+    let all_runs = storage.get_api_usage(None).await.unwrap();
+    assert_eq!(all_runs[1].requests, 3);
+    assert_eq!(all_runs[1].cost, Some(0.75));
+
+    // 例句按原句合并，只补全空的读音和翻译
+    let example = ExampleSentence {
+        japanese: "帯を締める。".to_string(),
+        reading: "おびをしめる。".to_string(),
+        translation: String::new(),
+    };
+    let sentence_id = storage.save_sentence(&example, SentenceSource::Example, None).await.unwrap();
+    let translated = ExampleSentence { translation: "系腰带。".to_string(), reading: "べつ".to_string(), ..example.clone() };
+    assert_eq!(storage.save_sentence(&translated, SentenceSource::Input, Some("input.txt")).await.unwrap(), sentence_id);
+    assert!(storage.link_sentence(sentence_id, EntryKind::Word, obi.id).await.unwrap());
+    assert!(!storage.link_sentence(sentence_id, EntryKind::Word, obi.id).await.unwrap());
+    let sentences = storage.get_sentences_for_entry(EntryKind::Word, obi.id).await.unwrap();
+    assert_eq!(sentences.len(), 1);
+    assert_eq!(sentences[0].reading, "おびをしめる。");
+    assert_eq!(sentences[0].translation, "系腰带。");
+    assert_eq!(sentences[0].source, "example");
+    assert_eq!(storage.get_sentence_links().await.unwrap(), vec![(sentence_id, "word".to_string(), obi.id)]);
+    storage.update_sentence_translation(sentence_id, "おびをしめる。", "束腰带。").await.unwrap();
+    assert_eq!(storage.get_all_sentences().await.unwrap()[0].translation, "束腰带。");
+
+    // 其他语言的解析与主语言解析分开保存
+    storage.save_entry_analysis(EntryKind::Word, obi.id, "en", "<div>sash</div>", 3).await.unwrap();
+    let english = storage.get_entry_analyses(EntryKind::Word, "en").await.unwrap();
+    assert_eq!(english.get(&obi.id).map(String::as_str), Some("<div>sash</div>"));
+    let versions = storage.get_entry_analysis_versions(EntryKind::Word, "en").await.unwrap();
+    assert_eq!(versions.get(&obi.id), Some(&3));
+    let exported_analyses = storage.export_analyses().await.unwrap();
+    assert_eq!(exported_analyses.len(), 1);
+    assert_eq!(exported_analyses[0].prompt_version, 3);
+    assert_eq!(exported_analyses[0].word, "帯");
+    assert_eq!(
+        storage.import_analysis(&exported_analyses[0], ConflictPolicy::KeepExisting).await.unwrap(),
+        ImportOutcome::Skipped
+    );
+
+    // 未通过校验的解析标记可以覆盖和清除
+    storage.set_analysis_flag(EntryKind::Word, obi.id, "zh", Some("解析内容为空")).await.unwrap();
+    storage.set_analysis_flag(EntryKind::Word, obi.id, "zh", Some("补全了未闭合的标签 <div>")).await.unwrap();
+    let flags = storage.get_analysis_flags(EntryKind::Word, "zh").await.unwrap();
+    assert_eq!(flags.get(&obi.id).map(String::as_str), Some("补全了未闭合的标签 <div>"));
+    assert!(storage.get_analysis_flags(EntryKind::Word, "en").await.unwrap().is_empty());
+    storage.set_analysis_flag(EntryKind::Word, obi.id, "zh", None).await.unwrap();
+    assert!(storage.get_analysis_flags(EntryKind::Word, "zh").await.unwrap().is_empty());
+
+    // 结构化解析按义项、例句和比较的顺序读回，导入时按冲突策略处理
+    let details = WordDetails {
+        core_meaning: "腰带、地带".to_string(),
+        senses: vec![
+            WordSense {
+                meaning: "腰带".to_string(),
+                explanation: String::new(),
+                examples: vec![
+                    ExampleSentence { japanese: "帯を締める".to_string(), reading: "obi o shimeru".to_string(), translation: "系腰带。".to_string() },
+                    ExampleSentence { japanese: "着物と帯".to_string(), ..Default::default() },
+                ],
+            },
+            WordSense { meaning: "地带".to_string(), ..Default::default() },
+        ],
+        comparisons: vec![WordComparison { word: "紐".to_string(), difference: "更细".to_string() }],
+        ..Default::default()
+    };
+    storage.save_word_details(obi.id, "zh", Some(&details)).await.unwrap();
+    assert_eq!(storage.get_word_details("zh").await.unwrap().get(&obi.id), Some(&details));
+    assert!(storage.get_word_details("en").await.unwrap().is_empty());
+    let exported_details = storage.export_word_details().await.unwrap();
+    assert_eq!(exported_details.len(), 1);
+    assert_eq!(exported_details[0].details, details);
+    assert_eq!(
+        storage.import_word_details(&exported_details[0], ConflictPolicy::KeepExisting).await.unwrap(),
+        ImportOutcome::Skipped
+    );
+    storage.save_word_details(obi.id, "zh", None).await.unwrap();
+    assert!(storage.get_word_details("zh").await.unwrap().is_empty());
+    assert_eq!(
+        storage.import_word_details(&exported_details[0], ConflictPolicy::KeepExisting).await.unwrap(),
+        ImportOutcome::Inserted
+    );
+    assert_eq!(storage.get_word_details("zh").await.unwrap().get(&obi.id), Some(&details));
+
+    // 结构化语法解析：相关语法已收录时读回对应的ID
+    storage
+        .save_grammar(&[GrammarAnalysis {
+            grammar: "〜てある".to_string(),
+            kana: "てある".to_string(),
+            analysis: "表示状态".to_string(),
+            prompt_version: 1,
+            details: None,
+        }])
+        .await
+        .unwrap();
+    let tearu = storage.get_grammar_by_word("〜てある").await.unwrap().unwrap();
+    let mut grammar_details = GrammarDetails {
+        meaning: "表示动作正在进行或状态持续".to_string(),
+        register: GrammarRegister::Neutral,
+        connections: vec![ConnectionRule { base: ConnectionBase::Verb, form: "て形".to_string(), note: String::new() }],
+        examples: vec![ExampleSentence { japanese: "雨が降っている".to_string(), ..Default::default() }],
+        related: vec![
+            RelatedGrammar { grammar: "〜てある".to_string(), difference: "强调人为动作的结果".to_string(), grammar_id: None },
+            RelatedGrammar { grammar: "〜ておく".to_string(), difference: "表示事先准备".to_string(), grammar_id: None },
+        ],
+        ..Default::default()
+    };
+    storage.save_grammar_details(grammar.id, "zh", Some(&grammar_details)).await.unwrap();
+    grammar_details.related[0].grammar_id = Some(tearu.id);
+    assert_eq!(storage.get_grammar_details("zh").await.unwrap().get(&grammar.id), Some(&grammar_details));
+    let exported_grammar_details = storage.export_grammar_details().await.unwrap();
+    assert_eq!(exported_grammar_details.len(), 1);
+    assert_eq!(
+        storage.import_grammar_details(&exported_grammar_details[0], ConflictPolicy::KeepExisting).await.unwrap(),
+        ImportOutcome::Skipped
+    );
+    storage.save_grammar_details(grammar.id, "zh", None).await.unwrap();
+    assert!(storage.get_grammar_details("zh").await.unwrap().is_empty());
+    assert_eq!(
+        storage.import_grammar_details(&exported_grammar_details[0], ConflictPolicy::KeepExisting).await.unwrap(),
+        ImportOutcome::Inserted
+    );
+    assert_eq!(storage.get_grammar_details("zh").await.unwrap().get(&grammar.id), Some(&grammar_details));
+
+    // 导出后按冲突策略导入
+    let exported_words = storage.export_words().await.unwrap();
+    let exported_occurrences = storage.export_occurrences().await.unwrap();
+    assert_eq!(exported_words.len(), 2);
+    assert_eq!(exported_occurrences.len(), 1);
+
+    let mut changed = exported_words[0].clone();
+    changed.analysis = "<div>导入</div>".to_string();
+    assert_eq!(
+        storage.import_word(&changed, ConflictPolicy::KeepExisting).await.unwrap(),
+        ImportOutcome::Skipped
+    );
+    assert_eq!(
+        storage.import_word(&changed, ConflictPolicy::Overwrite).await.unwrap(),
+        ImportOutcome::Updated
+    );
+    assert_eq!(
+        storage.import_occurrence(&exported_occurrences[0]).await.unwrap(),
+        ImportOutcome::Skipped
+    );
+
+    // pitch 冲突时冲突记录的解析、例句、汉字和出现记录归并到当前记录
+    storage
+        .save_words(&[word("時計", "とけい", "0", "名词", "<div>钟表</div>"), word("時計", "とけい", "1", "名词", "<div>重复</div>")])
+        .await
+        .unwrap();
+    let keeper = storage.get_existing_word("時計", "とけい", "0").await.unwrap().unwrap();
+    let duplicate = storage.get_existing_word("時計", "とけい", "1").await.unwrap().unwrap();
+    storage.save_entry_analysis(EntryKind::Word, duplicate.id, "en", "<div>clock</div>", 1).await.unwrap();
+    let clock = ExampleSentence { japanese: "時計が止まった。".to_string(), ..Default::default() };
+    let clock_id = storage.save_sentence(&clock, SentenceSource::Example, None).await.unwrap();
+    storage.link_sentence(clock_id, EntryKind::Word, duplicate.id).await.unwrap();
+    let kanji_id = storage
+        .save_kanji(&KanjiInfo { character: "計".to_string(), ..Default::default() })
+        .await
+        .unwrap();
+    storage.link_kanji_words(kanji_id, &[duplicate.id]).await.unwrap();
+    storage.record_occurrence(EntryKind::Word, duplicate.id, "clock.txt").await.unwrap();
+
+    storage.update_word_pitch_and_pos(keeper.id, "1", "名词").await.unwrap();
+    assert!(storage.get_word_by_id(duplicate.id).await.unwrap().is_none());
+    assert_eq!(storage.get_word_by_id(keeper.id).await.unwrap().unwrap().pitch, "1");
+    let english = storage.get_entry_analyses(EntryKind::Word, "en").await.unwrap();
+    assert_eq!(english.get(&keeper.id).map(String::as_str), Some("<div>clock</div>"));
+    assert!(!english.contains_key(&duplicate.id));
+    assert_eq!(storage.get_sentences_for_entry(EntryKind::Word, keeper.id).await.unwrap().len(), 1);
+    let kanji_links = storage.get_kanji_word_links().await.unwrap();
+    assert!(kanji_links.contains(&(kanji_id, keeper.id)));
+    assert!(!kanji_links.iter().any(|(_, word_id)| *word_id == duplicate.id));
+    let occurrences = storage.get_occurrence_counts(EntryKind::Word).await.unwrap();
+    assert_eq!(occurrences.get(&keeper.id), Some(&1));
+    assert!(!occurrences.contains_key(&duplicate.id));
+
+    // 关键词中的 LIKE 通配符按字面匹配
+    let literal = |text: &str| SearchQuery { text: text.to_string(), ..Default::default() };
+    assert!(storage.search(&literal("%")).await.unwrap().is_empty());
+    assert!(storage.search(&literal("_")).await.unwrap().is_empty());
+    assert!(storage.search(&literal("\\")).await.unwrap().is_empty());
+    assert_eq!(storage.search(&literal("時計")).await.unwrap().len(), 1);
+
+    // 复习卡片和复习记录按学习者名称和条目自然键导出、导入
+    let cards = storage.export_review_cards().await.unwrap();
+    assert_eq!(cards.len(), 1);
+    assert_eq!((cards[0].user_name.as_str(), cards[0].word.as_str(), cards[0].reps), ("alice", "帯", 1));
+    let history = storage.export_review_history().await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(storage.import_review_card(&cards[0], ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Skipped);
+    assert_eq!(storage.import_review_history(&history[0]).await.unwrap(), ImportOutcome::Skipped);
+    let bob_card = ExportedReviewCard { user_name: "bob".to_string(), ..cards[0].clone() };
+    assert_eq!(storage.import_review_card(&bob_card, ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Inserted);
+    let bob_review = ExportedReviewLog { user_name: "bob".to_string(), ..history[0].clone() };
+    assert_eq!(storage.import_review_history(&bob_review).await.unwrap(), ImportOutcome::Inserted);
+    assert_eq!(storage.import_review_history(&bob_review).await.unwrap(), ImportOutcome::Skipped);
+    let bob = storage.ensure_user("bob").await.unwrap();
+    assert_eq!(storage.get_review_cards(bob).await.unwrap()[0].due, "2030-01-01 00:00:00");
+    assert_eq!(storage.export_review_log(bob).await.unwrap().len(), 1);
+
+    // 汉字和记忆口诀按字导入，关联的单词按自然键合并
+    let exported_kanji = storage.export_kanji().await.unwrap();
+    let obi_kanji = exported_kanji.iter().find(|k| k.character == "帯").unwrap();
+    assert_eq!(obi_kanji.mnemonic, "口诀");
+    assert_eq!(obi_kanji.words.len(), 1);
+    assert_eq!(storage.import_kanji(obi_kanji, ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Skipped);
+    let copied = ExportedKanji { character: "幕".to_string(), mnemonic: "新口诀".to_string(), ..obi_kanji.clone() };
+    assert_eq!(storage.import_kanji(&copied, ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Inserted);
+    let copied_kanji = storage.get_all_kanji().await.unwrap().into_iter().find(|k| k.character == "幕").unwrap();
+    assert_eq!(copied_kanji.mnemonic, "新口诀");
+    assert!(storage.get_kanji_word_links().await.unwrap().iter().any(|(kanji_id, _)| *kanji_id == copied_kanji.id));
+
+    // 语法别名随语法导出，导入时合并到已有条目
+    let exported_grammar = storage.export_grammar().await.unwrap();
+    let teiru = exported_grammar.iter().find(|g| g.word == "〜ている").unwrap();
+    assert!(teiru.aliases.contains(&"ていた".to_string()));
+    let extra = ExportedGrammar { aliases: vec!["ていました".to_string()], ..teiru.clone() };
+    assert_eq!(storage.import_grammar(&extra, ConflictPolicy::KeepExisting).await.unwrap(), ImportOutcome::Skipped);
+    assert_eq!(storage.get_grammar_by_word("ていました").await.unwrap().map(|g| g.id), Some(grammar.id));
+
+    // 例句按日文导入，翻译、读音和关联的条目随例句导出
+    let exported_sentences = storage.export_sentences().await.unwrap();
+    let clock_sentence = exported_sentences.iter().find(|s| s.japanese == "時計が止まった。").unwrap();
+    assert_eq!(clock_sentence.entries, vec![ExportedEntryRef {
+        entry_kind: "word".to_string(),
+        word: "時計".to_string(),
+        kana: "とけい".to_string(),
+        pitch: Some("1".to_string()),
+    }]);
+    assert_eq!(storage.import_sentence(clock_sentence, ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Skipped);
+    let translated = ExportedSentence {
+        japanese: "時計が鳴った。".to_string(),
+        translation: "钟响了。".to_string(),
+        ..clock_sentence.clone()
+    };
+    assert_eq!(storage.import_sentence(&translated, ConflictPolicy::KeepNewer).await.unwrap(), ImportOutcome::Inserted);
+    let ringing = storage.get_sentences_for_entry(EntryKind::Word, keeper.id).await.unwrap();
+    assert!(ringing.iter().any(|s| s.japanese == "時計が鳴った。" && s.translation == "钟响了。"));
+}